@@ -0,0 +1,23 @@
+//! Wall time of each engine on random input with a cheap comparator.
+
+use std::hint::black_box;
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use rankfast_bench::{Distribution, Engine};
+
+fn bench_engines(c: &mut Criterion) {
+    for engine in Engine::ALL {
+        let mut group = c.benchmark_group(engine.to_string());
+        group.sample_size(10);
+        for n in [100, 1_000, 10_000] {
+            let values = Distribution::Random.generate(n, 0);
+            group.bench_with_input(BenchmarkId::from_parameter(n), &values, |b, values| {
+                b.iter(|| engine.sort(black_box(values.clone()), |a, b| a < b));
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_engines);
+criterion_main!(benches);
@@ -0,0 +1,38 @@
+//! Compares the engines across input sizes and shapes, one JSON object per
+//! line on standard output:
+//!
+//! ```text
+//! cargo run --release -p rankfast-bench --bin compare-engines -- --sizes 10,100,1000
+//! ```
+
+use std::io::{self, Write};
+
+use clap::Parser;
+use rankfast_bench::{Distribution, Engine, measure};
+
+/// Measure comparisons and wall time of every engine.
+#[derive(Parser)]
+#[command(name = "compare-engines")]
+struct Args {
+    /// Input sizes to measure.
+    #[arg(long, value_delimiter = ',', default_values_t = [10, 100, 1000, 10_000])]
+    sizes: Vec<usize>,
+    /// Inputs per size and shape, averaged.
+    #[arg(long, default_value_t = 10)]
+    runs: u64,
+}
+
+fn main() -> io::Result<()> {
+    let args = Args::parse();
+    let mut out = io::stdout().lock();
+    for &n in &args.sizes {
+        for distribution in Distribution::ALL {
+            for engine in Engine::ALL {
+                let measurement = measure(engine, distribution, n, args.runs);
+                serde_json::to_writer(&mut out, &measurement)?;
+                writeln!(out)?;
+            }
+        }
+    }
+    Ok(())
+}
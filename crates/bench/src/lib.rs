@@ -0,0 +1,245 @@
+//! Comparison counts and wall time of the sorting engines, side by side.
+//!
+//! Every [`Engine`] sorts the same inputs with the same counting comparator,
+//! so the numbers answer the question that matters when each comparison is
+//! a person's answer: how many questions does it ask? Inputs come from the
+//! [`Distribution`]s a ranking app typically sees, generated from a fixed
+//! seed so runs are repeatable.
+
+use std::fmt;
+use std::time::Instant;
+
+use rankfast::{estimate_turns, rank_items};
+use serde::Serialize;
+
+/// A way of sorting with a "better than" comparator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Engine {
+    /// [`rank_items`]: Ford-Johnson merge-insertion.
+    FordJohnson,
+    /// Binary insertion of each item into the sorted prefix.
+    BinaryInsertion,
+    /// The standard library's stable sort (`slice::sort_by`).
+    StdSort,
+}
+
+impl Engine {
+    pub const ALL: [Self; 3] = [Self::FordJohnson, Self::BinaryInsertion, Self::StdSort];
+
+    /// Sorts `items` ascending, calling `less` once per comparison.
+    pub fn sort<T, F>(self, items: Vec<T>, mut less: F) -> Vec<T>
+    where
+        F: FnMut(&T, &T) -> bool,
+    {
+        match self {
+            Self::FordJohnson => rank_items(items, less).into_vec(),
+            Self::BinaryInsertion => {
+                let mut sorted: Vec<T> = Vec::with_capacity(items.len());
+                for item in items {
+                    let at = sorted.partition_point(|placed| !less(&item, placed));
+                    sorted.insert(at, item);
+                }
+                sorted
+            }
+            Self::StdSort => {
+                let mut items = items;
+                items.sort_by(|a, b| {
+                    if less(a, b) {
+                        std::cmp::Ordering::Less
+                    } else if less(b, a) {
+                        std::cmp::Ordering::Greater
+                    } else {
+                        std::cmp::Ordering::Equal
+                    }
+                });
+                items
+            }
+        }
+    }
+}
+
+impl fmt::Display for Engine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::FordJohnson => "ford_johnson",
+            Self::BinaryInsertion => "binary_insertion",
+            Self::StdSort => "std_sort",
+        })
+    }
+}
+
+/// The shape of an input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Distribution {
+    /// A uniformly shuffled permutation.
+    Random,
+    /// Already in order.
+    Sorted,
+    /// In reverse order.
+    Reversed,
+    /// Sorted, then about one item in ten swapped with a random other.
+    NearlySorted,
+    /// Shuffled with only ten distinct values, so most pairs tie.
+    FewUnique,
+}
+
+impl Distribution {
+    pub const ALL: [Self; 5] = [
+        Self::Random,
+        Self::Sorted,
+        Self::Reversed,
+        Self::NearlySorted,
+        Self::FewUnique,
+    ];
+
+    /// `n` values of this shape, the same for the same `seed`.
+    #[must_use]
+    pub fn generate(self, n: usize, seed: u64) -> Vec<u64> {
+        let mut rng = SplitMix64(seed);
+        let mut values: Vec<u64> = (0..n as u64).collect();
+        match self {
+            Self::Random => shuffle(&mut values, &mut rng),
+            Self::Sorted => {}
+            Self::Reversed => values.reverse(),
+            Self::NearlySorted => {
+                for _ in 0..n / 10 {
+                    let (i, j) = (rng.below(n), rng.below(n));
+                    values.swap(i, j);
+                }
+            }
+            Self::FewUnique => {
+                shuffle(&mut values, &mut rng);
+                for value in &mut values {
+                    *value %= 10;
+                }
+            }
+        }
+        values
+    }
+}
+
+impl fmt::Display for Distribution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Random => "random",
+            Self::Sorted => "sorted",
+            Self::Reversed => "reversed",
+            Self::NearlySorted => "nearly_sorted",
+            Self::FewUnique => "few_unique",
+        })
+    }
+}
+
+/// One engine on one input shape and size.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Measurement {
+    pub engine: Engine,
+    pub distribution: Distribution,
+    pub n: usize,
+    /// Mean comparisons per run.
+    pub comparisons: f64,
+    /// Most comparisons in any run.
+    pub max_comparisons: usize,
+    /// Upper bound [`estimate_turns`] gives for `n`.
+    pub estimate: usize,
+    /// Mean wall time per run, in nanoseconds.
+    pub nanos: f64,
+}
+
+/// Sorts `runs` inputs of `distribution` and size `n` with `engine`, seeds
+/// `0..runs`.
+///
+/// # Panics
+///
+/// Panics if the engine returns an unsorted result.
+#[must_use]
+pub fn measure(engine: Engine, distribution: Distribution, n: usize, runs: u64) -> Measurement {
+    let mut total: u128 = 0;
+    let mut max_comparisons = 0;
+    let mut nanos = 0;
+    for seed in 0..runs {
+        let values = distribution.generate(n, seed);
+        let mut comparisons = 0;
+        let start = Instant::now();
+        let sorted = engine.sort(values, |a, b| {
+            comparisons += 1;
+            a < b
+        });
+        nanos += start.elapsed().as_nanos();
+        assert!(sorted.is_sorted(), "{engine} left {distribution} unsorted");
+        total += comparisons as u128;
+        max_comparisons = max_comparisons.max(comparisons);
+    }
+    let runs = runs.max(1);
+    Measurement {
+        engine,
+        distribution,
+        n,
+        comparisons: mean(total, runs),
+        max_comparisons,
+        estimate: estimate_turns(n),
+        nanos: mean(nanos, runs),
+    }
+}
+
+#[allow(clippy::cast_precision_loss)] // counts and nanoseconds far below 2^52
+fn mean(total: u128, runs: u64) -> f64 {
+    total as f64 / runs as f64
+}
+
+/// `SplitMix64`, enough randomness for test inputs without a dependency.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0..bound`, up to a negligible modulo bias.
+    #[allow(clippy::cast_possible_truncation)] // the result is below `bound`
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+}
+
+/// Fisher-Yates.
+fn shuffle<T>(items: &mut [T], rng: &mut SplitMix64) {
+    for i in (1..items.len()).rev() {
+        items.swap(i, rng.below(i + 1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Distribution, Engine, measure};
+
+    #[test]
+    fn every_engine_sorts_every_shape() {
+        for distribution in Distribution::ALL {
+            let values = distribution.generate(50, 3);
+            assert_eq!(values.len(), 50);
+            assert_eq!(values, distribution.generate(50, 3), "{distribution}");
+            let mut expected = values.clone();
+            expected.sort_unstable();
+            for engine in Engine::ALL {
+                assert_eq!(engine.sort(values.clone(), |a, b| a < b), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn ford_johnson_asks_least_on_random_input() {
+        let count = |engine| measure(engine, Distribution::Random, 64, 5).comparisons;
+        let ford_johnson = count(Engine::FordJohnson);
+        assert!(ford_johnson <= count(Engine::BinaryInsertion));
+        assert!(ford_johnson <= count(Engine::StdSort));
+        let worst = measure(Engine::FordJohnson, Distribution::Random, 64, 5);
+        assert!(worst.max_comparisons <= worst.estimate);
+    }
+}
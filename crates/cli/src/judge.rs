@@ -0,0 +1,160 @@
+//! Answering every question with an external program.
+//!
+//! `--judge 'cmd {a} {b}'` runs the command through `sh -c` once per
+//! question, with `{a}` and `{b}` standing for the two items. They are
+//! passed as the shell's positional parameters rather than pasted into the
+//! command, so items need no quoting, and the placeholders should not be
+//! quoted either: they already expand to one word each. The judge answers
+//! on standard output with `a`, `b`, `tie` or `skip` (or `1` and `2` for the
+//! sides); with nothing printed, exit code 0 means `a` is better and 1 means
+//! `b` is.
+
+use std::fmt;
+use std::process::{Command, Output};
+use std::time::Instant;
+
+use rankfast::Step;
+use rankfast_session::Answer;
+
+use crate::replay::{Resumed, resume};
+use crate::{Run, elapsed_ms, give, now_ms};
+
+/// Why a judge gave no answer.
+#[derive(Debug)]
+pub(crate) enum JudgeError {
+    /// The command could not be started.
+    Spawn(std::io::Error),
+    /// The command printed something that is not an answer.
+    BadOutput(String),
+    /// Nothing printed and an exit code other than 0 or 1, or none at all.
+    BadStatus(Option<i32>),
+}
+
+impl fmt::Display for JudgeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Spawn(err) => write!(f, "could not run the judge: {err}"),
+            Self::BadOutput(out) => {
+                write!(f, "the judge printed {out:?}; expected a, b, tie or skip")
+            }
+            Self::BadStatus(Some(code)) => {
+                write!(f, "the judge exited with {code}; expected 0 (a) or 1 (b)")
+            }
+            Self::BadStatus(None) => f.write_str("the judge was killed by a signal"),
+        }
+    }
+}
+
+/// Ranks `items` after replaying `answers`, asking the judge `template`
+/// every question the `known` pairs leave open. A judge error stops the run
+/// as if the user quit.
+pub(crate) fn run(
+    items: &[String],
+    known: &[(usize, usize)],
+    template: &str,
+    answers: Vec<Answer>,
+) -> Run {
+    let script = script(template);
+    let Resumed {
+        mut stepper,
+        mut step,
+        mut answers,
+    } = resume(items.len(), known, answers);
+    let mut durations_ms = Vec::new();
+    let mut answered_at_ms = Vec::new();
+    while let Step::Compare { a, b, .. } = step {
+        let asked = Instant::now();
+        let answer = match ask(&script, &items[a], &items[b]) {
+            Ok(answer) => answer,
+            Err(err) => {
+                eprintln!("[{}] vs [{}]: {err}", items[a], items[b]);
+                return Run {
+                    order: None,
+                    answers,
+                    durations_ms,
+                    answered_at_ms,
+                };
+            }
+        };
+        durations_ms.push(elapsed_ms(asked));
+        answered_at_ms.push(now_ms());
+        answers.push(answer);
+        step = give(&mut stepper, answer, a, b);
+    }
+    Run {
+        order: stepper.take_order(),
+        answers,
+        durations_ms,
+        answered_at_ms,
+    }
+}
+
+/// The shell script for `template`, reading the items from `$1` and `$2`.
+fn script(template: &str) -> String {
+    template.replace("{a}", "\"$1\"").replace("{b}", "\"$2\"")
+}
+
+fn ask(script: &str, a: &str, b: &str) -> Result<Answer, JudgeError> {
+    let Output { status, stdout, .. } = Command::new("sh")
+        .args(["-c", script, "rankfast-judge", a, b])
+        .output()
+        .map_err(JudgeError::Spawn)?;
+    decide(&String::from_utf8_lossy(&stdout), status.code())
+}
+
+/// The answer in a judge's `stdout`, or else its exit `code`.
+fn decide(stdout: &str, code: Option<i32>) -> Result<Answer, JudgeError> {
+    let out = stdout.trim();
+    if out.is_empty() {
+        return match code {
+            Some(0) => Ok(Answer::A),
+            Some(1) => Ok(Answer::B),
+            other => Err(JudgeError::BadStatus(other)),
+        };
+    }
+    match out.to_ascii_lowercase().as_str() {
+        "a" | "1" => Ok(Answer::A),
+        "b" | "2" => Ok(Answer::B),
+        "t" | "tie" => Ok(Answer::Tie),
+        "s" | "skip" => Ok(Answer::Skip),
+        _ => Err(JudgeError::BadOutput(out.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{JudgeError, decide, run, script};
+    use rankfast_session::Answer;
+
+    #[test]
+    fn reads_the_output_before_the_exit_code() {
+        assert_eq!(decide("", Some(0)).unwrap(), Answer::A);
+        assert_eq!(decide("\n", Some(1)).unwrap(), Answer::B);
+        assert_eq!(decide(" B\n", Some(0)).unwrap(), Answer::B);
+        assert_eq!(decide("2", Some(0)).unwrap(), Answer::B);
+        assert_eq!(decide("Tie", Some(3)).unwrap(), Answer::Tie);
+        assert!(matches!(
+            decide("", Some(2)),
+            Err(JudgeError::BadStatus(Some(2)))
+        ));
+        assert!(matches!(
+            decide("maybe", Some(0)),
+            Err(JudgeError::BadOutput(_))
+        ));
+    }
+
+    #[test]
+    fn ranks_with_a_shell_judge() {
+        assert_eq!(script("cmp {a} {b}"), "cmp \"$1\" \"$2\"");
+        // Longer items are better; quotes in items reach the judge intact.
+        let items: Vec<String> = ["it's", "a", "\"longest\""].map(String::from).to_vec();
+        let judge = r#"test "$(printf %s {a} | wc -c)" -gt "$(printf %s {b} | wc -c)""#;
+        let done = run(&items, &[], judge, Vec::new());
+        assert_eq!(done.order.unwrap().into_vec(), vec![2, 0, 1]);
+        assert_eq!(done.durations_ms.len(), done.answers.len());
+
+        let failed = run(&items, &[], "exit 7", Vec::new());
+        assert!(failed.order.is_none());
+        assert!(failed.answers.is_empty());
+    }
+}
@@ -0,0 +1,214 @@
+//! Sort keys for `rankfast sort`.
+//!
+//! A key is an arithmetic expression over the fields of an item:
+//! `$1` is the first field, `$0` the whole item, and `len` its length in
+//! characters. Numbers, `+ - * /`, unary minus and parentheses combine
+//! them, so `$2`, `-$3` and `($2 - $3) / len` are all keys. Fields are split
+//! on whitespace unless a delimiter is given.
+
+use std::fmt;
+
+/// A parsed sort key.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Key {
+    Number(f64),
+    Field(usize),
+    Len,
+    Neg(Box<Key>),
+    Binary(Box<Key>, Op, Box<Key>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+/// Why a key could not be computed for an item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum KeyError {
+    /// The item has fewer fields than the key refers to.
+    MissingField(usize),
+    /// The field is not a number.
+    NotANumber(usize, String),
+}
+
+impl fmt::Display for KeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingField(field) => write!(f, "no field ${field}"),
+            Self::NotANumber(field, text) => write!(f, "field ${field} is not a number: {text:?}"),
+        }
+    }
+}
+
+impl Key {
+    /// Parses a key expression.
+    pub(crate) fn parse(source: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            source,
+            rest: source.trim_start(),
+        };
+        let key = parser.expr()?;
+        if parser.rest.is_empty() {
+            Ok(key)
+        } else {
+            Err(parser.error("unexpected input"))
+        }
+    }
+
+    /// Computes the key for `item`, splitting fields on `delimiter` or on
+    /// whitespace.
+    pub(crate) fn eval(&self, item: &str, delimiter: Option<&str>) -> Result<f64, KeyError> {
+        Ok(match self {
+            Self::Number(value) => *value,
+            Self::Field(field) => {
+                let text = if *field == 0 {
+                    Some(item)
+                } else if let Some(delimiter) = delimiter {
+                    item.split(delimiter).nth(field - 1)
+                } else {
+                    item.split_whitespace().nth(field - 1)
+                };
+                let text = text.ok_or(KeyError::MissingField(*field))?.trim();
+                text.parse()
+                    .map_err(|_| KeyError::NotANumber(*field, text.to_string()))?
+            }
+            Self::Len => usize_to_f64(item.chars().count()),
+            Self::Neg(inner) => -inner.eval(item, delimiter)?,
+            Self::Binary(lhs, op, rhs) => {
+                let (lhs, rhs) = (lhs.eval(item, delimiter)?, rhs.eval(item, delimiter)?);
+                match op {
+                    Op::Add => lhs + rhs,
+                    Op::Sub => lhs - rhs,
+                    Op::Mul => lhs * rhs,
+                    Op::Div => lhs / rhs,
+                }
+            }
+        })
+    }
+}
+
+#[allow(clippy::cast_precision_loss)] // item lengths are far below 2^52
+fn usize_to_f64(value: usize) -> f64 {
+    value as f64
+}
+
+/// Recursive-descent parser over the unparsed tail of the source.
+struct Parser<'a> {
+    source: &'a str,
+    rest: &'a str,
+}
+
+impl Parser<'_> {
+    fn error(&self, what: &str) -> String {
+        let at = self.source.len() - self.rest.len();
+        format!("{what} at column {} of {:?}", at + 1, self.source)
+    }
+
+    fn eat(&mut self, token: char) -> bool {
+        if let Some(rest) = self.rest.strip_prefix(token) {
+            self.rest = rest.trim_start();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expr(&mut self) -> Result<Key, String> {
+        let mut key = self.term()?;
+        loop {
+            let op = if self.eat('+') {
+                Op::Add
+            } else if self.eat('-') {
+                Op::Sub
+            } else {
+                return Ok(key);
+            };
+            key = Key::Binary(Box::new(key), op, Box::new(self.term()?));
+        }
+    }
+
+    fn term(&mut self) -> Result<Key, String> {
+        let mut key = self.factor()?;
+        loop {
+            let op = if self.eat('*') {
+                Op::Mul
+            } else if self.eat('/') {
+                Op::Div
+            } else {
+                return Ok(key);
+            };
+            key = Key::Binary(Box::new(key), op, Box::new(self.factor()?));
+        }
+    }
+
+    fn factor(&mut self) -> Result<Key, String> {
+        if self.eat('-') {
+            return Ok(Key::Neg(Box::new(self.factor()?)));
+        }
+        if self.eat('(') {
+            let key = self.expr()?;
+            return if self.eat(')') {
+                Ok(key)
+            } else {
+                Err(self.error("expected `)`"))
+            };
+        }
+        if let Some(rest) = self.rest.strip_prefix("len") {
+            self.rest = rest.trim_start();
+            return Ok(Key::Len);
+        }
+        if let Some(rest) = self.rest.strip_prefix('$') {
+            let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            let field = rest[..digits]
+                .parse()
+                .map_err(|_| self.error("expected a field number after `$`"))?;
+            self.rest = rest[digits..].trim_start();
+            return Ok(Key::Field(field));
+        }
+        let len = self.rest.len()
+            - self
+                .rest
+                .trim_start_matches(|c: char| c.is_ascii_digit() || c == '.')
+                .len();
+        let number = self.rest[..len]
+            .parse()
+            .map_err(|_| self.error("expected a number, `$N`, `len` or `(`"))?;
+        self.rest = self.rest[len..].trim_start();
+        Ok(Key::Number(number))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Key, KeyError};
+
+    #[test]
+    fn evaluates_fields_and_arithmetic() {
+        let key = Key::parse("($2 - $3) / 2 + -len * 0").unwrap();
+        assert_eq!(key.eval("x 10 4", None), Ok(3.0));
+        assert_eq!(
+            Key::parse("$2").unwrap().eval("a,7.5,b", Some(",")),
+            Ok(7.5)
+        );
+        assert_eq!(Key::parse("$0").unwrap().eval(" 42 ", None), Ok(42.0));
+        assert_eq!(Key::parse("len").unwrap().eval("héllo", None), Ok(5.0));
+        assert_eq!(Key::parse("1 - 2 - 3").unwrap().eval("", None), Ok(-4.0));
+    }
+
+    #[test]
+    fn reports_bad_keys_and_items() {
+        assert!(Key::parse("$").is_err());
+        assert!(Key::parse("(1 + 2").is_err());
+        assert!(Key::parse("1 2").is_err());
+        let key = Key::parse("$2").unwrap();
+        assert_eq!(key.eval("one", None), Err(KeyError::MissingField(2)));
+        assert_eq!(
+            key.eval("one two", None),
+            Err(KeyError::NotANumber(2, "two".to_string()))
+        );
+    }
+}
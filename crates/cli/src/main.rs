@@ -1,19 +1,160 @@
+use std::fs;
 use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
-use rankfast::{estimate_turns, rank_items};
-
-fn main() {
-    // Hardcoded items to rank.
-    let items = vec![
-        "Blue".to_string(),
-        "Orange".to_string(),
-        "Red".to_string(),
-        "Black".to_string(),
-        "Green".to_string(),
-        "Yellow".to_string(),
-        "Purple".to_string(),
-        "White".to_string(),
-    ];
+use clap::error::ErrorKind;
+use clap::{Parser, Subcommand};
+use rankfast::pin::{Pin, pinned_pairs};
+use rankfast::{Ranking, Step, Stepper, estimate_turns, normalize_items};
+use rankfast_session::{Answer, Mode, Session, Sides, analyze, dataset, hash, preferences};
+
+mod judge;
+mod key;
+mod replay;
+mod sort;
+mod tui;
+
+/// Where `trunk serve` runs the web app; the default for `--emit-url`.
+const DEFAULT_WEB_URL: &str = "http://127.0.0.1:8080/";
+
+/// The question asked at each comparison unless `--prompt` says otherwise.
+const DEFAULT_PROMPT: &str = "Which is better?";
+
+/// Rank a list of items by answering "which is better?" questions.
+#[derive(Parser)]
+#[command(name = "rankfast", version, args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    rank: RankArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    Sort(sort::SortArgs),
+}
+
+#[derive(clap::Args)]
+struct RankArgs {
+    /// Items to rank; a sample list of colors when omitted.
+    #[arg(conflicts_with = "session")]
+    items: Vec<String>,
+    /// Rank in a full-screen view with a live partial ranking.
+    #[arg(long)]
+    tui: bool,
+    /// Answer from recorded `a`/`b`/`t`/`s` tokens instead of prompting, or
+    /// replay a web session link of an exact ranking, items included.
+    #[arg(long, conflicts_with_all = ["tui", "answers_file", "session"])]
+    answers: Option<String>,
+    /// Like `--answers`, reading the tokens or link from a file.
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["tui", "session"])]
+    answers_file: Option<PathBuf>,
+    /// Answer with a shell command run per question, `{a}` and `{b}` being
+    /// the items: it prints a, b, tie or skip, or exits 0 when `{a}` is
+    /// better and 1 when `{b}` is.
+    #[arg(long, value_name = "CMD", conflicts_with_all = ["tui", "answers", "answers_file"])]
+    judge: Option<String>,
+    /// Start from the judgments in a JSON Lines preference log, as written
+    /// by `--dataset`: questions they already decide are not asked.
+    #[arg(long, value_name = "PATH", conflicts_with = "emit_url")]
+    known: Option<PathBuf>,
+    /// Rank ITEM above every item not pinned first; no question about it is
+    /// asked unless several are. Repeatable.
+    #[arg(long, value_name = "ITEM", conflicts_with = "emit_url")]
+    first: Vec<String>,
+    /// Rank ITEM below every item not pinned last. Repeatable.
+    #[arg(long, value_name = "ITEM", conflicts_with = "emit_url")]
+    last: Vec<String>,
+    /// The question asked at each comparison, such as "Which bug is higher
+    /// priority?"; saved with the session, and taken from a continued one
+    /// when omitted.
+    #[arg(long, value_name = "TEXT")]
+    prompt: Option<String>,
+    /// Continue a session saved as JSON by any rankfast frontend.
+    #[arg(long, value_name = "PATH")]
+    session: Option<PathBuf>,
+    /// Save the session as JSON when the run ends or is abandoned.
+    #[arg(long, value_name = "PATH")]
+    save_session: Option<PathBuf>,
+    /// Save statistics about the run as JSON when it ends or is abandoned:
+    /// questions per item, answer streaks and time per answer.
+    #[arg(long, value_name = "PATH")]
+    stats: Option<PathBuf>,
+    /// Save every answered comparison as pairwise preference data when the
+    /// run ends or is abandoned: CSV if PATH ends in `.csv`, else JSON Lines.
+    #[arg(long, value_name = "PATH")]
+    dataset: Option<PathBuf>,
+    /// Print a link that opens the session in the web app served at BASE,
+    /// when the run ends or is abandoned.
+    #[arg(
+        long,
+        value_name = "BASE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = DEFAULT_WEB_URL
+    )]
+    emit_url: Option<String>,
+}
+
+/// How a run ended.
+struct Run {
+    /// The indices best first, or `None` when the user stopped early.
+    order: Option<Ranking<usize>>,
+    /// Every answer given, in order.
+    answers: Vec<Answer>,
+    /// Milliseconds spent on each question asked in this run, which are the
+    /// last of `answers`; replayed answers were not timed.
+    durations_ms: Vec<f64>,
+    /// When each of those questions was answered, in Unix milliseconds.
+    answered_at_ms: Vec<u64>,
+}
+
+fn main() -> io::Result<()> {
+    let cli = Cli::parse();
+    match cli.command {
+        Some(Command::Sort(args)) => sort::run(args),
+        None => rank(cli.rank),
+    }
+}
+
+fn rank(args: RankArgs) -> io::Result<()> {
+    let mut recorded = read_recorded(&args)?;
+    let linked = recorded.as_mut().and_then(|recorded| recorded.items.take());
+    let (items, saved_known, saved_answers, saved_prompt) = match (&args.session, linked) {
+        (Some(path), _) => {
+            let saved = Session::from_json(&fs::read_to_string(path)?).unwrap_or_else(|err| {
+                clap::Error::raw(ErrorKind::InvalidValue, format!("{err}\n")).exit()
+            });
+            if saved.engine != Mode::Exact {
+                clap::Error::raw(
+                    ErrorKind::InvalidValue,
+                    "only exact sessions can be continued here\n",
+                )
+                .exit();
+            }
+            // Saved items were normalized when the session started.
+            (saved.items, saved.known, saved.answers, saved.prompt)
+        }
+        // Link items were normalized by the app.
+        (None, Some(items)) => {
+            let prompt = recorded
+                .as_mut()
+                .and_then(|recorded| recorded.prompt.take());
+            (items, Vec::new(), Vec::new(), prompt)
+        }
+        (None, None) => (input_items(args.items), Vec::new(), Vec::new(), None),
+    };
+    let question = args.prompt.or(saved_prompt);
+    let imported = match &args.known {
+        Some(path) => import_known(&items, path)?,
+        None => Vec::new(),
+    };
+    let pinned = pin_pairs(&items, &args.first, &args.last);
+    // Pairs of a continued session come first, so its answers still replay
+    // to the same questions; pins override imported judgments.
+    let known = rankfast::consistent_pairs(items.len(), &[saved_known, pinned, imported].concat());
 
     let estimate = estimate_turns(items.len());
     println!(
@@ -22,37 +163,327 @@ fn main() {
         estimate
     );
 
-    let ranking = rank_items(items, |a, b| compare(a, b));
+    let run = if let Some(recorded) = recorded {
+        replay_answers(items.len(), &known, recorded.answers)
+    } else if let Some(template) = &args.judge {
+        judge::run(&items, &known, template, saved_answers)
+    } else if args.tui {
+        let question = question.as_deref().unwrap_or(DEFAULT_PROMPT);
+        tui::run(&items, &known, saved_answers, question)?
+    } else {
+        let url_base = args.emit_url.as_deref().unwrap_or(DEFAULT_WEB_URL);
+        prompt(&items, &known, url_base, question.as_deref(), saved_answers)
+    };
+
+    let session = Session {
+        prompt: question,
+        known,
+        ..Session::new(items, run.answers.clone(), Mode::Exact)
+    };
+    if let Some(path) = &args.save_session {
+        fs::write(path, session.to_json() + "\n")?;
+    }
+    if let Some(path) = &args.stats {
+        match analyze(&session, Some(&run.durations_ms)) {
+            Ok(stats) => fs::write(path, stats.to_json() + "\n")?,
+            Err(err) => eprintln!("No statistics: {err}"),
+        }
+    }
+    if let Some(path) = &args.dataset {
+        match preferences(&session, &run.answered_at_ms) {
+            Ok(log) if path.extension().is_some_and(|ext| ext == "csv") => {
+                fs::write(path, dataset::to_csv(&log))?;
+            }
+            Ok(log) => fs::write(path, dataset::to_jsonl(&log))?,
+            Err(err) => eprintln!("No dataset: {err}"),
+        }
+    }
+    if let Some(base) = &args.emit_url {
+        if !session.known.is_empty() {
+            eprintln!("The link leaves out the known pairs, so its answers may not fit.");
+        }
+        println!(
+            "Continue in the browser: {}",
+            session_url(
+                base,
+                &session.items,
+                &run.answers,
+                session.prompt.as_deref()
+            )
+        );
+    }
+    let Some(order) = run.order else {
+        println!("Ranking abandoned.");
+        return Ok(());
+    };
 
     println!("Final ranking:");
-    if ranking.is_empty() {
+    if order.is_empty() {
         println!("(empty)");
-        return;
+        return Ok(());
+    }
+    for (place, item) in order.map(|i| &session.items[i]).positions() {
+        println!("{place}. {item}");
     }
-    for (i, name) in ranking.iter().enumerate() {
-        println!("{}. {}", i + 1, name);
+    Ok(())
+}
+
+/// Gives `answer` to the pending question `a` vs `b`; ties are recorded so
+/// the tied items share a place.
+pub(crate) fn give(stepper: &mut Stepper, answer: Answer, a: usize, b: usize) -> Step {
+    if answer == Answer::Tie {
+        stepper.answer_tie()
+    } else {
+        stepper.answer(answer.a_is_better(a, b))
     }
 }
 
-fn compare(a: &str, b: &str) -> bool {
+/// Milliseconds since `start`.
+pub(crate) fn elapsed_ms(start: Instant) -> f64 {
+    start.elapsed().as_secs_f64() * 1000.0
+}
+
+/// The current time in Unix milliseconds, 0 if the clock is before 1970.
+pub(crate) fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| {
+            u64::try_from(since.as_millis()).unwrap_or(u64::MAX)
+        })
+}
+
+/// The pairs the preference log at `path` decides.
+fn import_known(items: &[String], path: &Path) -> io::Result<Vec<(usize, usize)>> {
+    let log = dataset::read_jsonl(&fs::read_to_string(path)?)
+        .unwrap_or_else(|err| clap::Error::raw(ErrorKind::InvalidValue, format!("{err}\n")).exit());
+    let pairs = dataset::known_pairs(items, &log);
+    println!("{} known pairs from {}", pairs.len(), path.display());
+    Ok(pairs)
+}
+
+/// The pairs putting the `first` items above the rest and the `last` ones
+/// below.
+fn pin_pairs(items: &[String], first: &[String], last: &[String]) -> Vec<(usize, usize)> {
+    let pins: Vec<(usize, Pin)> = first
+        .iter()
+        .map(|name| (name, Pin::First))
+        .chain(last.iter().map(|name| (name, Pin::Last)))
+        .map(|(name, pin)| (item_index(items, name), pin))
+        .collect();
+    pinned_pairs(items.len(), &pins)
+}
+
+/// The answers given with `--answers` or `--answers-file`, exiting if they
+/// cannot be read.
+fn read_recorded(args: &RankArgs) -> io::Result<Option<replay::Recorded>> {
+    let text = match (&args.answers, &args.answers_file) {
+        (Some(text), _) => text.clone(),
+        (None, Some(path)) => fs::read_to_string(path)?,
+        (None, None) => return Ok(None),
+    };
+    let recorded = replay::parse_recorded(&text).unwrap_or_else(|err| invalid(&err));
+    if recorded.items.is_some() {
+        check_link_args(args);
+    }
+    Ok(Some(recorded))
+}
+
+/// Exits for `args` that do not go with replaying a link: the link brings
+/// its own items, and its answers were given without known pairs.
+fn check_link_args(args: &RankArgs) {
+    if !args.items.is_empty() {
+        invalid("the link already holds the items to rank");
+    }
+    if args.known.is_some() || !args.first.is_empty() || !args.last.is_empty() {
+        invalid(
+            "a link's answers were given without known pairs, so --known, --first and --last do not apply",
+        );
+    }
+}
+
+/// Exits with `message` as an invalid value.
+fn invalid(message: impl std::fmt::Display) -> ! {
+    clap::Error::raw(ErrorKind::InvalidValue, format!("{message}\n")).exit()
+}
+
+/// The run answering with recorded `answers`, exiting if they do not fit.
+fn replay_answers(n: usize, known: &[(usize, usize)], answers: Vec<Answer>) -> Run {
+    let replayed = replay::replay(n, known, answers).unwrap_or_else(|err| invalid(&err));
+    if replayed.unused > 0 {
+        eprintln!(
+            "Ignored {} answers after the ranking was complete",
+            replayed.unused
+        );
+    }
+    replayed.run
+}
+
+/// Link to the web app at `base` holding `items` and `answers`, asked with
+/// `prompt`.
+fn session_url(base: &str, items: &[String], answers: &[Answer], prompt: Option<&str>) -> String {
+    let base = base.trim_end_matches('#');
+    format!(
+        "{base}#{}",
+        hash::build(items, answers, Mode::Exact, Sides::Fixed, prompt)
+    )
+}
+
+/// Items from the command line, falling back to a sample list, with
+/// duplicates merged.
+fn input_items(mut items: Vec<String>) -> Vec<String> {
+    if items.is_empty() {
+        items = [
+            "Blue", "Orange", "Red", "Black", "Green", "Yellow", "Purple", "White",
+        ]
+        .map(String::from)
+        .to_vec();
+    }
+
+    let normalized = normalize_items(&items);
+    for duplicate in &normalized.duplicates {
+        println!(
+            "Merged duplicate [{}] into [{}]",
+            duplicate.text, normalized.items[duplicate.kept]
+        );
+    }
+    normalized.items
+}
+
+/// The position of `name` among `items`, or exits with a usage error.
+fn item_index(items: &[String], name: &str) -> usize {
+    items
+        .iter()
+        .position(|item| item == name.trim())
+        .unwrap_or_else(|| {
+            clap::Error::raw(ErrorKind::InvalidValue, format!("no item named {name:?}\n")).exit()
+        })
+}
+
+/// Asks every question the `known` pairs leave open on the terminal, after
+/// replaying `answers`, as `question` or the default prompt. Typing `url`
+/// prints a link to the session so far, on the web app at `url_base`.
+fn prompt(
+    items: &[String],
+    known: &[(usize, usize)],
+    url_base: &str,
+    question: Option<&str>,
+    answers: Vec<Answer>,
+) -> Run {
+    let replay::Resumed {
+        mut stepper,
+        mut step,
+        mut answers,
+    } = replay::resume(items.len(), known, answers);
+    if step != Step::Done {
+        println!("Type URL at any question for a link to continue in the browser.");
+    }
+    let mut durations_ms = Vec::new();
+    let mut answered_at_ms = Vec::new();
+    while let Step::Compare { a, b, .. } = step {
+        let asked = Instant::now();
+        let answer = loop {
+            match compare(question.unwrap_or(DEFAULT_PROMPT), &items[a], &items[b]) {
+                Reply::Answer(answer) => break answer,
+                Reply::Url => println!("{}", session_url(url_base, items, &answers, question)),
+                Reply::Quit => {
+                    return Run {
+                        order: None,
+                        answers,
+                        durations_ms,
+                        answered_at_ms,
+                    };
+                }
+            }
+        };
+        durations_ms.push(elapsed_ms(asked));
+        answered_at_ms.push(now_ms());
+        answers.push(answer);
+        step = give(&mut stepper, answer, a, b);
+    }
+    Run {
+        order: stepper.take_order(),
+        answers,
+        durations_ms,
+        answered_at_ms,
+    }
+}
+
+/// What was typed at a question.
+enum Reply {
+    Answer(Answer),
+    /// Print the session link.
+    Url,
+    /// End of input.
+    Quit,
+}
+
+fn compare(question: &str, a: &str, b: &str) -> Reply {
     loop {
-        print!("Which is better? Type A or B: [{a}] vs [{b}] ");
+        print!("{question} Type A or B: [{a}] vs [{b}] ");
         io::stdout().flush().ok();
 
         let mut input = String::new();
-        if io::stdin().read_line(&mut input).is_err() {
-            println!("Could not read input. Try again.");
-            continue;
+        match io::stdin().read_line(&mut input) {
+            Ok(0) => {
+                println!();
+                return Reply::Quit;
+            }
+            Ok(_) => {}
+            Err(_) => {
+                println!("Could not read input. Try again.");
+                continue;
+            }
         }
 
         let answer = input.trim();
         if answer.eq_ignore_ascii_case("a") {
-            return true;
+            return Reply::Answer(Answer::A);
         }
         if answer.eq_ignore_ascii_case("b") {
-            return false;
+            return Reply::Answer(Answer::B);
+        }
+        if answer.eq_ignore_ascii_case("url") {
+            return Reply::Url;
         }
 
         println!("Please type A or B");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rankfast::Ranking;
+    use rankfast_session::{Answer, hash};
+
+    use super::{replay, replay_answers, session_url};
+
+    #[test]
+    fn session_urls_open_in_the_web_app() {
+        let items = vec!["x".to_string(), "y".to_string()];
+        let url = session_url("https://example.org/#", &items, &[Answer::B], Some("Why?"));
+        let (base, fragment) = url.split_once('#').unwrap();
+        assert_eq!(base, "https://example.org/");
+        let state = hash::parse(fragment);
+        assert_eq!(state.items, items);
+        assert_eq!(state.answers, vec![Answer::B]);
+        assert_eq!(state.prompt.as_deref(), Some("Why?"));
+    }
+
+    #[test]
+    fn links_replay_into_the_ranking_they_record() {
+        let items = ["red", "green", "blue"].map(String::from).to_vec();
+        let answers = replay::parse_answers("bab").unwrap();
+        let ranked = replay_answers(items.len(), &[], answers.clone());
+        let url = session_url("https://example.org/", &items, &answers, Some("Brighter?"));
+
+        let recorded = replay::parse_recorded(&url).unwrap();
+        assert_eq!(recorded.items, Some(items.clone()));
+        assert_eq!(recorded.prompt.as_deref(), Some("Brighter?"));
+        let replayed = replay_answers(items.len(), &[], recorded.answers);
+        assert_eq!(replayed.answers, answers);
+        assert_eq!(
+            replayed.order.map(Ranking::into_vec),
+            ranked.order.map(Ranking::into_vec)
+        );
+    }
+}
@@ -0,0 +1,258 @@
+//! Replaying a recorded session instead of prompting.
+//!
+//! Answers are the tokens the web app stores in its links: `a` or `b` for
+//! the preferred side, `t` for a tie and `s` for a skip. Ties and skips keep
+//! the items in input order, exactly as in the app, so the same items and
+//! tokens always produce the same ranking. Whitespace between tokens is
+//! ignored, which lets answer files wrap.
+//!
+//! A whole link, or its `#` fragment, is read with [`hash`] instead: its
+//! items and question come with the answers. Links hold no known pairs, so
+//! their answers replay to the questions of a plain exact sort, and links of
+//! any other engine are refused, their answers meaning other questions.
+
+use std::fmt;
+
+use rankfast::{Step, Stepper};
+use rankfast_session::hash::{self, HashError};
+use rankfast_session::{Answer, Mode};
+
+use crate::{Run, give};
+
+/// Why a replay could not finish.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ReplayError {
+    /// Not an answer token; the position counts tokens from 1.
+    BadToken(usize, char),
+    /// The ranking needs more answers than were given.
+    OutOfAnswers(usize),
+    /// A link that could not be decoded.
+    Link(HashError),
+    /// A link recorded with another engine than the exact sort.
+    NotExact(Mode),
+    /// A link whose items were edited after its answers were given.
+    Edited,
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BadToken(at, token) => {
+                write!(f, "answer {at} is {token:?}; expected a, b, t or s")
+            }
+            Self::OutOfAnswers(given) => {
+                write!(f, "the ranking needs more than the {given} answers given")
+            }
+            Self::Link(e) => e.fmt(f),
+            Self::NotExact(mode) => write!(
+                f,
+                "the link was ranked with the {} engine; only exact links can be replayed",
+                mode.name()
+            ),
+            Self::Edited => {
+                f.write_str("the link's items were edited after its answers were given")
+            }
+        }
+    }
+}
+
+/// A finished replay.
+pub(crate) struct Replayed {
+    pub(crate) run: Run,
+    /// Answers left over once the ranking was complete.
+    pub(crate) unused: usize,
+}
+
+/// Answers read from tokens or a link.
+pub(crate) struct Recorded {
+    pub(crate) answers: Vec<Answer>,
+    /// The items of a link, in its order.
+    pub(crate) items: Option<Vec<String>>,
+    /// The question a link asks, if it sets one.
+    pub(crate) prompt: Option<String>,
+}
+
+/// Reads `text` as a web app link, its fragment, or answer tokens.
+pub(crate) fn parse_recorded(text: &str) -> Result<Recorded, ReplayError> {
+    let text = text.trim();
+    let fragment = match text.split_once('#') {
+        Some((_, fragment)) => fragment,
+        None if text.starts_with("v2:") => text,
+        None => {
+            return parse_answers(text).map(|answers| Recorded {
+                answers,
+                items: None,
+                prompt: None,
+            });
+        }
+    };
+    let state = hash::try_parse(fragment).map_err(ReplayError::Link)?;
+    if state.mode != Mode::Exact {
+        return Err(ReplayError::NotExact(state.mode));
+    }
+    if state.mismatch {
+        return Err(ReplayError::Edited);
+    }
+    Ok(Recorded {
+        answers: state.answers,
+        items: Some(state.items),
+        prompt: state.prompt,
+    })
+}
+
+/// Reads answer tokens, skipping whitespace.
+pub(crate) fn parse_answers(tokens: &str) -> Result<Vec<Answer>, ReplayError> {
+    tokens
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .enumerate()
+        .map(|(at, token)| Answer::from_token(token).ok_or(ReplayError::BadToken(at + 1, token)))
+        .collect()
+}
+
+/// A ranking of `0..n` picked up after its saved answers.
+pub(crate) struct Resumed {
+    pub(crate) stepper: Stepper,
+    /// The first question the saved answers leave, or [`Step::Done`].
+    pub(crate) step: Step,
+    /// The saved answers the ranking used.
+    pub(crate) answers: Vec<Answer>,
+}
+
+/// Gives `answers` in turn to the questions about `0..n` that the `known`
+/// `(better, worse)` pairs leave open, until they run out or the ranking is
+/// complete; answers after that are dropped. Every frontend of the CLI
+/// starts from here, so a continued session asks what it asked before.
+pub(crate) fn resume(n: usize, known: &[(usize, usize)], mut answers: Vec<Answer>) -> Resumed {
+    let mut stepper = Stepper::with_known_pairs(n, known);
+    let mut step = stepper.step();
+    let mut used = 0;
+    while let (Step::Compare { a, b, .. }, Some(&answer)) = (step, answers.get(used)) {
+        used += 1;
+        step = give(&mut stepper, answer, a, b);
+    }
+    answers.truncate(used);
+    Resumed {
+        stepper,
+        step,
+        answers,
+    }
+}
+
+/// Ranks the indices `0..n` by answering each question from `answers`,
+/// skipping those the `known` `(better, worse)` pairs decide.
+pub(crate) fn replay(
+    n: usize,
+    known: &[(usize, usize)],
+    answers: Vec<Answer>,
+) -> Result<Replayed, ReplayError> {
+    let given = answers.len();
+    let Resumed {
+        mut stepper,
+        step,
+        answers,
+    } = resume(n, known, answers);
+    if step != Step::Done {
+        return Err(ReplayError::OutOfAnswers(given));
+    }
+    let unused = given - answers.len();
+    Ok(Replayed {
+        run: Run {
+            order: stepper.take_order(),
+            answers,
+            durations_ms: Vec::new(),
+            answered_at_ms: Vec::new(),
+        },
+        unused,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rankfast::{Ranking, Step};
+
+    use rankfast_session::{Answer, Mode, Sides, hash};
+
+    use super::{ReplayError, parse_answers, parse_recorded, replay, resume};
+
+    fn order(n: usize, tokens: &str) -> Option<Vec<usize>> {
+        replay(n, &[], parse_answers(tokens).unwrap())
+            .unwrap()
+            .run
+            .order
+            .map(Ranking::into_vec)
+    }
+
+    #[test]
+    fn replays_answers_into_a_ranking() {
+        // Two items: one question.
+        assert_eq!(order(2, "b"), Some(vec![1, 0]));
+        assert_eq!(order(2, "t"), Some(vec![0, 1]));
+        assert_eq!(order(1, ""), Some(vec![0]));
+        let tied = replay(2, &[], parse_answers("t").unwrap())
+            .unwrap()
+            .run
+            .order;
+        assert_eq!(tied.unwrap().groups().count(), 1);
+        let replayed = replay(2, &[], parse_answers("b\n a s").unwrap()).unwrap();
+        assert_eq!(replayed.run.answers.len(), 1);
+        assert_eq!(replayed.unused, 2);
+    }
+
+    #[test]
+    fn rejects_bad_or_missing_answers() {
+        assert_eq!(parse_answers("a x"), Err(ReplayError::BadToken(2, 'x')));
+        let answers = parse_answers("ab").unwrap();
+        assert!(matches!(
+            replay(4, &[], answers),
+            Err(ReplayError::OutOfAnswers(2))
+        ));
+    }
+
+    #[test]
+    fn resumes_at_the_first_unanswered_question() {
+        let partway = resume(4, &[], parse_answers("ab").unwrap());
+        assert_eq!(partway.answers.len(), 2);
+        assert_eq!(partway.stepper.comparisons_made(), 2);
+        assert!(matches!(partway.step, Step::Compare { .. }));
+        let done = resume(2, &[], parse_answers("bat").unwrap());
+        assert_eq!(done.answers, vec![Answer::B]);
+        assert_eq!(done.step, Step::Done);
+        // A known pair answers the only question itself.
+        assert!(
+            resume(2, &[(1, 0)], parse_answers("a").unwrap())
+                .answers
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn reads_links_and_bare_tokens() {
+        let items = ["x", "y"].map(String::from).to_vec();
+        let fragment = hash::build(&items, &[Answer::B], Mode::Exact, Sides::Fixed, None);
+        for text in [
+            format!("https://example.org/#{fragment}"),
+            format!("#{fragment}"),
+            fragment,
+        ] {
+            let recorded = parse_recorded(&text).unwrap();
+            assert_eq!(recorded.items.as_ref(), Some(&items));
+            assert_eq!(recorded.answers, vec![Answer::B]);
+        }
+        let bare = parse_recorded(" a b\n").unwrap();
+        assert_eq!(bare.items, None);
+        assert_eq!(bare.answers, vec![Answer::A, Answer::B]);
+
+        let approximate = hash::build(&items, &[], Mode::Approximate, Sides::Fixed, None);
+        let refused = parse_recorded(&format!("#{approximate}")).err().unwrap();
+        assert_eq!(refused, ReplayError::NotExact(Mode::Approximate));
+        assert_eq!(
+            refused.to_string(),
+            "the link was ranked with the approximate engine; only exact links can be replayed"
+        );
+        assert!(matches!(
+            parse_recorded("#v2:!!"),
+            Err(ReplayError::Link(_))
+        ));
+    }
+}
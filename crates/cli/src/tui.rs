@@ -0,0 +1,231 @@
+//! Full-screen ranking with a live view of the partial result.
+//!
+//! Answers are kept as a plain list and replayed through a fresh
+//! [`Stepper`] whenever it changes, so undo is just popping the last one.
+//! The partial ranking is fitted from the answers so far with
+//! [`rank_from_outcomes`]; it settles into the exact order as the run ends.
+
+use std::io;
+use std::time::Instant;
+
+use rankfast::ratings::rank_from_outcomes;
+use rankfast::{Step, Stepper};
+use rankfast_session::Answer;
+use ratatui::DefaultTerminal;
+use ratatui::Frame;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Gauge, List, ListItem, Paragraph};
+
+use crate::replay::{Resumed, resume};
+use crate::{Run, elapsed_ms, give, now_ms};
+
+/// A run in progress: the answers given and the stepper they lead to.
+struct Session {
+    n: usize,
+    /// `(better, worse)` pairs known before the run.
+    known: Vec<(usize, usize)>,
+    answers: Vec<Answer>,
+    stepper: Stepper,
+    step: Step,
+}
+
+impl Session {
+    /// The run after the saved `answers`.
+    fn new(n: usize, known: Vec<(usize, usize)>, answers: Vec<Answer>) -> Self {
+        let Resumed {
+            stepper,
+            step,
+            answers,
+        } = resume(n, &known, answers);
+        Self {
+            n,
+            known,
+            answers,
+            stepper,
+            step,
+        }
+    }
+
+    fn answer(&mut self, answer: Answer) {
+        if let Step::Compare { a, b, .. } = self.step {
+            self.answers.push(answer);
+            self.step = give(&mut self.stepper, answer, a, b);
+        }
+    }
+
+    fn undo(&mut self) {
+        if self.answers.pop().is_none() {
+            return;
+        }
+        let answers = std::mem::take(&mut self.answers);
+        *self = Self::new(self.n, std::mem::take(&mut self.known), answers);
+    }
+
+    /// Items best first, as far as the answers so far can tell.
+    fn partial_order(&self) -> Vec<usize> {
+        let answered = self
+            .stepper
+            .history()
+            .iter()
+            .map(|&(a, b, a_better)| if a_better { (a, b) } else { (b, a) });
+        let outcomes: Vec<(usize, usize)> = self.known.iter().copied().chain(answered).collect();
+        rank_from_outcomes(self.n, &outcomes)
+            .into_iter()
+            .map(|rated| rated.item)
+            .collect()
+    }
+}
+
+/// Ranks `items` in the terminal, starting from the `known` pairs and the
+/// given `answers`, until done or the user quits. Each pair is shown under
+/// `question`.
+pub(crate) fn run(
+    items: &[String],
+    known: &[(usize, usize)],
+    answers: Vec<Answer>,
+    question: &str,
+) -> io::Result<Run> {
+    let session = Session::new(items.len(), known.to_vec(), answers);
+    let mut terminal = ratatui::init();
+    let result = run_session(&mut terminal, session, items, question);
+    ratatui::restore();
+    result
+}
+
+fn run_session(
+    terminal: &mut DefaultTerminal,
+    mut session: Session,
+    items: &[String],
+    question: &str,
+) -> io::Result<Run> {
+    // Times of the answers given here, the last ones of the session.
+    let mut durations_ms = Vec::new();
+    let mut answered_at_ms = Vec::new();
+    let mut asked = Instant::now();
+    loop {
+        if session.step == Step::Done {
+            return Ok(Run {
+                order: session.stepper.take_order(),
+                answers: session.answers,
+                durations_ms,
+                answered_at_ms,
+            });
+        }
+        terminal.draw(|frame| draw(frame, &session, items, question))?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Char('a' | 'A') | KeyCode::Left => {
+                durations_ms.push(elapsed_ms(asked));
+                answered_at_ms.push(now_ms());
+                session.answer(Answer::A);
+                asked = Instant::now();
+            }
+            KeyCode::Char('b' | 'B') | KeyCode::Right => {
+                durations_ms.push(elapsed_ms(asked));
+                answered_at_ms.push(now_ms());
+                session.answer(Answer::B);
+                asked = Instant::now();
+            }
+            KeyCode::Char('u' | 'U') | KeyCode::Backspace => {
+                durations_ms.pop();
+                answered_at_ms.pop();
+                session.undo();
+                asked = Instant::now();
+            }
+            KeyCode::Char('q' | 'Q') | KeyCode::Esc => {
+                return Ok(Run {
+                    order: None,
+                    answers: session.answers,
+                    durations_ms,
+                    answered_at_ms,
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+fn draw(frame: &mut Frame, session: &Session, items: &[String], question: &str) {
+    let [progress, pair, ranking, keys] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Length(3),
+        Constraint::Min(3),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    let done = session.stepper.comparisons_made();
+    let total = done + session.stepper.remaining_estimate();
+    let percent = (100 * done).checked_div(total).unwrap_or(100);
+    frame.render_widget(
+        Gauge::default()
+            .block(Block::bordered().title(" Progress "))
+            .percent(u16::try_from(percent.min(100)).unwrap_or(100))
+            .label(format!("{done} / {total}")),
+        progress,
+    );
+
+    if let Step::Compare { a, b, .. } = session.step {
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        let line = Line::from(vec![
+            Span::raw("[a] "),
+            Span::styled(items[a].as_str(), bold),
+            Span::raw("   vs   "),
+            Span::raw("[b] "),
+            Span::styled(items[b].as_str(), bold),
+        ]);
+        frame.render_widget(
+            Paragraph::new(line).block(Block::bordered().title(format!(" {question} "))),
+            pair,
+        );
+    }
+
+    let rows: Vec<ListItem> = session
+        .partial_order()
+        .into_iter()
+        .enumerate()
+        .map(|(place, item)| ListItem::new(format!("{:>3}. {}", place + 1, items[item])))
+        .collect();
+    frame.render_widget(
+        List::new(rows).block(Block::bordered().title(" Ranking so far ")),
+        ranking,
+    );
+
+    frame.render_widget(
+        Paragraph::new("a: A is better   b: B is better   u: undo   q: quit"),
+        keys,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Session;
+    use rankfast::Step;
+    use rankfast_session::Answer;
+
+    #[test]
+    fn undo_returns_to_the_previous_question() {
+        let mut session = Session::new(4, Vec::new(), Vec::new());
+        let first = session.step;
+        session.answer(Answer::A);
+        let second = session.step;
+        session.answer(Answer::B);
+        session.undo();
+        assert_eq!(session.step, second);
+        session.undo();
+        assert_eq!(session.step, first);
+        assert_eq!(session.stepper.comparisons_made(), 0);
+        session.undo();
+        assert_eq!(session.step, first);
+        assert!(matches!(first, Step::Compare { .. }));
+    }
+}
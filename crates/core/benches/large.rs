@@ -0,0 +1,69 @@
+//! Allocations and wall time of `rank_items` over a million items, where
+//! the insertion phase's buffers matter. Run it with and without
+//! `--features large` to compare the arena with per-level buffers.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use rankfast::rank_items;
+
+/// The system allocator, counting allocations and reallocations.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+// SAFETY: every call is forwarded to the system allocator unchanged.
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        // SAFETY: the caller upholds `GlobalAlloc::alloc`'s contract.
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // SAFETY: `ptr` was allocated by `System` with `layout`.
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        // SAFETY: `ptr` was allocated by `System` with `layout`.
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// A fixed pseudo-random permutation of `0..n` (n is kept below the prime).
+fn shuffled(n: usize) -> Vec<usize> {
+    const PRIME: usize = 1_000_003;
+    (0..n).map(|i| i * 7919 % PRIME).collect()
+}
+
+fn bench_large(c: &mut Criterion) {
+    let items = shuffled(1_000_000);
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let _ = rank_items(items.clone(), |a, b| a < b);
+    let arena = if cfg!(feature = "large") {
+        "arena"
+    } else {
+        "per-level buffers"
+    };
+    println!(
+        "rank_items over 1M items, {arena}: {} allocations",
+        ALLOCATIONS.load(Ordering::Relaxed) - before - 1
+    );
+
+    let mut group = c.benchmark_group("large");
+    group.sample_size(10);
+    group.bench_function(arena, |b| {
+        b.iter(|| rank_items(black_box(items.clone()), |a, b| a < b));
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_large);
+criterion_main!(benches);
@@ -0,0 +1,28 @@
+//! Wall time of `rank_items` with a cheap comparator, where the chain
+//! bookkeeping rather than the comparisons dominates.
+
+use std::hint::black_box;
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use rankfast::rank_items;
+
+/// A fixed pseudo-random permutation of `0..n` (n is kept below the prime).
+fn shuffled(n: usize) -> Vec<usize> {
+    const PRIME: usize = 1_000_003;
+    (0..n).map(|i| i * 7919 % PRIME).collect()
+}
+
+fn bench_rank_items(c: &mut Criterion) {
+    let mut group = c.benchmark_group("rank_items");
+    group.sample_size(10);
+    for n in [1_000, 10_000, 100_000, 1_000_000] {
+        let items = shuffled(n);
+        group.bench_with_input(BenchmarkId::from_parameter(n), &items, |b, items| {
+            b.iter(|| rank_items(black_box(items.clone()), |a, b| a < b));
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_rank_items);
+criterion_main!(benches);
@@ -0,0 +1,242 @@
+//! Approximate ranking that picks the most informative question next.
+//!
+//! Exact sorting of 100+ items needs hundreds of answers. [`ActiveStepper`]
+//! instead stops after a fixed budget, choosing each question so it is
+//! likely to change the fitted [`ratings`](crate::ratings) the most: pairs
+//! that are close in the current order and have rarely met each other.
+
+use std::collections::HashMap;
+
+use crate::ratings::{Rated, bradley_terry_from, rank_by_scores, win_probability};
+use crate::{Phase, Ranking, Step};
+
+/// Step-by-step approximate ranker over the indices `0..n`, with the same
+/// `step`/`answer` protocol as [`Stepper`](crate::Stepper).
+pub struct ActiveStepper {
+    budget: usize,
+    /// Outcomes known before the first question, at the start of `outcomes`.
+    prior: usize,
+    scores: Vec<f64>,
+    outcomes: Vec<(usize, usize)>,
+    games: Vec<usize>,
+    pair_games: HashMap<(usize, usize), usize>,
+    pending: Option<(usize, usize)>,
+}
+
+impl ActiveStepper {
+    /// Creates a ranker that asks at most `budget` questions.
+    #[must_use]
+    pub fn new(n: usize, budget: usize) -> Self {
+        Self {
+            budget,
+            prior: 0,
+            scores: vec![0.0; n],
+            outcomes: Vec::new(),
+            games: vec![0; n],
+            pair_games: HashMap::new(),
+            pending: None,
+        }
+    }
+
+    /// Creates a ranker with [`default_budget`] questions.
+    #[must_use]
+    pub fn with_default_budget(n: usize) -> Self {
+        Self::new(n, default_budget(n))
+    }
+
+    /// Creates a ranker that starts from `outcomes` already known as
+    /// `(winner, loser)`, such as judgments imported from an earlier run.
+    /// They count towards `budget`, so only the rest of it is asked, but not
+    /// towards [`comparisons_made`](Self::comparisons_made).
+    ///
+    /// # Panics
+    ///
+    /// Panics if an outcome refers to an item outside `0..n`.
+    #[must_use]
+    pub fn with_prior_outcomes(n: usize, budget: usize, outcomes: &[(usize, usize)]) -> Self {
+        let mut stepper = Self::new(n, budget);
+        for &(winner, loser) in outcomes {
+            assert!(
+                winner < n && loser < n,
+                "outcome ({winner}, {loser}) out of range"
+            );
+            stepper.record(winner, loser);
+        }
+        stepper.prior = outcomes.len();
+        if !outcomes.is_empty() {
+            stepper.scores = bradley_terry_from(&stepper.outcomes, stepper.scores);
+        }
+        stepper
+    }
+
+    /// Returns the next question, or [`Step::Done`] once the budget is spent.
+    pub fn step(&mut self) -> Step {
+        if let Some((a, b)) = self.pending {
+            return Step::compare(a, b, Phase::Adaptive, 0);
+        }
+        if self.scores.len() < 2 || self.outcomes.len() >= self.budget {
+            return Step::Done;
+        }
+
+        let (a, b) = self.select_pair();
+        self.pending = Some((a, b));
+        Step::compare(a, b, Phase::Adaptive, 0)
+    }
+
+    /// Applies the answer to the pending question and returns the next step.
+    pub fn answer(&mut self, better_is_a: bool) -> Step {
+        let Some((a, b)) = self.pending.take() else {
+            return self.step();
+        };
+
+        let (winner, loser) = if better_is_a { (a, b) } else { (b, a) };
+        self.record(winner, loser);
+        self.scores = bradley_terry_from(&self.outcomes, std::mem::take(&mut self.scores));
+        self.step()
+    }
+
+    /// Current best-guess ranking with per-step confidence.
+    #[must_use]
+    pub fn ranking(&self) -> Vec<Rated> {
+        rank_by_scores(&self.scores)
+    }
+
+    /// Current best-guess order of indices, best first.
+    #[must_use]
+    pub fn order(&self) -> Ranking<usize> {
+        Ranking::new(self.ranking().into_iter().map(|r| r.item).collect())
+    }
+
+    /// Number of answers applied so far, not counting prior outcomes.
+    #[must_use]
+    pub fn comparisons_made(&self) -> usize {
+        self.outcomes.len() - self.prior
+    }
+
+    /// Questions left in the budget, including the pending one.
+    #[must_use]
+    pub fn remaining_estimate(&self) -> usize {
+        if self.scores.len() < 2 {
+            return 0;
+        }
+        self.budget.saturating_sub(self.outcomes.len())
+    }
+
+    /// Every answer so far as `(winner, loser)`, after any prior outcomes.
+    #[must_use]
+    pub fn outcomes(&self) -> &[(usize, usize)] {
+        &self.outcomes
+    }
+
+    fn record(&mut self, winner: usize, loser: usize) {
+        self.outcomes.push((winner, loser));
+        self.games[winner] += 1;
+        self.games[loser] += 1;
+        *self
+            .pair_games
+            .entry((winner.min(loser), winner.max(loser)))
+            .or_default() += 1;
+    }
+
+    fn select_pair(&self) -> (usize, usize) {
+        // Warm-up: make sure every item has been seen at least once before
+        // trusting the scores.
+        let mut unseen = (0..self.games.len()).filter(|&i| self.games[i] == 0);
+        if let Some(first) = unseen.next() {
+            let second = unseen.next().unwrap_or_else(|| self.closest_to(first));
+            return (first, second);
+        }
+
+        // Otherwise pick the adjacent pair in the current order whose outcome
+        // is most uncertain, discounted by how often they already met.
+        let order = self.order();
+        order
+            .windows(2)
+            .map(|w| (w[0], w[1]))
+            .max_by(|&x, &y| self.information(x).total_cmp(&self.information(y)))
+            .expect("at least two items")
+    }
+
+    fn information(&self, (a, b): (usize, usize)) -> f64 {
+        let p = win_probability(self.scores[a], self.scores[b]);
+        let met = self.pair_games.get(&(a.min(b), a.max(b))).copied();
+        let met = u32::try_from(met.unwrap_or(0)).unwrap_or(u32::MAX);
+        p * (1.0 - p) / (1.0 + f64::from(met))
+    }
+
+    fn closest_to(&self, item: usize) -> usize {
+        (0..self.scores.len())
+            .filter(|&j| j != item)
+            .min_by(|&x, &y| {
+                let dx = (self.scores[x] - self.scores[item]).abs();
+                let dy = (self.scores[y] - self.scores[item]).abs();
+                dx.total_cmp(&dy)
+            })
+            .expect("at least two items")
+    }
+}
+
+/// Suggested question budget for `n` items: two questions per item, well
+/// under the roughly `n log2 n` an exact sort needs once `n` grows past a
+/// few dozen.
+#[must_use]
+pub fn default_budget(n: usize) -> usize {
+    if n < 2 { 0 } else { 2 * n }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ActiveStepper;
+    use crate::Step;
+
+    fn run(stepper: &mut ActiveStepper, values: &[usize]) -> Vec<usize> {
+        let mut step = stepper.step();
+        while let Step::Compare { a, b, .. } = step {
+            step = stepper.answer(values[a] < values[b]);
+        }
+        stepper.order().into_vec()
+    }
+
+    #[test]
+    fn stops_at_budget() {
+        let values: Vec<usize> = (0..30).rev().collect();
+        let mut stepper = ActiveStepper::with_default_budget(values.len());
+        assert_eq!(stepper.remaining_estimate(), 60);
+        let _ = run(&mut stepper, &values);
+        assert_eq!(stepper.comparisons_made(), 60);
+        assert_eq!(stepper.remaining_estimate(), 0);
+    }
+
+    #[test]
+    fn recovers_order_with_enough_budget() {
+        let values = [3, 7, 0, 5, 1, 6, 2, 4];
+        let mut stepper = ActiveStepper::new(values.len(), 60);
+        let order = run(&mut stepper, &values);
+        let ranked: Vec<usize> = order.iter().map(|&i| values[i]).collect();
+        assert_eq!(ranked, (0..8).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn prior_outcomes_use_up_budget() {
+        let values = [3, 7, 0, 5, 1, 6, 2, 4];
+        // Winners have the lower value.
+        let prior = [(2, 4), (4, 6), (6, 0), (0, 7), (7, 3), (3, 5), (5, 1)];
+        let mut stepper = ActiveStepper::with_prior_outcomes(values.len(), 16, &prior);
+        assert_eq!(stepper.comparisons_made(), 0);
+        assert_eq!(stepper.remaining_estimate(), 9);
+        let order = run(&mut stepper, &values);
+        assert_eq!(stepper.comparisons_made(), 9);
+        let ranked: Vec<usize> = order.iter().map(|&i| values[i]).collect();
+        assert_eq!(ranked, (0..8).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn never_asks_an_item_against_itself() {
+        let mut stepper = ActiveStepper::new(5, 20);
+        let mut step = stepper.step();
+        while let Step::Compare { a, b, .. } = step {
+            assert_ne!(a, b);
+            step = stepper.answer(a < b);
+        }
+    }
+}
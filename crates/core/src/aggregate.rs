@@ -0,0 +1,213 @@
+//! Consensus ranking from several raters.
+//!
+//! Each input ranking lists item indices in `0..n`, best first. Rankings may
+//! be partial: items missing from a ranking carry no information about their
+//! relation to the others in that ranking, so only pairs where both items
+//! appear contribute to the pairwise tallies.
+
+/// Rankings with at most this many items are solved exactly by
+/// [`kemeny_young`]; larger ones fall back to local search.
+pub const KEMENY_EXACT_LIMIT: usize = 12;
+
+/// Borda count: an item at position `p` in a ranking of length `m` scores
+/// `m - 1 - p` points. Unlisted items score nothing for that ranking.
+///
+/// Ties are broken by item index.
+#[must_use]
+pub fn borda<R: AsRef<[usize]>>(n: usize, rankings: &[R]) -> Vec<usize> {
+    let mut scores = vec![0usize; n];
+    for ranking in rankings {
+        let ranking = ranking.as_ref();
+        for (pos, &item) in ranking.iter().enumerate() {
+            scores[item] += ranking.len() - 1 - pos;
+        }
+    }
+    order_by_score(&scores)
+}
+
+/// Copeland's method: each item scores one point per opponent it beats in a
+/// head-to-head majority and loses one per opponent that beats it.
+///
+/// Ties are broken by item index.
+#[must_use]
+pub fn copeland<R: AsRef<[usize]>>(n: usize, rankings: &[R]) -> Vec<usize> {
+    let wins = pairwise_wins(n, rankings);
+    let scores: Vec<isize> = (0..n)
+        .map(|i| {
+            (0..n)
+                .map(|j| match wins[i][j].cmp(&wins[j][i]) {
+                    std::cmp::Ordering::Greater => 1,
+                    std::cmp::Ordering::Less => -1,
+                    std::cmp::Ordering::Equal => 0,
+                })
+                .sum()
+        })
+        .collect();
+    order_by_score(&scores)
+}
+
+/// Kemeny-Young: the ordering that agrees with the largest number of
+/// pairwise preferences across all rankings.
+///
+/// Exact for `n <= KEMENY_EXACT_LIMIT` (dynamic programming over subsets).
+/// Beyond that, a Copeland ordering is refined by moving single items while
+/// the agreement improves, which finds a local optimum only.
+#[must_use]
+pub fn kemeny_young<R: AsRef<[usize]>>(n: usize, rankings: &[R]) -> Vec<usize> {
+    let wins = pairwise_wins(n, rankings);
+    if n <= KEMENY_EXACT_LIMIT {
+        kemeny_exact(&wins)
+    } else {
+        kemeny_local_search(copeland(n, rankings), &wins)
+    }
+}
+
+/// Total number of pairwise preferences in `rankings` that `order` agrees
+/// with. This is the quantity [`kemeny_young`] maximizes. To see how close
+/// a consensus is to each rater, see [`metrics`](crate::metrics).
+#[must_use]
+pub fn agreement<R: AsRef<[usize]>>(order: &[usize], rankings: &[R]) -> usize {
+    let n = order.iter().copied().max().map_or(0, |m| m + 1);
+    let wins = pairwise_wins(n, rankings);
+    let mut total = 0;
+    for (pos, &i) in order.iter().enumerate() {
+        for &j in &order[pos + 1..] {
+            total += wins[i][j];
+        }
+    }
+    total
+}
+
+/// `wins[i][j]` counts rankings placing `i` above `j`.
+fn pairwise_wins<R: AsRef<[usize]>>(n: usize, rankings: &[R]) -> Vec<Vec<usize>> {
+    let mut wins = vec![vec![0usize; n]; n];
+    for ranking in rankings {
+        let ranking = ranking.as_ref();
+        for (pos, &i) in ranking.iter().enumerate() {
+            for &j in &ranking[pos + 1..] {
+                wins[i][j] += 1;
+            }
+        }
+    }
+    wins
+}
+
+fn order_by_score<S: Ord + Copy>(scores: &[S]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..scores.len()).collect();
+    order.sort_by(|&a, &b| scores[b].cmp(&scores[a]));
+    order
+}
+
+fn kemeny_exact(wins: &[Vec<usize>]) -> Vec<usize> {
+    let n = wins.len();
+    let full = (1usize << n) - 1;
+
+    // best[set] = highest agreement achievable by placing exactly `set`
+    // first; last[set] = the item placed last within that prefix.
+    let mut best = vec![0usize; full + 1];
+    let mut last = vec![usize::MAX; full + 1];
+    for set in 1..=full {
+        for j in (0..n).filter(|&j| set & (1 << j) != 0) {
+            let rest = set & !(1 << j);
+            let gain: usize = (0..n)
+                .filter(|&i| rest & (1 << i) != 0)
+                .map(|i| wins[i][j])
+                .sum();
+            let score = best[rest] + gain;
+            if last[set] == usize::MAX || score > best[set] {
+                best[set] = score;
+                last[set] = j;
+            }
+        }
+    }
+
+    let mut order = Vec::with_capacity(n);
+    let mut set = full;
+    while set != 0 {
+        let j = last[set];
+        order.push(j);
+        set &= !(1 << j);
+    }
+    order.reverse();
+    order
+}
+
+fn kemeny_local_search(mut order: Vec<usize>, wins: &[Vec<usize>]) -> Vec<usize> {
+    let n = order.len();
+    loop {
+        let mut improved = false;
+        for from in 0..n {
+            let item = order[from];
+            // Gain of moving `item` to each other position, computed
+            // incrementally as it passes over its neighbours.
+            let (mut best_gain, mut best_to) = (0isize, from);
+            let mut gain = 0isize;
+            for to in (0..from).rev() {
+                let other = order[to];
+                gain += signed(wins[item][other]) - signed(wins[other][item]);
+                if gain > best_gain {
+                    (best_gain, best_to) = (gain, to);
+                }
+            }
+            gain = 0;
+            for (to, &other) in order.iter().enumerate().skip(from + 1) {
+                gain += signed(wins[other][item]) - signed(wins[item][other]);
+                if gain > best_gain {
+                    (best_gain, best_to) = (gain, to);
+                }
+            }
+            if best_to != from {
+                let item = order.remove(from);
+                order.insert(best_to, item);
+                improved = true;
+            }
+        }
+        if !improved {
+            return order;
+        }
+    }
+}
+
+fn signed(value: usize) -> isize {
+    isize::try_from(value).unwrap_or(isize::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{agreement, borda, copeland, kemeny_young};
+
+    const RANKINGS: [&[usize]; 3] = [&[0, 1, 2, 3], &[1, 0, 2, 3], &[0, 2, 1, 3]];
+
+    #[test]
+    fn unanimous_rankings_are_preserved() {
+        let rankings = [vec![2, 0, 1], vec![2, 0, 1]];
+        assert_eq!(borda(3, &rankings), vec![2, 0, 1]);
+        assert_eq!(copeland(3, &rankings), vec![2, 0, 1]);
+        assert_eq!(kemeny_young(3, &rankings), vec![2, 0, 1]);
+    }
+
+    #[test]
+    fn methods_agree_on_clear_majority() {
+        assert_eq!(borda(4, &RANKINGS), vec![0, 1, 2, 3]);
+        assert_eq!(copeland(4, &RANKINGS), vec![0, 1, 2, 3]);
+        assert_eq!(kemeny_young(4, &RANKINGS), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn partial_rankings_only_count_listed_pairs() {
+        let rankings: [&[usize]; 2] = [&[2, 0], &[0, 1]];
+        assert_eq!(kemeny_young(3, &rankings), vec![2, 0, 1]);
+    }
+
+    #[test]
+    fn local_search_matches_exact_on_consistent_input() {
+        let n = 20;
+        let forward: Vec<usize> = (0..n).collect();
+        let mut swapped = forward.clone();
+        swapped.swap(3, 4);
+        let rankings = [forward.clone(), forward.clone(), swapped];
+        let order = kemeny_young(n, &rankings);
+        assert_eq!(order, forward);
+        assert_eq!(agreement(&order, &rankings), 3 * n * (n - 1) / 2 - 1);
+    }
+}
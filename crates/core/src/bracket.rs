@@ -0,0 +1,363 @@
+//! Bracket-style ranking engines.
+//!
+//! Both engines play in rounds where every item meets at most one opponent,
+//! which maps directly onto a bracket drawing. They use the same
+//! `step`/`answer` protocol as [`Stepper`](crate::Stepper).
+//!
+//! - [`Elimination`] is single elimination with consolation brackets: the
+//!   winners and losers of each round keep playing among themselves until
+//!   every place is decided.
+//! - [`Swiss`] plays a fixed number of rounds, pairing items with equal
+//!   scores that have not met yet, and ranks by score.
+
+use crate::{Phase, Ranking, Step};
+
+/// A match in a bracket: `(a, b, result)` where `result` is `Some(true)`
+/// when `a` won and `None` while unplayed.
+pub type Match = (usize, usize, Option<bool>);
+
+/// Single elimination with consolation brackets for every place.
+///
+/// Each round splits every placement group into its winners (placed above)
+/// and losers (placed below); an odd item out gets a bye into the winners.
+/// Every item plays about `log2 n` matches. The result is a full ranking but
+/// not a guaranteed sort: an item knocked into a lower group never gets to
+/// face the groups above it.
+pub struct Elimination {
+    groups: Vec<Vec<usize>>,
+    rounds: Vec<Vec<Match>>,
+    next: usize,
+    comparisons: usize,
+}
+
+impl Elimination {
+    /// Creates a bracket over `0..n`, seeded in index order.
+    #[must_use]
+    pub fn new(n: usize) -> Self {
+        let mut bracket = Self {
+            groups: vec![(0..n).collect()],
+            rounds: Vec::new(),
+            next: 0,
+            comparisons: 0,
+        };
+        bracket.start_round();
+        bracket
+    }
+
+    /// Returns the next match, or [`Step::Done`] once every place is decided.
+    #[must_use]
+    pub fn step(&self) -> Step {
+        match self.rounds.last().and_then(|round| round.get(self.next)) {
+            Some(&(a, b, _)) => Step::compare(a, b, Phase::Pairing, self.rounds.len() - 1),
+            None => Step::Done,
+        }
+    }
+
+    /// Records the result of the current match and returns the next step.
+    pub fn answer(&mut self, better_is_a: bool) -> Step {
+        let Some(round) = self.rounds.last_mut() else {
+            return Step::Done;
+        };
+        let Some(m) = round.get_mut(self.next) else {
+            return Step::Done;
+        };
+        m.2 = Some(better_is_a);
+        self.comparisons += 1;
+        self.next += 1;
+        if self.next == round.len() {
+            self.finish_round();
+            self.start_round();
+        }
+        self.step()
+    }
+
+    /// Number of matches played so far.
+    #[must_use]
+    pub fn comparisons_made(&self) -> usize {
+        self.comparisons
+    }
+
+    /// Every round played or in progress, for drawing the bracket.
+    #[must_use]
+    pub fn rounds(&self) -> &[Vec<Match>] {
+        &self.rounds
+    }
+
+    /// Current placement, best first. Items still sharing a group tie, and
+    /// are listed in seed order.
+    #[must_use]
+    pub fn order(&self) -> Ranking<usize> {
+        Ranking::from_groups(self.groups.iter().cloned())
+    }
+
+    fn start_round(&mut self) {
+        let round: Vec<Match> = self
+            .groups
+            .iter()
+            .flat_map(|group| group.chunks_exact(2).map(|pair| (pair[0], pair[1], None)))
+            .collect();
+        if !round.is_empty() {
+            self.rounds.push(round);
+            self.next = 0;
+        }
+    }
+
+    fn finish_round(&mut self) {
+        let round = self.rounds.last().expect("round in progress");
+        let mut results = round.iter();
+        let mut groups = Vec::with_capacity(self.groups.len() * 2);
+        for group in &self.groups {
+            if group.len() < 2 {
+                groups.push(group.clone());
+                continue;
+            }
+            let mut winners = Vec::with_capacity(group.len().div_ceil(2));
+            let mut losers = Vec::with_capacity(group.len() / 2);
+            for _ in 0..group.len() / 2 {
+                let &(a, b, result) = results.next().expect("one match per pair");
+                let (w, l) = if result == Some(true) { (a, b) } else { (b, a) };
+                winners.push(w);
+                losers.push(l);
+            }
+            if group.len() % 2 == 1 {
+                winners.push(group[group.len() - 1]);
+            }
+            groups.push(winners);
+            groups.push(losers);
+        }
+        self.groups = groups;
+    }
+}
+
+/// Swiss-system ranking over a fixed number of rounds.
+///
+/// Each round pairs items in score order with the closest opponent they have
+/// not met yet; an odd item out gets a bye worth one win. Final standings
+/// order by score, then by the summed scores of opponents (Buchholz), then
+/// by index.
+pub struct Swiss {
+    scores: Vec<usize>,
+    opponents: Vec<Vec<usize>>,
+    byes: Vec<bool>,
+    rounds: Vec<Vec<Match>>,
+    total_rounds: usize,
+    next: usize,
+    comparisons: usize,
+}
+
+impl Swiss {
+    /// Creates a Swiss tournament over `0..n` with [`default_rounds`] rounds.
+    #[must_use]
+    pub fn new(n: usize) -> Self {
+        Self::with_rounds(n, default_rounds(n))
+    }
+
+    /// Creates a Swiss tournament over `0..n` with `rounds` rounds.
+    #[must_use]
+    pub fn with_rounds(n: usize, rounds: usize) -> Self {
+        let mut swiss = Self {
+            scores: vec![0; n],
+            opponents: vec![Vec::new(); n],
+            byes: vec![false; n],
+            rounds: Vec::new(),
+            total_rounds: rounds,
+            next: 0,
+            comparisons: 0,
+        };
+        swiss.start_round();
+        swiss
+    }
+
+    /// Returns the next match, or [`Step::Done`] after the last round.
+    #[must_use]
+    pub fn step(&self) -> Step {
+        match self.rounds.last().and_then(|round| round.get(self.next)) {
+            Some(&(a, b, _)) => Step::compare(a, b, Phase::Pairing, self.rounds.len() - 1),
+            None => Step::Done,
+        }
+    }
+
+    /// Records the result of the current match and returns the next step.
+    pub fn answer(&mut self, better_is_a: bool) -> Step {
+        let Some(round) = self.rounds.last_mut() else {
+            return Step::Done;
+        };
+        let Some(m) = round.get_mut(self.next) else {
+            return Step::Done;
+        };
+        m.2 = Some(better_is_a);
+        let (a, b, _) = *m;
+        self.scores[if better_is_a { a } else { b }] += 1;
+        self.comparisons += 1;
+        self.next += 1;
+        if self.next == round.len() {
+            self.start_round();
+        }
+        self.step()
+    }
+
+    /// Number of matches played so far.
+    #[must_use]
+    pub fn comparisons_made(&self) -> usize {
+        self.comparisons
+    }
+
+    /// Every round played or in progress.
+    #[must_use]
+    pub fn rounds(&self) -> &[Vec<Match>] {
+        &self.rounds
+    }
+
+    /// Score (wins plus byes) of each item, indexed by item.
+    #[must_use]
+    pub fn scores(&self) -> &[usize] {
+        &self.scores
+    }
+
+    /// Current standings, best first. Items level on score and Buchholz
+    /// tie.
+    #[must_use]
+    pub fn order(&self) -> Ranking<usize> {
+        let buchholz: Vec<usize> = self
+            .opponents
+            .iter()
+            .map(|opps| opps.iter().map(|&o| self.scores[o]).sum())
+            .collect();
+        let mut order: Vec<usize> = (0..self.scores.len()).collect();
+        order.sort_by(|&x, &y| {
+            self.scores[y]
+                .cmp(&self.scores[x])
+                .then(buchholz[y].cmp(&buchholz[x]))
+        });
+        let key = |i: usize| (self.scores[order[i]], buchholz[order[i]]);
+        Ranking::from_ties(order.clone(), |i| key(i) == key(i - 1))
+    }
+
+    fn start_round(&mut self) {
+        if self.rounds.len() >= self.total_rounds || self.scores.len() < 2 {
+            self.next = self.rounds.last().map_or(0, Vec::len);
+            return;
+        }
+
+        let mut unpaired = self.order().into_vec();
+        if unpaired.len() % 2 == 1 {
+            // Lowest-ranked item without a bye sits this round out.
+            let pos = unpaired
+                .iter()
+                .rposition(|&i| !self.byes[i])
+                .unwrap_or(unpaired.len() - 1);
+            let bye = unpaired.remove(pos);
+            self.byes[bye] = true;
+            self.scores[bye] += 1;
+        }
+
+        let mut round = Vec::with_capacity(unpaired.len() / 2);
+        while !unpaired.is_empty() {
+            let a = unpaired.remove(0);
+            let pos = unpaired
+                .iter()
+                .position(|b| !self.opponents[a].contains(b))
+                .unwrap_or(0);
+            let b = unpaired.remove(pos);
+            self.opponents[a].push(b);
+            self.opponents[b].push(a);
+            round.push((a, b, None));
+        }
+        self.rounds.push(round);
+        self.next = 0;
+    }
+}
+
+/// Rounds needed for a Swiss tournament to separate a single undefeated
+/// winner: `ceil(log2 n)`.
+#[must_use]
+pub fn default_rounds(n: usize) -> usize {
+    if n < 2 {
+        0
+    } else {
+        usize::try_from(n.next_power_of_two().trailing_zeros()).unwrap_or(usize::MAX)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Elimination, Swiss, default_rounds};
+    use crate::Step;
+
+    fn play_elimination(values: &[usize]) -> (Vec<usize>, usize) {
+        let mut bracket = Elimination::new(values.len());
+        let mut step = bracket.step();
+        while let Step::Compare { a, b, .. } = step {
+            step = bracket.answer(values[a] < values[b]);
+        }
+        (bracket.order().into_vec(), bracket.comparisons_made())
+    }
+
+    #[test]
+    fn elimination_sorts_power_of_two_with_consistent_answers() {
+        let values = [6, 2, 7, 0, 5, 3, 1, 4];
+        let (order, comparisons) = play_elimination(&values);
+        let ranked: Vec<usize> = order.iter().map(|&i| values[i]).collect();
+        assert_eq!(ranked[0], 0);
+        assert_eq!(ranked[7], 7);
+        assert_eq!(comparisons, 12);
+    }
+
+    #[test]
+    fn elimination_handles_odd_counts() {
+        for n in 0..10 {
+            let values: Vec<usize> = (0..n).rev().collect();
+            let (mut order, _) = play_elimination(&values);
+            assert_eq!(order.len(), n);
+            order.sort_unstable();
+            assert_eq!(order, (0..n).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn elimination_rounds_form_a_bracket() {
+        let mut bracket = Elimination::new(4);
+        assert_eq!(bracket.rounds().len(), 1);
+        let _ = bracket.answer(true);
+        let _ = bracket.answer(false);
+        assert_eq!(
+            bracket.rounds()[0],
+            vec![(0, 1, Some(true)), (2, 3, Some(false))]
+        );
+        assert_eq!(bracket.rounds()[1], vec![(0, 3, None), (1, 2, None)]);
+    }
+
+    #[test]
+    fn swiss_never_repeats_pairings_early() {
+        let values: Vec<usize> = (0..8).collect();
+        let mut swiss = Swiss::new(values.len());
+        let mut step = swiss.step();
+        while let Step::Compare { a, b, .. } = step {
+            step = swiss.answer(values[a] < values[b]);
+        }
+        let mut pairs: Vec<(usize, usize)> = swiss
+            .rounds()
+            .iter()
+            .flatten()
+            .map(|&(a, b, _)| (a.min(b), a.max(b)))
+            .collect();
+        let total = pairs.len();
+        pairs.sort_unstable();
+        pairs.dedup();
+        assert_eq!(pairs.len(), total);
+        assert_eq!(swiss.rounds().len(), default_rounds(8));
+        assert_eq!(swiss.order()[0], 0);
+        assert_eq!(swiss.scores()[0], 3);
+    }
+
+    #[test]
+    fn swiss_gives_each_bye_once() {
+        let mut swiss = Swiss::with_rounds(5, 4);
+        let mut step = swiss.step();
+        while let Step::Compare { a, b, .. } = step {
+            step = swiss.answer(a < b);
+        }
+        assert_eq!(swiss.comparisons_made(), 8);
+        assert_eq!(swiss.scores().iter().sum::<usize>(), 12);
+    }
+}
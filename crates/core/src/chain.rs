@@ -0,0 +1,313 @@
+//! Bookkeeping for the insertion phase of Ford-Johnson.
+
+/// Elements per block before a [`Chain`] block is split in two.
+const MAX_BLOCK: usize = 2048;
+
+/// Sequence with cheap insertion at arbitrary indices.
+///
+/// A plain `Vec::insert` shifts every later element, which makes the
+/// insertion phase quadratic once comparisons are cheap. `Chain` stores
+/// elements in blocks of at most [`MAX_BLOCK`] together with each block's
+/// starting index: indexing is a binary search over block starts, and an
+/// insertion shifts one block plus the start offsets of the blocks after
+/// it, `O(sqrt n)`-ish instead of `O(n)`. Small chains are a single block
+/// and behave like a `Vec`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Chain {
+    blocks: Vec<Vec<usize>>,
+    starts: Vec<usize>,
+    len: usize,
+}
+
+impl Chain {
+    pub(crate) fn from_vec(elements: Vec<usize>) -> Self {
+        let len = elements.len();
+        if len <= MAX_BLOCK {
+            return Self {
+                blocks: vec![elements],
+                starts: vec![0],
+                len,
+            };
+        }
+        let half = MAX_BLOCK / 2;
+        let blocks: Vec<Vec<usize>> = elements.chunks(half).map(<[usize]>::to_vec).collect();
+        let starts = (0..blocks.len()).map(|b| b * half).collect();
+        Self {
+            blocks,
+            starts,
+            len,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn get(&self, index: usize) -> usize {
+        let b = self.block_of(index);
+        self.blocks[b][index - self.starts[b]]
+    }
+
+    pub(crate) fn insert(&mut self, index: usize, element: usize) {
+        let b = if index == self.len {
+            self.blocks.len() - 1
+        } else {
+            self.block_of(index)
+        };
+        self.blocks[b].insert(index - self.starts[b], element);
+        for start in &mut self.starts[b + 1..] {
+            *start += 1;
+        }
+        self.len += 1;
+
+        if self.blocks[b].len() > MAX_BLOCK {
+            #[cfg(not(feature = "large"))]
+            let tail = self.blocks[b].split_off(MAX_BLOCK / 2);
+            #[cfg(feature = "large")]
+            let tail = {
+                let mut tail = Vec::with_capacity(MAX_BLOCK + 1);
+                tail.extend(self.blocks[b].drain(MAX_BLOCK / 2..));
+                tail
+            };
+            self.blocks.insert(b + 1, tail);
+            self.starts.insert(b + 1, self.starts[b] + MAX_BLOCK / 2);
+        }
+    }
+
+    pub(crate) fn into_vec(self) -> Vec<usize> {
+        let mut blocks = self.blocks.into_iter();
+        let mut out = blocks.next().unwrap_or_default();
+        out.reserve(self.len - out.len());
+        for block in blocks {
+            out.extend(block);
+        }
+        out
+    }
+
+    /// Replaces the elements with `elements`, reusing the blocks' buffers
+    /// and giving new ones room to fill up before they split.
+    #[cfg(feature = "large")]
+    pub(crate) fn refill(&mut self, elements: &[usize]) {
+        let half = MAX_BLOCK / 2;
+        let count = elements.len().div_ceil(half).max(1);
+        self.blocks
+            .resize_with(count, || Vec::with_capacity(MAX_BLOCK + 1));
+        self.starts.clear();
+        for (b, block) in self.blocks.iter_mut().enumerate() {
+            let start = (b * half).min(elements.len());
+            let end = (start + half).min(elements.len());
+            block.clear();
+            block.extend_from_slice(&elements[start..end]);
+            self.starts.push(start);
+        }
+        self.len = elements.len();
+    }
+
+    /// Copies the elements into `out`, keeping the chain's buffers.
+    #[cfg(feature = "large")]
+    pub(crate) fn copy_into(&self, out: &mut Vec<usize>) {
+        out.clear();
+        for block in &self.blocks {
+            out.extend_from_slice(block);
+        }
+    }
+
+    fn block_of(&self, index: usize) -> usize {
+        self.starts.partition_point(|&s| s <= index) - 1
+    }
+}
+
+/// Tracks the current chain index of each main while other elements are
+/// inserted around them, in `O(log n)` per query or insertion.
+///
+/// The initial chain is `[partner_0, main_0, main_1, ..., main_{k-1}]`.
+/// Main `j` starts at index `j + 1` and moves right by one for every element
+/// inserted at or before its index. A Fenwick tree holds, for each main, one
+/// for the main itself plus the number of elements inserted between it and
+/// the previous main, so the prefix sum up to `j` is exactly main `j`'s
+/// current index.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MainPositions {
+    /// 1-indexed Fenwick tree over the per-main weights.
+    tree: Vec<usize>,
+}
+
+impl MainPositions {
+    pub(crate) fn new(num_mains: usize) -> Self {
+        // All weights start at one; a Fenwick node covers `lowbit(i)` of them.
+        let tree = (0..=num_mains).map(|i| i & i.wrapping_neg()).collect();
+        Self { tree }
+    }
+
+    /// Starts over with `num_mains` mains, in the same buffer.
+    #[cfg(feature = "large")]
+    pub(crate) fn reset(&mut self, num_mains: usize) {
+        self.tree.clear();
+        self.tree
+            .extend((0..=num_mains).map(|i| i & i.wrapping_neg()));
+    }
+
+    /// Current chain index of main `j`.
+    pub(crate) fn position(&self, j: usize) -> usize {
+        let mut i = j + 1;
+        let mut sum = 0;
+        while i > 0 {
+            sum += self.tree[i];
+            i &= i - 1;
+        }
+        sum
+    }
+
+    /// Records that an element was inserted at chain index `pos`.
+    pub(crate) fn record_insert(&mut self, pos: usize) {
+        // The first main whose index is at least `pos` gets pushed right.
+        // Elements inserted after the last main affect no one.
+        let Some(j) = self.lower_bound(pos) else {
+            return;
+        };
+        let mut i = j + 1;
+        while i < self.tree.len() {
+            self.tree[i] += 1;
+            i += i & i.wrapping_neg();
+        }
+    }
+
+    /// Smallest `j` with `position(j) >= target`.
+    fn lower_bound(&self, target: usize) -> Option<usize> {
+        let n = self.tree.len() - 1;
+        let mut idx = 0;
+        let mut remaining = target;
+        let mut step = if n == 0 { 0 } else { 1 << n.ilog2() };
+        while step > 0 {
+            let next = idx + step;
+            if next <= n && self.tree[next] < remaining {
+                idx = next;
+                remaining -= self.tree[next];
+            }
+            step >>= 1;
+        }
+        (idx < n).then_some(idx)
+    }
+}
+
+/// The chain and main positions of one level's insertions.
+///
+/// By default every level builds its own. With the `large` feature one set
+/// of buffers, sized for the whole list up front, serves every level: the
+/// chain's blocks are refilled in place and have room to fill up before
+/// they split, which saves most of the allocations of a run over millions
+/// of items.
+#[derive(Debug, Default)]
+pub(crate) struct Arena {
+    pub(crate) chain: Chain,
+    pub(crate) positions: MainPositions,
+}
+
+impl Arena {
+    /// An arena for ranking `n` elements.
+    #[cfg(feature = "large")]
+    pub(crate) fn new(n: usize) -> Self {
+        let blocks = n.div_ceil(MAX_BLOCK / 2) + 1;
+        Self {
+            chain: Chain {
+                blocks: Vec::with_capacity(blocks),
+                starts: Vec::with_capacity(blocks),
+                len: 0,
+            },
+            positions: MainPositions {
+                tree: Vec::with_capacity(n / 2 + 1),
+            },
+        }
+    }
+
+    /// An arena for ranking `n` elements.
+    #[cfg(not(feature = "large"))]
+    pub(crate) fn new(_n: usize) -> Self {
+        Self::default()
+    }
+
+    /// Starts a level whose chain is `initial`, its mains at `1..=num_mains`,
+    /// leaving `initial` empty.
+    pub(crate) fn start(&mut self, initial: &mut Vec<usize>, num_mains: usize) {
+        #[cfg(feature = "large")]
+        {
+            self.chain.refill(initial);
+            initial.clear();
+            self.positions.reset(num_mains);
+        }
+        #[cfg(not(feature = "large"))]
+        {
+            self.chain = Chain::from_vec(std::mem::take(initial));
+            self.positions = MainPositions::new(num_mains);
+        }
+    }
+
+    /// Ends a level, leaving its chain in `out`.
+    pub(crate) fn finish(&mut self, out: &mut Vec<usize>) {
+        #[cfg(feature = "large")]
+        self.chain.copy_into(out);
+        #[cfg(not(feature = "large"))]
+        {
+            *out = std::mem::take(&mut self.chain).into_vec();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Chain, MAX_BLOCK, MainPositions};
+
+    #[test]
+    fn chain_matches_vec_across_block_splits() {
+        let mut expected: Vec<usize> = (0..MAX_BLOCK + 7).collect();
+        let mut chain = Chain::from_vec(expected.clone());
+        for step in 0..3 * MAX_BLOCK {
+            let pos = (step * 7919) % (expected.len() + 1);
+            expected.insert(pos, usize::MAX - step);
+            chain.insert(pos, usize::MAX - step);
+        }
+        assert_eq!(chain.len(), expected.len());
+        for i in (0..expected.len()).step_by(97) {
+            assert_eq!(chain.get(i), expected[i]);
+        }
+        assert_eq!(chain.into_vec(), expected);
+    }
+
+    #[test]
+    fn tracks_positions_like_a_vec() {
+        // Mirror a chain of markers: `Some(j)` is main `j`, `None` anything
+        // else. Insert at a spread of positions and compare.
+        let mains = 6;
+        let mut chain: Vec<Option<usize>> = vec![None];
+        chain.extend((0..mains).map(Some));
+        let mut positions = MainPositions::new(mains);
+
+        for step in 0..40usize {
+            let pos = (step * 7 + 3) % (chain.len() + 1);
+            chain.insert(pos, None);
+            positions.record_insert(pos);
+            for j in 0..mains {
+                let expected = chain.iter().position(|&x| x == Some(j)).unwrap();
+                assert_eq!(positions.position(j), expected, "step {step}, main {j}");
+            }
+        }
+    }
+
+    #[cfg(feature = "large")]
+    #[test]
+    fn arena_reuses_its_buffers_across_levels() {
+        let mut arena = super::Arena::new(3 * MAX_BLOCK);
+        let mut out = Vec::new();
+        for len in [3, MAX_BLOCK + 5, 2, 3 * MAX_BLOCK] {
+            let mut initial: Vec<usize> = (0..len).collect();
+            arena.start(&mut initial, len - 1);
+            assert!(initial.is_empty());
+            arena.chain.insert(len, usize::MAX);
+            arena.positions.record_insert(len);
+            assert_eq!(arena.positions.position(len - 2), len - 1);
+            arena.finish(&mut out);
+            let expected: Vec<usize> = (0..len).chain([usize::MAX]).collect();
+            assert_eq!(out, expected);
+        }
+    }
+}
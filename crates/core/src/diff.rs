@@ -0,0 +1,157 @@
+//! How a ranking changed between two runs.
+//!
+//! [`diff`] matches the items of two rankings of mostly the same items, best
+//! first, and tells how far each one moved. Items in only one of them are
+//! new or removed. [`RankDiff::kendall_tau`] counts the pairs of items in
+//! both rankings whose order flipped: 0 when nothing but additions and
+//! removals changed, [`RankDiff::pairs`] when the order was reversed. The
+//! correlations in [`metrics`](crate::metrics) weigh the same two rankings
+//! in other ways.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::metrics::kendall_distance;
+
+/// Where an item went.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Movement {
+    /// Up this many places.
+    Up(usize),
+    /// Down this many places.
+    Down(usize),
+    /// Same place.
+    Same,
+    /// Only in the new ranking.
+    New,
+    /// Only in the old ranking.
+    Removed,
+}
+
+/// One item in either ranking.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change<T> {
+    pub item: T,
+    /// Place in the old ranking, from 0.
+    pub old: Option<usize>,
+    /// Place in the new ranking, from 0.
+    pub new: Option<usize>,
+    pub movement: Movement,
+}
+
+/// The changes between two rankings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RankDiff<T> {
+    /// The new ranking's items in order, then the removed ones in their old
+    /// order.
+    pub changes: Vec<Change<T>>,
+    /// Kendall tau distance: pairs of shared items in opposite orders.
+    pub kendall_tau: usize,
+    /// Pairs of shared items, the largest possible distance.
+    pub pairs: usize,
+}
+
+impl<T> RankDiff<T> {
+    /// [`kendall_tau`](Self::kendall_tau) as a share of
+    /// [`pairs`](Self::pairs), 0 when fewer than two items are shared.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)] // pair counts are far below 2^52
+    pub fn normalized_distance(&self) -> f64 {
+        if self.pairs == 0 {
+            0.0
+        } else {
+            self.kendall_tau as f64 / self.pairs as f64
+        }
+    }
+}
+
+/// Compares `old` and `new`, both best first. Repeated items count at their
+/// first place.
+#[must_use]
+pub fn diff<T: Eq + Hash + Clone>(old: &[T], new: &[T]) -> RankDiff<T> {
+    let old_places = places(old);
+    let new_places = places(new);
+
+    let mut changes: Vec<Change<T>> = new
+        .iter()
+        .enumerate()
+        .filter(|&(place, item)| new_places[item] == place)
+        .map(|(place, item)| {
+            let old = old_places.get(item).copied();
+            let movement = match old {
+                None => Movement::New,
+                Some(was) if was > place => Movement::Up(was - place),
+                Some(was) if was < place => Movement::Down(place - was),
+                Some(_) => Movement::Same,
+            };
+            Change {
+                item: item.clone(),
+                old,
+                new: Some(place),
+                movement,
+            }
+        })
+        .collect();
+    changes.extend(
+        old.iter()
+            .enumerate()
+            .filter(|&(place, item)| old_places[item] == place && !new_places.contains_key(item))
+            .map(|(place, item)| Change {
+                item: item.clone(),
+                old: Some(place),
+                new: None,
+                movement: Movement::Removed,
+            }),
+    );
+
+    let shared = changes
+        .iter()
+        .filter(|change| change.old.is_some() && change.new.is_some())
+        .count();
+    RankDiff {
+        changes,
+        kendall_tau: kendall_distance(old, new),
+        pairs: shared * shared.saturating_sub(1) / 2,
+    }
+}
+
+/// First place of each item.
+fn places<T: Eq + Hash>(ranking: &[T]) -> HashMap<&T, usize> {
+    let mut places = HashMap::new();
+    for (place, item) in ranking.iter().enumerate() {
+        places.entry(item).or_insert(place);
+    }
+    places
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Movement, diff};
+
+    #[test]
+    fn tracks_moves_additions_and_removals() {
+        let result = diff(&["a", "b", "c", "d"], &["c", "a", "e", "b"]);
+        let moves: Vec<(&str, Movement)> = result
+            .changes
+            .iter()
+            .map(|change| (change.item, change.movement))
+            .collect();
+        assert_eq!(
+            moves,
+            [
+                ("c", Movement::Up(2)),
+                ("a", Movement::Down(1)),
+                ("e", Movement::New),
+                ("b", Movement::Down(2)),
+                ("d", Movement::Removed),
+            ]
+        );
+        // Of a, b and c, only c moved past a and b.
+        assert_eq!((result.kendall_tau, result.pairs), (2, 3));
+
+        let reversed = diff(&[1, 2, 3], &[3, 2, 1]);
+        assert_eq!(reversed.kendall_tau, 3);
+        assert!((reversed.normalized_distance() - 1.0).abs() < 1e-12);
+        assert!(diff::<u8>(&[], &[]).normalized_distance().abs() < 1e-12);
+    }
+}
@@ -0,0 +1,298 @@
+//! Ranking with an asynchronous judge.
+//!
+//! [`rank_with`] asks each question through a future, for judges that
+//! answer over the network or after a human clicks. The crate brings no
+//! runtime: futures are awaited as they come, and a per-question
+//! [`Timeout`] races each answer against a sleep the caller provides, such
+//! as `|| tokio::time::sleep(limit)`. A question that times out can be asked
+//! again, answered with a [`Fallback`], or put off until the others are
+//! done, and each time out is logged as a [`TimedOut`].
+//!
+//! [`answer_all`] drives an [`ItemStepper`] the caller keeps, and is
+//! cancellation-safe: an answer is applied as soon as its future resolves,
+//! so dropping the run mid-question loses that question only, and calling
+//! `answer_all` again asks it anew.
+
+use std::future::{Future, Pending, pending, poll_fn};
+use std::pin::pin;
+use std::task::Poll;
+
+use crate::ItemStepper;
+
+/// What to do with a question that timed out on its last try.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fallback {
+    /// Keep the pair in input order, as if the first one was better.
+    Skip,
+    /// Call the pair equal, see [`Stepper::answer_tie`](crate::Stepper::answer_tie).
+    Tie,
+    /// Ask the other open questions first, then this one a last time,
+    /// skipping it if it times out again.
+    Defer,
+}
+
+/// A time limit per question.
+#[derive(Debug, Clone, Copy)]
+pub struct Timeout<S> {
+    sleep: S,
+    retries: usize,
+    fallback: Fallback,
+}
+
+/// A question that ran out of time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimedOut {
+    /// Positions of the two items in the input.
+    pub a: usize,
+    pub b: usize,
+    /// What was done instead, or `None` when the question was asked again.
+    pub then: Option<Fallback>,
+}
+
+impl<S, Sleep> Timeout<S>
+where
+    S: FnMut() -> Sleep,
+    Sleep: Future<Output = ()>,
+{
+    /// Gives up on a question once the future `sleep()` starts with
+    /// resolves, and answers it with `fallback`.
+    #[must_use]
+    pub fn new(sleep: S, fallback: Fallback) -> Self {
+        Self {
+            sleep,
+            retries: 0,
+            fallback,
+        }
+    }
+
+    /// Asks a question that timed out up to `retries` more times before
+    /// falling back.
+    #[must_use]
+    pub fn retries(self, retries: usize) -> Self {
+        Self { retries, ..self }
+    }
+}
+
+impl Timeout<fn() -> Pending<()>> {
+    /// Waits as long as each answer takes.
+    #[must_use]
+    pub fn never() -> Self {
+        Self {
+            sleep: pending,
+            retries: 0,
+            fallback: Fallback::Skip,
+        }
+    }
+}
+
+/// Ranks `items`, best first, awaiting `ask(a, b)` for whether `a` is
+/// better than `b`.
+pub async fn rank_with<T, F, Fut>(items: Vec<T>, ask: F) -> Vec<T>
+where
+    F: FnMut(&T, &T) -> Fut,
+    Fut: Future<Output = bool>,
+{
+    rank_with_timeout(items, ask, Timeout::never()).await
+}
+
+/// [`rank_with`], answering questions that take longer than `timeout` with
+/// its fallback.
+pub async fn rank_with_timeout<T, F, Fut, S, Sleep>(
+    items: Vec<T>,
+    ask: F,
+    timeout: Timeout<S>,
+) -> Vec<T>
+where
+    F: FnMut(&T, &T) -> Fut,
+    Fut: Future<Output = bool>,
+    S: FnMut() -> Sleep,
+    Sleep: Future<Output = ()>,
+{
+    let mut stepper = ItemStepper::new(items);
+    answer_all(&mut stepper, ask, timeout).await;
+    stepper.into_ranking()
+}
+
+/// Answers every question `stepper` has left through `ask`, and returns
+/// the questions that timed out, in order.
+pub async fn answer_all<T, F, Fut, S, Sleep>(
+    stepper: &mut ItemStepper<T>,
+    mut ask: F,
+    mut timeout: Timeout<S>,
+) -> Vec<TimedOut>
+where
+    F: FnMut(&T, &T) -> Fut,
+    Fut: Future<Output = bool>,
+    S: FnMut() -> Sleep,
+    Sleep: Future<Output = ()>,
+{
+    let mut log = Vec::new();
+    let mut deferred = Vec::new();
+    loop {
+        let open = stepper.open_questions();
+        let Some(&(a, b)) = open
+            .iter()
+            .find(|question| !deferred.contains(*question))
+            .or(open.first())
+        else {
+            return log;
+        };
+        let mut tries = 0;
+        let answer = loop {
+            let (item_a, item_b) = stepper.pair(a, b);
+            let answer = race(ask(item_a, item_b), (timeout.sleep)()).await;
+            if answer.is_some() || tries == timeout.retries {
+                break answer;
+            }
+            tries += 1;
+            log.push(TimedOut { a, b, then: None });
+        };
+        if let Some(a_is_better) = answer {
+            stepper.answer_pair(a, b, a_is_better);
+            continue;
+        }
+        let fallback = match timeout.fallback {
+            Fallback::Defer if deferred.contains(&(a, b)) => Fallback::Skip,
+            fallback => fallback,
+        };
+        log.push(TimedOut {
+            a,
+            b,
+            then: Some(fallback),
+        });
+        match fallback {
+            Fallback::Skip => stepper.answer_pair(a, b, a < b),
+            // Only deferring leaves questions out of turn, so this is the
+            // pending one.
+            Fallback::Tie => {
+                stepper.answer_tie();
+            }
+            Fallback::Defer => deferred.push((a, b)),
+        }
+    }
+}
+
+/// `Some` of `answer`'s output, or `None` if `alarm` goes off first.
+async fn race<A, S>(answer: A, alarm: S) -> Option<bool>
+where
+    A: Future<Output = bool>,
+    S: Future<Output = ()>,
+{
+    let mut answer = pin!(answer);
+    let mut alarm = pin!(alarm);
+    poll_fn(|cx| {
+        if let Poll::Ready(better) = answer.as_mut().poll(cx) {
+            Poll::Ready(Some(better))
+        } else if alarm.as_mut().poll(cx).is_ready() {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::future::{Future, pending, ready};
+    use std::pin::pin;
+    use std::task::{Context, Poll, Waker};
+
+    use super::{Fallback, TimedOut, Timeout, answer_all, rank_with};
+    use crate::ItemStepper;
+
+    /// Polls `future` to completion; every future here is ready at once.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    #[test]
+    fn awaits_each_answer() {
+        let ranked = block_on(rank_with(vec![3, 1, 4, 1, 5], |&a, &b| ready(a > b)));
+        assert_eq!(ranked, [5, 4, 3, 1, 1]);
+    }
+
+    #[test]
+    fn timed_out_questions_fall_back() {
+        let silent = |_: &u8, _: &u8| async {
+            pending::<()>().await;
+            true
+        };
+        let mut stepper = ItemStepper::new(vec![2, 0, 3, 1]);
+        let log = block_on(answer_all(
+            &mut stepper,
+            silent,
+            Timeout::new(|| ready(()), Fallback::Skip),
+        ));
+        assert_eq!(log.len(), stepper.stepper().comparisons_made());
+        assert_eq!(stepper.into_ranking(), [2, 0, 3, 1]);
+
+        let mut stepper = ItemStepper::new(vec![2, 0, 3, 1]);
+        block_on(answer_all(
+            &mut stepper,
+            silent,
+            Timeout::new(|| ready(()), Fallback::Tie),
+        ));
+        assert_eq!(stepper.stepper().finalize_partial().groups().len(), 1);
+    }
+
+    #[test]
+    fn retries_and_deferrals_are_logged() {
+        // Every other call hangs, so each question needs one retry.
+        let calls = Cell::new(0);
+        let flaky = |&a: &u8, &b: &u8| {
+            calls.set(calls.get() + 1);
+            let hang = calls.get() % 2 == 1;
+            async move {
+                if hang {
+                    pending::<()>().await;
+                }
+                a > b
+            }
+        };
+        let mut stepper = ItemStepper::new(vec![2, 0, 3, 1]);
+        let timeout = Timeout::new(|| ready(()), Fallback::Tie).retries(1);
+        let log = block_on(answer_all(&mut stepper, flaky, timeout));
+        assert!(log.iter().all(|timed_out| timed_out.then.is_none()));
+        assert_eq!(log.len(), stepper.stepper().comparisons_made());
+        assert_eq!(stepper.into_ranking(), [3, 2, 1, 0]);
+
+        // Questions about 0 never get an answer: its pair waits for the
+        // other one, then is skipped.
+        let shy = |&a: &u8, &b: &u8| async move {
+            if a == 0 || b == 0 {
+                pending::<()>().await;
+            }
+            a > b
+        };
+        let mut stepper = ItemStepper::new(vec![2, 0, 3, 1]);
+        let log = block_on(answer_all(
+            &mut stepper,
+            shy,
+            Timeout::new(|| ready(()), Fallback::Defer),
+        ));
+        let first = TimedOut {
+            a: 0,
+            b: 1,
+            then: Some(Fallback::Defer),
+        };
+        assert_eq!(
+            log[..2],
+            [
+                first,
+                TimedOut {
+                    then: Some(Fallback::Skip),
+                    ..first
+                }
+            ]
+        );
+        assert!(stepper.stepper().history().contains(&(2, 3, true)));
+    }
+}
@@ -0,0 +1,317 @@
+//! One interface over the step-by-step rankers.
+//!
+//! Every ranker in this crate asks its questions the same way: a [`Step`]
+//! out, `better_is_a` back in. [`RankingEngine`] names that protocol, so a
+//! frontend can drive any of them through a `Box<dyn RankingEngine>`, and
+//! [`EngineKind`] builds one from its name, so which engine runs can come
+//! from configuration. The names are the ones sessions are saved with, see
+//! `rankfast_session::Mode`.
+
+use std::fmt;
+
+use crate::active::ActiveStepper;
+use crate::quickrank::QuickRank;
+use crate::tiers::Tiers;
+use crate::topk::TopK;
+use crate::tournament::RoundRobin;
+use crate::{Ranking, Step, Stepper};
+
+/// A ranker of the indices `0..n` that asks one question at a time.
+pub trait RankingEngine {
+    /// A ranker for `n` items.
+    fn new(n: usize) -> Self
+    where
+        Self: Sized;
+
+    /// The pending question, or [`Step::Done`] once the ranking is final.
+    fn next_question(&mut self) -> Step;
+
+    /// Applies the answer to the pending question and returns the next step.
+    fn answer(&mut self, better_is_a: bool) -> Step;
+
+    /// The ranking the answers so far support, best first; final once
+    /// [`next_question`](Self::next_question) returns [`Step::Done`].
+    fn result(&self) -> Ranking<usize>;
+
+    /// Questions still to come, including the pending one; zero once done.
+    /// Each engine's own `remaining_estimate` says how good a guess it is.
+    fn remaining_estimate(&self) -> usize;
+
+    /// Number of answers applied so far.
+    fn comparisons_made(&self) -> usize;
+
+    /// Answers every question with `better(a, b)` and returns the ranking.
+    fn run(&mut self, better: &mut dyn FnMut(usize, usize) -> bool) -> Ranking<usize> {
+        let mut step = self.next_question();
+        while let Step::Compare { a, b, .. } = step {
+            step = self.answer(better(a, b));
+        }
+        self.result()
+    }
+}
+
+impl RankingEngine for Stepper {
+    fn new(n: usize) -> Self {
+        Self::new(n)
+    }
+
+    fn next_question(&mut self) -> Step {
+        self.step()
+    }
+
+    fn answer(&mut self, better_is_a: bool) -> Step {
+        Self::answer(self, better_is_a)
+    }
+
+    fn result(&self) -> Ranking<usize> {
+        self.finalize_partial()
+    }
+
+    fn remaining_estimate(&self) -> usize {
+        Self::remaining_estimate(self)
+    }
+
+    fn comparisons_made(&self) -> usize {
+        Self::comparisons_made(self)
+    }
+}
+
+/// Binary insertion of each item into the ranking of the items before it:
+/// [`TopK`] with room for every item.
+#[derive(Debug, Clone)]
+pub struct BinaryInsertion(TopK);
+
+impl RankingEngine for BinaryInsertion {
+    fn new(n: usize) -> Self {
+        Self(TopK::new(n, n))
+    }
+
+    fn next_question(&mut self) -> Step {
+        self.0.step()
+    }
+
+    fn answer(&mut self, better_is_a: bool) -> Step {
+        self.0.answer(better_is_a)
+    }
+
+    fn result(&self) -> Ranking<usize> {
+        self.0.order()
+    }
+
+    fn remaining_estimate(&self) -> usize {
+        self.0.remaining_estimate()
+    }
+
+    fn comparisons_made(&self) -> usize {
+        self.0.comparisons_made()
+    }
+}
+
+impl RankingEngine for RoundRobin {
+    fn new(n: usize) -> Self {
+        Self::new(n)
+    }
+
+    fn next_question(&mut self) -> Step {
+        self.step()
+    }
+
+    fn answer(&mut self, better_is_a: bool) -> Step {
+        Self::answer(self, better_is_a)
+    }
+
+    fn result(&self) -> Ranking<usize> {
+        Ranking::new(self.standings().order)
+    }
+
+    fn remaining_estimate(&self) -> usize {
+        self.total_matches() - Self::comparisons_made(self)
+    }
+
+    fn comparisons_made(&self) -> usize {
+        Self::comparisons_made(self)
+    }
+}
+
+impl RankingEngine for QuickRank {
+    fn new(n: usize) -> Self {
+        Self::new(n)
+    }
+
+    fn next_question(&mut self) -> Step {
+        self.step()
+    }
+
+    fn answer(&mut self, better_is_a: bool) -> Step {
+        Self::answer(self, better_is_a)
+    }
+
+    fn result(&self) -> Ranking<usize> {
+        self.order()
+    }
+
+    fn remaining_estimate(&self) -> usize {
+        Self::remaining_estimate(self)
+    }
+
+    fn comparisons_made(&self) -> usize {
+        Self::comparisons_made(self)
+    }
+}
+
+/// Ranked by fitted ratings after [`default_budget`](crate::active::default_budget)
+/// questions.
+impl RankingEngine for ActiveStepper {
+    fn new(n: usize) -> Self {
+        Self::with_default_budget(n)
+    }
+
+    fn next_question(&mut self) -> Step {
+        self.step()
+    }
+
+    fn answer(&mut self, better_is_a: bool) -> Step {
+        Self::answer(self, better_is_a)
+    }
+
+    fn result(&self) -> Ranking<usize> {
+        self.order()
+    }
+
+    fn remaining_estimate(&self) -> usize {
+        Self::remaining_estimate(self)
+    }
+
+    fn comparisons_made(&self) -> usize {
+        Self::comparisons_made(self)
+    }
+}
+
+impl RankingEngine for Tiers {
+    fn new(n: usize) -> Self {
+        Self::new(n)
+    }
+
+    fn next_question(&mut self) -> Step {
+        self.step()
+    }
+
+    fn answer(&mut self, better_is_a: bool) -> Step {
+        Self::answer(self, better_is_a)
+    }
+
+    fn result(&self) -> Ranking<usize> {
+        self.order()
+    }
+
+    fn remaining_estimate(&self) -> usize {
+        Self::remaining_estimate(self)
+    }
+
+    fn comparisons_made(&self) -> usize {
+        Self::comparisons_made(self)
+    }
+}
+
+/// The engines [`EngineKind::build`] can make, by name.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EngineKind {
+    /// [`Stepper`]: Ford-Johnson, the fewest questions.
+    #[default]
+    FordJohnson,
+    /// [`BinaryInsertion`].
+    BinaryInsertion,
+    /// [`RoundRobin`]: every pair once.
+    Tournament,
+    /// [`QuickRank`]: one anchor at a time.
+    QuickRank,
+    /// [`ActiveStepper`]: a fixed budget, ranked by fitted ratings.
+    Ratings,
+    /// [`Tiers`]: tiers first, then each tier sorted.
+    Tiers,
+}
+
+impl EngineKind {
+    pub const ALL: [Self; 6] = [
+        Self::FordJohnson,
+        Self::BinaryInsertion,
+        Self::Tournament,
+        Self::QuickRank,
+        Self::Ratings,
+        Self::Tiers,
+    ];
+
+    /// The engine's name in configuration, such as `"exact"`.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::FordJohnson => "exact",
+            Self::BinaryInsertion => "binary_insertion",
+            Self::Tournament => "tournament",
+            Self::QuickRank => "quick_rank",
+            Self::Ratings => "approximate",
+            Self::Tiers => "tiers",
+        }
+    }
+
+    /// Reads a name written by [`name`](Self::name).
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    /// A fresh engine of this kind for `n` items.
+    #[must_use]
+    pub fn build(self, n: usize) -> Box<dyn RankingEngine> {
+        match self {
+            Self::FordJohnson => Box::new(Stepper::new(n)),
+            Self::BinaryInsertion => Box::new(BinaryInsertion::new(n)),
+            Self::Tournament => Box::new(RoundRobin::new(n)),
+            Self::QuickRank => Box::new(QuickRank::new(n)),
+            Self::Ratings => Box::new(ActiveStepper::with_default_budget(n)),
+            Self::Tiers => Box::new(Tiers::new(n)),
+        }
+    }
+}
+
+impl fmt::Display for EngineKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EngineKind;
+    use crate::Step;
+
+    #[test]
+    fn every_engine_ranks_through_the_trait() {
+        let values: Vec<usize> = (0..30).map(|i| (i * 7 + 3) % 30).collect();
+        for kind in EngineKind::ALL {
+            let mut engine = kind.build(values.len());
+            let estimate = engine.remaining_estimate();
+            let order = engine.run(&mut |a, b| values[a] < values[b]);
+            assert_eq!(engine.next_question(), Step::Done, "{kind}");
+            assert_eq!(engine.remaining_estimate(), 0, "{kind}");
+            let mut ranked: Vec<usize> = order.iter().map(|&i| values[i]).collect();
+            if kind != EngineKind::Ratings {
+                assert_eq!(ranked, (0..30).collect::<Vec<_>>(), "{kind}");
+            }
+            ranked.sort_unstable();
+            assert_eq!(ranked, (0..30).collect::<Vec<_>>(), "{kind}");
+            // These two estimate for even splits, not the worst case.
+            if !matches!(kind, EngineKind::QuickRank | EngineKind::Tiers) {
+                assert!(engine.comparisons_made() <= estimate, "{kind}");
+            }
+        }
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for kind in EngineKind::ALL {
+            assert_eq!(EngineKind::from_name(kind.name()), Some(kind));
+        }
+        assert_eq!(EngineKind::from_name("bogo"), None);
+    }
+}
@@ -0,0 +1,75 @@
+//! One error type for everything that can go wrong around a ranking.
+//!
+//! The modules keep their own precise errors, such as [`NotAsked`]. Callers
+//! that juggle several of them, a frontend that decodes a saved session,
+//! replays it and stores the result say, can convert each into an
+//! [`Error`] with `?` and handle them in one place. Errors from the crates
+//! around this one, decoding a session or reaching storage, are carried
+//! boxed.
+
+use std::fmt;
+
+use crate::NotAsked;
+
+/// A boxed error from outside this crate.
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Anything that can stop a ranking.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// A saved session or link could not be read.
+    Decode(BoxError),
+    /// An answer came while no question was pending.
+    NotAsked,
+    /// An answer about `a` and `b`, which is not an open question.
+    InvalidAnswer { a: usize, b: usize },
+    /// `budget` questions were asked and the ranking is still incomplete.
+    BudgetExhausted { budget: usize },
+    /// The comparator could not answer.
+    Comparator(BoxError),
+    /// A session could not be saved or loaded.
+    Storage(BoxError),
+}
+
+impl Error {
+    /// A [`Decode`](Self::Decode) error.
+    pub fn decode(e: impl Into<BoxError>) -> Self {
+        Self::Decode(e.into())
+    }
+
+    /// A [`Storage`](Self::Storage) error.
+    pub fn storage(e: impl Into<BoxError>) -> Self {
+        Self::Storage(e.into())
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Decode(e) => write!(f, "could not read the session: {e}"),
+            Self::NotAsked => NotAsked.fmt(f),
+            Self::InvalidAnswer { a, b } => write!(f, "{a} vs {b} is not an open question"),
+            Self::BudgetExhausted { budget } => {
+                write!(f, "the ranking is incomplete after {budget} questions")
+            }
+            Self::Comparator(e) => write!(f, "the comparator failed: {e}"),
+            Self::Storage(e) => write!(f, "storage failed: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Decode(e) | Self::Comparator(e) | Self::Storage(e) => Some(&**e),
+            Self::NotAsked | Self::InvalidAnswer { .. } | Self::BudgetExhausted { .. } => None,
+        }
+    }
+}
+
+impl From<NotAsked> for Error {
+    fn from(NotAsked: NotAsked) -> Self {
+        Self::NotAsked
+    }
+}
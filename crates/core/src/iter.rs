@@ -0,0 +1,201 @@
+//! Pull-based questions around the [`Stepper`].
+//!
+//! [`RankIter::questions`] yields each [`Question`] as a value that is
+//! answered by consuming it, so there is no way to answer when nothing was
+//! asked, or to answer one question twice:
+//!
+//! ```
+//! use rankfast::RankIter;
+//!
+//! let scores = [3, 9, 1, 7];
+//! let mut ranker = RankIter::new(scores.len());
+//! for question in ranker.questions() {
+//!     let (a, b) = (question.a(), question.b());
+//!     question.answer(scores[a] > scores[b]);
+//! }
+//! assert_eq!(ranker.into_ranking().unwrap(), vec![1, 3, 0, 2]);
+//! ```
+//!
+//! A question left unanswered pauses the run: the loop ends, and the next
+//! call to [`questions`](RankIter::questions) asks it again.
+
+use std::cell::Cell;
+
+use crate::{Context, Ranking, Step, Stepper};
+
+/// A [`Stepper`] whose questions come out of an iterator.
+#[derive(Clone)]
+pub struct RankIter {
+    stepper: Stepper,
+    reply: Cell<Option<Reply>>,
+}
+
+/// An answer held until the iterator moves on.
+#[derive(Debug, Clone, Copy)]
+enum Reply {
+    Better(bool),
+    Tie,
+}
+
+impl RankIter {
+    /// Ranks the indices `0..n`.
+    #[must_use]
+    pub fn new(n: usize) -> Self {
+        Stepper::new(n).into()
+    }
+
+    /// The questions still to ask, starting with the one left unanswered
+    /// last time, if any.
+    pub fn questions(&mut self) -> Questions<'_> {
+        Questions {
+            stepper: &mut self.stepper,
+            reply: &self.reply,
+            asked: false,
+        }
+    }
+
+    /// The stepper underneath, to read its progress or partial ranking.
+    #[must_use]
+    pub fn stepper(&self) -> &Stepper {
+        &self.stepper
+    }
+
+    /// Whether every question has been answered.
+    #[must_use]
+    pub fn is_done(&mut self) -> bool {
+        self.apply_reply();
+        matches!(self.stepper.step(), Step::Done)
+    }
+
+    /// The final ranking, or `None` while questions remain.
+    #[must_use]
+    pub fn into_ranking(mut self) -> Option<Ranking<usize>> {
+        if !self.is_done() {
+            return None;
+        }
+        self.stepper.take_order()
+    }
+
+    /// Hands the answer given since the last step to the stepper.
+    fn apply_reply(&mut self) {
+        match self.reply.take() {
+            Some(Reply::Better(a_is_better)) => {
+                self.stepper.answer(a_is_better);
+            }
+            Some(Reply::Tie) => {
+                self.stepper.answer_tie();
+            }
+            None => {}
+        }
+    }
+}
+
+impl From<Stepper> for RankIter {
+    /// Carries on from `stepper`, which may be part-way through a run.
+    fn from(stepper: Stepper) -> Self {
+        Self {
+            stepper,
+            reply: Cell::new(None),
+        }
+    }
+}
+
+/// The questions of a [`RankIter`], from [`RankIter::questions`].
+pub struct Questions<'a> {
+    stepper: &'a mut Stepper,
+    reply: &'a Cell<Option<Reply>>,
+    /// Whether a question is out, so an unanswered one ends the iteration.
+    asked: bool,
+}
+
+impl<'a> Iterator for Questions<'a> {
+    type Item = Question<'a>;
+
+    fn next(&mut self) -> Option<Question<'a>> {
+        let step = match self.reply.take() {
+            Some(Reply::Better(a_is_better)) => self.stepper.answer(a_is_better),
+            Some(Reply::Tie) => self.stepper.answer_tie(),
+            None if self.asked => return None,
+            None => self.stepper.step(),
+        };
+        let Step::Compare { a, b, context } = step else {
+            self.asked = false;
+            return None;
+        };
+        self.asked = true;
+        Some(Question {
+            a,
+            b,
+            context,
+            reply: self.reply,
+        })
+    }
+}
+
+/// One comparison to make: is item [`a`](Self::a) better than item
+/// [`b`](Self::b)?
+#[must_use = "an unanswered question ends the iteration"]
+pub struct Question<'a> {
+    a: usize,
+    b: usize,
+    context: Context,
+    reply: &'a Cell<Option<Reply>>,
+}
+
+impl Question<'_> {
+    #[must_use]
+    pub fn a(&self) -> usize {
+        self.a
+    }
+
+    #[must_use]
+    pub fn b(&self) -> usize {
+        self.b
+    }
+
+    /// Why the question is asked.
+    #[must_use]
+    pub fn context(&self) -> Context {
+        self.context
+    }
+
+    /// Answers whether `a` is better than `b`.
+    pub fn answer(self, a_is_better: bool) {
+        self.reply.set(Some(Reply::Better(a_is_better)));
+    }
+
+    /// Answers that `a` and `b` are equal, see [`Stepper::answer_tie`].
+    pub fn tie(self) {
+        self.reply.set(Some(Reply::Tie));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RankIter;
+
+    #[test]
+    fn an_unanswered_question_pauses_the_run() {
+        let scores = [5, 1, 4, 2, 3];
+        let mut ranker = RankIter::new(scores.len());
+        let first = ranker.questions().next().expect("a question");
+        let (a, b) = (first.a(), first.b());
+
+        let mut questions = ranker.questions();
+        let again = questions.next().expect("the same question");
+        assert_eq!((again.a(), again.b()), (a, b));
+        assert!(questions.next().is_none(), "left unanswered");
+
+        let mut asked = 0;
+        for question in ranker.questions() {
+            asked += 1;
+            let better = scores[question.a()] > scores[question.b()];
+            question.answer(better);
+        }
+        assert_eq!(asked, ranker.stepper().comparisons_made());
+        assert!(ranker.is_done());
+        assert_eq!(ranker.into_ranking().unwrap(), vec![0, 2, 4, 3, 1]);
+
+        assert!(RankIter::new(3).into_ranking().is_none());
+    }
+}
@@ -0,0 +1,141 @@
+//! The [`Stepper`] over the caller's own IDs.
+//!
+//! A [`Stepper`] ranks the indices `0..n`. Callers whose items already have
+//! keys, database rows say, can hand those to an [`IdStepper`] instead and
+//! get questions, history and the ranking back in the same keys. The keys
+//! need not be dense or small: the stepper refers to elements by position
+//! internally, and the keys are only translated at the edges.
+
+use std::collections::HashSet;
+use std::hash::Hash;
+
+use crate::{Context, Ranking, Step, Stepper};
+
+/// What an [`IdStepper`] needs next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdStep<K> {
+    /// Ask whether item `a` is better than item `b`.
+    Compare { a: K, b: K, context: Context },
+    /// The ranking is complete.
+    Done,
+}
+
+/// Step-by-step Ford-Johnson sorter over a list of IDs.
+#[derive(Clone)]
+pub struct IdStepper<K> {
+    ids: Vec<K>,
+    stepper: Stepper,
+}
+
+impl<K: Copy + Eq + Hash> IdStepper<K> {
+    /// Creates a stepper that ranks `ids`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an ID appears twice.
+    #[must_use]
+    pub fn new(ids: Vec<K>) -> Self {
+        let mut seen = HashSet::with_capacity(ids.len());
+        for (index, &id) in ids.iter().enumerate() {
+            assert!(seen.insert(id), "ID at {index} appears twice");
+        }
+        Self {
+            stepper: Stepper::new(ids.len()),
+            ids,
+        }
+    }
+
+    /// The IDs being ranked, in the order given.
+    #[must_use]
+    pub fn ids(&self) -> &[K] {
+        &self.ids
+    }
+
+    /// Advances until a comparison is needed or the ranking is complete.
+    pub fn step(&mut self) -> IdStep<K> {
+        let step = self.stepper.step();
+        self.translate(step)
+    }
+
+    /// Applies the answer to the pending comparison and returns the next
+    /// step; see [`Stepper::answer`].
+    pub fn answer(&mut self, better_is_a: bool) -> IdStep<K> {
+        let step = self.stepper.answer(better_is_a);
+        self.translate(step)
+    }
+
+    /// Answers the pending comparison as a tie; see
+    /// [`Stepper::answer_tie`].
+    pub fn answer_tie(&mut self) -> IdStep<K> {
+        let step = self.stepper.answer_tie();
+        self.translate(step)
+    }
+
+    /// Takes the final ranking once [`IdStep::Done`] has been reached.
+    pub fn take_order(&mut self) -> Option<Ranking<K>> {
+        let order = self.stepper.take_order()?;
+        Some(order.map(|index| self.ids[index]))
+    }
+
+    /// The best ranking the answers so far support; see
+    /// [`Stepper::finalize_partial`].
+    #[must_use]
+    pub fn finalize_partial(&self) -> Ranking<K> {
+        self.stepper.finalize_partial().map(|index| self.ids[index])
+    }
+
+    /// Every question answered so far as `(a, b, a_is_better)`, in the
+    /// order the answers were applied.
+    #[must_use]
+    pub fn history(&self) -> impl ExactSizeIterator<Item = (K, K, bool)> + '_ {
+        self.stepper
+            .history()
+            .iter()
+            .map(|&(a, b, better)| (self.ids[a], self.ids[b], better))
+    }
+
+    /// The stepper underneath, over the positions of [`ids`](Self::ids).
+    #[must_use]
+    pub fn stepper(&self) -> &Stepper {
+        &self.stepper
+    }
+
+    fn translate(&self, step: Step) -> IdStep<K> {
+        match step {
+            Step::Compare { a, b, context } => IdStep::Compare {
+                a: self.ids[a],
+                b: self.ids[b],
+                context,
+            },
+            Step::Done => IdStep::Done,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IdStep, IdStepper};
+
+    #[test]
+    fn asks_and_ranks_in_the_given_ids() {
+        let ids = vec![9_000_000_001_u64, 42, 7_000_000_000_000];
+        let score = |id: u64| id % 1000;
+        let mut stepper = IdStepper::new(ids.clone());
+        let mut step = stepper.step();
+        while let IdStep::Compare { a, b, .. } = step {
+            assert!(ids.contains(&a) && ids.contains(&b));
+            step = stepper.answer(score(a) > score(b));
+        }
+        assert!(stepper.history().all(|(a, b, _)| a != b));
+        assert_eq!(
+            stepper.take_order().unwrap(),
+            vec![42, 9_000_000_001, 7_000_000_000_000]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "appears twice")]
+    fn rejects_repeated_ids() {
+        let _ = IdStepper::new(vec!["a", "b", "a"]);
+    }
+}
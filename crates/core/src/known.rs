@@ -0,0 +1,146 @@
+//! Transitive inference over relations that are already known.
+
+use crate::{Ranking, Step, Stepper};
+
+/// The `(better, worse)` pairs over `0..n` that can seed
+/// [`Stepper::with_known_pairs`](crate::Stepper::with_known_pairs): each
+/// pair is kept unless the pairs kept before it already decide its two
+/// items, either way. Contradictions and repeats are dropped, so the result
+/// is consistent with a strict order whatever order the judgments came in,
+/// and so are pairs referring to items outside `0..n`.
+#[must_use]
+pub fn consistent_pairs(n: usize, pairs: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let mut known = KnownOrder::new(n);
+    let mut kept = Vec::new();
+    for &(better, worse) in pairs {
+        if better >= n || worse >= n || better == worse {
+            continue;
+        }
+        if known.relation(better, worse).is_none() {
+            known.insert(better, worse);
+            kept.push((better, worse));
+        }
+    }
+    kept
+}
+
+/// Ranks `items` like [`rank_items`](crate::rank_items), with a stepper
+/// seeded with the `(better, worse)` pairs `known`.
+pub(crate) fn rank_with_known<T, F>(
+    items: Vec<T>,
+    known: &[(usize, usize)],
+    mut better: F,
+) -> Ranking<T>
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let mut stepper = Stepper::with_known_pairs(items.len(), known);
+    let mut step = stepper.step();
+    while let Step::Compare { a, b, .. } = step {
+        // Put the question as `rank_permutation` does, for stability.
+        let a_better = if a < b {
+            !better(&items[b], &items[a])
+        } else {
+            better(&items[a], &items[b])
+        };
+        step = stepper.answer(a_better);
+    }
+    let order = stepper
+        .take_order()
+        .unwrap_or_else(|| stepper.finalize_partial());
+    order.apply(items)
+}
+
+/// Directed graph of known "better than" relations between items.
+///
+/// Edges are kept as given; [`KnownOrder::relation`] follows them
+/// transitively with a depth-first search, which stays cheap for the
+/// sparse graphs a few seeded pairs plus a session's answers produce.
+#[derive(Debug, Clone)]
+pub(crate) struct KnownOrder {
+    /// `worse[a]` lists every item directly known to rank below `a`.
+    worse: Vec<Vec<usize>>,
+}
+
+impl KnownOrder {
+    pub(crate) fn new(n: usize) -> Self {
+        Self {
+            worse: vec![Vec::new(); n],
+        }
+    }
+
+    /// Records that `better` ranks above `worse`.
+    pub(crate) fn insert(&mut self, better: usize, worse: usize) {
+        self.worse[better].push(worse);
+    }
+
+    /// Every recorded `(better, worse)` pair.
+    pub(crate) fn pairs(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.worse
+            .iter()
+            .enumerate()
+            .flat_map(|(better, worse)| worse.iter().map(move |&worse| (better, worse)))
+    }
+
+    /// `Some(true)` if `a` is known to rank above `b`, `Some(false)` if
+    /// below, `None` if the known relations do not decide it.
+    pub(crate) fn relation(&self, a: usize, b: usize) -> Option<bool> {
+        if self.reaches(a, b) {
+            Some(true)
+        } else if self.reaches(b, a) {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    fn reaches(&self, from: usize, to: usize) -> bool {
+        let mut seen = vec![false; self.worse.len()];
+        let mut stack = vec![from];
+        seen[from] = true;
+        while let Some(node) = stack.pop() {
+            for &next in &self.worse[node] {
+                if next == to {
+                    return true;
+                }
+                if !seen[next] {
+                    seen[next] = true;
+                    stack.push(next);
+                }
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KnownOrder, consistent_pairs};
+
+    #[test]
+    fn follows_chains_in_both_directions() {
+        let mut known = KnownOrder::new(5);
+        known.insert(0, 1);
+        known.insert(1, 2);
+        known.insert(3, 2);
+        assert_eq!(known.relation(0, 2), Some(true));
+        assert_eq!(known.relation(2, 0), Some(false));
+        assert_eq!(known.relation(0, 3), None);
+        assert_eq!(known.relation(4, 1), None);
+    }
+
+    #[test]
+    fn drops_pairs_the_earlier_ones_decide() {
+        let pairs = [
+            (0, 1),
+            (1, 2),
+            (2, 0),
+            (0, 2),
+            (1, 0),
+            (3, 3),
+            (3, 1),
+            (4, 0),
+        ];
+        assert_eq!(consistent_pairs(4, &pairs), vec![(0, 1), (1, 2), (3, 1)]);
+    }
+}
@@ -1,3 +1,54 @@
+pub mod active;
+pub mod aggregate;
+pub mod bracket;
+mod chain;
+pub mod diff;
+pub mod driver;
+pub mod engine;
+pub mod error;
+pub mod iter;
+pub mod keyed;
+mod known;
+pub mod metrics;
+pub mod normalize;
+pub mod observe;
+pub mod outcome;
+pub mod owned;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+pub mod pin;
+pub mod plan;
+pub mod prior;
+pub mod quickrank;
+pub mod quorum;
+pub mod ranking;
+pub mod ratings;
+pub mod simulate;
+mod small;
+pub mod stage;
+pub mod stepper;
+pub mod tiers;
+pub mod topk;
+pub mod tournament;
+
+pub use diff::{RankDiff, diff};
+pub use driver::rank_with;
+pub use engine::{EngineKind, RankingEngine};
+pub use error::Error;
+pub use iter::RankIter;
+pub use keyed::{IdStep, IdStepper};
+pub use known::consistent_pairs;
+pub use normalize::normalize_items;
+pub use owned::{ItemStep, ItemStepper};
+#[cfg(feature = "rayon")]
+pub use parallel::rank_items_parallel;
+pub use quorum::{QuorumComparator, QuorumStats};
+pub use ranking::Ranking;
+pub use simulate::{TurnStats, simulate_expected_turns};
+pub use stepper::{Context, NotAsked, Phase, Step, Stepper};
+
+use chain::{Arena, Chain};
+
 /// Sorts `items` using the Ford-Johnson merge-insertion algorithm,
 /// which is designed to minimize the number of calls to `better`.
 ///
@@ -7,42 +58,257 @@
 /// (irreflexive, transitive, and consistent). Results are undefined if
 /// this contract is violated.
 ///
-/// # Panics
+/// # Stability
 ///
-/// Cannot panic. The internal `expect` is guarded by construction.
+/// The sort is stable: items the comparator considers equal (neither is
+/// better than the other) keep their input order. This costs no extra
+/// comparisons, see [`rank_permutation`].
 #[must_use]
-pub fn rank_items<T, F>(items: Vec<T>, mut better: F) -> Vec<T>
+pub fn rank_items<T, F>(mut items: Vec<T>, better: F) -> Ranking<T>
 where
     F: FnMut(&T, &T) -> bool,
 {
-    let n = items.len();
-    if n <= 1 {
-        return items;
+    rank_in_place(&mut items, better);
+    Ranking::new(items)
+}
+
+/// Sorts `items` in place with the same comparisons as [`rank_items`].
+///
+/// The order is computed on indices first and then applied by following the
+/// permutation's cycles with swaps, so no item is moved more than once.
+pub fn rank_in_place<T, F>(items: &mut [T], mut better: F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    if items.len() <= 1 {
+        return;
+    }
+
+    let order = rank_permutation(items.len(), |a, b| better(&items[a], &items[b]));
+    apply_order(items, order.into_vec());
+}
+
+/// Ranks the indices `0..n` and returns them best first.
+///
+/// This is the permutation [`rank_items`] applies, for data that cannot be
+/// moved or cloned cheaply (rows in an external store, say): `better(a, b)`
+/// compares items by index, and the caller applies the order itself. The
+/// comparator contract and the stability guarantee are the same as for
+/// [`rank_items`].
+///
+/// Stability comes from the way each question is put: the later item is
+/// always passed first, so `better(later, earlier)` is `false` for equal
+/// items and the earlier one stays in front. Equal items thus compare as if
+/// the input order broke the tie, a strict order the sort handles like any
+/// other.
+#[must_use]
+pub fn rank_permutation<F>(n: usize, mut better: F) -> Ranking<usize>
+where
+    F: FnMut(usize, usize) -> bool,
+{
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("rank_permutation", n).entered();
+    Ranking::new(ford_johnson((0..n).collect(), &mut |a, b| {
+        if a < b { !better(b, a) } else { better(a, b) }
+    }))
+}
+
+/// Ranks references to `items`, leaving the items where they are.
+///
+/// For callers that keep one owned list, in a UI's state say, and only need
+/// the order: this asks the same questions as [`rank_items`] and returns the
+/// items it would, borrowed. The comparator contract and the stability
+/// guarantee are the same.
+///
+/// ```
+/// let names = vec!["pear".to_string(), "fig".to_string(), "apple".to_string()];
+/// let ranked = rankfast::rank_refs(&names, |a, b| a.len() < b.len());
+/// assert_eq!(*ranked, [&names[1], &names[0], &names[2]]);
+/// ```
+#[must_use]
+pub fn rank_refs<T, F>(items: &[T], better: F) -> Ranking<&T>
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    rank_indices(items, better).map(|i| &items[i])
+}
+
+/// The positions of `items`, ranked as [`rank_refs`] ranks the items.
+#[must_use]
+pub fn rank_indices<T, F>(items: &[T], mut better: F) -> Ranking<usize>
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    rank_permutation(items.len(), |a, b| better(&items[a], &items[b]))
+}
+
+/// Ranks `items` like [`rank_items`], but stops asking once
+/// `max_comparisons` answers have been given.
+///
+/// A run that finishes within the budget returns the same ranking as
+/// [`rank_items`]. Otherwise the result is the best the answers so far
+/// support: groups as in [`Ranking::from_outcomes`], where items the answers
+/// cannot tell apart share a group. The comparator contract and the
+/// stability guarantee are the same as for [`rank_items`].
+#[must_use]
+pub fn rank_with_budget<T, F>(items: Vec<T>, max_comparisons: usize, mut better: F) -> Ranking<T>
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let mut stepper = Stepper::new(items.len());
+    let mut step = stepper.step();
+    while let Step::Compare { a, b, .. } = step {
+        if stepper.comparisons_made() >= max_comparisons {
+            break;
+        }
+        // Put the question as `rank_permutation` does, for stability.
+        let a_better = if a < b {
+            !better(&items[b], &items[a])
+        } else {
+            better(&items[a], &items[b])
+        };
+        step = stepper.answer(a_better);
     }
+    let order = stepper.take_order().unwrap_or_else(|| {
+        let outcomes: Vec<(usize, usize)> = stepper
+            .history()
+            .iter()
+            .map(|&(a, b, a_better)| if a_better { (a, b) } else { (b, a) })
+            .collect();
+        Ranking::from_outcomes(items.len(), &outcomes)
+    });
+    order.apply(items)
+}
 
-    let indices: Vec<usize> = (0..n).collect();
-    let sorted = ford_johnson(indices, &mut |a, b| better(&items[a], &items[b]));
+/// Ranks `items` like [`rank_items`] with a comparator that can fail.
+///
+/// # Errors
+///
+/// Stops at the first error `better` returns, as [`Error::Comparator`].
+pub fn try_rank_items<T, F, E>(items: Vec<T>, better: F) -> Result<Ranking<T>, Error>
+where
+    F: FnMut(&T, &T) -> Result<bool, E>,
+    E: Into<error::BoxError>,
+{
+    try_rank_with_budget(items, usize::MAX, better)
+}
+
+/// Ranks `items` like [`try_rank_items`], but fails instead of returning a
+/// partial ranking once `max_comparisons` answers have been given, unlike
+/// [`rank_with_budget`].
+///
+/// # Errors
+///
+/// Fails with [`Error::BudgetExhausted`] if the ranking needs more
+/// questions, and with [`Error::Comparator`] at the first error `better`
+/// returns.
+pub fn try_rank_with_budget<T, F, E>(
+    items: Vec<T>,
+    max_comparisons: usize,
+    mut better: F,
+) -> Result<Ranking<T>, Error>
+where
+    F: FnMut(&T, &T) -> Result<bool, E>,
+    E: Into<error::BoxError>,
+{
+    let mut stepper = Stepper::new(items.len());
+    let mut step = stepper.step();
+    while let Step::Compare { a, b, .. } = step {
+        if stepper.comparisons_made() >= max_comparisons {
+            return Err(Error::BudgetExhausted {
+                budget: max_comparisons,
+            });
+        }
+        // Put the question as `rank_permutation` does, for stability.
+        let a_better = if a < b {
+            better(&items[b], &items[a]).map(|b_better| !b_better)
+        } else {
+            better(&items[a], &items[b])
+        };
+        step = stepper.answer(a_better.map_err(|e| Error::Comparator(e.into()))?);
+    }
+    // The loop only ends once the ranking is done, so this is the final
+    // order.
+    Ok(stepper.finalize_partial().apply(items))
+}
 
-    let mut slots: Vec<Option<T>> = items.into_iter().map(Some).collect();
-    sorted
-        .into_iter()
-        .map(|i| slots[i].take().expect("each index used exactly once"))
-        .collect()
+/// Rearranges `items` so that position `i` holds the item previously at
+/// `order[i]`. Consumes `order` as scratch space to mark finished slots.
+fn apply_order<T>(items: &mut [T], mut order: Vec<usize>) {
+    const DONE: usize = usize::MAX;
+    for start in 0..items.len() {
+        let mut slot = start;
+        loop {
+            let source = order[slot];
+            if source == DONE {
+                break;
+            }
+            order[slot] = DONE;
+            if source == start {
+                break;
+            }
+            items.swap(slot, source);
+            slot = source;
+        }
+    }
 }
 
+/// Largest `n` that [`optimal_turns`] has a value for.
+const OPTIMAL_MAX: usize = 256;
+
+/// `OPTIMAL_TURNS[n]` is [`optimal_turns`]`(n)`.
+const OPTIMAL_TURNS: [usize; OPTIMAL_MAX + 1] = merge_insertion_turns();
+
 /// Returns an upper-bound estimate of the number of comparisons (turns)
 /// `rank_items` may need for `n` items.
 ///
-/// The estimate assumes worst-case paths in binary searches. Actual turns
-/// can be lower depending on the comparator outcomes.
+/// Up to [`optimal_turns`]'s limit this is the exact worst case. Beyond it,
+/// the estimate assumes every binary search spans the whole chain, which
+/// overestimates a little. Actual turns can be lower depending on the
+/// comparator outcomes.
 #[must_use]
 pub fn estimate_turns(n: usize) -> usize {
+    optimal_turns(n).unwrap_or_else(|| chain_bound(n))
+}
+
+/// The worst-case number of comparisons merge insertion makes on `n`
+/// items, `F(n) = Σ ⌈log2(3k/4)⌉` for `k` in `1..=n`, which is what
+/// [`rank_items`] and the [`Stepper`] ask at most. For `n ≤ 11` (and some
+/// larger `n`) no comparison sort does better.
+///
+/// `None` above 256 items, where [`estimate_turns`] falls back to a looser
+/// bound.
+#[must_use]
+pub fn optimal_turns(n: usize) -> Option<usize> {
+    OPTIMAL_TURNS.get(n).copied()
+}
+
+const fn merge_insertion_turns() -> [usize; OPTIMAL_MAX + 1] {
+    let mut table = [0; OPTIMAL_MAX + 1];
+    let mut k = 1;
+    while k <= OPTIMAL_MAX {
+        // ⌈log2(3k/4)⌉: the smallest `c` with 4 * 2^c >= 3k.
+        let mut c = 0;
+        while 4 << c < 3 * k {
+            c += 1;
+        }
+        table[k] = table[k - 1] + c;
+        k += 1;
+    }
+    table
+}
+
+/// Upper bound on the comparisons for `n` items that assumes each binary
+/// search spans the whole chain built so far. Looser than [`optimal_turns`],
+/// but it follows the recursion, so the stepper can shrink it as answers
+/// come in.
+pub(crate) fn chain_bound(n: usize) -> usize {
     if n <= 1 {
         return 0;
     }
 
     let num_pairs = n / 2;
-    let mut total = num_pairs + estimate_turns(num_pairs);
+    let mut total = num_pairs + chain_bound(num_pairs);
 
     // After the initial chain is built, we insert the remaining elements.
     // Each insertion performs a binary search over a prefix of the chain.
@@ -56,73 +322,147 @@ pub fn estimate_turns(n: usize) -> usize {
 
 /// Sorts a vec of element IDs using Ford-Johnson.
 /// `cmp(a, b)` returns true when `a` should rank before `b`.
+///
+/// Lists of up to five elements are sorted from precomputed
+/// decision trees that ask the same questions as [`merge_insertion`].
 fn ford_johnson(elements: Vec<usize>, cmp: &mut impl FnMut(usize, usize) -> bool) -> Vec<usize> {
+    small::sort(&elements, cmp).unwrap_or_else(|| merge_insertion(elements, cmp))
+}
+
+/// The general recursion behind [`ford_johnson`].
+///
+/// The recursion is unrolled into two passes: all pairing rounds top-down,
+/// then all insertion rounds bottom-up, which asks the same questions in the
+/// same order as the recursive formulation. Each level refers to its
+/// elements by position (`0..len`): element `p` of a level is the main of
+/// pair `p` of the level above, so no lookup table keyed by element ID is
+/// needed and IDs can be arbitrarily large.
+fn merge_insertion(elements: Vec<usize>, cmp: &mut impl FnMut(usize, usize) -> bool) -> Vec<usize> {
     let n = elements.len();
     if n <= 1 {
         return elements;
     }
 
-    // Step 1: Pair up and compare. The worse element of each pair ("main")
-    // goes into the recursive step; the better element ("partner") gets a
-    // free insertion later because partner < main.
-    let num_pairs = n / 2;
-    let max_elem = elements.iter().copied().max().unwrap_or(0);
-    let mut mains = Vec::with_capacity(num_pairs);
-    let mut partner_of = vec![0usize; max_elem + 1];
-
-    for i in 0..num_pairs {
-        let (a, b) = (elements[2 * i], elements[2 * i + 1]);
-        if cmp(a, b) {
-            mains.push(b);
-            partner_of[b] = a;
-        } else {
-            mains.push(a);
-            partner_of[a] = b;
+    // Step 1: Pair up and compare, level by level. The worse element of each
+    // pair ("main") moves on to the next level; the better element
+    // ("partner") gets a free insertion later because partner < main.
+    // Pairs of every level are stored back to back: `main_pos` holds the
+    // main's position within its level (the partner is its neighbour at
+    // `pos ^ 1`) and `main_ids` the main's element ID.
+    let mut main_pos = Vec::with_capacity(n);
+    let mut main_ids = Vec::with_capacity(n);
+    let mut levels: Vec<Level> = Vec::new();
+    let mut len = n;
+    while len > 1 {
+        let parent_start = levels.last().map(|l| l.start);
+        let start = main_pos.len();
+        let num_pairs = len / 2;
+        for i in 0..num_pairs {
+            let (a, b) = match parent_start {
+                Some(s) => (main_ids[s + 2 * i], main_ids[s + 2 * i + 1]),
+                None => (elements[2 * i], elements[2 * i + 1]),
+            };
+            if cmp(a, b) {
+                main_pos.push(2 * i + 1);
+                main_ids.push(b);
+            } else {
+                main_pos.push(2 * i);
+                main_ids.push(a);
+            }
         }
+        levels.push(Level {
+            start,
+            len,
+            num_pairs,
+        });
+        len = num_pairs;
     }
-    let straggler = if n % 2 == 1 {
-        Some(elements[n - 1])
-    } else {
-        None
-    };
-
-    // Step 2: Recursively sort the main (worse) elements.
-    let sorted_mains = ford_johnson(mains, cmp);
 
-    // Step 3: Build initial chain.
-    // partner[sorted_mains[0]] is better than sorted_mains[0], which is better
-    // than sorted_mains[1], etc. So the partner goes at the front for free.
+    // Step 2: The deepest level holds a single element, which is sorted.
+    // Walk back up, each level's sorted mains becoming the next chain.
     let mut chain = Vec::with_capacity(n);
-    chain.push(partner_of[sorted_mains[0]]);
-    chain.extend_from_slice(&sorted_mains);
-
-    // Step 4: Collect remaining partners (and straggler) for insertion.
-    // Each partner is better than its main, so we only search before the
-    // main's current position in the chain.
-    let mut pending: Vec<(usize, Option<usize>)> = Vec::new();
-    for &m in sorted_mains.iter().skip(1) {
-        pending.push((partner_of[m], Some(m)));
-    }
-    if let Some(s) = straggler {
-        pending.push((s, None));
-    }
-
-    // Step 5: Insert in Jacobsthal order so each binary search operates on
-    // a range of size 2^k - 1, wasting zero information per comparison.
-    for i in jacobsthal_order(pending.len()) {
-        let (elem, main) = pending[i];
-        let bound = match main {
-            Some(m) => chain.iter().position(|&x| x == m).unwrap(),
-            None => chain.len(),
-        };
-        let pos = binary_search_pos(&chain[..bound], elem, cmp);
-        chain.insert(pos, elem);
+    chain.push(0);
+    let mut next = Vec::with_capacity(n);
+    let mut arena = Arena::new(n);
+    let mut pending: Vec<(usize, Option<usize>)> = Vec::with_capacity(n / 2 + 1);
+    let mut order = Vec::with_capacity(n / 2 + 1);
+
+    for k in (0..levels.len()).rev() {
+        let level = &levels[k];
+        let ids = level_ids(&elements, &main_ids, k.checked_sub(1).map(|j| &levels[j]));
+        let mains = &main_pos[level.start..level.start + level.num_pairs];
+        let sorted_mains = &chain;
+
+        // Step 3: Build initial chain.
+        // partner[sorted_mains[0]] is better than sorted_mains[0], which is
+        // better than sorted_mains[1], etc. So the partner goes at the front
+        // for free.
+        next.clear();
+        next.push(mains[sorted_mains[0]] ^ 1);
+        next.extend(sorted_mains.iter().map(|&p| mains[p]));
+
+        // Step 4: Collect remaining partners (and straggler) for insertion.
+        // Each partner is better than its main, so we only search before the
+        // main's current position in the chain. Mains are referred to by
+        // their index in `sorted_mains`, which `positions` maps to their
+        // current chain index.
+        pending.clear();
+        for (j, &p) in sorted_mains.iter().enumerate().skip(1) {
+            pending.push((mains[p] ^ 1, Some(j)));
+        }
+        arena.start(&mut next, sorted_mains.len());
+        let Arena {
+            chain: inserted,
+            positions,
+        } = &mut arena;
+        if level.len % 2 == 1 {
+            pending.push((level.len - 1, None));
+        }
+
+        // Step 5: Insert in Jacobsthal order so each binary search operates
+        // on a range of size 2^k - 1, wasting zero information per
+        // comparison.
+        jacobsthal_order_into(pending.len(), &mut order);
+        for &i in &order {
+            let (elem, main) = pending[i];
+            let bound = match main {
+                Some(j) => positions.position(j),
+                None => inserted.len(),
+            };
+            let pos = binary_search_pos(inserted, bound, elem, &mut |x, y| cmp(ids[x], ids[y]));
+            inserted.insert(pos, elem);
+            positions.record_insert(pos);
+        }
+
+        arena.finish(&mut next);
+        std::mem::swap(&mut chain, &mut next);
     }
 
-    chain
+    chain.into_iter().map(|p| elements[p]).collect()
+}
+
+/// Pairing round of one recursion level of [`ford_johnson`].
+struct Level {
+    /// Offset of this level's pairs in the shared pair buffers.
+    start: usize,
+    len: usize,
+    num_pairs: usize,
 }
 
-fn ceil_log2(value: usize) -> usize {
+/// Element IDs of the level below `parent`, or of the top level when
+/// `parent` is `None`.
+fn level_ids<'a>(
+    elements: &'a [usize],
+    main_ids: &'a [usize],
+    parent: Option<&Level>,
+) -> &'a [usize] {
+    match parent {
+        Some(p) => &main_ids[p.start..p.start + p.num_pairs],
+        None => elements,
+    }
+}
+
+pub(crate) fn ceil_log2(value: usize) -> usize {
     if value <= 1 {
         return 0;
     }
@@ -135,15 +475,17 @@ fn ceil_log2(value: usize) -> usize {
     bits
 }
 
+/// Finds where `element` belongs among `chain[..hi]`.
 fn binary_search_pos(
-    range: &[usize],
+    chain: &Chain,
+    hi: usize,
     element: usize,
     cmp: &mut impl FnMut(usize, usize) -> bool,
 ) -> usize {
-    let (mut lo, mut hi) = (0, range.len());
+    let (mut lo, mut hi) = (0, hi);
     while lo < hi {
         let mid = lo + (hi - lo) / 2;
-        if cmp(element, range[mid]) {
+        if cmp(element, chain.get(mid)) {
             hi = mid;
         } else {
             lo = mid + 1;
@@ -156,13 +498,20 @@ fn binary_search_pos(
 /// Jacobsthal numbers for optimal insertion.
 #[must_use]
 pub fn jacobsthal_order(count: usize) -> Vec<usize> {
+    let mut order = Vec::with_capacity(count);
+    jacobsthal_order_into(count, &mut order);
+    order
+}
+
+/// Fills `order` with [`jacobsthal_order`]`(count)`, reusing its allocation.
+fn jacobsthal_order_into(count: usize, order: &mut Vec<usize>) {
+    order.clear();
     if count == 0 {
-        return Vec::new();
+        return;
     }
     // Jacobsthal boundaries (b-notation, 1-indexed): 1, 3, 5, 11, 21, 43, ...
     // Each group inserts from boundary[k] down to boundary[k-1]+1.
     // pending[i] corresponds to b_{i+2}, so b_k maps to index k-2.
-    let mut order = Vec::with_capacity(count);
     let (mut prev, mut curr) = (1usize, 3usize);
     loop {
         let top = curr.min(count + 1);
@@ -176,12 +525,15 @@ pub fn jacobsthal_order(count: usize) -> Vec<usize> {
         prev = curr;
         curr = next;
     }
-    order
 }
 
 #[cfg(test)]
 mod tests {
-    use super::rank_items;
+    use super::{
+        Error, Step, Stepper, chain_bound, estimate_turns, ford_johnson, optimal_turns,
+        rank_in_place, rank_indices, rank_items, rank_permutation, rank_refs, rank_with_budget,
+        try_rank_items, try_rank_with_budget,
+    };
 
     #[test]
     fn ranks_numbers_ascending() {
@@ -199,6 +551,52 @@ mod tests {
         assert_eq!(ranked, vec!["a", "c", "aa", "cc", "bbb"]);
     }
 
+    #[test]
+    fn ranks_large_input() {
+        // Deterministic shuffle via a multiplicative permutation of 0..n.
+        let n = 10_007usize;
+        let items: Vec<usize> = (0..n).map(|i| i * 7919 % n).collect();
+        let ranked = rank_items(items, |a, b| a < b);
+        assert_eq!(ranked, (0..n).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn ranks_in_place_for_every_permutation() {
+        let mut values: Vec<usize> = (0..6).collect();
+        permute(&mut values, 6, &mut |perm| {
+            let mut items: Vec<String> = perm.iter().map(ToString::to_string).collect();
+            rank_in_place(&mut items, |a, b| a < b);
+            assert_eq!(items, ["0", "1", "2", "3", "4", "5"], "{perm:?}");
+        });
+    }
+
+    #[test]
+    fn permutation_indexes_external_data() {
+        let scores = [30, 10, 40, 20];
+        let order = rank_permutation(scores.len(), |a, b| scores[a] > scores[b]);
+        assert_eq!(order, vec![2, 0, 3, 1]);
+        assert!(rank_permutation(0, |_, _| unreachable!()).is_empty());
+    }
+
+    #[test]
+    fn references_rank_like_the_items() {
+        let words = ["kiwi", "fig", "plum", "date", "banana"];
+        let by_length = |a: &&str, b: &&str| a.len() < b.len();
+        let ranked = rank_refs(&words, by_length);
+        assert_eq!(
+            ranked.map(|word| *word),
+            rank_items(words.to_vec(), by_length)
+        );
+        assert_eq!(rank_indices(&words, by_length), vec![1, 0, 2, 3, 4]);
+    }
+
+    #[test]
+    fn sorts_sparse_ids_without_dense_tables() {
+        let ids = vec![usize::MAX - 1, 5, usize::MAX, 0, 1 << 40];
+        let sorted = ford_johnson(ids, &mut |a, b| a < b);
+        assert_eq!(sorted, vec![0, 5, 1 << 40, usize::MAX - 1, usize::MAX]);
+    }
+
     #[test]
     fn worst_case_comparisons_are_optimal() {
         let optimal = [0, 0, 1, 3, 5, 7, 10, 13, 16];
@@ -217,10 +615,93 @@ mod tests {
                 worst = worst.max(count);
             });
             assert_eq!(worst, opt, "n={n}: worst={worst}, optimal={opt}");
+            assert_eq!(optimal_turns(n), Some(opt));
+        }
+    }
+
+    #[test]
+    fn stepper_asks_what_rank_items_asks_for_every_permutation() {
+        for n in 0..=8 {
+            let mut values: Vec<usize> = (0..n).collect();
+            permute(&mut values, n, &mut |values| {
+                // Questions as unordered pairs: `rank_items` puts the later
+                // item first, the stepper does not.
+                let mut expected = Vec::new();
+                let ranked = rank_items((0..n).collect(), |&a: &usize, &b| {
+                    expected.push((a.min(b), a.max(b)));
+                    values[a] < values[b]
+                });
+                let mut stepper = Stepper::new(n);
+                let mut asked = Vec::new();
+                let mut step = stepper.step();
+                while let Step::Compare { a, b, .. } = step {
+                    asked.push((a.min(b), a.max(b)));
+                    step = stepper.answer(values[a] < values[b]);
+                }
+                assert_eq!(asked, expected, "{values:?}");
+                assert_eq!(stepper.take_order(), Some(ranked), "{values:?}");
+            });
+        }
+    }
+
+    #[test]
+    fn estimate_is_the_merge_insertion_worst_case() {
+        // F(n) for n in 9..=20.
+        let known = [19, 22, 26, 30, 34, 38, 42, 46, 50, 54, 58, 62];
+        for (n, &turns) in (9..).zip(&known) {
+            assert_eq!(estimate_turns(n), turns);
         }
+        assert!(estimate_turns(10) < chain_bound(10));
+        assert_eq!(optimal_turns(257), None);
+        assert_eq!(estimate_turns(257), chain_bound(257));
     }
 
-    fn permute(items: &mut [usize], k: usize, f: &mut impl FnMut(&[usize])) {
+    #[test]
+    fn equal_items_keep_input_order() {
+        // Every assignment of three keys to up to seven items.
+        for n in 0..=7u32 {
+            for code in 0..3usize.pow(n) {
+                let keys: Vec<usize> = (0..n).map(|i| code / 3usize.pow(i) % 3).collect();
+                let items: Vec<(usize, usize)> = keys.iter().copied().zip(0..).collect();
+                let ranked = rank_items(items.clone(), |a, b| a.0 < b.0);
+                let mut expected = items;
+                expected.sort_by_key(|&(key, _)| key);
+                assert_eq!(ranked, expected, "{keys:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn budget_keeps_every_answer_given() {
+        let mut items: Vec<usize> = (0..6).collect();
+        permute(&mut items, 6, &mut |perm| {
+            let full = rank_items(perm.to_vec(), |a, b| a < b);
+            for budget in 0..=estimate_turns(6) {
+                let mut answers = Vec::new();
+                let ranked = rank_with_budget(perm.to_vec(), budget, |&a, &b| {
+                    answers.push((a.min(b), a.max(b)));
+                    a < b
+                });
+                assert!(answers.len() <= budget, "{perm:?}");
+                for (better, worse) in answers {
+                    assert!(ranked.rank_of(&better) < ranked.rank_of(&worse), "{perm:?}");
+                }
+                if ranked.groups().len() == 6 {
+                    assert_eq!(ranked, full.to_vec(), "{perm:?}");
+                }
+            }
+            let unlimited = rank_with_budget(perm.to_vec(), usize::MAX, |a, b| a < b);
+            assert_eq!(unlimited, full);
+        });
+        assert_eq!(
+            rank_with_budget(vec![3, 1], 0, |a, b| a < b).groups().len(),
+            1
+        );
+    }
+
+    /// Calls `f` with every order of the first `k` of `items`, by Heap's
+    /// algorithm.
+    pub(crate) fn permute(items: &mut [usize], k: usize, f: &mut impl FnMut(&[usize])) {
         if k <= 1 {
             f(items);
             return;
@@ -249,4 +730,23 @@ mod tests {
             println!("n={n}: min={lo} max={hi}");
         }
     }
+
+    #[test]
+    fn fallible_runs_report_what_stopped_them() {
+        let ranked = try_rank_items(vec![5, 2, 9], |a, b| Ok::<_, Error>(a < b)).unwrap();
+        assert_eq!(ranked, vec![2, 5, 9]);
+
+        let offline = try_rank_items(vec![1, 2], |_, _| Err::<bool, _>("judge offline"));
+        assert!(matches!(offline, Err(Error::Comparator(e)) if e.to_string() == "judge offline"));
+
+        let short = try_rank_with_budget(vec![5, 2, 9, 1], 2, |a, b| Ok::<_, Error>(a < b));
+        assert!(matches!(short, Err(Error::BudgetExhausted { budget: 2 })));
+
+        let mut stepper = Stepper::new(4);
+        assert!(matches!(
+            stepper.try_answer_question(0, 3, true),
+            Err(Error::InvalidAnswer { a: 0, b: 3 })
+        ));
+        assert!(stepper.try_answer_question(1, 0, true).is_ok());
+    }
 }
@@ -0,0 +1,198 @@
+//! Rank correlation between two orderings of the same items.
+//!
+//! Every function takes two orderings, best first, and compares the places
+//! of the items they share; items in only one of them are left out, and a
+//! repeated item counts at its first place. Correlations run from 1 (same
+//! order) to -1 (reversed), and are 1 when fewer than two items are shared,
+//! since nothing disagrees.
+//!
+//! The weighted variants count disagreements near the top of the first
+//! ordering more: place `p` (from 0) weighs `1 / (p + 1)`, so swapping the
+//! first two items matters far more than swapping the last two. This suits
+//! rankings where only the top is read carefully, like consensus rankings
+//! from [`aggregate`](crate::aggregate) or a [`diff`](crate::diff()) between
+//! two runs.
+
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+/// Kendall tau distance: pairs of shared items the orderings put in
+/// opposite orders. Counted in `O(n log n)`.
+#[must_use]
+pub fn kendall_distance<T: Eq + Hash>(a: &[T], b: &[T]) -> usize {
+    let mut places: Vec<usize> = shared_places(a, b).into_iter().map(|(_, pb)| pb).collect();
+    inversions(&mut places)
+}
+
+/// Kendall's tau (tau-a): the share of concordant minus discordant pairs.
+#[must_use]
+#[allow(clippy::cast_precision_loss)] // pair counts are far below 2^52
+pub fn kendall_tau<T: Eq + Hash>(a: &[T], b: &[T]) -> f64 {
+    let mut places: Vec<usize> = shared_places(a, b).into_iter().map(|(_, pb)| pb).collect();
+    let pairs = places.len() * places.len().saturating_sub(1) / 2;
+    if pairs == 0 {
+        return 1.0;
+    }
+    1.0 - 2.0 * inversions(&mut places) as f64 / pairs as f64
+}
+
+/// Spearman's rho: how well the places of the shared items agree, from
+/// their squared differences.
+#[must_use]
+pub fn spearman_rho<T: Eq + Hash>(a: &[T], b: &[T]) -> f64 {
+    weighted_pearson(&shared_places(a, b), |_| 1.0)
+}
+
+/// Kendall's tau with each pair weighing the sum of its two items' weights
+/// (Vigna's additive hyperbolic weighting), by place in `a`.
+#[must_use]
+#[allow(clippy::cast_precision_loss)] // places are far below 2^52
+pub fn weighted_kendall_tau<T: Eq + Hash>(a: &[T], b: &[T]) -> f64 {
+    let places = shared_places(a, b);
+    if places.len() < 2 {
+        return 1.0;
+    }
+    let (mut agree, mut total) = (0.0, 0.0);
+    for (i, &(ai, bi)) in places.iter().enumerate() {
+        for &(aj, bj) in &places[i + 1..] {
+            let weight = 1.0 / (ai + 1) as f64 + 1.0 / (aj + 1) as f64;
+            total += weight;
+            if (ai < aj) == (bi < bj) {
+                agree += weight;
+            } else {
+                agree -= weight;
+            }
+        }
+    }
+    agree / total
+}
+
+/// Spearman's rho as the correlation of the two places, each item weighing
+/// by its place in `a`.
+#[must_use]
+#[allow(clippy::cast_precision_loss)] // places are far below 2^52
+pub fn weighted_spearman_rho<T: Eq + Hash>(a: &[T], b: &[T]) -> f64 {
+    weighted_pearson(&shared_places(a, b), |pa| 1.0 / (pa + 1) as f64)
+}
+
+/// `(place in a, place in b)` of the shared items, both counted among the
+/// shared items only, in `a`'s order.
+fn shared_places<T: Eq + Hash>(a: &[T], b: &[T]) -> Vec<(usize, usize)> {
+    let mut in_b: HashMap<&T, usize> = HashMap::new();
+    for (place, item) in b.iter().enumerate() {
+        in_b.entry(item).or_insert(place);
+    }
+    let mut seen = HashSet::new();
+    let mut pairs: Vec<(usize, usize)> = Vec::new();
+    for item in a {
+        if let Some(&place) = in_b.get(item)
+            && seen.insert(item)
+        {
+            pairs.push((pairs.len(), place));
+        }
+    }
+    // Places in `b` among the shared items only.
+    let mut by_b: Vec<usize> = (0..pairs.len()).collect();
+    by_b.sort_by_key(|&i| pairs[i].1);
+    for (place, i) in by_b.into_iter().enumerate() {
+        pairs[i].1 = place;
+    }
+    pairs
+}
+
+/// Pearson correlation of the two places, with `weight` by place in `a`;
+/// 1 for fewer than two items.
+#[allow(clippy::cast_precision_loss)] // places are far below 2^52
+fn weighted_pearson(places: &[(usize, usize)], weight: impl Fn(usize) -> f64) -> f64 {
+    if places.len() < 2 {
+        return 1.0;
+    }
+    let weights: Vec<f64> = places.iter().map(|&(pa, _)| weight(pa)).collect();
+    let total: f64 = weights.iter().sum();
+    let mean = |pick: fn(&(usize, usize)) -> usize| {
+        places
+            .iter()
+            .zip(&weights)
+            .map(|(p, w)| w * pick(p) as f64)
+            .sum::<f64>()
+            / total
+    };
+    let (mean_a, mean_b) = (mean(|p| p.0), mean(|p| p.1));
+    let (mut cov, mut var_a, mut var_b) = (0.0, 0.0, 0.0);
+    for (&(pa, pb), w) in places.iter().zip(&weights) {
+        let (da, db) = (pa as f64 - mean_a, pb as f64 - mean_b);
+        cov += w * da * db;
+        var_a += w * da * da;
+        var_b += w * db * db;
+    }
+    cov / (var_a * var_b).sqrt()
+}
+
+/// Pairs out of order in `values`, which ends up sorted.
+fn inversions(values: &mut [usize]) -> usize {
+    if values.len() < 2 {
+        return 0;
+    }
+    let mid = values.len() / 2;
+    let mut count = inversions(&mut values[..mid]) + inversions(&mut values[mid..]);
+    let mut merged = Vec::with_capacity(values.len());
+    let (mut i, mut j) = (0, mid);
+    while i < mid && j < values.len() {
+        if values[j] < values[i] {
+            // Everything left in the first half is larger.
+            count += mid - i;
+            merged.push(values[j]);
+            j += 1;
+        } else {
+            merged.push(values[i]);
+            i += 1;
+        }
+    }
+    merged.extend_from_slice(&values[i..mid]);
+    merged.extend_from_slice(&values[j..]);
+    values.copy_from_slice(&merged);
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        kendall_distance, kendall_tau, spearman_rho, weighted_kendall_tau, weighted_spearman_rho,
+    };
+
+    fn close(x: f64, y: f64) -> bool {
+        (x - y).abs() < 1e-9
+    }
+
+    #[test]
+    fn same_and_reversed_orders_are_the_extremes() {
+        let forward = [0, 1, 2, 3, 4];
+        let backward = [4, 3, 2, 1, 0];
+        assert_eq!(kendall_distance(&forward, &backward), 10);
+        for metric in [
+            kendall_tau::<i32>,
+            spearman_rho,
+            weighted_kendall_tau,
+            weighted_spearman_rho,
+        ] {
+            assert!(close(metric(&forward, &forward), 1.0));
+            assert!(close(metric(&forward, &backward), -1.0));
+        }
+        assert!(close(kendall_tau(&[1], &[1]), 1.0));
+    }
+
+    #[test]
+    fn only_shared_items_count_and_the_top_weighs_more() {
+        // One adjacent swap among four shared items; 9 and 7 are ignored.
+        let a = [0, 1, 9, 2, 3];
+        let b = [1, 0, 2, 7, 3];
+        assert_eq!(kendall_distance(&a, &b), 1);
+        assert!(close(kendall_tau(&a, &b), 1.0 - 2.0 / 6.0));
+        assert!(close(spearman_rho(&a, &b), 1.0 - 6.0 * 2.0 / 60.0));
+
+        let bottom_swapped = [0, 1, 3, 2];
+        assert!(weighted_kendall_tau(&a, &b) < weighted_kendall_tau(&a, &bottom_swapped));
+        assert!(weighted_spearman_rho(&a, &b) < weighted_spearman_rho(&a, &bottom_swapped));
+        assert!(close(kendall_tau(&a, &b), kendall_tau(&a, &bottom_swapped)));
+    }
+}
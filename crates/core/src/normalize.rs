@@ -0,0 +1,102 @@
+//! Cleaning up a user-entered item list before ranking.
+//!
+//! Duplicates cost comparisons without adding information: the user is asked
+//! to choose between two identical options, and whichever answer they give
+//! puts two copies of the same thing at different places. [`normalize_items`]
+//! trims and collapses whitespace, drops blank entries and merges items that
+//! only differ in case or spacing, reporting what it merged so frontends can
+//! tell the user.
+
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+
+/// An input entry merged into an earlier one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Duplicate {
+    /// The entry as it was entered, with whitespace collapsed.
+    pub text: String,
+    /// Index into [`Normalized::items`] of the item it was merged into.
+    pub kept: usize,
+}
+
+/// Result of [`normalize_items`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Normalized {
+    /// Distinct items in input order, each spelled as it was first entered
+    /// (with whitespace collapsed).
+    pub items: Vec<String>,
+    /// Entries dropped because an earlier item matched them, in input order.
+    pub duplicates: Vec<Duplicate>,
+}
+
+/// Trims every entry, collapses runs of whitespace to a single space, drops
+/// empty entries and merges entries that are equal ignoring case. The first
+/// spelling of each item is kept.
+#[must_use]
+pub fn normalize_items<I, S>(items: I) -> Normalized
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut normalized = Normalized::default();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for item in items {
+        let item = collapse_whitespace(item.as_ref());
+        if item.is_empty() {
+            continue;
+        }
+        match seen.entry(item.to_lowercase()) {
+            Entry::Occupied(entry) => normalized.duplicates.push(Duplicate {
+                text: item,
+                kept: *entry.get(),
+            }),
+            Entry::Vacant(entry) => {
+                entry.insert(normalized.items.len());
+                normalized.items.push(item);
+            }
+        }
+    }
+    normalized
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Duplicate, normalize_items};
+
+    #[test]
+    fn merges_case_and_spacing_variants() {
+        let normalized = normalize_items([
+            "Pizza",
+            "  sushi ",
+            "",
+            "PIZZA",
+            "Ice  cream",
+            "ice cream\t",
+        ]);
+        assert_eq!(normalized.items, vec!["Pizza", "sushi", "Ice cream"]);
+        assert_eq!(
+            normalized.duplicates,
+            vec![
+                Duplicate {
+                    text: "PIZZA".to_string(),
+                    kept: 0
+                },
+                Duplicate {
+                    text: "ice cream".to_string(),
+                    kept: 2
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn distinct_items_pass_through() {
+        let normalized = normalize_items(vec!["a".to_string(), "b".to_string(), "ä".to_string()]);
+        assert_eq!(normalized.items, vec!["a", "b", "ä"]);
+        assert!(normalized.duplicates.is_empty());
+    }
+}
@@ -0,0 +1,259 @@
+//! Hooks into a run, for logging, metrics or animations.
+//!
+//! An [`Observer`] hears about every question, answer and finished
+//! insertion of a run without touching the sort itself. Attach one to a
+//! [`Stepper`] with [`ObservedStepper`], or to a whole sort with
+//! [`rank_items_observed`]:
+//!
+//! ```
+//! use rankfast::observe::{Observer, rank_items_observed};
+//!
+//! #[derive(Default)]
+//! struct Count(usize);
+//!
+//! impl Observer for Count {
+//!     fn on_answer(&mut self, _: usize, _: usize, _: bool) {
+//!         self.0 += 1;
+//!     }
+//! }
+//!
+//! let mut count = Count::default();
+//! let ranking = rank_items_observed(vec![3, 1, 2], |a, b| a > b, &mut count);
+//! assert_eq!(*ranking, [3, 2, 1]);
+//! assert_eq!(count.0, 3);
+//! ```
+//!
+//! Questions a stepper answers itself, from
+//! [known pairs](Stepper::with_known_pairs), are not reported.
+
+use crate::{Context, Phase, Ranking, Step, Stepper};
+
+/// Callbacks for the events of a run. Every method does nothing by default.
+pub trait Observer {
+    /// Item `a` is about to be compared with item `b`.
+    fn on_question(&mut self, a: usize, b: usize, context: Context) {
+        let _ = (a, b, context);
+    }
+
+    /// The answer to the question about `a` and `b`.
+    fn on_answer(&mut self, a: usize, b: usize, a_is_better: bool) {
+        let _ = (a, b, a_is_better);
+    }
+
+    /// The binary search for `item`'s place among the items ranked at
+    /// `depth` is over, see [`Context::depth`].
+    fn on_insert(&mut self, item: usize, depth: usize) {
+        let _ = (item, depth);
+    }
+
+    /// The run is complete.
+    fn on_done(&mut self, ranking: &Ranking<usize>) {
+        let _ = ranking;
+    }
+}
+
+impl<O: Observer + ?Sized> Observer for &mut O {
+    fn on_question(&mut self, a: usize, b: usize, context: Context) {
+        (**self).on_question(a, b, context);
+    }
+
+    fn on_answer(&mut self, a: usize, b: usize, a_is_better: bool) {
+        (**self).on_answer(a, b, a_is_better);
+    }
+
+    fn on_insert(&mut self, item: usize, depth: usize) {
+        (**self).on_insert(item, depth);
+    }
+
+    fn on_done(&mut self, ranking: &Ranking<usize>) {
+        (**self).on_done(ranking);
+    }
+}
+
+/// A [`Stepper`] that reports to an [`Observer`].
+#[derive(Clone)]
+pub struct ObservedStepper<O> {
+    stepper: Stepper,
+    observer: O,
+    /// The question reported last, until it is answered.
+    asked: Option<(usize, usize, Context)>,
+    reported_done: bool,
+}
+
+impl<O: Observer> ObservedStepper<O> {
+    /// Reports the run of `stepper`, which may be part-way through, to
+    /// `observer`.
+    #[must_use]
+    pub fn new(stepper: Stepper, observer: O) -> Self {
+        Self {
+            stepper,
+            observer,
+            asked: None,
+            reported_done: false,
+        }
+    }
+
+    /// Advances until a comparison is needed or the ranking is complete;
+    /// see [`Stepper::step`].
+    pub fn step(&mut self) -> Step {
+        let step = self.stepper.step();
+        self.report(step)
+    }
+
+    /// Applies the answer to the pending comparison and returns the next
+    /// step; see [`Stepper::answer`].
+    pub fn answer(&mut self, better_is_a: bool) -> Step {
+        self.report_answer(better_is_a);
+        let step = self.stepper.answer(better_is_a);
+        self.report(step)
+    }
+
+    /// Answers the pending comparison as a tie; see
+    /// [`Stepper::answer_tie`].
+    pub fn answer_tie(&mut self) -> Step {
+        if let Some((a, b, _)) = self.asked {
+            self.report_answer(a < b);
+        }
+        let step = self.stepper.answer_tie();
+        self.report(step)
+    }
+
+    #[must_use]
+    pub fn stepper(&self) -> &Stepper {
+        &self.stepper
+    }
+
+    #[must_use]
+    pub fn observer(&self) -> &O {
+        &self.observer
+    }
+
+    pub fn observer_mut(&mut self) -> &mut O {
+        &mut self.observer
+    }
+
+    /// The stepper and the observer, apart.
+    #[must_use]
+    pub fn into_parts(self) -> (Stepper, O) {
+        (self.stepper, self.observer)
+    }
+
+    fn report(&mut self, step: Step) -> Step {
+        match step {
+            Step::Compare { a, b, context } => {
+                if self.asked != Some((a, b, context)) {
+                    self.asked = Some((a, b, context));
+                    self.observer.on_question(a, b, context);
+                }
+            }
+            Step::Done => {
+                if !self.reported_done {
+                    self.reported_done = true;
+                    self.observer.on_done(&self.stepper.finalize_partial());
+                }
+            }
+        }
+        step
+    }
+
+    fn report_answer(&mut self, better_is_a: bool) {
+        let Some((a, b, context)) = self.asked.take() else {
+            return;
+        };
+        self.observer.on_answer(a, b, better_is_a);
+        // The search splits its places as the stepper does, and is over
+        // once one is left.
+        if let Phase::Insertion { candidates } = context.phase {
+            let spread = candidates - 1;
+            let left = if better_is_a {
+                spread / 2 + 1
+            } else {
+                spread - spread / 2
+            };
+            if left == 1 {
+                self.observer.on_insert(a, context.depth);
+            }
+        }
+    }
+}
+
+/// Ranks `items` like [`rank_items`](crate::rank_items), reporting the run
+/// to `observer` with item indices into `items`.
+#[must_use]
+pub fn rank_items_observed<T, F, O>(items: Vec<T>, mut better: F, observer: O) -> Ranking<T>
+where
+    F: FnMut(&T, &T) -> bool,
+    O: Observer,
+{
+    let mut stepper = ObservedStepper::new(Stepper::new(items.len()), observer);
+    let mut step = stepper.step();
+    while let Step::Compare { a, b, .. } = step {
+        // Put the question as `rank_permutation` does, for stability.
+        let a_better = if a < b {
+            !better(&items[b], &items[a])
+        } else {
+            better(&items[a], &items[b])
+        };
+        step = stepper.answer(a_better);
+    }
+    let (mut stepper, _) = stepper.into_parts();
+    let order = stepper
+        .take_order()
+        .unwrap_or_else(|| stepper.finalize_partial());
+    order.apply(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ObservedStepper, Observer, rank_items_observed};
+    use crate::{Context, Ranking, Stepper, rank_items};
+
+    #[derive(Default)]
+    struct Log {
+        questions: Vec<(usize, usize)>,
+        answers: Vec<(usize, usize, bool)>,
+        inserts: Vec<(usize, usize)>,
+        done: Option<Vec<usize>>,
+    }
+
+    impl Observer for Log {
+        fn on_question(&mut self, a: usize, b: usize, _: Context) {
+            self.questions.push((a, b));
+        }
+
+        fn on_answer(&mut self, a: usize, b: usize, a_is_better: bool) {
+            self.answers.push((a, b, a_is_better));
+        }
+
+        fn on_insert(&mut self, item: usize, depth: usize) {
+            self.inserts.push((item, depth));
+        }
+
+        fn on_done(&mut self, ranking: &Ranking<usize>) {
+            assert!(self.done.is_none(), "done twice");
+            self.done = Some(ranking.to_vec());
+        }
+    }
+
+    #[test]
+    fn reports_every_event_once() {
+        let values = vec![4, 7, 1, 0, 6, 3, 2, 5, 9];
+        let mut log = Log::default();
+        let ranking = rank_items_observed(values.clone(), |a, b| a > b, &mut log);
+        assert_eq!(ranking, rank_items(values.clone(), |a, b| a > b));
+        assert_eq!(log.questions.len(), log.answers.len());
+        assert!(!log.inserts.is_empty());
+        for (item, _) in &log.inserts {
+            assert!(log.answers.iter().any(|(a, _, _)| a == item));
+        }
+        assert_eq!(log.done.as_deref().map(<[usize]>::len), Some(values.len()));
+
+        // Asking again without answering is one question.
+        let mut stepper = ObservedStepper::new(Stepper::new(3), Log::default());
+        stepper.step();
+        stepper.step();
+        assert_eq!(stepper.observer().questions.len(), 1);
+        stepper.answer_tie();
+        assert_eq!(stepper.observer().answers.len(), 1);
+    }
+}
@@ -0,0 +1,180 @@
+//! Which parts of a ranking rest on direct answers.
+//!
+//! Ford-Johnson never compares most pairs: their order follows by
+//! transitivity. [`RankOutcome`] pairs the final order with the answers that
+//! produced it so frontends can tell "solid" relations (asked directly) from
+//! inferred ones.
+//!
+//! Any complete, correct sort has compared every pair of neighbours in the
+//! final order — otherwise swapping them would fit the answers just as well.
+//! Inferred neighbours therefore only show up in partial results, while
+//! inferred non-adjacent pairs are the norm.
+
+use std::collections::HashMap;
+
+use crate::rank_permutation;
+use crate::ratings::{Rated, score_ranking};
+
+/// How the relative order of two items was established.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Evidence {
+    /// The two items were compared against each other.
+    Direct,
+    /// The order follows from other answers by transitivity.
+    Inferred,
+}
+
+/// A final order of item indices together with the answer history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RankOutcome {
+    order: Vec<usize>,
+    history: Vec<(usize, usize, bool)>,
+    answers: HashMap<(usize, usize), bool>,
+}
+
+impl RankOutcome {
+    /// Builds an outcome from a final `order` and the `(a, b, a_is_better)`
+    /// answers asked along the way.
+    #[must_use]
+    pub fn new(order: Vec<usize>, history: Vec<(usize, usize, bool)>) -> Self {
+        let answers = history
+            .iter()
+            .map(|&(a, b, a_better)| ((a, b), a_better))
+            .collect();
+        Self {
+            order,
+            history,
+            answers,
+        }
+    }
+
+    /// Item indices, best first.
+    #[must_use]
+    pub fn order(&self) -> &[usize] {
+        &self.order
+    }
+
+    /// Every question asked, as `(a, b, a_is_better)`, in order.
+    #[must_use]
+    pub fn history(&self) -> &[(usize, usize, bool)] {
+        &self.history
+    }
+
+    /// Whether items `i` and `j` were compared directly: `Some(true)` if `i`
+    /// was answered better than `j`, `Some(false)` if worse, `None` if they
+    /// never met. The last answer wins if the pair was asked more than once.
+    #[must_use]
+    pub fn direct_evidence(&self, i: usize, j: usize) -> Option<bool> {
+        self.answers
+            .get(&(i, j))
+            .copied()
+            .or_else(|| self.answers.get(&(j, i)).map(|&b| !b))
+    }
+
+    /// How the order of items `i` and `j` was established.
+    #[must_use]
+    pub fn evidence(&self, i: usize, j: usize) -> Evidence {
+        if self.direct_evidence(i, j).is_some() {
+            Evidence::Direct
+        } else {
+            Evidence::Inferred
+        }
+    }
+
+    /// Evidence for each adjacent pair of the final order: entry `k` covers
+    /// positions `k` and `k + 1`.
+    #[must_use]
+    pub fn adjacent_evidence(&self) -> Vec<Evidence> {
+        self.order
+            .windows(2)
+            .map(|w| self.evidence(w[0], w[1]))
+            .collect()
+    }
+
+    /// Strength estimates for each item of the order, best first; see
+    /// [`score_ranking`].
+    #[must_use]
+    pub fn scores(&self) -> Vec<Rated> {
+        score_ranking(&self.order, &self.history)
+    }
+
+    /// Adjacent pairs `(better, worse)` of the final order that were never
+    /// compared directly.
+    #[must_use]
+    pub fn inferred_pairs(&self) -> Vec<(usize, usize)> {
+        self.order
+            .windows(2)
+            .filter(|w| self.direct_evidence(w[0], w[1]).is_none())
+            .map(|w| (w[0], w[1]))
+            .collect()
+    }
+}
+
+/// Ranks `items` like [`rank_items`](crate::rank_items) but keeps the
+/// answer history, returning indices into `items`.
+#[must_use]
+pub fn rank_outcome<T, F>(items: &[T], mut better: F) -> RankOutcome
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let mut history = Vec::new();
+    let order = rank_permutation(items.len(), |a, b| {
+        let a_better = better(&items[a], &items[b]);
+        history.push((a, b, a_better));
+        a_better
+    });
+    RankOutcome::new(order.into_vec(), history)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Evidence, RankOutcome, rank_outcome};
+
+    #[test]
+    fn sorted_pair_is_direct() {
+        let outcome = rank_outcome(&[2, 1], |a, b| a < b);
+        assert_eq!(outcome.order(), &[1, 0]);
+        assert_eq!(outcome.direct_evidence(1, 0), Some(true));
+        assert_eq!(outcome.direct_evidence(0, 1), Some(false));
+        assert_eq!(outcome.adjacent_evidence(), vec![Evidence::Direct]);
+    }
+
+    #[test]
+    fn complete_sort_compares_every_neighbour() {
+        let mut items: Vec<usize> = (0..6).collect();
+        permute(&mut items, 6, &mut |perm| {
+            let outcome = rank_outcome(perm, |a, b| a < b);
+            assert!(outcome.inferred_pairs().is_empty(), "{perm:?}");
+        });
+    }
+
+    #[test]
+    fn distant_pairs_are_inferred() {
+        let outcome = rank_outcome(&[0, 1, 2, 3], |a, b| a < b);
+        assert_eq!(outcome.order(), &[0, 1, 2, 3]);
+        assert_eq!(outcome.evidence(0, 3), Evidence::Inferred);
+        assert_eq!(outcome.evidence(3, 2), Evidence::Direct);
+    }
+
+    #[test]
+    fn partial_history_leaves_inferred_neighbours() {
+        let outcome = RankOutcome::new(vec![0, 1, 2], vec![(0, 1, true), (0, 2, true)]);
+        assert_eq!(
+            outcome.adjacent_evidence(),
+            vec![Evidence::Direct, Evidence::Inferred]
+        );
+        assert_eq!(outcome.inferred_pairs(), vec![(1, 2)]);
+    }
+
+    fn permute(items: &mut [usize], k: usize, f: &mut impl FnMut(&[usize])) {
+        if k <= 1 {
+            f(items);
+            return;
+        }
+        permute(items, k - 1, f);
+        for i in 0..k - 1 {
+            items.swap(if k.is_multiple_of(2) { i } else { 0 }, k - 1);
+            permute(items, k - 1, f);
+        }
+    }
+}
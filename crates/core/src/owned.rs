@@ -0,0 +1,150 @@
+//! The [`Stepper`] for callers who never want to see an index.
+//!
+//! An [`ItemStepper`] owns the items being ranked, asks about them by
+//! reference and hands them back in ranked order:
+//!
+//! ```
+//! use rankfast::{ItemStep, ItemStepper};
+//!
+//! let mut stepper = ItemStepper::new(vec!["pear", "fig", "banana"]);
+//! while let ItemStep::Compare { a, b, .. } = stepper.step() {
+//!     let shorter = a.len() < b.len();
+//!     stepper.answer(shorter);
+//! }
+//! assert_eq!(stepper.into_ranking(), ["fig", "pear", "banana"]);
+//! ```
+
+use crate::{Context, Step, Stepper};
+
+/// What an [`ItemStepper`] needs next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemStep<'a, T> {
+    /// Ask whether item `a` is better than item `b`.
+    Compare {
+        a: &'a T,
+        b: &'a T,
+        context: Context,
+    },
+    /// The ranking is complete.
+    Done,
+}
+
+/// Step-by-step Ford-Johnson sorter that owns its items.
+#[derive(Clone)]
+pub struct ItemStepper<T> {
+    items: Vec<T>,
+    stepper: Stepper,
+}
+
+impl<T> ItemStepper<T> {
+    /// Creates a stepper that ranks `items`.
+    #[must_use]
+    pub fn new(items: Vec<T>) -> Self {
+        Self {
+            stepper: Stepper::new(items.len()),
+            items,
+        }
+    }
+
+    /// The items, in the order given.
+    #[must_use]
+    pub fn items(&self) -> &[T] {
+        &self.items
+    }
+
+    /// Advances until a comparison is needed or the ranking is complete.
+    pub fn step(&mut self) -> ItemStep<'_, T> {
+        let step = self.stepper.step();
+        self.translate(step)
+    }
+
+    /// Applies the answer to the pending comparison and returns the next
+    /// step; see [`Stepper::answer`].
+    pub fn answer(&mut self, better_is_a: bool) -> ItemStep<'_, T> {
+        let step = self.stepper.answer(better_is_a);
+        self.translate(step)
+    }
+
+    /// Answers the pending comparison as a tie; see
+    /// [`Stepper::answer_tie`].
+    pub fn answer_tie(&mut self) -> ItemStep<'_, T> {
+        let step = self.stepper.answer_tie();
+        self.translate(step)
+    }
+
+    /// The positions of the questions that can be answered now, the
+    /// pending one first; see [`Stepper::available_questions`].
+    pub(crate) fn open_questions(&mut self) -> Vec<(usize, usize)> {
+        self.stepper.available_questions()
+    }
+
+    /// The items at positions `a` and `b`.
+    pub(crate) fn pair(&self, a: usize, b: usize) -> (&T, &T) {
+        (&self.items[a], &self.items[b])
+    }
+
+    /// Answers the open question between positions `a` and `b`; see
+    /// [`Stepper::answer_question`].
+    pub(crate) fn answer_pair(&mut self, a: usize, b: usize, a_is_better: bool) {
+        self.stepper.answer_question(a, b, a_is_better);
+    }
+
+    /// The stepper underneath, over the positions of [`items`](Self::items).
+    #[must_use]
+    pub fn stepper(&self) -> &Stepper {
+        &self.stepper
+    }
+
+    /// The items, best first. Before the run is done this is the best
+    /// order the answers so far support, see [`Stepper::finalize_partial`].
+    #[must_use]
+    pub fn into_ranking(self) -> Vec<T> {
+        let order = self.stepper.finalize_partial();
+        let mut slots: Vec<Option<T>> = self.items.into_iter().map(Some).collect();
+        order
+            .iter()
+            .filter_map(|&index| slots[index].take())
+            .collect()
+    }
+
+    fn translate(&self, step: Step) -> ItemStep<'_, T> {
+        match step {
+            Step::Compare { a, b, context } => ItemStep::Compare {
+                a: &self.items[a],
+                b: &self.items[b],
+                context,
+            },
+            Step::Done => ItemStep::Done,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ItemStep, ItemStepper};
+
+    #[test]
+    fn ranks_owned_items_without_indices() {
+        #[derive(Debug, PartialEq)]
+        struct Song(&'static str, u32);
+
+        let songs = vec![Song("b", 2), Song("c", 3), Song("a", 1)];
+        let mut stepper = ItemStepper::new(songs);
+        let mut questions = 0;
+        while let ItemStep::Compare { a, b, .. } = stepper.step() {
+            questions += 1;
+            let better = a.1 > b.1;
+            stepper.answer(better);
+        }
+        assert_eq!(questions, stepper.stepper().comparisons_made());
+        assert_eq!(
+            stepper.into_ranking(),
+            [Song("c", 3), Song("b", 2), Song("a", 1)]
+        );
+
+        // Stopping early still gives every item back.
+        let mut partial = ItemStepper::new(vec![1, 2, 3, 4]);
+        partial.step();
+        assert_eq!(partial.into_ranking().len(), 4);
+    }
+}
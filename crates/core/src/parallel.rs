@@ -0,0 +1,97 @@
+//! Ranking with a comparator that runs on several threads.
+//!
+//! For comparators that are pure but slow, scoring two images with a model
+//! say, waiting for one answer at a time leaves cores idle.
+//! [`rank_items_parallel`] asks the same questions as
+//! [`rank_items`](crate::rank_items) and returns the same ranking, but asks
+//! independent ones at once on the rayon thread pool: every pair of a
+//! pairing round together, and during the binary searches, the current
+//! question alongside both questions that could follow it. One of those
+//! two is wasted, so the searches make about half again as many calls in
+//! exchange for taking half as many rounds.
+
+use rayon::prelude::*;
+
+use crate::{Ranking, Step, Stepper};
+
+/// Ranks `items` like [`rank_items`](crate::rank_items), calling `better`
+/// from several threads at once.
+///
+/// The comparator contract and the stability guarantee are the same as
+/// for `rank_items`, and so is the ranking. `better` may be called more
+/// often than there are questions, see the [module docs](self).
+#[must_use]
+pub fn rank_items_parallel<T, F>(items: Vec<T>, better: F) -> Ranking<T>
+where
+    T: Sync,
+    F: Fn(&T, &T) -> bool + Sync,
+{
+    // Put each question as `rank_permutation` does, for stability.
+    let ask = |(a, b): (usize, usize)| {
+        if a < b {
+            !better(&items[b], &items[a])
+        } else {
+            better(&items[a], &items[b])
+        }
+    };
+    let mut stepper = Stepper::new(items.len());
+    let mut step = stepper.step();
+    while let Step::Compare { a, b, .. } = step {
+        let questions = stepper.available_questions();
+        if questions.len() > 1 {
+            let answers: Vec<bool> = questions
+                .par_iter()
+                .map(|&question| ask(question))
+                .collect();
+            for (&(a, b), a_is_better) in questions.iter().zip(answers) {
+                stepper.answer_question(a, b, a_is_better);
+            }
+            step = stepper.step();
+            continue;
+        }
+        let if_a = stepper.peek_next_after(true);
+        let if_b = stepper.peek_next_after(false);
+        let (now, (then_a, then_b)) = rayon::join(
+            || ask((a, b)),
+            || rayon::join(|| if_a.map(ask), || if_b.map(ask)),
+        );
+        step = stepper.answer(now);
+        let (next, answer) = if now { (if_a, then_a) } else { (if_b, then_b) };
+        if let (Step::Compare { a, b, .. }, Some(answer)) = (step, answer)
+            && next == Some((a, b))
+        {
+            step = stepper.answer(answer);
+        }
+    }
+    let order = stepper
+        .take_order()
+        .unwrap_or_else(|| stepper.finalize_partial());
+    order.apply(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::rank_items_parallel;
+    use crate::rank_items;
+
+    #[test]
+    fn ranks_as_the_sequential_sort_does() {
+        let values: Vec<u32> = (0..60).map(|i| (i * 37 + 11) % 60 / 2).collect();
+        let calls = AtomicUsize::new(0);
+        let ranking = rank_items_parallel(values.clone(), |a, b| {
+            calls.fetch_add(1, Ordering::Relaxed);
+            a / 3 > b / 3
+        });
+        let mut asked = 0;
+        let expected = rank_items(values, |a, b| {
+            asked += 1;
+            a / 3 > b / 3
+        });
+        assert_eq!(ranking, expected);
+        let calls = calls.into_inner();
+        assert!(asked <= calls && calls <= 2 * asked, "{calls} for {asked}");
+        assert!(rank_items_parallel(Vec::<u8>::new(), |_, _| unreachable!()).is_empty());
+    }
+}
@@ -0,0 +1,105 @@
+//! Items pinned to the top or the bottom of a ranking.
+//!
+//! A baseline or control item often has a known place: "none of the above"
+//! goes last whatever the answers. Pinning it turns that into known pairs,
+//! so no question about it is asked unless another item is pinned to the
+//! same end, and the ranking puts it there:
+//!
+//! ```
+//! use rankfast::pin::{Pin, rank_items_pinned};
+//!
+//! let mut asked = 0;
+//! let ranking = rank_items_pinned(vec![3, 0, 1, 2], &[(1, Pin::Last)], |a, b| {
+//!     asked += 1;
+//!     a > b
+//! });
+//! assert_eq!(*ranking, [3, 2, 1, 0]);
+//! assert_eq!(asked, 3);
+//! ```
+
+use crate::Ranking;
+use crate::known::rank_with_known;
+
+/// Where an item is pinned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pin {
+    /// Above every item not pinned first.
+    First,
+    /// Below every item not pinned last.
+    Last,
+}
+
+/// The `(better, worse)` pairs over `0..n` that put each item of `pins`
+/// at its end, to seed
+/// [`Stepper::with_known_pairs`](crate::Stepper::with_known_pairs).
+///
+/// Items pinned to the same end are still ranked among themselves. An item
+/// pinned twice keeps its last pin, and pins outside `0..n` are ignored.
+#[must_use]
+pub fn pinned_pairs(n: usize, pins: &[(usize, Pin)]) -> Vec<(usize, usize)> {
+    let mut pin_of = vec![None; n];
+    for &(item, pin) in pins {
+        if let Some(slot) = pin_of.get_mut(item) {
+            *slot = Some(pin);
+        }
+    }
+    let mut pairs = Vec::new();
+    for (item, pin) in pin_of.iter().enumerate() {
+        for (other, other_pin) in pin_of.iter().enumerate() {
+            if pin == other_pin {
+                continue;
+            }
+            match pin {
+                Some(Pin::First) => pairs.push((item, other)),
+                Some(Pin::Last) => pairs.push((other, item)),
+                None => {}
+            }
+        }
+    }
+    // An item pinned first and one pinned last are each other's pair.
+    pairs.sort_unstable();
+    pairs.dedup();
+    pairs
+}
+
+/// Ranks `items` like [`rank_items`](crate::rank_items), with each item of
+/// `pins`, by position in `items`, at its end of the ranking.
+#[must_use]
+pub fn rank_items_pinned<T, F>(items: Vec<T>, pins: &[(usize, Pin)], better: F) -> Ranking<T>
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let pairs = pinned_pairs(items.len(), pins);
+    rank_with_known(items, &pairs, better)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Pin, pinned_pairs, rank_items_pinned};
+
+    #[test]
+    fn pinned_items_stay_at_their_end() {
+        let values = vec![5, 2, 9, 1, 3, 8, 0];
+        let pins = [
+            (2, Pin::Last),
+            (4, Pin::First),
+            (6, Pin::First),
+            (9, Pin::Last),
+        ];
+        let mut asked = Vec::new();
+        let ranking = rank_items_pinned(values, &pins, |&a, &b| {
+            asked.push((a, b));
+            a > b
+        });
+        assert_eq!(*ranking, [3, 0, 8, 5, 2, 1, 9]);
+        // Only the two items pinned first meet each other.
+        assert!(asked.iter().all(|&(a, b)| a != 9 && b != 9));
+        let met = |&&(a, b): &&(i32, i32)| matches!((a, b), (3, 0) | (0, 3));
+        assert_eq!(asked.iter().filter(met).count(), 1);
+
+        assert_eq!(
+            pinned_pairs(3, &[(0, Pin::First), (0, Pin::Last)]),
+            [(1, 0), (2, 0)]
+        );
+    }
+}
@@ -0,0 +1,163 @@
+//! The shape of a ranking, before any question is asked.
+//!
+//! Which items meet is up to the answers, but the structure of merge
+//! insertion is fixed by the number of items alone: how many pairs each
+//! level compares, which partners each level inserts together, in what
+//! order, and how long the chain each binary search spans can get.
+//! [`plan`] lays that out, for showing how the sort works or checking the
+//! Jacobsthal grouping against the sort itself:
+//!
+//! ```
+//! let plan = rankfast::plan::plan(5);
+//! // 5 items make 2 pairs, whose 2 winners make 1 pair.
+//! let sizes: Vec<_> = plan.levels.iter().map(|l| (l.len, l.pairs)).collect();
+//! assert_eq!(sizes, [(5, 2), (2, 1)]);
+//! // The top level inserts the fifth item, then the second partner.
+//! let groups = &plan.levels[0].groups;
+//! assert_eq!(groups[0].insertions, [None, Some(1)]);
+//! assert_eq!(groups[0].range, 3);
+//! assert_eq!(plan.comparisons(), rankfast::estimate_turns(5));
+//! ```
+
+use crate::ceil_log2;
+
+/// The levels of merge insertion for a number of items, see [`plan`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plan {
+    /// Top-down: the first level pairs up every item, each next one the
+    /// mains of the one before. Insertion runs the other way, from the
+    /// last level up.
+    pub levels: Vec<Level>,
+}
+
+/// One level of the recursion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Level {
+    /// Items at this level.
+    pub len: usize,
+    /// Pairs compared; their mains make the next level.
+    pub pairs: usize,
+    /// The partners inserted once the mains are sorted, group by group.
+    /// The partner of the best main goes in front of it for free and
+    /// is not listed.
+    pub groups: Vec<Group>,
+}
+
+/// Insertions whose binary searches all span the same chain length.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Group {
+    /// What is inserted, in order: `Some(k)` for the partner of the main
+    /// ranked `k` among the sorted mains (from 0), `None` for the item left
+    /// over from an odd level.
+    pub insertions: Vec<Option<usize>>,
+    /// The most chain items any one of the binary searches spans.
+    pub range: usize,
+}
+
+impl Group {
+    /// Questions each binary search of the group asks at most.
+    #[must_use]
+    pub fn search_comparisons(&self) -> usize {
+        ceil_log2(self.range + 1)
+    }
+}
+
+impl Plan {
+    /// Questions the whole sort asks at most.
+    #[must_use]
+    pub fn comparisons(&self) -> usize {
+        self.levels
+            .iter()
+            .map(|level| {
+                let searches: usize = level
+                    .groups
+                    .iter()
+                    .map(|g| g.insertions.len() * g.search_comparisons())
+                    .sum();
+                level.pairs + searches
+            })
+            .sum()
+    }
+}
+
+/// The structure merge insertion follows for `n` items, without a
+/// comparator. Fewer than two items have no levels.
+#[must_use]
+pub fn plan(n: usize) -> Plan {
+    let mut levels = Vec::new();
+    let mut len = n;
+    while len > 1 {
+        let pairs = len / 2;
+        levels.push(Level {
+            len,
+            pairs,
+            groups: groups(pairs, len % 2 == 1),
+        });
+        len = pairs;
+    }
+    Plan { levels }
+}
+
+/// The insertion groups of a level with `pairs` pairs, as
+/// [`jacobsthal_order`](crate::jacobsthal_order) orders them.
+fn groups(pairs: usize, straggler: bool) -> Vec<Group> {
+    // In b-notation the partners are b_2..=b_pairs and the straggler
+    // b_(pairs + 1). Group `j` inserts from the `j`th Jacobsthal boundary
+    // down to the one before; every item of b_2..=b_prev is placed by then.
+    let last = pairs + usize::from(straggler);
+    let mut groups = Vec::new();
+    let (mut prev, mut curr) = (1usize, 3usize);
+    while prev < last {
+        let top = curr.min(last);
+        let insertions = (prev + 1..=top)
+            .rev()
+            .map(|b| (b <= pairs).then_some(b - 1))
+            .collect();
+        // Searching for b_k spans b_1, a_1..a_(k-1), b_2..=b_prev and, at
+        // worst, the group's b_(k+1)..=b_top already placed: the same
+        // count for every k. For the straggler that is the whole chain.
+        groups.push(Group {
+            insertions,
+            range: top + prev - 1,
+        });
+        let next = curr + 2 * prev;
+        prev = curr;
+        curr = next;
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::plan;
+    use crate::{jacobsthal_order, optimal_turns};
+
+    #[test]
+    fn plans_follow_the_sort() {
+        for n in 0..=256 {
+            let plan = plan(n);
+            assert_eq!(Some(plan.comparisons()), optimal_turns(n), "n = {n}");
+            for (k, level) in plan.levels.iter().enumerate() {
+                let below = plan.levels.get(k + 1).map_or(1, |l| l.len);
+                assert_eq!(level.pairs, below);
+                // Groups list the pending items in the sort's own order.
+                let count = level.pairs - 1 + level.len % 2;
+                let pending: Vec<usize> = level
+                    .groups
+                    .iter()
+                    .flat_map(|g| &g.insertions)
+                    .map(|main| main.map_or(count - 1, |k| k - 1))
+                    .collect();
+                assert_eq!(pending, jacobsthal_order(count), "n = {n}");
+            }
+        }
+        assert_eq!(
+            plan(21).levels[0]
+                .groups
+                .iter()
+                .map(|g| g.range)
+                .collect::<Vec<_>>(),
+            [3, 7, 15]
+        );
+    }
+}
@@ -0,0 +1,109 @@
+//! Rankings that start from prior scores.
+//!
+//! When most of a list's order is already known, from star ratings or an
+//! earlier scoring say, asking about every pair is wasted effort. Items
+//! whose priors differ by more than a `gap` are taken to be in prior order,
+//! so only items with close priors, and those without one, are compared:
+//!
+//! ```
+//! use rankfast::prior::rank_items_with_priors;
+//!
+//! // Star ratings; the two 4.5s and the unrated item need the human.
+//! let priors = [Some(4.5), Some(2.0), Some(4.5), None, Some(1.0)];
+//! let mut asked = 0;
+//! let ranking = rank_items_with_priors(vec![9, 3, 8, 5, 1], &priors, 0.5, |a, b| {
+//!     asked += 1;
+//!     a > b
+//! });
+//! assert_eq!(*ranking, [9, 8, 5, 3, 1]);
+//! assert!(asked < rankfast::estimate_turns(5));
+//! ```
+//!
+//! The priors only seed the run: the answers have the last word wherever
+//! they are asked, and the final ranking follows them.
+
+use crate::Ranking;
+use crate::known::rank_with_known;
+
+/// The `(better, worse)` pairs over the items of `priors` that rank one
+/// above another whenever its prior is higher by more than `gap`, to seed
+/// [`Stepper::with_known_pairs`](crate::Stepper::with_known_pairs).
+///
+/// Items without a prior, or with a NaN one, are left out. Pairs the others
+/// imply transitively are left out too, so the list stays short when the
+/// priors spread well.
+#[must_use]
+pub fn prior_pairs(priors: &[Option<f64>], gap: f64) -> Vec<(usize, usize)> {
+    let mut scored: Vec<(usize, f64)> = priors
+        .iter()
+        .enumerate()
+        .filter_map(|(item, prior)| Some((item, (*prior).filter(|p| !p.is_nan())?)))
+        .collect();
+    scored.sort_by(|x, y| y.1.total_cmp(&x.1));
+    // `beyond[i]` is the first position whose prior is more than `gap`
+    // below position `i`'s; it never goes back up.
+    let mut beyond = Vec::with_capacity(scored.len());
+    let mut j = 0;
+    for &(_, prior) in &scored {
+        while j < scored.len() && prior - scored[j].1 <= gap {
+            j += 1;
+        }
+        beyond.push(j);
+    }
+    // The item at `i` beats everything from `beyond[i]` on; the item at
+    // `beyond[i]` already beats everything from its own `beyond` on.
+    let mut pairs = Vec::new();
+    for (i, &(item, _)) in scored.iter().enumerate() {
+        let first = beyond[i];
+        let Some(&next) = beyond.get(first) else {
+            continue;
+        };
+        let last = next.max(first + 1);
+        pairs.extend(scored[first..last].iter().map(|&(worse, _)| (item, worse)));
+    }
+    pairs
+}
+
+/// Ranks `items` like [`rank_items`](crate::rank_items), asking only about
+/// items whose `priors`, by position in `items`, are within `gap` of each
+/// other or missing; see [`prior_pairs`].
+#[must_use]
+pub fn rank_items_with_priors<T, F>(
+    items: Vec<T>,
+    priors: &[Option<f64>],
+    gap: f64,
+    better: F,
+) -> Ranking<T>
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let priors = &priors[..priors.len().min(items.len())];
+    rank_with_known(items, &prior_pairs(priors, gap), better)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{prior_pairs, rank_items_with_priors};
+    use crate::rank_items;
+
+    #[test]
+    fn distant_priors_are_not_asked_about() {
+        let values: Vec<u32> = (0..40).map(|i| (i * 17 + 3) % 40).collect();
+        // Priors in tens, so only items in the same ten are close.
+        let priors: Vec<Option<f64>> = values.iter().map(|&v| Some(f64::from(v / 10))).collect();
+        let mut asked = 0;
+        let ranking = rank_items_with_priors(values.clone(), &priors, 0.5, |a, b| {
+            asked += 1;
+            assert_eq!(a / 10, b / 10, "asked about {a} and {b}");
+            a > b
+        });
+        assert_eq!(ranking, rank_items(values, |a, b| a > b));
+        assert!(asked < crate::estimate_turns(40) * 3 / 5, "{asked}");
+
+        // Every pair beyond the gap follows, transitively.
+        let pairs = prior_pairs(&[Some(3.0), Some(2.0), Some(1.0), Some(0.0)], 0.5);
+        assert_eq!(pairs, [(0, 1), (1, 2), (2, 3)]);
+        let pairs = prior_pairs(&[Some(0.0), None, Some(f64::NAN), Some(2.0)], 1.0);
+        assert_eq!(pairs, [(3, 0)]);
+    }
+}
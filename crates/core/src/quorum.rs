@@ -0,0 +1,146 @@
+//! Majority votes over a noisy judge.
+//!
+//! An automated judge, a language model sampled above temperature zero say,
+//! can answer the same question differently from one call to the next. A
+//! [`QuorumComparator`] asks it each question several times and goes with
+//! the majority, counting how often the judge disagreed with itself:
+//!
+//! ```
+//! use rankfast::{QuorumComparator, rank_items};
+//!
+//! let mut quorum = QuorumComparator::new(|a: &u32, b: &u32| a > b, 3);
+//! let ranking = rank_items(vec![2, 9, 4], |a, b| quorum.better(a, b));
+//! assert_eq!(*ranking, [9, 4, 2]);
+//! assert_eq!(quorum.stats().split, 0);
+//! ```
+
+/// How a [`QuorumComparator`]'s votes went so far.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QuorumStats {
+    /// Questions decided.
+    pub questions: usize,
+    /// Calls to the judge.
+    pub calls: usize,
+    /// Questions the judge did not answer the same way every time.
+    pub split: usize,
+    /// Answers that went against the verdict.
+    pub dissents: usize,
+}
+
+/// A comparator that asks its judge up to `asks` times per question and
+/// answers with the majority.
+///
+/// Asking stops as soon as the majority is settled, or once one side leads
+/// by the [`margin`](Self::margin). A vote that ends even goes the way of
+/// the first answer.
+#[derive(Debug, Clone)]
+pub struct QuorumComparator<F> {
+    judge: F,
+    asks: usize,
+    margin: usize,
+    stats: QuorumStats,
+}
+
+impl<F> QuorumComparator<F> {
+    /// Votes over up to `asks` calls to `judge(a, b)`, whether `a` is better
+    /// than `b`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `asks` is 0.
+    #[must_use]
+    pub fn new(judge: F, asks: usize) -> Self {
+        assert!(asks > 0, "a quorum needs at least one ask");
+        Self {
+            judge,
+            asks,
+            margin: asks,
+            stats: QuorumStats::default(),
+        }
+    }
+
+    /// Stops asking once one side leads by `margin` answers, so clear-cut
+    /// questions cost fewer calls.
+    #[must_use]
+    pub fn margin(self, margin: usize) -> Self {
+        Self {
+            margin: margin.max(1),
+            ..self
+        }
+    }
+
+    /// Whether `a` is better than `b`, by majority.
+    pub fn better<T>(&mut self, a: &T, b: &T) -> bool
+    where
+        F: FnMut(&T, &T) -> bool,
+    {
+        let first = (self.judge)(a, b);
+        let (mut yes, mut no) = (usize::from(first), usize::from(!first));
+        for asked in 1..self.asks {
+            let lead = yes.abs_diff(no);
+            if lead >= self.margin || lead > self.asks - asked {
+                break;
+            }
+            if (self.judge)(a, b) {
+                yes += 1;
+            } else {
+                no += 1;
+            }
+        }
+        let verdict = if yes == no { first } else { yes > no };
+        self.stats.questions += 1;
+        self.stats.calls += yes + no;
+        if yes > 0 && no > 0 {
+            self.stats.split += 1;
+        }
+        self.stats.dissents += if verdict { no } else { yes };
+        verdict
+    }
+
+    #[must_use]
+    pub fn stats(&self) -> QuorumStats {
+        self.stats
+    }
+
+    /// The judge, giving up the statistics.
+    #[must_use]
+    pub fn into_inner(self) -> F {
+        self.judge
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{QuorumComparator, QuorumStats};
+    use crate::rank_items;
+
+    #[test]
+    fn outvotes_a_judge_that_errs_once_in_a_while() {
+        // Every fifth answer is flipped, never twice in a row.
+        let mut calls = 0;
+        let noisy = |a: &u32, b: &u32| {
+            calls += 1;
+            (a > b) != (calls % 5 == 0)
+        };
+        let mut quorum = QuorumComparator::new(noisy, 3);
+        let ranking = rank_items((0..12).rev().collect(), |a, b| quorum.better(a, b));
+        assert_eq!(*ranking, (0..12).rev().collect::<Vec<_>>());
+        let stats = quorum.stats();
+        assert!(stats.split > 0);
+        assert_eq!(stats.dissents, stats.split);
+        assert!(stats.calls <= 3 * stats.questions);
+
+        // Unanimous answers stop after the margin.
+        let mut quorum = QuorumComparator::new(|a: &u32, b: &u32| a > b, 5).margin(2);
+        assert!(quorum.better(&2, &1));
+        assert_eq!(
+            quorum.stats(),
+            QuorumStats {
+                questions: 1,
+                calls: 2,
+                split: 0,
+                dissents: 0
+            }
+        );
+    }
+}
@@ -0,0 +1,263 @@
+//! Ranked results.
+//!
+//! Every engine returns a [`Ranking`]: the items best first, split into
+//! groups of items that tie. Engines that only produce strict orders return
+//! one item per group, so frontends can render positions (`1, 2, 2, 4`) and
+//! groups the same way whichever engine ran.
+
+use std::ops::Deref;
+
+/// Items best first, in groups of items that tie.
+///
+/// Dereferences to the flat slice of items, so it indexes and iterates like
+/// the `Vec` it replaces; [`groups`](Self::groups) and
+/// [`positions`](Self::positions) expose the ties.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ranking<T> {
+    items: Vec<T>,
+    /// Exclusive end of each group in `items`, increasing.
+    ends: Vec<usize>,
+}
+
+impl<T> Ranking<T> {
+    /// A strict ranking: every item in a group of its own.
+    #[must_use]
+    pub fn new(items: Vec<T>) -> Self {
+        let ends = (1..=items.len()).collect();
+        Self { items, ends }
+    }
+
+    /// A ranking from groups of tied items, best group first. Empty groups
+    /// are dropped.
+    #[must_use]
+    pub fn from_groups<I: IntoIterator<Item = Vec<T>>>(groups: I) -> Self {
+        let mut items = Vec::new();
+        let mut ends = Vec::new();
+        for group in groups {
+            if !group.is_empty() {
+                items.extend(group);
+                ends.push(items.len());
+            }
+        }
+        Self { items, ends }
+    }
+
+    /// A ranking where `tied_with_previous(i)` tells whether item `i` ties
+    /// with item `i - 1`.
+    pub(crate) fn from_ties(
+        items: Vec<T>,
+        mut tied_with_previous: impl FnMut(usize) -> bool,
+    ) -> Self {
+        let mut ends: Vec<usize> = (1..items.len())
+            .filter(|&i| !tied_with_previous(i))
+            .collect();
+        if !items.is_empty() {
+            ends.push(items.len());
+        }
+        Self { items, ends }
+    }
+
+    /// The items, best first, ties in no particular order.
+    #[must_use]
+    pub fn as_slice(&self) -> &[T] {
+        &self.items
+    }
+
+    #[must_use]
+    pub fn into_vec(self) -> Vec<T> {
+        self.items
+    }
+
+    /// Groups of tied items, best first.
+    #[must_use]
+    pub fn groups(&self) -> impl ExactSizeIterator<Item = &[T]> {
+        self.ends.iter().enumerate().map(|(i, &end)| {
+            let start = if i == 0 { 0 } else { self.ends[i - 1] };
+            &self.items[start..end]
+        })
+    }
+
+    /// Every item with its 1-based position. Tied items share the position
+    /// of the first of them, and the next group skips ahead (`1, 2, 2, 4`).
+    pub fn positions(&self) -> impl Iterator<Item = (usize, &T)> {
+        let starts = std::iter::once(0).chain(self.ends.iter().copied());
+        self.groups()
+            .zip(starts)
+            .flat_map(|(group, start)| group.iter().map(move |item| (start + 1, item)))
+    }
+
+    /// The 1-based position of `item`, as in [`positions`](Self::positions).
+    #[must_use]
+    pub fn rank_of(&self, item: &T) -> Option<usize>
+    where
+        T: PartialEq,
+    {
+        self.positions()
+            .find(|&(_, candidate)| candidate == item)
+            .map(|(position, _)| position)
+    }
+
+    /// The first `k` items, or all of them if there are fewer. A group that
+    /// straddles the cut is split.
+    #[must_use]
+    pub fn top(&self, k: usize) -> &[T] {
+        &self.items[..k.min(self.items.len())]
+    }
+
+    /// Applies `f` to every item, keeping the groups.
+    #[must_use]
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Ranking<U> {
+        Ranking {
+            items: self.items.into_iter().map(f).collect(),
+            ends: self.ends,
+        }
+    }
+}
+
+impl Ranking<usize> {
+    /// The indices `0..n` in the groups the `(better, worse)` outcomes
+    /// imply, for answers that stopped before the sort finished.
+    ///
+    /// Each group holds the items whose known betters all sit in earlier
+    /// groups, so every outcome is respected and an item shares a group with
+    /// the items it cannot yet be told apart from. Items within a group are
+    /// in input order. Outcomes that contradict each other leave the items
+    /// on the cycle, and any they rank above, in one last group.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an outcome refers to an item outside `0..n`.
+    #[must_use]
+    pub fn from_outcomes(n: usize, outcomes: &[(usize, usize)]) -> Self {
+        let mut worse = vec![Vec::new(); n];
+        let mut betters = vec![0_usize; n];
+        for &(better, loser) in outcomes {
+            assert!(
+                better < n && loser < n,
+                "outcome ({better}, {loser}) out of range"
+            );
+            worse[better].push(loser);
+            betters[loser] += 1;
+        }
+        let mut placed = vec![false; n];
+        let mut group: Vec<usize> = (0..n).filter(|&i| betters[i] == 0).collect();
+        let mut groups = Vec::new();
+        while !group.is_empty() {
+            let mut next = Vec::new();
+            for &item in &group {
+                placed[item] = true;
+                for &loser in &worse[item] {
+                    betters[loser] -= 1;
+                    if betters[loser] == 0 {
+                        next.push(loser);
+                    }
+                }
+            }
+            next.sort_unstable();
+            groups.push(std::mem::replace(&mut group, next));
+        }
+        groups.push((0..n).filter(|&i| !placed[i]).collect());
+        Self::from_groups(groups)
+    }
+
+    /// Moves `items` into the places this ranking of their indices gives
+    /// them, keeping the groups.
+    pub(crate) fn apply<U>(self, mut items: Vec<U>) -> Ranking<U> {
+        crate::apply_order(&mut items, self.items);
+        Ranking {
+            items,
+            ends: self.ends,
+        }
+    }
+}
+
+impl<T> Default for Ranking<T> {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+impl<T> Deref for Ranking<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.items
+    }
+}
+
+impl<T> AsRef<[T]> for Ranking<T> {
+    fn as_ref(&self) -> &[T] {
+        &self.items
+    }
+}
+
+/// Compares the items only, like comparing [`as_slice`](Ranking::as_slice).
+impl<T: PartialEq> PartialEq<Vec<T>> for Ranking<T> {
+    fn eq(&self, other: &Vec<T>) -> bool {
+        self.items == *other
+    }
+}
+
+impl<T> From<Vec<T>> for Ranking<T> {
+    fn from(items: Vec<T>) -> Self {
+        Self::new(items)
+    }
+}
+
+impl<T> IntoIterator for Ranking<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Ranking<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Ranking;
+
+    #[test]
+    fn positions_share_places_within_groups() {
+        let ranking = Ranking::from_groups([vec!['a'], vec!['b', 'c'], vec![], vec!['d']]);
+        assert_eq!(ranking.len(), 4);
+        assert_eq!(ranking.groups().count(), 3);
+        let positions: Vec<(usize, char)> = ranking.positions().map(|(p, &c)| (p, c)).collect();
+        assert_eq!(positions, vec![(1, 'a'), (2, 'b'), (2, 'c'), (4, 'd')]);
+        assert_eq!(ranking.rank_of(&'c'), Some(2));
+        assert_eq!(ranking.rank_of(&'z'), None);
+        assert_eq!(ranking.top(2), &['a', 'b']);
+        assert_eq!(ranking.top(9), &['a', 'b', 'c', 'd']);
+    }
+
+    #[test]
+    fn outcomes_group_what_they_cannot_tell_apart() {
+        // 0 > 1 > 3, 2 > 3; item 4 never played; 5 and 6 contradict.
+        let outcomes = [(0, 1), (1, 3), (2, 3), (5, 6), (6, 5)];
+        let ranking = Ranking::from_outcomes(7, &outcomes);
+        let groups: Vec<&[usize]> = ranking.groups().collect();
+        assert_eq!(groups, vec![&[0, 2, 4][..], &[1], &[3], &[5, 6]]);
+        assert_eq!(Ranking::from_outcomes(0, &[]).groups().count(), 0);
+    }
+
+    #[test]
+    fn strict_rankings_and_mapping_keep_groups() {
+        let strict = Ranking::new(vec![3, 1, 2]);
+        assert!(strict.groups().all(|group| group.len() == 1));
+        assert_eq!(strict.rank_of(&2), Some(3));
+
+        let tied = Ranking::from_ties(vec![0, 1, 2], |i| i == 2).map(|i| i * 10);
+        assert_eq!(tied.groups().collect::<Vec<_>>(), vec![&[0][..], &[10, 20]]);
+        assert_eq!(tied.into_vec(), vec![0, 10, 20]);
+        assert_eq!(Ranking::<u8>::default().groups().count(), 0);
+    }
+}
@@ -0,0 +1,325 @@
+//! Rating models for noisy, possibly contradictory comparisons.
+//!
+//! Ford-Johnson assumes a consistent comparator. When answers come from
+//! people who change their minds, these models fit a strength per item from
+//! a list of `(winner, loser)` outcomes instead, and never need the outcomes
+//! to be transitive.
+//!
+//! Scores are on a natural-log scale: an item whose score is `d` higher than
+//! another is expected to beat it with probability `1 / (1 + e^-d)`.
+//!
+//! [`consistency`] measures how far outcomes are from transitive, to tell
+//! how much a ranking built from them can be trusted.
+
+use crate::known::KnownOrder;
+
+/// A single item in a rating-based ranking.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rated {
+    /// Index of the item in `0..n`.
+    pub item: usize,
+    /// Fitted strength; the mean over all items is zero.
+    pub score: f64,
+    /// Modeled probability that this item beats the one ranked right below
+    /// it. `1.0` for the last item.
+    pub confidence: f64,
+}
+
+/// Fits Bradley-Terry strengths with the minorization-maximization
+/// algorithm.
+///
+/// Every item gets one virtual win and one virtual loss against an average
+/// opponent, which keeps scores finite for items that never lost (or never
+/// won) and pulls items with no data towards zero.
+#[must_use]
+pub fn bradley_terry(n: usize, outcomes: &[(usize, usize)]) -> Vec<f64> {
+    bradley_terry_from(outcomes, vec![0.0; n])
+}
+
+/// Refines existing log-scale `scores` against `outcomes`; warm-starting
+/// from a previous fit converges in a handful of iterations when only a few
+/// outcomes were added.
+pub(crate) fn bradley_terry_from(outcomes: &[(usize, usize)], scores: Vec<f64>) -> Vec<f64> {
+    const MAX_ITERATIONS: usize = 1000;
+    const TOLERANCE: f64 = 1e-9;
+
+    let n = scores.len();
+    let mut wins = vec![1.0f64; n];
+    for &(winner, _) in outcomes {
+        wins[winner] += 1.0;
+    }
+
+    let mut strength: Vec<f64> = scores.into_iter().map(f64::exp).collect();
+    for _ in 0..MAX_ITERATIONS {
+        let mut denom: Vec<f64> = strength.iter().map(|s| 2.0 / (s + 1.0)).collect();
+        for &(winner, loser) in outcomes {
+            let share = 1.0 / (strength[winner] + strength[loser]);
+            denom[winner] += share;
+            denom[loser] += share;
+        }
+        let mut next: Vec<f64> = wins.iter().zip(&denom).map(|(w, d)| w / d).collect();
+
+        let mean_log = next.iter().map(|s| s.ln()).sum::<f64>() / len_f64(n);
+        for s in &mut next {
+            *s /= mean_log.exp();
+        }
+
+        let delta = next
+            .iter()
+            .zip(&strength)
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0, f64::max);
+        strength = next;
+        if delta < TOLERANCE {
+            break;
+        }
+    }
+
+    strength.into_iter().map(f64::ln).collect()
+}
+
+/// Computes Elo-style ratings by replaying `outcomes` in order.
+///
+/// `k` is the step size in the same log scale as [`bradley_terry`]; `0.1` to
+/// `0.3` suits short human sessions. Multiply scores by `400 / ln 10` to get
+/// conventional Elo points. Unlike Bradley-Terry, the result depends on the
+/// order of the outcomes, favouring recent answers.
+#[must_use]
+pub fn elo(n: usize, outcomes: &[(usize, usize)], k: f64) -> Vec<f64> {
+    let mut scores = vec![0.0f64; n];
+    for &(winner, loser) in outcomes {
+        let surprise = 1.0 - win_probability(scores[winner], scores[loser]);
+        scores[winner] += k * surprise;
+        scores[loser] -= k * surprise;
+    }
+    scores
+}
+
+/// Probability that an item scored `a` beats one scored `b`.
+#[must_use]
+pub fn win_probability(a: f64, b: f64) -> f64 {
+    1.0 / (1.0 + (b - a).exp())
+}
+
+/// Orders items by descending score and attaches the confidence of each
+/// adjacent step. Ties keep index order.
+#[must_use]
+pub fn rank_by_scores(scores: &[f64]) -> Vec<Rated> {
+    let mut order: Vec<usize> = (0..scores.len()).collect();
+    order.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]));
+
+    order
+        .iter()
+        .enumerate()
+        .map(|(pos, &item)| Rated {
+            item,
+            score: scores[item],
+            confidence: order
+                .get(pos + 1)
+                .map_or(1.0, |&next| win_probability(scores[item], scores[next])),
+        })
+        .collect()
+}
+
+/// Ranks `n` items from `(winner, loser)` outcomes using [`bradley_terry`].
+#[must_use]
+pub fn rank_from_outcomes(n: usize, outcomes: &[(usize, usize)]) -> Vec<Rated> {
+    rank_by_scores(&bradley_terry(n, outcomes))
+}
+
+/// Attaches strengths to a finished `order` (best first) from the
+/// `(a, b, a_is_better)` answers that produced it.
+///
+/// Fits [`bradley_terry`] to the answers, then makes the scores
+/// non-increasing along `order` by pooling adjacent violators (isotonic
+/// regression), so the scores never contradict the ranking they annotate.
+/// Pooled items share a score and a confidence of `0.5`: the answers cannot
+/// tell them apart. Gaps between consecutive scores estimate how far apart
+/// neighbours are, which positions alone cannot show.
+///
+/// Items are the indices `0..order.len()`.
+#[must_use]
+pub fn score_ranking(order: &[usize], history: &[(usize, usize, bool)]) -> Vec<Rated> {
+    let outcomes: Vec<(usize, usize)> = history
+        .iter()
+        .map(|&(a, b, a_better)| if a_better { (a, b) } else { (b, a) })
+        .collect();
+    let fitted = bradley_terry(order.len(), &outcomes);
+
+    // Pool adjacent violators: blocks of (sum, count) with non-increasing means.
+    let mut blocks: Vec<(f64, usize)> = Vec::with_capacity(order.len());
+    for &item in order {
+        blocks.push((fitted[item], 1));
+        while let [.., (prev_sum, prev_len), (sum, len)] = blocks[..] {
+            if prev_sum / len_f64(prev_len) >= sum / len_f64(len) {
+                break;
+            }
+            blocks.pop();
+            blocks.pop();
+            blocks.push((prev_sum + sum, prev_len + len));
+        }
+    }
+    let scores: Vec<f64> = blocks
+        .iter()
+        .flat_map(|&(sum, len)| std::iter::repeat_n(sum / len_f64(len), len))
+        .collect();
+
+    order
+        .iter()
+        .enumerate()
+        .map(|(pos, &item)| Rated {
+            item,
+            score: scores[pos],
+            confidence: scores
+                .get(pos + 1)
+                .map_or(1.0, |&next| win_probability(scores[pos], next)),
+        })
+        .collect()
+}
+
+/// How well a list of outcomes hangs together, from [`consistency`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Consistency {
+    /// Outcomes checked.
+    pub outcomes: usize,
+    /// Outcomes whose pair the earlier ones had already decided, directly or
+    /// by transitivity.
+    pub implied: usize,
+    /// Of the `implied` outcomes, those that went the other way.
+    pub contradicted: usize,
+    /// Outcomes the fitted [`bradley_terry`] scores rank the other way.
+    pub against_fit: usize,
+}
+
+impl Consistency {
+    /// Share of the outcomes the fitted scores agree with, `1.0` with none.
+    #[must_use]
+    pub fn agreement(&self) -> f64 {
+        if self.outcomes == 0 {
+            return 1.0;
+        }
+        1.0 - len_f64(self.against_fit) / len_f64(self.outcomes)
+    }
+
+    /// Share of the implied outcomes that contradicted the earlier ones, or
+    /// `None` if no outcome was implied. A sort that never asks about a pair
+    /// it can infer implies none, unless answers are undone or imported.
+    #[must_use]
+    pub fn contradiction_rate(&self) -> Option<f64> {
+        (self.implied > 0).then(|| len_f64(self.contradicted) / len_f64(self.implied))
+    }
+}
+
+/// Checks `(winner, loser)` outcomes in order against what the earlier ones
+/// imply, and all of them against a [`bradley_terry`] fit.
+///
+/// A contradicted outcome is left out of what later ones are checked
+/// against, so one slip is counted once rather than for every pair it
+/// touches.
+#[must_use]
+pub fn consistency(n: usize, outcomes: &[(usize, usize)]) -> Consistency {
+    let mut known = KnownOrder::new(n);
+    let mut result = Consistency {
+        outcomes: outcomes.len(),
+        ..Consistency::default()
+    };
+    for &(winner, loser) in outcomes {
+        match known.relation(winner, loser) {
+            Some(agrees) => {
+                result.implied += 1;
+                if !agrees {
+                    result.contradicted += 1;
+                }
+            }
+            None => known.insert(winner, loser),
+        }
+    }
+    let scores = bradley_terry(n, outcomes);
+    result.against_fit = outcomes
+        .iter()
+        .filter(|&&(winner, loser)| scores[winner] < scores[loser])
+        .count();
+    result
+}
+
+fn len_f64(n: usize) -> f64 {
+    u32::try_from(n).map_or(f64::from(u32::MAX), f64::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        bradley_terry, consistency, elo, rank_from_outcomes, score_ranking, win_probability,
+    };
+    use crate::outcome::rank_outcome;
+
+    #[test]
+    fn consistent_outcomes_rank_in_order() {
+        let outcomes = [(0, 1), (1, 2), (0, 2), (2, 3), (1, 3)];
+        let ranked = rank_from_outcomes(4, &outcomes);
+        let order: Vec<usize> = ranked.iter().map(|r| r.item).collect();
+        assert_eq!(order, vec![0, 1, 2, 3]);
+        assert!(ranked.iter().all(|r| r.confidence > 0.5));
+    }
+
+    #[test]
+    fn majority_wins_with_contradictions() {
+        let outcomes = [(0, 1), (0, 1), (1, 0), (1, 2), (2, 1), (1, 2)];
+        let scores = bradley_terry(3, &outcomes);
+        assert!(scores[0] > scores[1] && scores[1] > scores[2]);
+    }
+
+    #[test]
+    fn scores_are_centered() {
+        let scores = bradley_terry(3, &[(0, 1), (1, 2)]);
+        assert!(scores.iter().sum::<f64>().abs() < 1e-6);
+    }
+
+    #[test]
+    fn elo_rewards_upsets_more() {
+        let scores = elo(2, &[(0, 1)], 0.2);
+        assert!((scores[0] - 0.1).abs() < 1e-12);
+        let upset = elo(2, &[(0, 1), (1, 0)], 0.2);
+        assert!(upset[1] > upset[0]);
+        assert!((win_probability(0.0, 0.0) - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn ranking_scores_follow_the_order() {
+        let values = [3.0, 0.5, 9.0, 4.0, 1.0, 7.5];
+        let outcome = rank_outcome(&values, |a, b| a > b);
+        let scored = score_ranking(outcome.order(), outcome.history());
+        let items: Vec<usize> = scored.iter().map(|r| r.item).collect();
+        assert_eq!(items, outcome.order());
+        assert!(scored.windows(2).all(|w| w[0].score >= w[1].score));
+        assert!(scored.iter().map(|r| r.score).sum::<f64>().abs() < 1e-6);
+    }
+
+    #[test]
+    fn ranking_scores_pool_contradicted_neighbours() {
+        // The order puts 0 above 1, but 1 collected more wins.
+        let history = [(0, 2, true), (1, 2, true), (1, 3, true), (1, 4, true)];
+        let scored = score_ranking(&[0, 1, 2, 3, 4], &history);
+        assert!((scored[0].score - scored[1].score).abs() < 1e-12);
+        assert!((scored[0].confidence - 0.5).abs() < 1e-12);
+        assert!(scored[1].score > scored[2].score);
+    }
+
+    #[test]
+    fn consistency_counts_contradicted_implications() {
+        let chain = consistency(4, &[(0, 1), (1, 2), (2, 3), (0, 3)]);
+        assert_eq!(
+            (chain.implied, chain.contradicted, chain.against_fit),
+            (1, 0, 0)
+        );
+        assert!((chain.agreement() - 1.0).abs() < 1e-12);
+        assert_eq!(chain.contradiction_rate(), Some(0.0));
+
+        // 2 beating 0 goes against 0 > 1 > 2.
+        let cycle = consistency(3, &[(0, 1), (1, 2), (2, 0), (0, 2)]);
+        assert_eq!((cycle.implied, cycle.contradicted), (2, 1));
+        assert!(cycle.against_fit >= 1);
+        assert!(cycle.agreement() < 1.0);
+        assert_eq!(consistency(2, &[]).contradiction_rate(), None);
+    }
+}
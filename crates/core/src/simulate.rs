@@ -0,0 +1,85 @@
+//! How many questions a ranking usually takes.
+//!
+//! [`estimate_turns`](crate::estimate_turns) bounds the worst case, which
+//! few runs come close to. [`simulate_expected_turns`] sorts random
+//! permutations with a comparator that always knows the answer and reports
+//! the spread of questions asked, for a friendlier "usually about 19
+//! questions". The random numbers come from the caller, so the core stays
+//! free of dependencies and runs are reproducible from a seed.
+
+use crate::rank_permutation;
+
+/// Questions asked over a number of simulated runs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TurnStats {
+    pub min: usize,
+    pub mean: f64,
+    pub max: usize,
+}
+
+/// Ranks `trials` random permutations of `n` items and counts the
+/// questions each run asks. `rng` returns uniformly random `u64`s.
+///
+/// With no trials, or fewer than two items, all stats are 0.
+#[must_use]
+#[allow(clippy::cast_precision_loss)] // question counts are far below 2^52
+pub fn simulate_expected_turns(n: usize, trials: usize, mut rng: impl FnMut() -> u64) -> TurnStats {
+    let mut stats = TurnStats {
+        min: usize::MAX,
+        mean: 0.0,
+        max: 0,
+    };
+    let mut total = 0;
+    let mut values: Vec<usize> = (0..n).collect();
+    for _ in 0..trials {
+        // Fisher-Yates; the modulo bias is negligible for any real `n`.
+        for i in (1..n).rev() {
+            let j = usize::try_from(rng() % (i as u64 + 1)).unwrap_or(i);
+            values.swap(i, j);
+        }
+        let mut turns = 0;
+        let _ = rank_permutation(n, |a, b| {
+            turns += 1;
+            values[a] < values[b]
+        });
+        stats.min = stats.min.min(turns);
+        stats.max = stats.max.max(turns);
+        total += turns;
+    }
+    if trials == 0 {
+        stats.min = 0;
+    } else {
+        stats.mean = total as f64 / trials as f64;
+    }
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::simulate_expected_turns;
+    use crate::estimate_turns;
+
+    #[test]
+    #[allow(clippy::cast_precision_loss)] // small counts
+    fn stays_within_the_worst_case() {
+        let mut bits = 1u64;
+        let xorshift = move || {
+            bits ^= bits << 13;
+            bits ^= bits >> 7;
+            bits ^= bits << 17;
+            bits
+        };
+        let stats = simulate_expected_turns(12, 200, xorshift);
+        assert!(stats.min <= stats.max);
+        assert!(stats.max <= estimate_turns(12));
+        assert!(stats.min as f64 <= stats.mean && stats.mean <= stats.max as f64);
+        assert!(
+            stats.mean > 12.0,
+            "sorting 12 items takes more than 12 questions"
+        );
+
+        let none = simulate_expected_turns(1, 10, || 0);
+        assert_eq!((none.min, none.max), (0, 0));
+        assert_eq!(simulate_expected_turns(5, 0, || 0).max, 0);
+    }
+}
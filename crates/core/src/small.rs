@@ -0,0 +1,593 @@
+//! Decision trees for the smallest lists.
+//!
+//! Up to five items, merge insertion is spelled out as a table: each node
+//! asks one question and names the node to go to for either answer, and
+//! each leaf is a final order. The trees were generated from the general
+//! recursion and ask exactly the same questions, at most [`optimal_turns`]
+//! of them, so the last levels of every run, and all of it for short lists,
+//! stay the same whatever becomes of the recursion. Both
+//! [`ford_johnson`](crate::ford_johnson) and the [`Stepper`] rank such
+//! levels from the tables; the tests rebuild the tables from the recursion
+//! to check them.
+//!
+//! [`optimal_turns`]: crate::optimal_turns
+//! [`Stepper`]: crate::Stepper
+
+/// Child indices from here on are leaves, `LEAF + i` being `orders[i]`.
+const LEAF: u8 = 128;
+
+/// Merge insertion over `N` items as a decision tree.
+#[derive(Clone, Copy)]
+struct Tree<const N: usize> {
+    /// `(a, b, then, otherwise)`: ask whether position `a` ranks before
+    /// position `b`, then go to `then` if so and `otherwise` if not. The
+    /// root is the first node.
+    asks: &'static [(u8, u8, u8, u8)],
+    /// Final orders, as positions best first.
+    orders: &'static [[u8; N]],
+}
+
+/// A node of a tree, with positions in the level it ranks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Node {
+    /// Whether position `.0` ranks before position `.1`.
+    Ask(usize, usize),
+    /// The final order, best first.
+    Order(&'static [u8]),
+}
+
+impl<const N: usize> Tree<N> {
+    fn node(self, node: u8) -> Node {
+        if node >= LEAF {
+            return Node::Order(&self.orders[usize::from(node - LEAF)]);
+        }
+        let (a, b, ..) = self.asks[usize::from(node)];
+        Node::Ask(usize::from(a), usize::from(b))
+    }
+
+    fn next(self, node: u8, a_first: bool) -> u8 {
+        let (_, _, then, otherwise) = self.asks[usize::from(node)];
+        if a_first { then } else { otherwise }
+    }
+
+    /// The most questions left below `node`.
+    fn height(self, node: u8) -> usize {
+        if node >= LEAF {
+            return 0;
+        }
+        let (_, _, then, otherwise) = self.asks[usize::from(node)];
+        1 + self.height(then).max(self.height(otherwise))
+    }
+
+    fn sort(&self, elements: &[usize], cmp: &mut impl FnMut(usize, usize) -> bool) -> Vec<usize> {
+        let mut node = 0;
+        while node < LEAF {
+            let (a, b, then, otherwise) = self.asks[usize::from(node)];
+            node = if cmp(elements[usize::from(a)], elements[usize::from(b)]) {
+                then
+            } else {
+                otherwise
+            };
+        }
+        self.orders[usize::from(node - LEAF)]
+            .iter()
+            .map(|&p| elements[usize::from(p)])
+            .collect()
+    }
+}
+
+/// Whether a level of `n` items has a tree, rooted at node 0.
+pub(crate) fn has_tree(n: usize) -> bool {
+    (2..=5).contains(&n)
+}
+
+/// Node `node` of the tree for `n` items.
+///
+/// # Panics
+///
+/// If `n` has no tree, see [`has_tree`], or it has no such node.
+pub(crate) fn node(n: usize, node: u8) -> Node {
+    match n {
+        2 => TWO.node(node),
+        3 => THREE.node(node),
+        4 => FOUR.node(node),
+        5 => FIVE.node(node),
+        _ => unreachable!("no tree for {n} items"),
+    }
+}
+
+/// The node after answering the question at `node` of the tree for `n`
+/// items, with whether its first position ranks before the second.
+///
+/// # Panics
+///
+/// As [`node`], or if `node` is a leaf.
+pub(crate) fn next(n: usize, node: u8, a_first: bool) -> u8 {
+    match n {
+        2 => TWO.next(node, a_first),
+        3 => THREE.next(node, a_first),
+        4 => FOUR.next(node, a_first),
+        5 => FIVE.next(node, a_first),
+        _ => unreachable!("no tree for {n} items"),
+    }
+}
+
+/// The most questions left from `node` of the tree for `n` items.
+///
+/// # Panics
+///
+/// As [`node`].
+pub(crate) fn height(n: usize, node: u8) -> usize {
+    match n {
+        2 => TWO.height(node),
+        3 => THREE.height(node),
+        4 => FOUR.height(node),
+        5 => FIVE.height(node),
+        _ => unreachable!("no tree for {n} items"),
+    }
+}
+
+/// Sorts `elements` as [`ford_johnson`](crate::ford_johnson) does, or
+/// `None` if there are more than five.
+pub(crate) fn sort(
+    elements: &[usize],
+    cmp: &mut impl FnMut(usize, usize) -> bool,
+) -> Option<Vec<usize>> {
+    match elements.len() {
+        0 | 1 => Some(elements.to_vec()),
+        2 => Some(TWO.sort(elements, cmp)),
+        3 => Some(THREE.sort(elements, cmp)),
+        4 => Some(FOUR.sort(elements, cmp)),
+        5 => Some(FIVE.sort(elements, cmp)),
+        _ => None,
+    }
+}
+
+const TWO: Tree<2> = Tree {
+    asks: &[(0, 1, 128, 129)],
+    orders: &[[0, 1], [1, 0]],
+};
+
+const THREE: Tree<3> = Tree {
+    asks: &[
+        (0, 1, 1, 3),
+        (2, 1, 2, 130),
+        (2, 0, 128, 129),
+        (2, 0, 4, 133),
+        (2, 1, 131, 132),
+    ],
+    orders: &[
+        [2, 0, 1],
+        [0, 2, 1],
+        [0, 1, 2],
+        [2, 1, 0],
+        [1, 2, 0],
+        [1, 0, 2],
+    ],
+};
+
+const FOUR: Tree<4> = Tree {
+    asks: &[
+        (0, 1, 1, 12),
+        (2, 3, 2, 7),
+        (1, 3, 3, 5),
+        (2, 1, 4, 130),
+        (2, 0, 128, 129),
+        (0, 3, 6, 133),
+        (0, 2, 131, 132),
+        (1, 2, 8, 10),
+        (3, 1, 9, 136),
+        (3, 0, 134, 135),
+        (0, 2, 11, 139),
+        (0, 3, 137, 138),
+        (2, 3, 13, 18),
+        (0, 3, 14, 16),
+        (2, 0, 15, 142),
+        (2, 1, 140, 141),
+        (1, 3, 17, 145),
+        (1, 2, 143, 144),
+        (0, 2, 19, 21),
+        (3, 0, 20, 148),
+        (3, 1, 146, 147),
+        (1, 2, 22, 151),
+        (1, 3, 149, 150),
+    ],
+    orders: &[
+        [2, 0, 1, 3],
+        [0, 2, 1, 3],
+        [0, 1, 2, 3],
+        [0, 2, 3, 1],
+        [2, 0, 3, 1],
+        [2, 3, 0, 1],
+        [3, 0, 1, 2],
+        [0, 3, 1, 2],
+        [0, 1, 3, 2],
+        [0, 3, 2, 1],
+        [3, 0, 2, 1],
+        [3, 2, 0, 1],
+        [2, 1, 0, 3],
+        [1, 2, 0, 3],
+        [1, 0, 2, 3],
+        [1, 2, 3, 0],
+        [2, 1, 3, 0],
+        [2, 3, 1, 0],
+        [3, 1, 0, 2],
+        [1, 3, 0, 2],
+        [1, 0, 3, 2],
+        [1, 3, 2, 0],
+        [3, 1, 2, 0],
+        [3, 2, 1, 0],
+    ],
+};
+
+const FIVE: Tree<5> = Tree {
+    asks: &[
+        (0, 1, 1, 60),
+        (2, 3, 2, 31),
+        (1, 3, 3, 17),
+        (4, 1, 4, 11),
+        (4, 0, 5, 8),
+        (2, 0, 6, 7),
+        (2, 4, 128, 129),
+        (2, 1, 130, 131),
+        (2, 4, 9, 10),
+        (2, 0, 132, 133),
+        (2, 1, 134, 135),
+        (4, 3, 12, 15),
+        (2, 1, 13, 14),
+        (2, 0, 136, 137),
+        (2, 4, 138, 139),
+        (2, 1, 16, 142),
+        (2, 0, 140, 141),
+        (4, 3, 18, 25),
+        (4, 2, 19, 22),
+        (0, 2, 20, 21),
+        (0, 4, 143, 144),
+        (0, 3, 145, 146),
+        (0, 4, 23, 24),
+        (0, 2, 147, 148),
+        (0, 3, 149, 150),
+        (4, 1, 26, 29),
+        (0, 3, 27, 28),
+        (0, 2, 151, 152),
+        (0, 4, 153, 154),
+        (0, 3, 30, 157),
+        (0, 2, 155, 156),
+        (1, 2, 32, 46),
+        (4, 1, 33, 40),
+        (4, 0, 34, 37),
+        (3, 0, 35, 36),
+        (3, 4, 158, 159),
+        (3, 1, 160, 161),
+        (3, 4, 38, 39),
+        (3, 0, 162, 163),
+        (3, 1, 164, 165),
+        (4, 2, 41, 44),
+        (3, 1, 42, 43),
+        (3, 0, 166, 167),
+        (3, 4, 168, 169),
+        (3, 1, 45, 172),
+        (3, 0, 170, 171),
+        (4, 2, 47, 54),
+        (4, 3, 48, 51),
+        (0, 3, 49, 50),
+        (0, 4, 173, 174),
+        (0, 2, 175, 176),
+        (0, 4, 52, 53),
+        (0, 3, 177, 178),
+        (0, 2, 179, 180),
+        (4, 1, 55, 58),
+        (0, 2, 56, 57),
+        (0, 3, 181, 182),
+        (0, 4, 183, 184),
+        (0, 2, 59, 187),
+        (0, 3, 185, 186),
+        (2, 3, 61, 90),
+        (0, 3, 62, 76),
+        (4, 0, 63, 70),
+        (4, 1, 64, 67),
+        (2, 1, 65, 66),
+        (2, 4, 188, 189),
+        (2, 0, 190, 191),
+        (2, 4, 68, 69),
+        (2, 1, 192, 193),
+        (2, 0, 194, 195),
+        (4, 3, 71, 74),
+        (2, 0, 72, 73),
+        (2, 1, 196, 197),
+        (2, 4, 198, 199),
+        (2, 0, 75, 202),
+        (2, 1, 200, 201),
+        (4, 3, 77, 84),
+        (4, 2, 78, 81),
+        (1, 2, 79, 80),
+        (1, 4, 203, 204),
+        (1, 3, 205, 206),
+        (1, 4, 82, 83),
+        (1, 2, 207, 208),
+        (1, 3, 209, 210),
+        (4, 0, 85, 88),
+        (1, 3, 86, 87),
+        (1, 2, 211, 212),
+        (1, 4, 213, 214),
+        (1, 3, 89, 217),
+        (1, 2, 215, 216),
+        (0, 2, 91, 105),
+        (4, 0, 92, 99),
+        (4, 1, 93, 96),
+        (3, 1, 94, 95),
+        (3, 4, 218, 219),
+        (3, 0, 220, 221),
+        (3, 4, 97, 98),
+        (3, 1, 222, 223),
+        (3, 0, 224, 225),
+        (4, 2, 100, 103),
+        (3, 0, 101, 102),
+        (3, 1, 226, 227),
+        (3, 4, 228, 229),
+        (3, 0, 104, 232),
+        (3, 1, 230, 231),
+        (4, 2, 106, 113),
+        (4, 3, 107, 110),
+        (1, 3, 108, 109),
+        (1, 4, 233, 234),
+        (1, 2, 235, 236),
+        (1, 4, 111, 112),
+        (1, 3, 237, 238),
+        (1, 2, 239, 240),
+        (4, 0, 114, 117),
+        (1, 2, 115, 116),
+        (1, 3, 241, 242),
+        (1, 4, 243, 244),
+        (1, 2, 118, 247),
+        (1, 3, 245, 246),
+    ],
+    orders: &[
+        [2, 4, 0, 1, 3],
+        [4, 2, 0, 1, 3],
+        [4, 0, 2, 1, 3],
+        [4, 0, 1, 2, 3],
+        [2, 0, 4, 1, 3],
+        [0, 2, 4, 1, 3],
+        [0, 4, 2, 1, 3],
+        [0, 4, 1, 2, 3],
+        [2, 0, 1, 4, 3],
+        [0, 2, 1, 4, 3],
+        [0, 1, 2, 4, 3],
+        [0, 1, 4, 2, 3],
+        [2, 0, 1, 3, 4],
+        [0, 2, 1, 3, 4],
+        [0, 1, 2, 3, 4],
+        [0, 4, 2, 3, 1],
+        [4, 0, 2, 3, 1],
+        [4, 2, 0, 3, 1],
+        [4, 2, 3, 0, 1],
+        [0, 2, 4, 3, 1],
+        [2, 0, 4, 3, 1],
+        [2, 4, 0, 3, 1],
+        [2, 4, 3, 0, 1],
+        [0, 2, 3, 4, 1],
+        [2, 0, 3, 4, 1],
+        [2, 3, 0, 4, 1],
+        [2, 3, 4, 0, 1],
+        [0, 2, 3, 1, 4],
+        [2, 0, 3, 1, 4],
+        [2, 3, 0, 1, 4],
+        [3, 4, 0, 1, 2],
+        [4, 3, 0, 1, 2],
+        [4, 0, 3, 1, 2],
+        [4, 0, 1, 3, 2],
+        [3, 0, 4, 1, 2],
+        [0, 3, 4, 1, 2],
+        [0, 4, 3, 1, 2],
+        [0, 4, 1, 3, 2],
+        [3, 0, 1, 4, 2],
+        [0, 3, 1, 4, 2],
+        [0, 1, 3, 4, 2],
+        [0, 1, 4, 3, 2],
+        [3, 0, 1, 2, 4],
+        [0, 3, 1, 2, 4],
+        [0, 1, 3, 2, 4],
+        [0, 4, 3, 2, 1],
+        [4, 0, 3, 2, 1],
+        [4, 3, 0, 2, 1],
+        [4, 3, 2, 0, 1],
+        [0, 3, 4, 2, 1],
+        [3, 0, 4, 2, 1],
+        [3, 4, 0, 2, 1],
+        [3, 4, 2, 0, 1],
+        [0, 3, 2, 4, 1],
+        [3, 0, 2, 4, 1],
+        [3, 2, 0, 4, 1],
+        [3, 2, 4, 0, 1],
+        [0, 3, 2, 1, 4],
+        [3, 0, 2, 1, 4],
+        [3, 2, 0, 1, 4],
+        [2, 4, 1, 0, 3],
+        [4, 2, 1, 0, 3],
+        [4, 1, 2, 0, 3],
+        [4, 1, 0, 2, 3],
+        [2, 1, 4, 0, 3],
+        [1, 2, 4, 0, 3],
+        [1, 4, 2, 0, 3],
+        [1, 4, 0, 2, 3],
+        [2, 1, 0, 4, 3],
+        [1, 2, 0, 4, 3],
+        [1, 0, 2, 4, 3],
+        [1, 0, 4, 2, 3],
+        [2, 1, 0, 3, 4],
+        [1, 2, 0, 3, 4],
+        [1, 0, 2, 3, 4],
+        [1, 4, 2, 3, 0],
+        [4, 1, 2, 3, 0],
+        [4, 2, 1, 3, 0],
+        [4, 2, 3, 1, 0],
+        [1, 2, 4, 3, 0],
+        [2, 1, 4, 3, 0],
+        [2, 4, 1, 3, 0],
+        [2, 4, 3, 1, 0],
+        [1, 2, 3, 4, 0],
+        [2, 1, 3, 4, 0],
+        [2, 3, 1, 4, 0],
+        [2, 3, 4, 1, 0],
+        [1, 2, 3, 0, 4],
+        [2, 1, 3, 0, 4],
+        [2, 3, 1, 0, 4],
+        [3, 4, 1, 0, 2],
+        [4, 3, 1, 0, 2],
+        [4, 1, 3, 0, 2],
+        [4, 1, 0, 3, 2],
+        [3, 1, 4, 0, 2],
+        [1, 3, 4, 0, 2],
+        [1, 4, 3, 0, 2],
+        [1, 4, 0, 3, 2],
+        [3, 1, 0, 4, 2],
+        [1, 3, 0, 4, 2],
+        [1, 0, 3, 4, 2],
+        [1, 0, 4, 3, 2],
+        [3, 1, 0, 2, 4],
+        [1, 3, 0, 2, 4],
+        [1, 0, 3, 2, 4],
+        [1, 4, 3, 2, 0],
+        [4, 1, 3, 2, 0],
+        [4, 3, 1, 2, 0],
+        [4, 3, 2, 1, 0],
+        [1, 3, 4, 2, 0],
+        [3, 1, 4, 2, 0],
+        [3, 4, 1, 2, 0],
+        [3, 4, 2, 1, 0],
+        [1, 3, 2, 4, 0],
+        [3, 1, 2, 4, 0],
+        [3, 2, 1, 4, 0],
+        [3, 2, 4, 1, 0],
+        [1, 3, 2, 0, 4],
+        [3, 1, 2, 0, 4],
+        [3, 2, 1, 0, 4],
+    ],
+};
+
+#[cfg(test)]
+mod tests {
+    use super::{FIVE, FOUR, LEAF, THREE, TWO, Tree, sort};
+    use crate::tests::permute;
+    use crate::{Step, Stepper, merge_insertion, optimal_turns};
+
+    /// The asks and orders of a [`Tree`].
+    type Table = (Vec<(u8, u8, u8, u8)>, Vec<Vec<u8>>);
+
+    /// The tree for `n` items as the recursion asks it, numbered like the
+    /// tables: nodes and leaves in the order a depth-first walk reaches
+    /// them, taking the first position ranking before the second first.
+    fn rebuild(n: usize) -> Table {
+        fn walk(
+            n: usize,
+            answers: &mut Vec<bool>,
+            asks: &mut Vec<(u8, u8, u8, u8)>,
+            orders: &mut Vec<Vec<u8>>,
+        ) -> u8 {
+            let mut given = answers.iter().copied();
+            let mut next = None;
+            let order = merge_insertion((0..n).collect(), &mut |a, b| {
+                given.next().unwrap_or_else(|| {
+                    next.get_or_insert((a, b));
+                    true
+                })
+            });
+            let small = |p: usize| u8::try_from(p).unwrap();
+            let Some((a, b)) = next else {
+                orders.push(order.into_iter().map(small).collect());
+                return LEAF + small(orders.len() - 1);
+            };
+            let node = asks.len();
+            asks.push((small(a), small(b), 0, 0));
+            for a_first in [true, false] {
+                answers.push(a_first);
+                let child = walk(n, answers, asks, orders);
+                answers.pop();
+                if a_first {
+                    asks[node].2 = child;
+                } else {
+                    asks[node].3 = child;
+                }
+            }
+            small(node)
+        }
+        let (mut asks, mut orders) = (Vec::new(), Vec::new());
+        walk(n, &mut Vec::new(), &mut asks, &mut orders);
+        (asks, orders)
+    }
+
+    fn table<const N: usize>(tree: Tree<N>) -> Table {
+        (
+            tree.asks.to_vec(),
+            tree.orders.iter().map(|order| order.to_vec()).collect(),
+        )
+    }
+
+    #[test]
+    fn tables_are_the_recursion_spelled_out() {
+        assert_eq!(table(TWO), rebuild(2));
+        assert_eq!(table(THREE), rebuild(3));
+        assert_eq!(table(FOUR), rebuild(4));
+        assert_eq!(table(FIVE), rebuild(5));
+    }
+
+    #[test]
+    fn tables_ask_what_the_recursion_and_the_stepper_ask() {
+        for n in 0..=5 {
+            let mut worst = 0;
+            let mut values: Vec<usize> = (0..n).collect();
+            permute(&mut values, n, &mut |values| {
+                let mut asked = Vec::new();
+                let ids: Vec<usize> = (0..n).collect();
+                let order = sort(&ids, &mut |a, b| {
+                    asked.push((a, b));
+                    values[a] < values[b]
+                });
+                let mut expected = Vec::new();
+                let general = merge_insertion(ids, &mut |a, b| {
+                    expected.push((a, b));
+                    values[a] < values[b]
+                });
+                assert_eq!(order, Some(general), "{values:?}");
+                assert_eq!(asked, expected, "{values:?}");
+
+                let mut stepper = Stepper::new(n);
+                let mut questions = Vec::new();
+                let mut step = stepper.step();
+                while let Step::Compare { a, b, .. } = step {
+                    stepper.check_invariants();
+                    questions.push((a, b));
+                    step = stepper.answer(values[a] < values[b]);
+                }
+                assert_eq!(questions, asked, "{values:?}");
+                worst = worst.max(asked.len());
+            });
+            assert_eq!(Some(worst), optimal_turns(n));
+        }
+        assert_eq!(sort(&[0; 6], &mut |_, _| true), None);
+    }
+
+    #[test]
+    fn stepper_ranks_small_levels_from_the_tables() {
+        // Eleven items pair up into five winners, which a tree ranks.
+        let values = [7, 3, 9, 0, 5, 10, 1, 8, 2, 6, 4];
+        let mut stepper = Stepper::new(values.len());
+        let mut recursion = Stepper::replay(values.len(), &[]);
+        let mut step = stepper.step();
+        let mut expected = recursion.step();
+        while let Step::Compare { a, b, .. } = step {
+            stepper.check_invariants();
+            assert_eq!(step, expected);
+            assert_eq!(stepper.levels(), recursion.levels());
+            assert_eq!(
+                stepper.available_questions(),
+                recursion.available_questions()
+            );
+            step = stepper.answer(values[a] < values[b]);
+            expected = recursion.answer(values[a] < values[b]);
+        }
+        assert_eq!(expected, Step::Done);
+        assert_eq!(stepper.take_order(), recursion.take_order());
+    }
+}
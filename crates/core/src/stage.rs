@@ -0,0 +1,173 @@
+//! The [`Stepper`] as typed handles.
+//!
+//! A plain [`Stepper`] accepts an answer at any time and drops it when
+//! nothing was asked. [`Stage`] splits the run into what it is waiting for:
+//! a [`NeedsAnswer`] holds the question and is the only handle that takes
+//! an answer, and a [`Ready`] holds the finished ranking. Every answer
+//! consumes the handle and returns the next stage, so answering twice or
+//! without a question does not compile:
+//!
+//! ```
+//! use rankfast::stage::Stage;
+//!
+//! let scores = [3, 9, 1, 7];
+//! let mut stage = Stage::new(scores.len());
+//! let ranking = loop {
+//!     match stage {
+//!         Stage::NeedsAnswer(question) => {
+//!             let better = scores[question.a()] > scores[question.b()];
+//!             stage = question.answer(better);
+//!         }
+//!         Stage::Ready(ready) => break ready.into_ranking(),
+//!     }
+//! };
+//! assert_eq!(ranking, vec![1, 3, 0, 2]);
+//! ```
+
+use crate::{Context, Ranking, Step, Stepper};
+
+/// Where a run stands.
+#[derive(Clone)]
+pub enum Stage {
+    /// A question is waiting for its answer.
+    NeedsAnswer(NeedsAnswer),
+    /// Every question has been answered.
+    Ready(Ready),
+}
+
+impl Stage {
+    /// Starts ranking the indices `0..n`.
+    #[must_use]
+    pub fn new(n: usize) -> Self {
+        Self::from(Stepper::new(n))
+    }
+
+    /// The stepper underneath, to save the run or read its progress.
+    #[must_use]
+    pub fn stepper(&self) -> &Stepper {
+        match self {
+            Self::NeedsAnswer(question) => &question.stepper,
+            Self::Ready(ready) => &ready.stepper,
+        }
+    }
+}
+
+impl From<Stepper> for Stage {
+    /// Carries on from `stepper`, which may be part-way through a run.
+    fn from(mut stepper: Stepper) -> Self {
+        match stepper.step() {
+            Step::Compare { a, b, context } => Self::NeedsAnswer(NeedsAnswer {
+                stepper,
+                a,
+                b,
+                context,
+            }),
+            Step::Done => Self::Ready(Ready { stepper }),
+        }
+    }
+}
+
+/// A run waiting for the answer to one question: is item
+/// [`a`](Self::a) better than item [`b`](Self::b)?
+#[derive(Clone)]
+#[must_use = "the run goes on once the question is answered"]
+pub struct NeedsAnswer {
+    stepper: Stepper,
+    a: usize,
+    b: usize,
+    context: Context,
+}
+
+impl NeedsAnswer {
+    #[must_use]
+    pub fn a(&self) -> usize {
+        self.a
+    }
+
+    #[must_use]
+    pub fn b(&self) -> usize {
+        self.b
+    }
+
+    /// Why the question is asked.
+    #[must_use]
+    pub fn context(&self) -> Context {
+        self.context
+    }
+
+    /// Answers whether `a` is better than `b`.
+    #[must_use]
+    pub fn answer(mut self, a_is_better: bool) -> Stage {
+        self.stepper.answer(a_is_better);
+        self.stepper.into()
+    }
+
+    /// Answers that `a` and `b` are equal, see [`Stepper::answer_tie`].
+    #[must_use]
+    pub fn tie(mut self) -> Stage {
+        self.stepper.answer_tie();
+        self.stepper.into()
+    }
+
+    /// Gives the stepper back, the question still pending.
+    #[must_use]
+    pub fn into_stepper(self) -> Stepper {
+        self.stepper
+    }
+}
+
+/// A finished run.
+#[derive(Clone)]
+pub struct Ready {
+    stepper: Stepper,
+}
+
+impl Ready {
+    /// Number of questions the run asked.
+    #[must_use]
+    pub fn comparisons_made(&self) -> usize {
+        self.stepper.comparisons_made()
+    }
+
+    /// The final ranking.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal state machine is inconsistent.
+    #[must_use]
+    pub fn into_ranking(mut self) -> Ranking<usize> {
+        self.stepper
+            .take_order()
+            .expect("a ready stepper has its order")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Stage;
+    use crate::{NotAsked, Stepper};
+
+    #[test]
+    fn answers_need_a_question() {
+        let mut stepper = Stepper::new(3);
+        assert_eq!(stepper.try_answer(true), Err(NotAsked));
+        stepper.step();
+        assert!(stepper.try_answer(true).is_ok());
+
+        // Resumes where the stepper was and keeps its answers.
+        let Stage::NeedsAnswer(question) = Stage::from(stepper) else {
+            panic!("three items need more than one question");
+        };
+        let mut stage = question.tie();
+        while let Stage::NeedsAnswer(question) = stage {
+            let better = question.a() < question.b();
+            stage = question.answer(better);
+        }
+        let Stage::Ready(ready) = stage else {
+            unreachable!()
+        };
+        assert!((2..=3).contains(&ready.comparisons_made()));
+        assert_eq!(ready.into_ranking().len(), 3);
+        assert!(matches!(Stage::new(1), Stage::Ready(_)));
+    }
+}
@@ -0,0 +1,1694 @@
+//! Interactive, resumable Ford-Johnson sorter.
+//!
+//! [`Stepper`] runs the same algorithm as [`rank_items`](crate::rank_items)
+//! but hands control back to the caller whenever a comparison is needed,
+//! so answers can come from a UI, a network peer, or a replayed history.
+//!
+//! A stepper can also start from relations that are already known
+//! ([`Stepper::with_known_pairs`]); questions those relations decide, directly
+//! or by transitivity, are answered internally and never asked.
+//!
+//! Levels of up to five items are ranked from precomputed decision trees,
+//! like in [`rank_items`](crate::rank_items), so their questions stay the
+//! same whatever becomes of the recursion. Their question contexts and
+//! [`levels`](Stepper::levels) are still those of the recursion, which the
+//! stepper replays over the level's answers.
+//!
+//! With the `tracing` feature, each question, answer and change of phase is
+//! emitted as a `trace` event, with the recursion depth where it happens.
+
+use std::fmt;
+
+use crate::chain::{Chain, MainPositions};
+use crate::known::{KnownOrder, consistent_pairs};
+use crate::small::{self, Node};
+use crate::{Error, Ranking, ceil_log2, chain_bound, estimate_turns, jacobsthal_order};
+
+/// What the stepper needs next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    /// Ask whether item `a` is better than item `b`.
+    Compare {
+        a: usize,
+        b: usize,
+        /// Why this question is asked.
+        context: Context,
+    },
+    /// The ranking is complete.
+    Done,
+}
+
+impl Step {
+    /// A question about `a` vs `b` in `phase`, at `depth`.
+    pub(crate) fn compare(a: usize, b: usize, phase: Phase, depth: usize) -> Self {
+        Self::Compare {
+            a,
+            b,
+            context: Context { phase, depth },
+        }
+    }
+}
+
+/// An answer given while no question was pending, from
+/// [`Stepper::try_answer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NotAsked;
+
+impl fmt::Display for NotAsked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("no question is pending")
+    }
+}
+
+impl std::error::Error for NotAsked {}
+
+/// Where a question sits in the run, for frontends that explain why they
+/// ask it ("this narrows X's place among 7 candidates").
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Context {
+    pub phase: Phase,
+    /// The Ford-Johnson recursion level (0 ranks the whole list, each level
+    /// below ranks the winners of its pairs), the round of a tournament, or
+    /// the splits above a [`QuickRank`](crate::quickrank::QuickRank) group.
+    /// Always 0 for engines with none of these.
+    pub depth: usize,
+}
+
+/// The kind of question being asked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Pairing items up, or a scheduled match between two of them.
+    Pairing,
+    /// Binary-searching the place of `a` among `candidates` places it can
+    /// still take.
+    Insertion { candidates: usize },
+    /// A pair chosen adaptively for what its answer would reveal.
+    Adaptive,
+    /// Splitting a group around an anchor, `b` once it is chosen, with
+    /// `left` more of the group's items to compare with it after this one.
+    Partition { left: usize },
+}
+
+/// One recursion level of a running [`Stepper`], from
+/// [`Stepper::levels`], for showing how the sort gets along. Items are
+/// indices into `0..n`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LevelState {
+    /// The items this level ranks.
+    pub items: Vec<usize>,
+    pub stage: LevelStage,
+}
+
+/// What a [`LevelState`] is doing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LevelStage {
+    /// Comparing its items two by two; `compared` of `pairs` are done.
+    Pairing { compared: usize, pairs: usize },
+    /// Waiting for the level below to rank the pair winners.
+    RankingWinners,
+    /// Inserting partners into `chain`, best first. `pending` waits in
+    /// insertion order, after the item being searched for, if any.
+    Inserting {
+        chain: Vec<usize>,
+        pending: Vec<usize>,
+        search: Option<SearchBounds>,
+    },
+    /// Ranked, about to hand its order to the level above.
+    Done,
+}
+
+/// The place of `item` being searched for: before `chain[lo]` at best,
+/// before `chain[hi]` (or last) at worst.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchBounds {
+    pub item: usize,
+    pub lo: usize,
+    pub hi: usize,
+}
+
+/// Step-by-step Ford-Johnson sorter over the indices `0..n`.
+#[derive(Clone)]
+pub struct Stepper {
+    n: usize,
+    stack: Vec<Frame>,
+    pending: Option<Pending>,
+    history: Vec<(usize, usize, bool)>,
+    done: Option<Vec<usize>>,
+    /// Answers received out of order via [`Stepper::answer_question`],
+    /// applied automatically once the stepper reaches them.
+    buffered: Vec<(usize, usize, bool)>,
+    /// Seeded relations plus every answer since, when seeded.
+    known: Option<KnownOrder>,
+    /// Pairs answered with [`Stepper::answer_tie`].
+    ties: Vec<(usize, usize)>,
+    /// Whether small levels are ranked from their decision trees; not for
+    /// the replays that describe them.
+    tables: bool,
+}
+
+impl Stepper {
+    /// Creates a stepper that ranks the indices `0..n`.
+    #[must_use]
+    pub fn new(n: usize) -> Self {
+        if n <= 1 {
+            return Self {
+                n,
+                stack: Vec::new(),
+                pending: None,
+                history: Vec::new(),
+                done: Some((0..n).collect()),
+                buffered: Vec::new(),
+                known: None,
+                ties: Vec::new(),
+                tables: true,
+            };
+        }
+
+        Self {
+            n,
+            stack: vec![Frame::new((0..n).collect())],
+            pending: None,
+            history: Vec::new(),
+            done: None,
+            buffered: Vec::new(),
+            known: None,
+            ties: Vec::new(),
+            tables: true,
+        }
+    }
+
+    /// Creates a stepper over `0..n` that already knows, for every
+    /// `(better, worse)` in `pairs`, that `better` ranks above `worse`.
+    ///
+    /// Questions decided by these pairs and the answers given since — also
+    /// transitively — are skipped: they never show up as a [`Step`], in
+    /// [`history`](Self::history), or in
+    /// [`comparisons_made`](Self::comparisons_made). As with the comparator
+    /// of [`rank_items`](crate::rank_items), the pairs must be consistent
+    /// with a strict order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a pair refers to an item outside `0..n`.
+    #[must_use]
+    pub fn with_known_pairs(n: usize, pairs: &[(usize, usize)]) -> Self {
+        let mut known = KnownOrder::new(n);
+        for &(better, worse) in pairs {
+            assert!(
+                better < n && worse < n,
+                "pair ({better}, {worse}) out of range"
+            );
+            known.insert(better, worse);
+        }
+        Self {
+            known: Some(known),
+            ..Self::new(n)
+        }
+    }
+
+    /// Advances the sorter until it needs a comparison or is done.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal state machine is inconsistent, which indicates
+    /// a bug in the stepper implementation.
+    pub fn step(&mut self) -> Step {
+        if let Some(step) = self.pending_step() {
+            return step;
+        }
+
+        loop {
+            if self.done.is_some() {
+                return Step::Done;
+            }
+
+            if self.stack.is_empty() {
+                self.done = Some(Vec::new());
+                return Step::Done;
+            }
+
+            if self.pop_done_frame() {
+                continue;
+            }
+
+            if let Some((a, b)) = self.advance_frame() {
+                if let Some(better_is_a) = self.take_buffered(a, b) {
+                    self.apply(better_is_a);
+                    continue;
+                }
+                if let Some(better_is_a) = self.infer(a, b) {
+                    if let Some(pending) = self.pending.take() {
+                        self.resolve(pending, better_is_a);
+                    }
+                    continue;
+                }
+                #[cfg(feature = "tracing")]
+                tracing::trace!(a, b, depth = self.stack.len() - 1, "question");
+                return self.pending_step().expect("an asked question is pending");
+            }
+        }
+    }
+
+    /// Applies the result of the last comparison and advances to the next step.
+    ///
+    /// Without a pending comparison (before the first [`step`](Self::step),
+    /// or once done) the answer is dropped and this is just `step`; see
+    /// [`try_answer`](Self::try_answer) to catch that, or
+    /// [`Stage`](crate::stage::Stage) for handles that rule it out.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal state machine is inconsistent.
+    pub fn answer(&mut self, better_is_a: bool) -> Step {
+        self.apply(better_is_a);
+        self.step()
+    }
+
+    /// Like [`answer`](Self::answer), but fails instead of dropping the
+    /// answer when no comparison is pending.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`NotAsked`] if [`step`](Self::step) has not returned a
+    /// question since the last answer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal state machine is inconsistent.
+    pub fn try_answer(&mut self, better_is_a: bool) -> Result<Step, NotAsked> {
+        if self.pending.is_none() {
+            return Err(NotAsked);
+        }
+        Ok(self.answer(better_is_a))
+    }
+
+    /// Answers the pending comparison as a tie, ranking whichever of the two
+    /// items comes first in the input higher.
+    ///
+    /// Ties answered this way break in input order consistently, so a run
+    /// whose other answers follow a strict weak order ranks equal items
+    /// stably, as [`rank_items`](crate::rank_items) does. A run rebuilt by
+    /// replaying its answers (to undo the last one, say) asks the same
+    /// questions, so the guarantee carries over. Tied items end up in one
+    /// group of the final [`Ranking`], together with any item ranked between
+    /// them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal state machine is inconsistent.
+    pub fn answer_tie(&mut self) -> Step {
+        let Some(
+            Pending::Pairing { a, b } | Pending::Search { a, b } | Pending::Table { a, b, .. },
+        ) = self.pending
+        else {
+            return self.step();
+        };
+        self.ties.push((a, b));
+        self.answer(a < b)
+    }
+
+    /// The question that would follow answering the pending one with
+    /// `better_is_a`, or `None` if the ranking would then be done (or no
+    /// question is pending).
+    ///
+    /// Peeking both outcomes lets a UI preload whatever the next pair needs
+    /// while the user is still thinking. The stepper itself is unchanged;
+    /// the lookahead runs on a copy of its state, which costs `O(n)`.
+    #[must_use]
+    pub fn peek_next_after(&self, better_is_a: bool) -> Option<(usize, usize)> {
+        self.pending?;
+        match self.clone().answer(better_is_a) {
+            Step::Compare { a, b, .. } => Some((a, b)),
+            Step::Done => None,
+        }
+    }
+
+    /// Returns every comparison that can be answered right now, in any
+    /// order and independently of each other.
+    ///
+    /// During a pairing round this is every pair not yet answered; during
+    /// insertion it is only the current binary-search question, since each
+    /// one depends on the previous outcome. Empty once the ranking is done.
+    pub fn available_questions(&mut self) -> Vec<(usize, usize)> {
+        let Step::Compare { a, b, .. } = self.step() else {
+            return Vec::new();
+        };
+
+        let mut questions = vec![(a, b)];
+        let rest: Vec<(usize, usize)> = match self.stack.last() {
+            Some(Frame {
+                elements,
+                state: State::Pairing { i, num_pairs, .. },
+            }) => (*i + 1..*num_pairs)
+                .map(|p| (elements[2 * p], elements[2 * p + 1]))
+                .collect(),
+            Some(Frame {
+                elements,
+                state: State::Table { answers, .. },
+            }) => Self::replay(elements.len(), answers)
+                .available_questions()
+                .into_iter()
+                .skip(1)
+                .map(|(a, b)| (elements[a], elements[b]))
+                .collect(),
+            _ => Vec::new(),
+        };
+        questions.extend(
+            rest.into_iter()
+                .filter(|&(a, b)| !self.is_buffered(a, b))
+                .filter(|&(a, b)| self.infer(a, b).is_none()),
+        );
+        questions
+    }
+
+    /// Answers one of the questions returned by
+    /// [`available_questions`](Self::available_questions), which need not be
+    /// the current one. The pair may be given in either order.
+    ///
+    /// Returns the next step, or `None` if `(a, b)` is not currently
+    /// answerable.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal state machine is inconsistent.
+    pub fn answer_question(&mut self, a: usize, b: usize, a_is_better: bool) -> Option<Step> {
+        let (a, b, better_is_a) = match self.step() {
+            Step::Compare { a: pa, b: pb, .. } if (pa, pb) == (a, b) => {
+                return Some(self.answer(a_is_better));
+            }
+            Step::Compare { a: pa, b: pb, .. } if (pa, pb) == (b, a) => {
+                return Some(self.answer(!a_is_better));
+            }
+            Step::Compare { .. } => {
+                let questions = self.available_questions();
+                if questions.contains(&(a, b)) {
+                    (a, b, a_is_better)
+                } else if questions.contains(&(b, a)) {
+                    (b, a, !a_is_better)
+                } else {
+                    return None;
+                }
+            }
+            Step::Done => return None,
+        };
+
+        self.buffered.push((a, b, better_is_a));
+        Some(self.step())
+    }
+
+    /// Like [`answer_question`](Self::answer_question), but fails instead
+    /// of returning `None` when the pair is not open.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidAnswer`] if `(a, b)` is not among the
+    /// [`available_questions`](Self::available_questions).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal state machine is inconsistent.
+    pub fn try_answer_question(
+        &mut self,
+        a: usize,
+        b: usize,
+        a_is_better: bool,
+    ) -> Result<Step, Error> {
+        self.answer_question(a, b, a_is_better)
+            .ok_or(Error::InvalidAnswer { a, b })
+    }
+
+    /// Records the answer to the pending comparison without advancing.
+    fn apply(&mut self, better_is_a: bool) {
+        let Some(pending) = self.pending.take() else {
+            return;
+        };
+
+        let (Pending::Pairing { a, b } | Pending::Search { a, b } | Pending::Table { a, b, .. }) =
+            pending;
+        #[cfg(feature = "tracing")]
+        tracing::trace!(a, b, better_is_a, "answer");
+        self.history.push((a, b, better_is_a));
+        if let Some(known) = &mut self.known {
+            if better_is_a {
+                known.insert(a, b);
+            } else {
+                known.insert(b, a);
+            }
+        }
+        self.resolve(pending, better_is_a);
+    }
+
+    /// Feeds an answer for `pending`, asked or inferred, into the sort.
+    fn resolve(&mut self, pending: Pending, better_is_a: bool) {
+        match pending {
+            Pending::Pairing { .. } => {
+                let frame = self
+                    .stack
+                    .last_mut()
+                    .expect("pairing answer requires active frame");
+                let State::Pairing { i, mains, .. } = &mut frame.state else {
+                    unreachable!("pairing answer requires pairing state")
+                };
+
+                mains.push(if better_is_a { 2 * *i + 1 } else { 2 * *i });
+                *i += 1;
+            }
+            Pending::Search { .. } => {
+                let frame = self
+                    .stack
+                    .last_mut()
+                    .expect("search answer requires active frame");
+                let State::Insert(insertion) = &mut frame.state else {
+                    unreachable!("search answer requires insert state")
+                };
+
+                let search_state = insertion
+                    .search
+                    .as_mut()
+                    .expect("search state must exist for comparison");
+                let mid = search_state.mid.take().expect("mid must be set");
+                if better_is_a {
+                    search_state.hi = mid;
+                } else {
+                    search_state.lo = mid + 1;
+                }
+
+                if search_state.lo == search_state.hi {
+                    insertion.finish_search();
+                }
+            }
+            Pending::Table { .. } => {
+                let frame = self
+                    .stack
+                    .last_mut()
+                    .expect("table answer requires active frame");
+                let State::Table { node, answers } = &mut frame.state else {
+                    unreachable!("table answer requires table state")
+                };
+
+                *node = small::next(frame.elements.len(), *node, better_is_a);
+                answers.push(better_is_a);
+            }
+        }
+    }
+
+    /// Drops item `idx` from the ranking, part-way through or after it.
+    ///
+    /// Every answer not involving `idx` is kept, and so is what answers
+    /// through it implied: if `a` beat `idx` and `idx` beat `b`, `a` still
+    /// ranks above `b`. The run then carries on over the remaining items
+    /// with only the questions those answers leave open. Items after `idx`
+    /// move down one index, the stepper starts over as if
+    /// [seeded](Self::with_known_pairs) with everything it knew, so its
+    /// [`history`](Self::history) is empty, and ties are kept in input
+    /// order like any other answer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is not below `n`.
+    pub fn remove_item(&mut self, idx: usize) {
+        assert!(idx < self.n, "item {idx} out of range");
+        let mut pairs: Vec<(usize, usize)> = self
+            .history
+            .iter()
+            .chain(&self.buffered)
+            .map(|&(a, b, a_better)| if a_better { (a, b) } else { (b, a) })
+            .collect();
+        if let Some(known) = &self.known {
+            pairs.extend(known.pairs());
+        }
+        let above: Vec<usize> = pairs
+            .iter()
+            .filter(|&&(_, worse)| worse == idx)
+            .map(|&(better, _)| better)
+            .collect();
+        let below: Vec<usize> = pairs
+            .iter()
+            .filter(|&&(better, _)| better == idx)
+            .map(|&(_, worse)| worse)
+            .collect();
+        for &better in &above {
+            pairs.extend(below.iter().map(|&worse| (better, worse)));
+        }
+        let shift = |item: usize| if item > idx { item - 1 } else { item };
+        let pairs: Vec<(usize, usize)> = pairs
+            .into_iter()
+            .filter(|&(better, worse)| better != idx && worse != idx)
+            .map(|(better, worse)| (shift(better), shift(worse)))
+            .collect();
+        let n = self.n - 1;
+        *self = Self::with_known_pairs(n, &consistent_pairs(n, &pairs));
+    }
+
+    /// Takes the final ranking once [`Step::Done`] has been reached.
+    pub fn take_order(&mut self) -> Option<Ranking<usize>> {
+        let order = self.done.take()?;
+        Some(self.with_ties(order))
+    }
+
+    /// The best ranking the answers so far support, for users who stop
+    /// before the sort is done.
+    ///
+    /// Once [`Step::Done`] has been reached this is the final ranking, as
+    /// [`take_order`](Self::take_order) would return it. Before that, items
+    /// are grouped as in [`Ranking::from_outcomes`] over the answers and any
+    /// known pairs: items the answers do not yet tell apart share a group,
+    /// and so do items answered as a tie. The stepper itself is left as it
+    /// was, so the run can still carry on.
+    #[must_use]
+    pub fn finalize_partial(&self) -> Ranking<usize> {
+        if let Some(order) = &self.done {
+            return self.with_ties(order.clone());
+        }
+        let tied = |a: usize, b: usize| self.ties.contains(&(a, b));
+        let mut outcomes: Vec<(usize, usize)> = self
+            .history
+            .iter()
+            .filter(|&&(a, b, _)| !tied(a, b))
+            .map(|&(a, b, a_better)| if a_better { (a, b) } else { (b, a) })
+            .collect();
+        if let Some(known) = &self.known {
+            outcomes.extend(known.pairs());
+        }
+        Ranking::from_outcomes(self.n, &outcomes)
+    }
+
+    fn with_ties(&self, order: Vec<usize>) -> Ranking<usize> {
+        let mut position = vec![0; order.len()];
+        for (pos, &item) in order.iter().enumerate() {
+            position[item] = pos;
+        }
+        // A tie joins its two items and everything ranked between them.
+        let mut tied = vec![false; order.len()];
+        for &(a, b) in &self.ties {
+            let (lo, hi) = (position[a].min(position[b]), position[a].max(position[b]));
+            tied[lo + 1..=hi].fill(true);
+        }
+        Ranking::from_ties(order, |i| tied[i])
+    }
+
+    /// Number of answers applied so far.
+    #[must_use]
+    pub fn comparisons_made(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Every question answered so far as `(a, b, a_is_better)`, in the
+    /// order the answers were applied.
+    #[must_use]
+    pub fn history(&self) -> &[(usize, usize, bool)] {
+        &self.history
+    }
+
+    /// The recursion levels in progress, the whole list first and the
+    /// level asking the pending question last. Empty once the ranking is
+    /// done.
+    #[must_use]
+    pub fn levels(&self) -> Vec<LevelState> {
+        self.stack.iter().flat_map(Frame::levels).collect()
+    }
+
+    /// Checks the stepper's bookkeeping and panics at the first
+    /// inconsistency: each level ranks the mains of the level above,
+    /// pairing and insertion indices are in range, every inserted partner
+    /// sits above its main, the pending question is the one the last level
+    /// asks, and each chain agrees with every answer, and every known
+    /// relation, between two of its items.
+    ///
+    /// This walks the whole state, in up to cubic time, so it only exists
+    /// in debug and test builds, for tests to run after every transition.
+    ///
+    /// # Panics
+    ///
+    /// If the state is inconsistent, saying what is wrong.
+    #[cfg(any(debug_assertions, test))]
+    pub fn check_invariants(&self) {
+        if let Some(root) = self.stack.first() {
+            assert!(
+                root.elements.iter().copied().eq(0..self.n),
+                "the first level does not rank 0..{}",
+                self.n
+            );
+        }
+        for (depth, frame) in self.stack.iter().enumerate() {
+            let below = self.stack.get(depth + 1);
+            if let State::AwaitMains { mains } = &frame.state {
+                let child = below.unwrap_or_else(|| panic!("level {depth} awaits no level"));
+                let expected: Vec<usize> = mains.iter().map(|&p| frame.elements[p]).collect();
+                assert_eq!(
+                    child.elements,
+                    expected,
+                    "level {} does not rank the mains of level {depth}",
+                    depth + 1
+                );
+            } else {
+                assert!(
+                    below.is_none(),
+                    "level {depth} is not awaiting the one below"
+                );
+            }
+            frame.check_invariants(depth, &self.history, self.known.as_ref());
+        }
+        let Some(pending) = self.pending else {
+            return;
+        };
+        let frame = self
+            .stack
+            .last()
+            .expect("a question is pending from a level");
+        let asked = match (pending, &frame.state) {
+            (Pending::Pairing { a, b }, State::Pairing { i, .. }) => {
+                (a, b) == (frame.elements[2 * i], frame.elements[2 * i + 1])
+            }
+            (Pending::Search { a, b }, State::Insert(ins)) => ins.search.is_some_and(|search| {
+                search.mid.is_some_and(|mid| {
+                    (a, b)
+                        == (
+                            frame.elements[search.elem],
+                            frame.elements[ins.chain.get(mid)],
+                        )
+                })
+            }),
+            (Pending::Table { a, b, .. }, State::Table { node, .. }) => matches!(
+                small::node(frame.elements.len(), *node),
+                Node::Ask(pa, pb) if (frame.elements[pa], frame.elements[pb]) == (a, b)
+            ),
+            _ => false,
+        };
+        assert!(asked, "{pending:?} is not what the last level asks");
+    }
+
+    /// Upper bound on the comparisons still needed, including the pending
+    /// one.
+    ///
+    /// Starts at [`estimate_turns`] and shrinks as answers narrow the
+    /// binary searches, so `comparisons_made() + remaining_estimate()` is a
+    /// total that only ever goes down. It assumes worst-case searches and
+    /// ignores questions that known pairs will answer, so the run may finish
+    /// early; it is zero once the ranking is done.
+    #[must_use]
+    pub fn remaining_estimate(&self) -> usize {
+        if self.done.is_some() {
+            return 0;
+        }
+        // The searches are tracked against the whole chain, which is looser
+        // than the worst case of the run as a whole until late in the run.
+        let searches: usize = self.stack.iter().map(Frame::remaining_estimate).sum();
+        searches.min(estimate_turns(self.n).saturating_sub(self.comparisons_made()))
+    }
+
+    fn is_buffered(&self, a: usize, b: usize) -> bool {
+        self.buffered.iter().any(|&(x, y, _)| (x, y) == (a, b))
+    }
+
+    /// The answer to `step` if the known relations already decide it.
+    fn infer(&self, a: usize, b: usize) -> Option<bool> {
+        self.known.as_ref()?.relation(a, b)
+    }
+
+    fn take_buffered(&mut self, a: usize, b: usize) -> Option<bool> {
+        let idx = self
+            .buffered
+            .iter()
+            .position(|&(x, y, _)| (x, y) == (a, b))?;
+        Some(self.buffered.swap_remove(idx).2)
+    }
+
+    /// The pending question. The asking frame is always on top of the
+    /// stack, above one frame per recursion level.
+    fn pending_step(&self) -> Option<Step> {
+        let (phase, a, b, below) = match self.pending? {
+            Pending::Pairing { a, b } => (Phase::Pairing, a, b, 0),
+            Pending::Search { a, b } => {
+                let search = self.stack.last().and_then(|frame| match &frame.state {
+                    State::Insert(insertion) => insertion.search,
+                    _ => None,
+                });
+                let candidates = search.map_or(1, |search| search.hi - search.lo + 1);
+                (Phase::Insertion { candidates }, a, b, 0)
+            }
+            Pending::Table { a, b, context } => (context.phase, a, b, context.depth),
+        };
+        Some(Step::compare(
+            a,
+            b,
+            phase,
+            self.stack.len().saturating_sub(1) + below,
+        ))
+    }
+
+    /// A stepper over `0..n` without decision trees, given `answers` in
+    /// turn: the recursion that the tree of a small level spells out.
+    pub(crate) fn replay(n: usize, answers: &[bool]) -> Self {
+        let mut replay = Self {
+            tables: false,
+            ..Self::new(n)
+        };
+        replay.step();
+        for &answer in answers {
+            replay.answer(answer);
+        }
+        replay
+    }
+
+    fn pop_done_frame(&mut self) -> bool {
+        let is_done = matches!(
+            self.stack.last().map(|frame| &frame.state),
+            Some(State::Done(_))
+        );
+        if !is_done {
+            return false;
+        }
+
+        let Some(frame) = self.stack.pop() else {
+            return false;
+        };
+        let State::Done(result) = frame.state else {
+            unreachable!("checked above")
+        };
+        self.propagate_result(&frame.elements, result);
+        true
+    }
+
+    /// Advances the top frame, returning the pair it asks about, if any.
+    fn advance_frame(&mut self) -> Option<(usize, usize)> {
+        let mut frame = self.stack.pop()?;
+        let elements = &frame.elements;
+        let state = std::mem::replace(&mut frame.state, State::Start);
+
+        let (next_state, step, child) = match state {
+            State::Start => (Self::advance_start(elements, self.tables), None, None),
+            State::Pairing {
+                i,
+                num_pairs,
+                mains,
+            } => self.advance_pairing(elements, i, num_pairs, mains),
+            State::AwaitMains { mains } => {
+                frame.state = State::AwaitMains { mains };
+                self.stack.push(frame);
+                unreachable!("awaiting child frame result")
+            }
+            State::Insert(insertion) => {
+                let (state, step) = self.advance_insert(elements, insertion);
+                (state, step, None)
+            }
+            State::Table { node, answers } => {
+                let (state, step) = self.advance_table(elements, node, answers);
+                (state, step, None)
+            }
+            State::Done(result) => (State::Done(result), None, None),
+        };
+
+        frame.state = next_state;
+        self.stack.push(frame);
+        if let Some(child) = child {
+            self.stack.push(child);
+        }
+        step
+    }
+
+    fn advance_start(elements: &[usize], tables: bool) -> State {
+        let n = elements.len();
+        if n <= 1 {
+            return State::Done((0..n).collect());
+        }
+        if tables && small::has_tree(n) {
+            return State::Table {
+                node: 0,
+                answers: Vec::new(),
+            };
+        }
+
+        let num_pairs = n / 2;
+        State::Pairing {
+            i: 0,
+            num_pairs,
+            mains: Vec::with_capacity(num_pairs),
+        }
+    }
+
+    fn advance_pairing(
+        &mut self,
+        elements: &[usize],
+        i: usize,
+        num_pairs: usize,
+        mains: Vec<usize>,
+    ) -> (State, Option<(usize, usize)>, Option<Frame>) {
+        if i < num_pairs {
+            let a = elements[2 * i];
+            let b = elements[2 * i + 1];
+            self.pending = Some(Pending::Pairing { a, b });
+            return (
+                State::Pairing {
+                    i,
+                    num_pairs,
+                    mains,
+                },
+                Some((a, b)),
+                None,
+            );
+        }
+
+        let child = Frame::new(mains.iter().map(|&p| elements[p]).collect());
+        #[cfg(feature = "tracing")]
+        tracing::trace!(
+            depth = self.stack.len() + 1,
+            pairs = num_pairs,
+            "ranking the pair winners"
+        );
+        (State::AwaitMains { mains }, None, Some(child))
+    }
+
+    fn advance_insert(
+        &mut self,
+        elements: &[usize],
+        mut ins: Insertion,
+    ) -> (State, Option<(usize, usize)>) {
+        if ins.order_idx >= ins.order.len() {
+            return (State::Done(ins.chain.into_vec()), None);
+        }
+
+        let search_state = ins.search.get_or_insert_with(|| {
+            let (elem, main) = ins.pending[ins.order[ins.order_idx]];
+            let bound = match main {
+                Some(j) => ins.positions.position(j),
+                None => ins.chain.len(),
+            };
+            SearchState {
+                elem,
+                lo: 0,
+                hi: bound,
+                mid: None,
+            }
+        });
+
+        if search_state.lo == search_state.hi {
+            ins.finish_search();
+            return (State::Insert(ins), None);
+        }
+
+        let mid = search_state.lo + (search_state.hi - search_state.lo) / 2;
+        search_state.mid = Some(mid);
+        let a = elements[search_state.elem];
+        let b = elements[ins.chain.get(mid)];
+        self.pending = Some(Pending::Search { a, b });
+        (State::Insert(ins), Some((a, b)))
+    }
+
+    fn advance_table(
+        &mut self,
+        elements: &[usize],
+        node: u8,
+        answers: Vec<bool>,
+    ) -> (State, Option<(usize, usize)>) {
+        let (a, b) = match small::node(elements.len(), node) {
+            Node::Ask(a, b) => (elements[a], elements[b]),
+            Node::Order(order) => {
+                return (
+                    State::Done(order.iter().map(|&p| usize::from(p)).collect()),
+                    None,
+                );
+            }
+        };
+        let Some(Step::Compare { context, .. }) =
+            Self::replay(elements.len(), &answers).pending_step()
+        else {
+            unreachable!("the recursion asks where its tree does")
+        };
+        self.pending = Some(Pending::Table { a, b, context });
+        (State::Table { node, answers }, Some((a, b)))
+    }
+
+    /// Hands a finished frame's result to its parent. Results are positions
+    /// within the finished frame, which for a child frame are pair indices
+    /// of its parent.
+    fn propagate_result(&mut self, elements: &[usize], result: Vec<usize>) {
+        let Some(parent) = self.stack.last_mut() else {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(comparisons = self.history.len(), "ranking complete");
+            self.done = Some(result.into_iter().map(|p| elements[p]).collect());
+            return;
+        };
+
+        let State::AwaitMains { mains } = std::mem::replace(&mut parent.state, State::Start) else {
+            unreachable!("only await-mains can receive a result")
+        };
+
+        // Chain and pending entries are positions within the parent frame;
+        // the partner of pair `p` sits next to its main, at `mains[p] ^ 1`.
+        let n = parent.elements.len();
+        let mut chain = Vec::with_capacity(n);
+        chain.push(mains[result[0]] ^ 1);
+        chain.extend(result.iter().map(|&p| mains[p]));
+
+        // Mains are referred to by their index in `result`, which
+        // `positions` maps to their current chain index.
+        let mut pending: Vec<(usize, Option<usize>)> = Vec::new();
+        for (j, &p) in result.iter().enumerate().skip(1) {
+            pending.push((mains[p] ^ 1, Some(j)));
+        }
+        if n % 2 == 1 {
+            pending.push((n - 1, None));
+        }
+
+        let order = jacobsthal_order(pending.len());
+        parent.state = State::Insert(Insertion {
+            chain: Chain::from_vec(chain),
+            pending,
+            order,
+            order_idx: 0,
+            search: None,
+            positions: MainPositions::new(result.len()),
+        });
+        #[cfg(feature = "tracing")]
+        tracing::trace!(depth = self.stack.len() - 1, "inserting the partners");
+    }
+}
+
+/// One recursion level. `elements` holds item indices; every other
+/// bookkeeping structure refers to positions within `elements`.
+#[derive(Debug, Clone)]
+struct Frame {
+    elements: Vec<usize>,
+    state: State,
+}
+
+impl Frame {
+    fn new(elements: Vec<usize>) -> Self {
+        Self {
+            elements,
+            state: State::Start,
+        }
+    }
+
+    /// The levels this frame ranks: its own, and those below it that a
+    /// decision tree spells out.
+    fn levels(&self) -> Vec<LevelState> {
+        let State::Table { node, answers } = &self.state else {
+            return vec![self.level_state()];
+        };
+        if let Node::Order(_) = small::node(self.elements.len(), *node) {
+            return vec![LevelState {
+                items: self.elements.clone(),
+                stage: LevelStage::Done,
+            }];
+        }
+        let item = |p: usize| self.elements[p];
+        Stepper::replay(self.elements.len(), answers)
+            .levels()
+            .into_iter()
+            .map(|level| LevelState {
+                items: level.items.into_iter().map(item).collect(),
+                stage: match level.stage {
+                    LevelStage::Inserting {
+                        chain,
+                        pending,
+                        search,
+                    } => LevelStage::Inserting {
+                        chain: chain.into_iter().map(item).collect(),
+                        pending: pending.into_iter().map(item).collect(),
+                        search: search.map(|search| SearchBounds {
+                            item: item(search.item),
+                            ..search
+                        }),
+                    },
+                    stage => stage,
+                },
+            })
+            .collect()
+    }
+
+    fn level_state(&self) -> LevelState {
+        let item = |p: usize| self.elements[p];
+        let stage = match &self.state {
+            State::Start => LevelStage::Pairing {
+                compared: 0,
+                pairs: self.elements.len() / 2,
+            },
+            State::Pairing { i, num_pairs, .. } => LevelStage::Pairing {
+                compared: *i,
+                pairs: *num_pairs,
+            },
+            State::AwaitMains { .. } => LevelStage::RankingWinners,
+            State::Insert(ins) => {
+                let searching = usize::from(ins.search.is_some());
+                LevelStage::Inserting {
+                    chain: (0..ins.chain.len())
+                        .map(|i| item(ins.chain.get(i)))
+                        .collect(),
+                    pending: ins.order[ins.order_idx + searching..]
+                        .iter()
+                        .map(|&i| item(ins.pending[i].0))
+                        .collect(),
+                    search: ins.search.map(|search| SearchBounds {
+                        item: item(search.elem),
+                        lo: search.lo,
+                        hi: search.hi,
+                    }),
+                }
+            }
+            State::Table { .. } => unreachable!("tree levels are replayed"),
+            State::Done(_) => LevelStage::Done,
+        };
+        LevelState {
+            items: self.elements.clone(),
+            stage,
+        }
+    }
+
+    /// Worst-case comparisons left in this frame, not counting a child frame
+    /// further up the stack.
+    fn remaining_estimate(&self) -> usize {
+        let n = self.elements.len();
+        // Binary insertion of every partner into a chain that starts with
+        // one partner and all mains, as in `chain_bound`.
+        let insertion = || {
+            ((n / 2 + 1)..n)
+                .map(|len| ceil_log2(len + 1))
+                .sum::<usize>()
+        };
+        match &self.state {
+            State::Start => chain_bound(n),
+            State::Pairing { i, num_pairs, .. } => {
+                num_pairs - i + chain_bound(*num_pairs) + insertion()
+            }
+            State::AwaitMains { .. } => insertion(),
+            State::Insert(insertion) => insertion.remaining_estimate(),
+            State::Table { node, .. } => small::height(n, *node),
+            State::Done(_) => 0,
+        }
+    }
+    /// The part of [`Stepper::check_invariants`] within this level.
+    #[cfg(any(debug_assertions, test))]
+    fn check_invariants(
+        &self,
+        depth: usize,
+        history: &[(usize, usize, bool)],
+        known: Option<&KnownOrder>,
+    ) {
+        let n = self.elements.len();
+        let order = match &self.state {
+            State::Start | State::AwaitMains { .. } => return,
+            State::Pairing {
+                i,
+                num_pairs,
+                mains,
+            } => {
+                assert!(
+                    *num_pairs == n / 2 && *i <= *num_pairs && mains.len() == *i,
+                    "level {depth} paired {i} of {num_pairs} pairs over {n} items into {mains:?}"
+                );
+                for (pair, &main) in mains.iter().enumerate() {
+                    assert_eq!(
+                        main / 2,
+                        pair,
+                        "level {depth}: main {main} is not in pair {pair}"
+                    );
+                }
+                return;
+            }
+            State::Insert(ins) => {
+                ins.check_invariants(depth, n);
+                (0..ins.chain.len()).map(|i| ins.chain.get(i)).collect()
+            }
+            State::Table { node, answers } => {
+                let mut replay = Stepper::replay(n, answers);
+                replay.check_invariants();
+                match (small::node(n, *node), replay.step()) {
+                    (Node::Ask(a, b), Step::Compare { a: ra, b: rb, .. }) => assert_eq!(
+                        (a, b),
+                        (ra, rb),
+                        "level {depth}: the tree asks otherwise than the recursion"
+                    ),
+                    (Node::Order(order), Step::Done) => assert!(
+                        order
+                            .iter()
+                            .map(|&p| usize::from(p))
+                            .eq(replay.take_order().expect("done").into_vec()),
+                        "level {depth}: the tree ranks otherwise than the recursion"
+                    ),
+                    _ => panic!("level {depth}: the tree and the recursion end apart"),
+                }
+                return;
+            }
+            State::Done(result) => result.clone(),
+        };
+        // Each answer and known relation between two ranked items holds.
+        let mut rank = vec![None; n];
+        for (r, &p) in order.iter().enumerate() {
+            assert!(rank[p].is_none(), "level {depth} ranks position {p} twice");
+            rank[p] = Some(r);
+        }
+        let rank_of = |item: usize| {
+            let p = self.elements.iter().position(|&e| e == item)?;
+            rank[p]
+        };
+        for &(a, b, a_is_better) in history {
+            if let (Some(ra), Some(rb)) = (rank_of(a), rank_of(b)) {
+                assert_eq!(
+                    ra < rb,
+                    a_is_better,
+                    "level {depth} ranks {a} and {b} against the answer"
+                );
+            }
+        }
+        if let Some(known) = known {
+            for (r, &p) in order.iter().enumerate() {
+                for &q in &order[r + 1..] {
+                    let (a, b) = (self.elements[p], self.elements[q]);
+                    assert_ne!(
+                        known.relation(a, b),
+                        Some(false),
+                        "level {depth} ranks {a} above {b} against what is known"
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum State {
+    Start,
+    /// Comparing pairs `(2i, 2i + 1)`; `mains[i]` is the position of the
+    /// worse element of pair `i`.
+    Pairing {
+        i: usize,
+        num_pairs: usize,
+        mains: Vec<usize>,
+    },
+    AwaitMains {
+        mains: Vec<usize>,
+    },
+    Insert(Insertion),
+    /// At `node` of the level's decision tree, reached by `answers`, each
+    /// whether the question's first position ranks before the second.
+    Table {
+        node: u8,
+        answers: Vec<bool>,
+    },
+    Done(Vec<usize>),
+}
+
+/// Insertion phase of a frame: pending partners are binary-searched into
+/// `chain` one at a time, in Jacobsthal `order`.
+#[derive(Debug, Clone)]
+struct Insertion {
+    chain: Chain,
+    pending: Vec<(usize, Option<usize>)>,
+    order: Vec<usize>,
+    order_idx: usize,
+    search: Option<SearchState>,
+    positions: MainPositions,
+}
+
+impl Insertion {
+    /// Inserts the element of a converged search and moves to the next one.
+    fn finish_search(&mut self) {
+        let search = self.search.take().expect("search in progress");
+        self.chain.insert(search.lo, search.elem);
+        self.positions.record_insert(search.lo);
+        self.order_idx += 1;
+    }
+
+    /// The part of [`Frame::check_invariants`] within the insertions of a
+    /// level of `n` items.
+    #[cfg(any(debug_assertions, test))]
+    fn check_invariants(&self, depth: usize, n: usize) {
+        let mut order = self.order.clone();
+        order.sort_unstable();
+        assert!(
+            order.iter().copied().eq(0..self.pending.len()),
+            "level {depth}: insertion order {:?} over {} pending",
+            self.order,
+            self.pending.len()
+        );
+        assert!(
+            self.order_idx <= self.order.len(),
+            "level {depth}: order index out of range"
+        );
+        let waiting = self.pending.len() - self.order_idx;
+        assert_eq!(self.chain.len() + waiting, n, "level {depth}: chain length");
+        let chain: Vec<usize> = (0..self.chain.len()).map(|i| self.chain.get(i)).collect();
+        let index = |p: usize| chain.iter().position(|&c| c == p);
+        for (k, &i) in self.order.iter().enumerate() {
+            let (elem, main) = self.pending[i];
+            let inserted = index(elem);
+            assert_eq!(
+                inserted.is_some(),
+                k < self.order_idx,
+                "level {depth}: partner {elem} inserted out of turn"
+            );
+            let Some(j) = main else {
+                continue;
+            };
+            // A partner's main is its neighbour in the pair, at `elem ^ 1`.
+            let at = self.positions.position(j);
+            assert_eq!(
+                chain.get(at),
+                Some(&(elem ^ 1)),
+                "level {depth}: main {j} is not at {at}"
+            );
+            if let Some(pos) = inserted {
+                assert!(pos < at, "level {depth}: partner {elem} below its main");
+            }
+        }
+        if let Some(search) = self.search {
+            let (elem, main) = self.pending[self.order[self.order_idx]];
+            let bound = main.map_or(chain.len(), |j| self.positions.position(j));
+            assert!(
+                search.elem == elem && search.lo <= search.hi && search.hi <= bound,
+                "level {depth}: {search:?} for {elem} below {bound}"
+            );
+        }
+    }
+
+    fn remaining_estimate(&self) -> usize {
+        let mut len = self.chain.len();
+        let mut waiting = self.order.len() - self.order_idx;
+        let mut total = 0;
+        if let Some(search) = &self.search {
+            total += ceil_log2(search.hi - search.lo + 1);
+            len += 1;
+            waiting -= 1;
+        }
+        total
+            + (len..len + waiting)
+                .map(|len| ceil_log2(len + 1))
+                .sum::<usize>()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SearchState {
+    elem: usize,
+    lo: usize,
+    hi: usize,
+    mid: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Pending {
+    Pairing {
+        a: usize,
+        b: usize,
+    },
+    Search {
+        a: usize,
+        b: usize,
+    },
+    /// A question from a decision tree, asked in `context` below its level.
+    Table {
+        a: usize,
+        b: usize,
+        context: Context,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::{LevelStage, Phase, Step, Stepper};
+    use crate::Ranking;
+
+    fn run(stepper: &mut Stepper, values: &[usize]) -> Vec<usize> {
+        stepper.check_invariants();
+        let mut step = stepper.step();
+        while let Step::Compare { a, b, .. } = step {
+            stepper.check_invariants();
+            step = stepper.answer(values[a] < values[b]);
+        }
+        stepper.check_invariants();
+        stepper.take_order().expect("done").into_vec()
+    }
+
+    #[test]
+    fn matches_rank_items() {
+        let values = [5, 2, 9, 1, 3, 8, 0];
+        let order = run(&mut Stepper::new(values.len()), &values);
+        let ranked: Vec<usize> = order.iter().map(|&i| values[i]).collect();
+        assert_eq!(ranked, vec![0, 1, 2, 3, 5, 8, 9]);
+    }
+
+    #[test]
+    fn history_records_each_question() {
+        let values = [2, 0, 1];
+        let mut stepper = Stepper::new(values.len());
+        let _ = run(&mut stepper, &values);
+        assert_eq!(
+            stepper.history(),
+            &[(0, 1, false), (2, 0, true), (2, 1, false)]
+        );
+        assert_eq!(stepper.comparisons_made(), 3);
+    }
+
+    #[test]
+    fn levels_show_the_search_in_progress() {
+        let values = [4, 7, 1, 0, 6, 3, 2, 5, 8];
+        let mut stepper = Stepper::new(values.len());
+        let levels = stepper.levels();
+        assert_eq!(levels.len(), 1);
+        assert_eq!(levels[0].items, (0..9).collect::<Vec<_>>());
+        let mut step = stepper.step();
+        while let Step::Compare { a, b, .. } = step {
+            let levels = stepper.levels();
+            let top = levels.last().expect("a level asks");
+            match &top.stage {
+                LevelStage::Pairing { compared, .. } => {
+                    assert_eq!(
+                        (a, b),
+                        (top.items[2 * compared], top.items[2 * compared + 1])
+                    );
+                }
+                LevelStage::Inserting {
+                    chain,
+                    pending,
+                    search: Some(search),
+                } => {
+                    assert_eq!(chain.len() + pending.len() + 1, top.items.len());
+                    assert_eq!(a, search.item);
+                    assert_eq!(b, chain[search.lo + (search.hi - search.lo) / 2]);
+                }
+                stage => panic!("asked while {stage:?}"),
+            }
+            step = stepper.answer(values[a] < values[b]);
+        }
+        assert!(stepper.levels().is_empty());
+    }
+
+    #[test]
+    fn pairing_questions_are_available_together() {
+        let mut stepper = Stepper::new(7);
+        assert_eq!(stepper.available_questions(), vec![(0, 1), (2, 3), (4, 5)]);
+    }
+
+    #[test]
+    fn out_of_order_answers_match_sequential_run() {
+        let values = [4, 7, 1, 0, 6, 3, 2, 5];
+        let mut sequential = Stepper::new(values.len());
+        let expected = run(&mut sequential, &values);
+
+        let mut stepper = Stepper::new(values.len());
+        let mut questions = stepper.available_questions();
+        questions.reverse();
+        for (a, b) in questions {
+            // Answer with the pair flipped to exercise normalization.
+            assert!(
+                stepper
+                    .answer_question(b, a, values[b] < values[a])
+                    .is_some()
+            );
+        }
+        assert_eq!(run(&mut stepper, &values), expected);
+        assert_eq!(stepper.comparisons_made(), sequential.comparisons_made());
+    }
+
+    #[test]
+    fn fully_known_order_asks_nothing() {
+        let mut stepper = Stepper::with_known_pairs(4, &[(2, 0), (0, 3), (3, 1)]);
+        assert_eq!(stepper.step(), Step::Done);
+        assert_eq!(stepper.take_order().expect("done"), vec![2, 0, 3, 1]);
+        assert_eq!(stepper.comparisons_made(), 0);
+    }
+
+    #[test]
+    fn known_pairs_are_never_asked() {
+        let values = [4, 7, 1, 0, 6, 3, 2, 5];
+        let n = values.len();
+        // Seed roughly a third of the true relations.
+        let known: Vec<(usize, usize)> = (0..n)
+            .flat_map(|i| (i + 1..n).map(move |j| (i, j)))
+            .filter(|&(i, j)| (i + j) % 3 == 0)
+            .map(|(i, j)| {
+                if values[i] < values[j] {
+                    (i, j)
+                } else {
+                    (j, i)
+                }
+            })
+            .collect();
+
+        let mut plain = Stepper::new(n);
+        let expected = run(&mut plain, &values);
+        let mut seeded = Stepper::with_known_pairs(n, &known);
+        assert!(
+            !seeded
+                .available_questions()
+                .iter()
+                .any(|&(a, b)| { known.contains(&(a, b)) || known.contains(&(b, a)) })
+        );
+        assert_eq!(run(&mut seeded, &values), expected);
+        assert!(seeded.comparisons_made() < plain.comparisons_made());
+        for &(a, b, _) in seeded.history() {
+            assert!(!known.contains(&(a, b)) && !known.contains(&(b, a)));
+        }
+    }
+
+    #[test]
+    fn remaining_estimate_bounds_the_rest_of_the_run() {
+        let values = [4, 7, 1, 0, 6, 3, 2, 5, 9, 8, 10];
+        let n = values.len();
+        let mut stepper = Stepper::new(n);
+        let mut bounds = vec![stepper.remaining_estimate()];
+        let mut step = stepper.step();
+        while let Step::Compare { a, b, .. } = step {
+            step = stepper.answer(values[a] < values[b]);
+            bounds.push(stepper.remaining_estimate());
+        }
+        let made = stepper.comparisons_made();
+        assert_eq!(bounds[0], crate::estimate_turns(n));
+        assert_eq!(bounds.last(), Some(&0));
+        for (asked, &bound) in bounds.iter().enumerate() {
+            assert!(bound >= made - asked, "after {asked}: {bound}");
+        }
+        // The projected total never grows.
+        let totals: Vec<usize> = bounds.iter().enumerate().map(|(i, b)| i + b).collect();
+        assert!(totals.windows(2).all(|w| w[1] <= w[0]), "{totals:?}");
+    }
+
+    #[test]
+    fn ties_keep_input_order_even_after_undo() {
+        let keys = [2, 0, 1, 0, 2, 1, 0, 2];
+        // `None` is a tie.
+        let replay = |stepper: &mut Stepper, answer: Option<bool>| match answer {
+            Some(better_is_a) => stepper.answer(better_is_a),
+            None => stepper.answer_tie(),
+        };
+        let answer = |a: usize, b: usize| (keys[a] != keys[b]).then(|| keys[a] < keys[b]);
+
+        let mut stepper = Stepper::new(keys.len());
+        let mut answers = Vec::new();
+        let mut step = stepper.step();
+        while let Step::Compare { a, b, .. } = step {
+            answers.push(answer(a, b));
+            step = replay(&mut stepper, answer(a, b));
+            stepper.check_invariants();
+        }
+        let order = stepper.take_order().expect("done");
+        assert_eq!(order, vec![1, 3, 6, 2, 5, 0, 4, 7]);
+        // Neighbours are always compared, so every tie is in one group.
+        let groups: Vec<&[usize]> = order.groups().collect();
+        assert_eq!(groups, [&[1, 3, 6][..], &[2, 5], &[0, 4, 7]]);
+
+        // Undo the last answer by replaying the others, then answer again.
+        let mut replayed = Stepper::new(keys.len());
+        let mut step = replayed.step();
+        for &given in &answers[..answers.len() - 1] {
+            step = replay(&mut replayed, given);
+        }
+        while let Step::Compare { a, b, .. } = step {
+            step = replay(&mut replayed, answer(a, b));
+        }
+        assert_eq!(replayed.take_order(), Some(order));
+    }
+
+    #[test]
+    fn partial_rankings_respect_every_answer_so_far() {
+        let keys = [4, 1, 5, 0, 3, 2];
+        let mut stepper = Stepper::new(keys.len());
+        assert_eq!(stepper.finalize_partial().groups().len(), 1);
+        let mut step = stepper.step();
+        while let Step::Compare { a, b, .. } = step {
+            let partial = stepper.finalize_partial();
+            assert_eq!(stepper.step(), step, "finalizing must not advance");
+            for &(x, y, x_better) in stepper.history() {
+                let (better, worse) = if x_better { (x, y) } else { (y, x) };
+                assert!(partial.rank_of(&better) < partial.rank_of(&worse));
+            }
+            step = stepper.answer(keys[a] < keys[b]);
+        }
+        let finished = stepper.finalize_partial();
+        assert_eq!(finished, vec![3, 1, 5, 4, 0, 2]);
+        assert_eq!(stepper.take_order(), Some(finished));
+
+        // Seeded pairs count as answers; ties leave the pair unordered.
+        let mut seeded = Stepper::with_known_pairs(3, &[(2, 0)]);
+        assert!(matches!(seeded.step(), Step::Compare { .. }));
+        seeded.answer_tie();
+        let partial = seeded.finalize_partial();
+        assert!(partial.rank_of(&2) < partial.rank_of(&0));
+    }
+
+    #[test]
+    fn removing_an_item_keeps_the_other_answers() {
+        let values = [4, 7, 1, 0, 6, 3, 2, 5];
+        let mut stepper = Stepper::new(values.len());
+        let mut step = stepper.step();
+        for _ in 0..10 {
+            let Step::Compare { a, b, .. } = step else {
+                break;
+            };
+            step = stepper.answer(values[a] < values[b]);
+        }
+        stepper.remove_item(2);
+        let rest: Vec<usize> = values.iter().copied().filter(|&v| v != 1).collect();
+        let order = run(&mut stepper, &rest);
+        let ranked: Vec<usize> = order.iter().map(|&i| rest[i]).collect();
+        assert_eq!(ranked, [0, 2, 3, 4, 5, 6, 7]);
+        // Only the questions the kept answers leave open are asked.
+        let mut fresh = Stepper::new(rest.len());
+        run(&mut fresh, &rest);
+        assert!(stepper.comparisons_made() < fresh.comparisons_made());
+
+        // Removing the last item finishes a two-item run.
+        let mut pair = Stepper::new(2);
+        pair.step();
+        pair.remove_item(1);
+        assert_eq!(pair.step(), Step::Done);
+    }
+
+    #[test]
+    fn questions_report_phase_depth_and_range() {
+        let mut stepper = Stepper::new(8);
+        let mut step = stepper.step();
+        let mut contexts = Vec::new();
+        while let Step::Compare { a, b, context } = step {
+            contexts.push((a, context));
+            step = stepper.answer(a < b);
+        }
+        let depths: Vec<usize> = contexts.iter().map(|(_, c)| c.depth).collect();
+        // 4 pairs at the top, 2 among their winners, 1 among theirs.
+        assert_eq!(depths[..7], [0, 0, 0, 0, 1, 1, 2]);
+        assert!(contexts[..7].iter().all(|(_, c)| c.phase == Phase::Pairing));
+        assert_eq!(depths.iter().max(), Some(&2));
+        // A search narrows as it goes.
+        let searches: Vec<(usize, usize)> = contexts
+            .iter()
+            .filter_map(|&(a, c)| match c.phase {
+                Phase::Insertion { candidates } => Some((a, candidates)),
+                _ => None,
+            })
+            .collect();
+        for pair in searches.windows(2) {
+            if pair[0].0 == pair[1].0 {
+                assert!(pair[1].1 < pair[0].1, "{searches:?}");
+            }
+        }
+        assert!(searches.iter().any(|&(_, candidates)| candidates > 2));
+    }
+
+    #[test]
+    fn peeking_matches_the_question_that_follows() {
+        for answer in [true, false] {
+            let mut stepper = Stepper::new(7);
+            assert_eq!(stepper.peek_next_after(answer), None, "nothing pending yet");
+            let mut step = stepper.step();
+            while let Step::Compare { .. } = step {
+                let peeked = stepper.peek_next_after(answer);
+                step = stepper.answer(answer);
+                match step {
+                    Step::Compare { a, b, .. } => assert_eq!(peeked, Some((a, b))),
+                    Step::Done => assert_eq!(peeked, None),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_unavailable_questions() {
+        let mut stepper = Stepper::new(4);
+        assert!(stepper.answer_question(0, 2, true).is_none());
+        assert!(stepper.answer_question(2, 3, true).is_some());
+        assert!(stepper.answer_question(2, 3, true).is_none());
+    }
+
+    /// The questions `rank_permutation` puts to `keys`, as unordered pairs,
+    /// and the order it returns.
+    fn batch(keys: &[usize]) -> (Vec<(usize, usize)>, Vec<usize>) {
+        let mut asked = Vec::new();
+        let order = crate::rank_permutation(keys.len(), |a, b| {
+            asked.push((a.min(b), a.max(b)));
+            keys[a] < keys[b]
+        });
+        (asked, order.into_vec())
+    }
+
+    /// The same through the stepper, with a read-only call picked by `noise`
+    /// before every question.
+    fn stepped(keys: &[usize], noise: &[u8]) -> (Vec<(usize, usize)>, Vec<usize>) {
+        let mut stepper = Stepper::new(keys.len());
+        let mut noise = noise.iter().copied().cycle();
+        let mut asked = Vec::new();
+        loop {
+            match noise.next().unwrap_or(0) % 5 {
+                0 => {}
+                1 => drop(stepper.step()),
+                2 => drop(stepper.available_questions()),
+                3 => drop(stepper.peek_next_after(asked.len() % 2 == 0)),
+                _ => drop((stepper.finalize_partial(), stepper.remaining_estimate())),
+            }
+            stepper.check_invariants();
+            let Step::Compare { a, b, .. } = stepper.step() else {
+                break;
+            };
+            asked.push((a.min(b), a.max(b)));
+            // Put the question as `rank_permutation` does, for stability.
+            let a_better = if a < b {
+                keys[b] >= keys[a]
+            } else {
+                keys[a] < keys[b]
+            };
+            stepper.answer(a_better);
+        }
+        (asked, stepper.take_order().expect("done").into_vec())
+    }
+
+    /// A shuffled `0..n`, coarsened by `ties` so that equal keys tie.
+    fn keys() -> impl Strategy<Value = Vec<usize>> {
+        (0_usize..40, 1_usize..5).prop_flat_map(|(n, ties)| {
+            Just((0..n).collect::<Vec<_>>())
+                .prop_shuffle()
+                .prop_map(move |keys| keys.into_iter().map(|key| key / ties).collect())
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn stepper_asks_what_rank_items_asks(
+            keys in keys(),
+            noise in prop::collection::vec(any::<u8>(), 0..16),
+        ) {
+            prop_assert_eq!(stepped(&keys, &noise), batch(&keys));
+        }
+
+        #[test]
+        fn out_of_order_answers_reach_the_same_order(keys in keys(), picks: Vec<usize>) {
+            let n = keys.len();
+            let mut stepper = Stepper::new(n);
+            let mut picks = picks.into_iter().chain(std::iter::repeat(0));
+            loop {
+                let questions = stepper.available_questions();
+                let Some(pick) = picks.next().filter(|_| !questions.is_empty()) else {
+                    break;
+                };
+                let (a, b) = questions[pick % questions.len()];
+                let a_better = (keys[a], a) < (keys[b], b);
+                prop_assert!(stepper.answer_question(a, b, a_better).is_some());
+                stepper.check_invariants();
+            }
+            prop_assert!(stepper.comparisons_made() <= crate::estimate_turns(n));
+            prop_assert_eq!(stepper.take_order().map(Ranking::into_vec), Some(batch(&keys).1));
+        }
+    }
+}
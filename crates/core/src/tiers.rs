@@ -0,0 +1,367 @@
+//! Big lists in tiers first, then in order.
+//!
+//! A full sort of 200 items asks over a thousand questions before anything
+//! is known for sure. [`Tiers`] first sorts a small sample spread over the
+//! list and takes its quantiles as pivots, then places every other item in
+//! one of [`TIERS`] tiers (S, A, B, C) with two questions against the
+//! pivots: about `2n` questions for a coarse ranking that can be kept as is.
+//! Ford-Johnson then sorts each tier on its own, best tier first, so
+//! stopping early leaves the top of the list in order. Each pivot is known
+//! to head its tier, which saves a question per member.
+
+use crate::{Phase, Ranking, Step, Stepper, ceil_log2, estimate_turns};
+
+/// Number of tiers.
+pub const TIERS: usize = 4;
+
+/// Tier names, best first.
+pub const LABELS: [&str; TIERS] = ["S", "A", "B", "C"];
+
+/// Items sorted to pick the pivots: one per tier and one between each pair.
+const SAMPLE: usize = 2 * TIERS - 1;
+
+/// Step-by-step tiering and sorting of the indices `0..n`, with the same
+/// `step`/`answer` protocol as [`Stepper`]. Lists shorter than the sample
+/// are sorted as a single tier.
+#[derive(Clone)]
+pub struct Tiers {
+    n: usize,
+    /// Items sorted first, spread evenly over the input.
+    sample: Vec<usize>,
+    /// The sample's quantiles, best first; tier `t > 0` is headed by
+    /// `pivots[t - 1]`.
+    pivots: Vec<usize>,
+    /// Members of each tier, pivot first; best first once refined.
+    members: Vec<Vec<usize>>,
+    /// Tiers refined so far, from the best.
+    refined: usize,
+    stage: Stage,
+    /// Every answer as `(better, worse)`, for rankings before the tiers are
+    /// all filled.
+    outcomes: Vec<(usize, usize)>,
+}
+
+#[derive(Clone)]
+enum Stage {
+    /// Sorting the sample.
+    Sample(Stepper),
+    /// Placing `item` in one of the tiers `lo..=hi`.
+    Bucket {
+        item: usize,
+        lo: usize,
+        hi: usize,
+    },
+    /// Sorting `tiers[refined]`, by position within the tier.
+    Refine(Stepper),
+    Done,
+}
+
+impl Tiers {
+    /// Creates a ranker for `n` items.
+    #[must_use]
+    pub fn new(n: usize) -> Self {
+        let sample: Vec<usize> = if n < SAMPLE {
+            (0..n).collect()
+        } else {
+            (0..SAMPLE).map(|j| j * n / SAMPLE).collect()
+        };
+        let mut tiers = Self {
+            n,
+            stage: Stage::Sample(Stepper::new(sample.len())),
+            sample,
+            pivots: Vec::new(),
+            members: Vec::new(),
+            refined: 0,
+            outcomes: Vec::new(),
+        };
+        tiers.settle();
+        tiers
+    }
+
+    /// Returns the next question, or [`Step::Done`] once every tier is
+    /// sorted.
+    pub fn step(&mut self) -> Step {
+        match &mut self.stage {
+            Stage::Sample(stepper) => within(stepper.step(), &self.sample),
+            &mut Stage::Bucket { item, lo, hi } => {
+                let phase = Phase::Insertion {
+                    candidates: hi - lo + 1,
+                };
+                Step::compare(item, self.pivots[lo.midpoint(hi)], phase, 0)
+            }
+            Stage::Refine(stepper) => within(stepper.step(), &self.members[self.refined]),
+            Stage::Done => Step::Done,
+        }
+    }
+
+    /// Applies the answer to the pending question and returns the next step.
+    pub fn answer(&mut self, better_is_a: bool) -> Step {
+        let Step::Compare { a, b, .. } = self.step() else {
+            return Step::Done;
+        };
+        self.outcomes
+            .push(if better_is_a { (a, b) } else { (b, a) });
+        match &mut self.stage {
+            Stage::Sample(stepper) | Stage::Refine(stepper) => {
+                stepper.answer(better_is_a);
+            }
+            Stage::Bucket { item, lo, hi } => {
+                let probe = lo.midpoint(*hi);
+                if better_is_a {
+                    *hi = probe;
+                } else {
+                    *lo = probe + 1;
+                }
+                if lo == hi {
+                    self.members[*lo].push(*item);
+                    *item += 1;
+                    (*lo, *hi) = (0, TIERS - 1);
+                }
+            }
+            Stage::Done => {}
+        }
+        self.settle();
+        self.step()
+    }
+
+    /// Number of answers applied so far.
+    #[must_use]
+    pub fn comparisons_made(&self) -> usize {
+        self.outcomes.len()
+    }
+
+    /// The tiers so far, best first: sorted ones in order, the others as one
+    /// group each. Empty while the sample is being sorted, and missing the
+    /// items not placed yet until every item has a tier.
+    #[must_use]
+    pub fn tiers(&self) -> Vec<Ranking<usize>> {
+        self.members
+            .iter()
+            .enumerate()
+            .map(|(t, members)| match (&self.stage, t.cmp(&self.refined)) {
+                (_, std::cmp::Ordering::Less) => Ranking::new(members.clone()),
+                (Stage::Refine(stepper), std::cmp::Ordering::Equal) => {
+                    stepper.finalize_partial().map(|i| members[i])
+                }
+                _ => Ranking::from_groups([members.clone()]),
+            })
+            .collect()
+    }
+
+    /// The best ranking the answers so far support: the [`tiers`](Self::tiers)
+    /// one after the other once every item has one. Final once
+    /// [`step`](Self::step) returns [`Step::Done`].
+    #[must_use]
+    pub fn order(&self) -> Ranking<usize> {
+        match self.stage {
+            Stage::Sample(_) | Stage::Bucket { .. } => {
+                Ranking::from_outcomes(self.n, &self.outcomes)
+            }
+            Stage::Refine(_) | Stage::Done => Ranking::from_groups(
+                self.tiers()
+                    .iter()
+                    .flat_map(|tier| tier.groups().map(<[usize]>::to_vec).collect::<Vec<_>>()),
+            ),
+        }
+    }
+
+    /// Questions for `n` items when the tiers come out even. Lopsided tiers
+    /// take more to sort.
+    #[must_use]
+    pub fn estimate(n: usize) -> usize {
+        if n < SAMPLE {
+            return estimate_turns(n);
+        }
+        Self::tiering_estimate(n) + even_refine(n)
+    }
+
+    /// Upper bound on the questions until every one of `n` items has a tier.
+    #[must_use]
+    pub fn tiering_estimate(n: usize) -> usize {
+        if n < SAMPLE {
+            return estimate_turns(n);
+        }
+        estimate_turns(SAMPLE) + (n - SAMPLE) * ceil_log2(TIERS)
+    }
+
+    /// Questions still needed, including the pending one, with the same
+    /// assumption as [`estimate`](Self::estimate) until every item has a
+    /// tier. Zero once done.
+    #[must_use]
+    pub fn remaining_estimate(&self) -> usize {
+        match &self.stage {
+            Stage::Sample(stepper) if self.n < SAMPLE => stepper.remaining_estimate(),
+            Stage::Sample(stepper) => {
+                stepper.remaining_estimate()
+                    + (self.n - SAMPLE) * ceil_log2(TIERS)
+                    + even_refine(self.n)
+            }
+            &Stage::Bucket { item, lo, hi } => {
+                let later = (item + 1..self.n)
+                    .filter(|i| !self.sample.contains(i))
+                    .count();
+                ceil_log2(hi - lo + 1) + later * ceil_log2(TIERS) + even_refine(self.n)
+            }
+            Stage::Refine(stepper) => {
+                stepper.remaining_estimate()
+                    + self.members[self.refined + 1..]
+                        .iter()
+                        .map(|tier| estimate_turns(tier.len()))
+                        .sum::<usize>()
+            }
+            Stage::Done => 0,
+        }
+    }
+
+    /// Moves past every stage that needs no question.
+    fn settle(&mut self) {
+        loop {
+            match &mut self.stage {
+                Stage::Sample(stepper) => {
+                    if let Step::Compare { .. } = stepper.step() {
+                        return;
+                    }
+                    let sorted: Vec<usize> = stepper
+                        .take_order()
+                        .expect("the sample is sorted")
+                        .iter()
+                        .map(|&i| self.sample[i])
+                        .collect();
+                    if sorted.len() < SAMPLE {
+                        self.members = vec![sorted];
+                        self.refined = 1;
+                        self.stage = Stage::Done;
+                        return;
+                    }
+                    // Odd places become pivots, each heading its tier.
+                    self.pivots = (1..TIERS).map(|t| sorted[2 * t - 1]).collect();
+                    self.members = (0..TIERS)
+                        .map(|t| sorted[(2 * t).saturating_sub(1)..=2 * t].to_vec())
+                        .collect();
+                    self.stage = Stage::Bucket {
+                        item: 0,
+                        lo: 0,
+                        hi: TIERS - 1,
+                    };
+                }
+                Stage::Bucket { item, .. } => {
+                    while self.sample.contains(item) {
+                        *item += 1;
+                    }
+                    if *item < self.n {
+                        return;
+                    }
+                    self.stage = Stage::Refine(self.refiner());
+                }
+                Stage::Refine(stepper) => {
+                    if let Step::Compare { .. } = stepper.step() {
+                        return;
+                    }
+                    let order = stepper.take_order().expect("the tier is sorted");
+                    let tier = &mut self.members[self.refined];
+                    *tier = order.iter().map(|&i| tier[i]).collect();
+                    self.refined += 1;
+                    self.stage = if self.refined < self.members.len() {
+                        Stage::Refine(self.refiner())
+                    } else {
+                        Stage::Done
+                    };
+                }
+                Stage::Done => return,
+            }
+        }
+    }
+
+    /// A sorter for the next tier to refine, told that its pivot heads it.
+    fn refiner(&self) -> Stepper {
+        let members = self.members[self.refined].len();
+        if self.refined == 0 {
+            return Stepper::new(members);
+        }
+        let below_pivot: Vec<(usize, usize)> = (1..members).map(|i| (0, i)).collect();
+        Stepper::with_known_pairs(members, &below_pivot)
+    }
+}
+
+/// Maps a question about positions in `members` to the items.
+fn within(step: Step, members: &[usize]) -> Step {
+    match step {
+        Step::Compare { a, b, context } => Step::Compare {
+            a: members[a],
+            b: members[b],
+            context,
+        },
+        Step::Done => Step::Done,
+    }
+}
+
+/// Questions to sort `n` items split into even tiers.
+fn even_refine(n: usize) -> usize {
+    TIERS * estimate_turns(n.div_ceil(TIERS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TIERS, Tiers};
+    use crate::Step;
+
+    fn scrambled(n: usize) -> Vec<usize> {
+        (0..n).map(|i| (i * 37 + 11) % n).collect()
+    }
+
+    #[test]
+    fn sorts_every_tier_in_order() {
+        let values = scrambled(200);
+        let mut tiers = Tiers::new(values.len());
+        let mut step = tiers.step();
+        while let Step::Compare { a, b, .. } = step {
+            assert_ne!(a, b);
+            step = tiers.answer(values[a] < values[b]);
+        }
+        assert_eq!(tiers.remaining_estimate(), 0);
+        let ranked: Vec<usize> = tiers.order().iter().map(|&i| values[i]).collect();
+        assert_eq!(ranked, (0..200).collect::<Vec<_>>());
+        let sizes: Vec<usize> = tiers.tiers().iter().map(|tier| tier.len()).collect();
+        assert_eq!(sizes.len(), TIERS);
+        assert_eq!(sizes.iter().sum::<usize>(), 200);
+    }
+
+    #[test]
+    fn tiers_come_after_two_questions_per_item() {
+        let n = 200;
+        let values = scrambled(n);
+        let mut tiers = Tiers::new(n);
+        let mut step = tiers.step();
+        while tiers.tiers().iter().map(|tier| tier.len()).sum::<usize>() < n {
+            let Step::Compare { a, b, .. } = step else {
+                panic!("done before every item had a tier");
+            };
+            step = tiers.answer(values[a] < values[b]);
+        }
+        let made = tiers.comparisons_made();
+        assert!(made <= Tiers::tiering_estimate(n), "{made}");
+        assert!(made < crate::estimate_turns(n) / 2);
+
+        // Every item of a tier beats every item of the tiers below.
+        let tiers = tiers.tiers();
+        for pair in tiers.windows(2) {
+            let worst = pair[0].iter().map(|&i| values[i]).max();
+            let best = pair[1].iter().map(|&i| values[i]).min();
+            assert!(worst < best);
+        }
+    }
+
+    #[test]
+    fn short_lists_are_one_tier() {
+        let values = [3, 0, 4, 1, 2];
+        let mut tiers = Tiers::new(values.len());
+        let mut step = tiers.step();
+        while let Step::Compare { a, b, .. } = step {
+            step = tiers.answer(values[a] < values[b]);
+        }
+        assert_eq!(tiers.order(), vec![1, 3, 4, 0, 2]);
+        assert_eq!(tiers.tiers().len(), 1);
+        assert!(tiers.comparisons_made() <= Tiers::estimate(values.len()));
+        assert_eq!(Tiers::new(0).step(), Step::Done);
+    }
+}
@@ -0,0 +1,209 @@
+//! Finding only the best few items.
+//!
+//! When only the top of a long list matters, sorting everything wastes most
+//! of the answers on places nobody looks at. [`TopK`] keeps a sorted list of
+//! the best `k` items seen so far and offers every other item to it: one
+//! question against the current `k`-th place rejects most items, and the rest
+//! are binary-inserted. That is about `n + m log2 k` questions when `m` items
+//! make it into the list at some point, against `n log2 n` for a full sort.
+
+use crate::{Phase, Ranking, Step, ceil_log2};
+
+/// Step-by-step selection of the best `k` of the indices `0..n`, with the
+/// same `step`/`answer` protocol as [`Stepper`](crate::Stepper).
+#[derive(Debug, Clone)]
+pub struct TopK {
+    n: usize,
+    k: usize,
+    /// Best items so far, best first, at most `k` of them.
+    top: Vec<usize>,
+    /// Item currently being placed; `n` once every item was offered.
+    candidate: usize,
+    /// Still-possible insertion positions for the candidate, `lo..=hi`.
+    lo: usize,
+    hi: usize,
+    comparisons: usize,
+}
+
+impl TopK {
+    /// Creates a selector for the best `k` of `n` items. A `k` of `n` or more
+    /// ranks everything by binary insertion.
+    #[must_use]
+    pub fn new(n: usize, k: usize) -> Self {
+        let mut top_k = Self {
+            n,
+            k: k.min(n),
+            top: Vec::new(),
+            candidate: 0,
+            lo: 0,
+            hi: 0,
+            comparisons: 0,
+        };
+        top_k.advance();
+        top_k
+    }
+
+    /// Returns the next question, or [`Step::Done`] once every item was
+    /// offered.
+    #[must_use]
+    pub fn step(&self) -> Step {
+        if self.candidate >= self.n {
+            return Step::Done;
+        }
+        // Rejection at the gate is one of the places, just past the list.
+        let phase = Phase::Insertion {
+            candidates: self.hi - self.lo + 1,
+        };
+        Step::compare(self.candidate, self.top[self.probe()], phase, 0)
+    }
+
+    /// Applies the answer to the pending question (`a` is the candidate) and
+    /// returns the next step.
+    pub fn answer(&mut self, better_is_a: bool) -> Step {
+        if self.candidate >= self.n {
+            return Step::Done;
+        }
+        let probe = self.probe();
+        self.comparisons += 1;
+        if better_is_a {
+            self.hi = probe;
+        } else {
+            self.lo = probe + 1;
+        }
+        if self.lo == self.hi {
+            self.place();
+        }
+        self.step()
+    }
+
+    /// Number of answers applied so far.
+    #[must_use]
+    pub fn comparisons_made(&self) -> usize {
+        self.comparisons
+    }
+
+    /// The best items found so far, best first. Final once [`step`](Self::step)
+    /// returns [`Step::Done`].
+    #[must_use]
+    pub fn order(&self) -> Ranking<usize> {
+        Ranking::new(self.top.clone())
+    }
+
+    /// Upper bound on the questions needed for the best `k` of `n` items.
+    #[must_use]
+    pub fn estimate(n: usize, k: usize) -> usize {
+        let k = k.min(n);
+        (1..n).map(|seen| offer_cost(seen.min(k), k)).sum()
+    }
+
+    /// Upper bound on the questions still needed, including the pending one.
+    /// Zero once every item was offered.
+    #[must_use]
+    pub fn remaining_estimate(&self) -> usize {
+        if self.candidate >= self.n {
+            return 0;
+        }
+        let current = if self.at_gate() {
+            offer_cost(self.k, self.k)
+        } else {
+            ceil_log2(self.hi - self.lo + 1)
+        };
+        let len = self.top.len();
+        current
+            + (1..self.n - self.candidate)
+                .map(|later| offer_cost((len + later).min(self.k), self.k))
+                .sum::<usize>()
+    }
+
+    /// Index into `top` the candidate is compared with next. While the list
+    /// is full the candidate first meets the last place, which rejects it
+    /// (or not) in one question.
+    fn probe(&self) -> usize {
+        if self.at_gate() {
+            self.k - 1
+        } else {
+            self.lo.midpoint(self.hi)
+        }
+    }
+
+    /// Whether the candidate has yet to meet the last place of a full list.
+    fn at_gate(&self) -> bool {
+        self.top.len() == self.k && self.hi == self.k && self.lo < self.k
+    }
+
+    /// Inserts the candidate at its settled position and moves on.
+    fn place(&mut self) {
+        if self.lo < self.k {
+            self.top.insert(self.lo, self.candidate);
+            self.top.truncate(self.k);
+        }
+        self.candidate += 1;
+        self.advance();
+    }
+
+    /// Skips over candidates that need no question: the first item of an
+    /// empty list, or everything when `k` is zero.
+    fn advance(&mut self) {
+        while self.candidate < self.n && (self.k == 0 || self.top.is_empty()) {
+            if self.k > 0 {
+                self.top.push(self.candidate);
+            }
+            self.candidate += 1;
+        }
+        // For a full list, the slot past the end means "rejected".
+        self.lo = 0;
+        self.hi = self.top.len();
+    }
+}
+
+/// Worst-case questions to offer one item to a list of `len` of at most `k`.
+fn offer_cost(len: usize, k: usize) -> usize {
+    if len == k {
+        // One question against the k-th place, then a search over the k
+        // positions above it.
+        1 + ceil_log2(k)
+    } else {
+        ceil_log2(len + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TopK;
+    use crate::Step;
+
+    fn run(values: &[usize], k: usize) -> (Vec<usize>, usize) {
+        let mut top_k = TopK::new(values.len(), k);
+        let mut step = top_k.step();
+        while let Step::Compare { a, b, .. } = step {
+            assert_ne!(a, b);
+            let before = top_k.remaining_estimate();
+            assert!(before >= 1);
+            step = top_k.answer(values[a] < values[b]);
+            assert!(top_k.remaining_estimate() < before);
+        }
+        assert_eq!(top_k.remaining_estimate(), 0);
+        let best = top_k.order().iter().map(|&i| values[i]).collect();
+        (best, top_k.comparisons_made())
+    }
+
+    #[test]
+    fn finds_the_best_k_in_order() {
+        let values: Vec<usize> = (0..50).map(|i| (i * 37) % 50).collect();
+        for k in [0, 1, 3, 10, 50, 80] {
+            let (best, comparisons) = run(&values, k);
+            assert_eq!(best, (0..k.min(50)).collect::<Vec<_>>(), "k = {k}");
+            assert!(comparisons <= TopK::estimate(50, k), "k = {k}");
+        }
+    }
+
+    #[test]
+    fn rejects_most_items_in_one_question() {
+        // Best items first: every later item loses its only question.
+        let values: Vec<usize> = (0..200).collect();
+        let (best, comparisons) = run(&values, 5);
+        assert_eq!(best, vec![0, 1, 2, 3, 4]);
+        assert!(comparisons < 200 + 5 * 3, "{comparisons}");
+        assert!(TopK::estimate(200, 5) < crate::estimate_turns(200));
+    }
+}
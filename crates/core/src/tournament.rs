@@ -0,0 +1,200 @@
+//! Exhaustive round-robin ranking.
+//!
+//! Every item meets every other item exactly once, so `n (n - 1) / 2`
+//! questions are asked instead of Ford-Johnson's roughly `n log2 n`. In
+//! exchange the result holds a record for every pair, and contradictory
+//! answers still produce a ranking.
+
+use crate::{Phase, Step};
+
+/// Win tallies and per-pair records of a round-robin.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Standings {
+    /// Item indices, best first.
+    pub order: Vec<usize>,
+    /// Number of wins of each item, indexed by item.
+    pub wins: Vec<usize>,
+    matches: Vec<(usize, usize, bool)>,
+}
+
+impl Standings {
+    fn from_matches(n: usize, matches: Vec<(usize, usize, bool)>) -> Self {
+        let mut wins = vec![0usize; n];
+        for &(a, b, a_won) in &matches {
+            wins[if a_won { a } else { b }] += 1;
+        }
+
+        // Sonneborn-Berger: total wins of the opponents an item beat.
+        let mut strength = vec![0usize; n];
+        for &(a, b, a_won) in &matches {
+            let (winner, loser) = if a_won { (a, b) } else { (b, a) };
+            strength[winner] += wins[loser];
+        }
+
+        let mut order: Vec<usize> = (0..n).collect();
+        order.sort_by(|&x, &y| wins[y].cmp(&wins[x]).then(strength[y].cmp(&strength[x])));
+
+        Self {
+            order,
+            wins,
+            matches,
+        }
+    }
+
+    /// Every match played as `(a, b, a_won)`, in the order asked.
+    #[must_use]
+    pub fn matches(&self) -> &[(usize, usize, bool)] {
+        &self.matches
+    }
+
+    /// Winner of the match between `a` and `b`, if it was played.
+    #[must_use]
+    pub fn winner_of(&self, a: usize, b: usize) -> Option<usize> {
+        self.matches.iter().find_map(|&(x, y, x_won)| {
+            ((x, y) == (a, b) || (x, y) == (b, a)).then_some(if x_won { x } else { y })
+        })
+    }
+}
+
+/// Runs a full round-robin over `items` and returns the standings.
+///
+/// Ties in win count are broken by the Sonneborn-Berger score (the summed
+/// wins of beaten opponents), then by input order.
+#[must_use]
+pub fn round_robin<T, F>(items: &[T], mut better: F) -> Standings
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let mut rr = RoundRobin::new(items.len());
+    let mut step = rr.step();
+    while let Step::Compare { a, b, .. } = step {
+        step = rr.answer(better(&items[a], &items[b]));
+    }
+    rr.standings()
+}
+
+/// Step-by-step round-robin over the indices `0..n`, with the same
+/// `step`/`answer` protocol as [`Stepper`](crate::Stepper).
+///
+/// Matches follow the circle schedule, so consecutive questions rarely
+/// repeat the same item.
+pub struct RoundRobin {
+    n: usize,
+    schedule: Vec<(usize, usize)>,
+    results: Vec<bool>,
+}
+
+impl RoundRobin {
+    /// Creates a round-robin over `n` items.
+    #[must_use]
+    pub fn new(n: usize) -> Self {
+        Self {
+            n,
+            schedule: circle_schedule(n),
+            results: Vec::new(),
+        }
+    }
+
+    /// Returns the next match, or [`Step::Done`] once all were played.
+    #[must_use]
+    pub fn step(&self) -> Step {
+        match self.schedule.get(self.results.len()) {
+            Some(&(a, b)) => {
+                let round = self.results.len() / (self.n / 2).max(1);
+                Step::compare(a, b, Phase::Pairing, round)
+            }
+            None => Step::Done,
+        }
+    }
+
+    /// Records the result of the current match and returns the next step.
+    pub fn answer(&mut self, better_is_a: bool) -> Step {
+        if self.results.len() < self.schedule.len() {
+            self.results.push(better_is_a);
+        }
+        self.step()
+    }
+
+    /// Number of matches played so far.
+    #[must_use]
+    pub fn comparisons_made(&self) -> usize {
+        self.results.len()
+    }
+
+    /// Total number of matches: `n (n - 1) / 2`.
+    #[must_use]
+    pub fn total_matches(&self) -> usize {
+        self.schedule.len()
+    }
+
+    /// Standings over the matches played so far.
+    #[must_use]
+    pub fn standings(&self) -> Standings {
+        let matches = self
+            .schedule
+            .iter()
+            .zip(&self.results)
+            .map(|(&(a, b), &a_won)| (a, b, a_won))
+            .collect();
+        Standings::from_matches(self.n, matches)
+    }
+}
+
+/// Pairs for every round of the circle method: one position stays fixed
+/// while the others rotate. Odd `n` gets a bye slot that is skipped.
+fn circle_schedule(n: usize) -> Vec<(usize, usize)> {
+    if n < 2 {
+        return Vec::new();
+    }
+    let slots = n + n % 2;
+    let mut ring: Vec<usize> = (0..slots).collect();
+    let mut schedule = Vec::with_capacity(n * (n - 1) / 2);
+    for _ in 0..slots - 1 {
+        for i in 0..slots / 2 {
+            let (a, b) = (ring[i], ring[slots - 1 - i]);
+            if a < n && b < n {
+                schedule.push((a.min(b), a.max(b)));
+            }
+        }
+        ring[1..].rotate_right(1);
+    }
+    schedule
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{circle_schedule, round_robin};
+
+    #[test]
+    fn schedule_covers_every_pair_once() {
+        for n in 0..9 {
+            let mut schedule = circle_schedule(n);
+            schedule.sort_unstable();
+            let expected: Vec<(usize, usize)> = (0..n)
+                .flat_map(|a| (a + 1..n).map(move |b| (a, b)))
+                .collect();
+            assert_eq!(schedule, expected, "n={n}");
+        }
+    }
+
+    #[test]
+    fn ranks_by_wins() {
+        let items = [3, 1, 4, 0, 5];
+        let standings = round_robin(&items, |a, b| a > b);
+        assert_eq!(standings.order, vec![4, 2, 0, 1, 3]);
+        assert_eq!(standings.wins, vec![2, 1, 3, 0, 4]);
+        assert_eq!(standings.winner_of(2, 0), Some(2));
+        assert_eq!(standings.matches().len(), 10);
+    }
+
+    #[test]
+    fn tie_break_uses_beaten_opponents() {
+        // A cycle 0 > 1 > 2 > 0; 3 only beats 1.
+        let beats = [(0, 1), (1, 2), (2, 0), (0, 3), (3, 1), (2, 3)];
+        let items = [0usize, 1, 2, 3];
+        let standings = round_robin(&items, |&a, &b| beats.contains(&(a, b)));
+        assert_eq!(standings.wins, vec![2, 1, 2, 1]);
+        // 0 beat {1, 3} (2 wins total), 2 beat {0, 3} (3 wins total).
+        assert_eq!(standings.order, vec![2, 0, 1, 3]);
+    }
+}
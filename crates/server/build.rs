@@ -0,0 +1,9 @@
+//! Generates the gRPC service from `proto/` with the `grpc` feature.
+
+#[cfg(feature = "grpc")]
+fn main() -> std::io::Result<()> {
+    tonic_prost_build::compile_protos("proto/rankfast.proto")
+}
+
+#[cfg(not(feature = "grpc"))]
+fn main() {}
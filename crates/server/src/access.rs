@@ -0,0 +1,174 @@
+//! Who may do what with a session.
+//!
+//! Creating or importing a session hands out two random tokens. The owner
+//! token can do anything: answer, change the items, finalize, delete, and
+//! issue more participant tokens. A participant token can only follow the
+//! session and answer or vote, so it is the one to put in a shared link.
+//! In a voting session, only the owner may answer directly, and each
+//! participant token votes under the one name it first voted with.
+//!
+//! Listing sessions (`GET /sessions`) shows only those the token owns,
+//! unless it is the server's admin token from `RANKFAST_ADMIN_TOKEN`, which
+//! lists them all. Without a token nothing is listed.
+//!
+//! Requests carry their token as `Authorization: Bearer <token>` or, where
+//! headers cannot be set (links, browser `WebSocket`s), as `?token=<token>`.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Write};
+
+use serde::{Deserialize, Serialize};
+
+/// What a token allows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Role {
+    /// Follow the session and answer its questions.
+    Participant,
+    /// Anything, including changes a participant could use to reset the
+    /// ranking.
+    Owner,
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Participant => "participant",
+            Self::Owner => "owner",
+        })
+    }
+}
+
+/// The tokens of one session.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Access {
+    pub(crate) owner: String,
+    pub(crate) participants: Vec<String>,
+    /// The name each participant token votes under, by token.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) voters: BTreeMap<String, String>,
+}
+
+impl Access {
+    /// Fresh tokens: an owner and a first participant.
+    pub(crate) fn new() -> Self {
+        Self {
+            owner: token(),
+            participants: vec![token()],
+            voters: BTreeMap::new(),
+        }
+    }
+
+    /// Issues another participant token and returns it.
+    pub(crate) fn add_participant(&mut self) -> String {
+        let token = token();
+        self.participants.push(token.clone());
+        token
+    }
+
+    /// What `token` allows, if anything.
+    pub(crate) fn role(&self, token: &str) -> Option<Role> {
+        if same(&self.owner, token) {
+            Some(Role::Owner)
+        } else if self.participants.iter().any(|p| same(p, token)) {
+            Some(Role::Participant)
+        } else {
+            None
+        }
+    }
+
+    /// Whether `token` may vote as `voter`. The owner votes as anyone; a
+    /// participant token only as the name it is bound to, and before its
+    /// first vote as any name no other token is bound to.
+    pub(crate) fn may_vote_as(&self, token: &str, voter: &str) -> bool {
+        match (self.role(token), self.voters.get(token)) {
+            (Some(Role::Owner), _) => true,
+            (Some(Role::Participant), Some(bound)) => bound == voter,
+            (Some(Role::Participant), None) => !self.voters.values().any(|bound| bound == voter),
+            (None, _) => false,
+        }
+    }
+
+    /// Binds participant `token` to `voter` if it is not bound yet, and
+    /// tells whether it was.
+    pub(crate) fn bind(&mut self, token: &str, voter: &str) -> bool {
+        if self.role(token) != Some(Role::Participant) || self.voters.contains_key(token) {
+            return false;
+        }
+        self.voters.insert(token.to_string(), voter.to_string());
+        true
+    }
+}
+
+/// The server-wide token that may list every session, from
+/// `RANKFAST_ADMIN_TOKEN`. Without one, no token may.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Admin(Option<String>);
+
+impl Admin {
+    pub(crate) fn new(token: Option<String>) -> Self {
+        Self(token.filter(|t| !t.is_empty()))
+    }
+
+    pub(crate) fn from_env() -> Self {
+        Self::new(std::env::var("RANKFAST_ADMIN_TOKEN").ok())
+    }
+
+    /// Whether `token` is the admin token.
+    pub(crate) fn is(&self, token: &str) -> bool {
+        self.0.as_deref().is_some_and(|admin| same(admin, token))
+    }
+}
+
+/// 128 random bits as 32 hex digits.
+fn token() -> String {
+    let mut bytes = [0; 16];
+    // Without an entropy source the server cannot hand out safe tokens.
+    getrandom::fill(&mut bytes).expect("no randomness for session tokens");
+    bytes.iter().fold(String::with_capacity(32), |mut hex, b| {
+        // Writing to a String cannot fail.
+        let _ = write!(hex, "{b:02x}");
+        hex
+    })
+}
+
+/// Compares tokens in time that does not depend on where they differ.
+fn same(expected: &str, given: &str) -> bool {
+    expected.len() == given.len()
+        && expected
+            .bytes()
+            .zip(given.bytes())
+            .fold(0, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Access, Admin, Role};
+
+    #[test]
+    fn tokens_grant_their_role() {
+        let mut access = Access::new();
+        assert_eq!(access.owner.len(), 32);
+        assert_ne!(access.owner, access.participants[0]);
+        assert_eq!(access.role(&access.owner.clone()), Some(Role::Owner));
+        let joined = access.add_participant();
+        assert_eq!(access.role(&joined), Some(Role::Participant));
+        assert_eq!(access.role(""), None);
+        assert_eq!(access.role(&joined[1..]), None);
+        assert!(Role::Owner > Role::Participant);
+
+        let first = access.participants[0].clone();
+        assert!(access.may_vote_as(&first, "ann"));
+        assert!(access.bind(&first, "ann"));
+        assert!(!access.bind(&first, "bob"));
+        assert!(!access.may_vote_as(&first, "bob"));
+        assert!(!access.may_vote_as(&joined, "ann"));
+        assert!(access.may_vote_as(&joined, "bob"));
+        assert!(access.may_vote_as(&access.owner.clone(), "ann"));
+        assert!(!access.may_vote_as("", "cid"));
+
+        let admin = Admin::new(Some("secret".into()));
+        assert!(admin.is("secret") && !admin.is("secreT"));
+        assert!(!Admin::new(Some(String::new())).is(""));
+    }
+}
@@ -0,0 +1,791 @@
+//! REST endpoints.
+//!
+//! | Method | Path                      | Body                            |
+//! |--------|---------------------------|---------------------------------|
+//! | GET    | `/sessions`               | owner or admin, `?finished=true` and `?item=text` filter |
+//! | POST   | `/sessions`               | `{"items": ["a", "b", ...], "engine": "exact", "preferences": [...], "voting": {...}, "webhook": "https://..."}` |
+//! | POST   | `/sessions/import`        | a saved session, see [`rankfast_session::json`] |
+//! | DELETE | `/sessions/{id}`          | owner                           |
+//! | GET    | `/sessions/{id}/question` |                                 |
+//! | POST   | `/sessions/{id}/answer`   | `{"a": 0, "b": 1, "a_is_better": true}` |
+//! | POST   | `/sessions/{id}/vote`     | `{"participant": "ann", "a": 0, "b": 1, "a_is_better": true}` |
+//! | GET    | `/sessions/{id}/votes`    |                                 |
+//! | PUT    | `/sessions/{id}/items`    | owner: `{"items": ["a", "b", ...]}` |
+//! | POST   | `/sessions/{id}/finalize` | owner                           |
+//! | POST   | `/sessions/{id}/tokens`   | owner                           |
+//! | GET    | `/sessions/{id}/result`   |                                 |
+//! | GET    | `/sessions/{id}/export`   |                                 |
+//! | GET    | `/sessions/{id}/dataset`  | `?format=csv` or `jsonl` (default), see [`rankfast_session::dataset`] |
+//! | GET    | `/sessions/{id}/live`     | WebSocket upgrade, see [`crate::live`] |
+//! | POST   | `/sessions/{id}/judge`    | owner, with `llm-judge`, see [`crate::llm`] |
+//! | PUT    | `/sessions/{id}/webhook`  | owner, with `webhooks`: `{"url": "https://..."}`, see [`crate::webhook`] |
+//!
+//! Creating or importing a session returns its `owner_token` and a first
+//! `participant_token`, see [`crate::access`]. Every `/sessions/{id}`
+//! endpoint needs one of them, and those marked owner need the owner's.
+//! Questions carry an `id`. An answer or vote may send it back as
+//! `question_id`, and is then rejected with `409` if the items changed
+//! since. Sending an `Idempotency-Key` header makes retries safe: a repeated
+//! key gets the first attempt's outcome and answers nothing twice. Answers
+//! and votes are rate limited per token, see [`crate::limit`].
+//!
+//! In a voting session `/answer` takes the owner's token, as an answer
+//! overrides the votes. A participant token votes under the first
+//! `participant` name it votes with, and is refused any other.
+//!
+//! `GET /sessions` lists the sessions the request's token owns, or every
+//! session for the admin token, see [`crate::access`].
+//!
+//! `PUT /items` restarts the ranking over new items, keeping what is
+//! already decided between items that stay. `/finalize` ends the session
+//! with the ranking so far. `/tokens` issues another participant token.
+//!
+//! Items may be strings or objects like `{"label": "a", "description": "..."}`,
+//! see [`rankfast_session::item`].
+//!
+//! `engine` is optional and names the engine as
+//! [`Mode::from_name`](rankfast_session::Mode::from_name) does. The server
+//! only runs `"exact"`, the default, as the only engine whose questions can
+//! be answered in parallel; sessions in another mode are refused, imported
+//! ones too.
+//!
+//! `preferences` is optional: earlier judgments as in
+//! [`rankfast_session::dataset`], so the questions they decide are skipped.
+//! `voting` is optional too: `{"quorum": 3, "weights": {"ann": 2.0}}` makes
+//! the session decide each question by the participants' votes, see
+//! [`crate::vote`]. `/votes` lists every vote by participant, next to the
+//! tallies of the undecided questions. `webhook` is optional as well, and
+//! only read with the `webhooks` feature: a URL the complete ranking is
+//! sent to as a POST, see [`crate::webhook`].
+//!
+//! Errors are returned as `{"error": "..."}` with a matching status code.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use axum::extract::ws::WebSocketUpgrade;
+use axum::extract::{FromRequestParts, Path, Query, State};
+use axum::http::request::Parts;
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post, put};
+use axum::{Extension, Json, Router};
+use rankfast_session::json::JsonError;
+use rankfast_session::{Mode, Preference, dataset};
+use serde::{Deserialize, Serialize};
+
+use crate::access::{Admin, Role};
+use crate::limit::RateLimiter;
+#[cfg(feature = "llm-judge")]
+use crate::llm::{self, JudgeError, LlmError, LlmJudge};
+use crate::session::{Session, SessionError, SessionStore, check_engine};
+use crate::vote::{Tally, Vote, VotingRules};
+#[cfg(feature = "webhooks")]
+use crate::webhook;
+
+pub(crate) fn router(store: Arc<SessionStore>, limiter: Arc<RateLimiter>) -> Router {
+    let router = Router::new()
+        .route("/sessions", get(list_sessions).post(create_session))
+        .route("/sessions/import", post(import_session))
+        .route("/sessions/{id}", delete(delete_session))
+        .route("/sessions/{id}/question", get(question))
+        .route("/sessions/{id}/answer", post(answer))
+        .route("/sessions/{id}/vote", post(vote))
+        .route("/sessions/{id}/votes", get(votes))
+        .route("/sessions/{id}/items", put(set_items))
+        .route("/sessions/{id}/finalize", post(finalize))
+        .route("/sessions/{id}/tokens", post(add_token))
+        .route("/sessions/{id}/result", get(result))
+        .route("/sessions/{id}/export", get(export))
+        .route("/sessions/{id}/dataset", get(export_dataset))
+        .route("/sessions/{id}/live", get(live));
+    #[cfg(feature = "llm-judge")]
+    let router = router
+        .route("/sessions/{id}/judge", post(judge))
+        .layer(Extension(Arc::new(LlmJudge::from_env())));
+    #[cfg(feature = "webhooks")]
+    let router = router.route("/sessions/{id}/webhook", put(set_webhook));
+    router
+        .layer(Extension(limiter))
+        .layer(Extension(Arc::new(Admin::from_env())))
+        .with_state(store)
+}
+
+#[derive(Deserialize)]
+struct CreateSession {
+    #[serde(deserialize_with = "rankfast_session::item::lines::deserialize")]
+    items: Vec<String>,
+    #[serde(default)]
+    engine: Mode,
+    #[serde(default)]
+    preferences: Vec<Preference>,
+    voting: Option<VotingRules>,
+    #[cfg(feature = "webhooks")]
+    webhook: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Items {
+    #[serde(deserialize_with = "rankfast_session::item::lines::deserialize")]
+    items: Vec<String>,
+}
+
+#[cfg(feature = "webhooks")]
+#[derive(Deserialize)]
+struct Webhook {
+    url: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ListQuery {
+    finished: Option<bool>,
+    /// Only sessions with an item containing this text, ignoring case.
+    item: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Summary {
+    id: u64,
+    items: usize,
+    comparisons: usize,
+    finished: bool,
+}
+
+#[derive(Serialize)]
+struct Created {
+    id: u64,
+    owner_token: String,
+    participant_token: String,
+    #[serde(flatten)]
+    progress: Progress,
+}
+
+#[derive(Serialize)]
+struct ParticipantToken {
+    participant_token: String,
+}
+
+#[derive(Deserialize)]
+struct TokenQuery {
+    token: Option<String>,
+}
+
+/// The session token a request carries, from its `Authorization: Bearer`
+/// header or else its `?token=` query.
+struct Token(Option<String>);
+
+impl Token {
+    fn get(&self) -> Option<&str> {
+        self.0.as_deref()
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for Token {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        let bearer = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(|token| token.trim().to_string());
+        let query = || {
+            Query::<TokenQuery>::try_from_uri(&parts.uri)
+                .ok()
+                .and_then(|Query(query)| query.token)
+        };
+        Ok(Self(bearer.or_else(query)))
+    }
+}
+
+/// Where a session stands: the next question, if any, every question that
+/// can be answered in parallel, and the turn count.
+#[derive(Serialize)]
+pub(crate) struct Progress {
+    question: Option<Question>,
+    open_questions: Vec<Question>,
+    comparisons: usize,
+    estimate: usize,
+    /// Votes on undecided questions, in a voting session.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    votes: Vec<Tally>,
+    /// Questions the language model took too long on, see [`crate::llm`].
+    #[cfg(feature = "llm-judge")]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    timeouts: Vec<llm::TimedOut>,
+}
+
+#[derive(Serialize)]
+struct Question {
+    /// Unique within the session, see [`Session::question_id`].
+    id: u64,
+    a: usize,
+    b: usize,
+    item_a: String,
+    item_b: String,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct Answer {
+    pub(crate) a: usize,
+    pub(crate) b: usize,
+    pub(crate) a_is_better: bool,
+    /// The id of the question answered, as the client last saw it.
+    #[serde(default)]
+    pub(crate) question_id: Option<u64>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct Ballot {
+    pub(crate) participant: String,
+    #[serde(flatten)]
+    pub(crate) answer: Answer,
+}
+
+#[derive(Serialize)]
+struct Votes {
+    participants: BTreeMap<String, Vec<Vote>>,
+    open: Vec<Tally>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+enum DatasetFormat {
+    Csv,
+    #[default]
+    Jsonl,
+}
+
+#[derive(Deserialize)]
+struct DatasetQuery {
+    #[serde(default)]
+    format: DatasetFormat,
+}
+
+#[derive(Serialize)]
+pub(crate) struct RankingResult {
+    ranking: Vec<String>,
+    comparisons: usize,
+}
+
+impl Progress {
+    pub(crate) fn of(session: &Session) -> Self {
+        let items = session.items();
+        let question = |(a, b): (usize, usize)| Question {
+            // Every open question has an id.
+            id: session.question_id(a, b).unwrap_or_default(),
+            a,
+            b,
+            item_a: items[a].clone(),
+            item_b: items[b].clone(),
+        };
+        Self {
+            question: session.question().map(question),
+            open_questions: session
+                .open_questions()
+                .iter()
+                .copied()
+                .map(question)
+                .collect(),
+            comparisons: session.comparisons(),
+            estimate: session.estimate(),
+            votes: session.tallies(),
+            #[cfg(feature = "llm-judge")]
+            timeouts: session.timeouts().to_vec(),
+        }
+    }
+}
+
+impl Created {
+    fn of(id: u64, session: &Session) -> Self {
+        let access = session.access();
+        Self {
+            id,
+            owner_token: access.owner.clone(),
+            participant_token: access.participants[0].clone(),
+            progress: Progress::of(session),
+        }
+    }
+}
+
+impl RankingResult {
+    pub(crate) fn of(session: &Session) -> Result<Self, SessionError> {
+        Ok(Self {
+            ranking: session.ranking()?,
+            comparisons: session.comparisons(),
+        })
+    }
+}
+
+async fn list_sessions(
+    State(store): State<Arc<SessionStore>>,
+    Extension(admin): Extension<Arc<Admin>>,
+    token: Token,
+    Query(query): Query<ListQuery>,
+) -> Result<Json<Vec<Summary>>, ApiError> {
+    Ok(Json(summaries(&store, &admin, token.get(), query)?))
+}
+
+/// The sessions `token` may list that match `query`: all of them for the
+/// admin token, else those it owns. Any other token is forbidden, so
+/// labels cannot be probed through `?item=`.
+fn summaries(
+    store: &SessionStore,
+    admin: &Admin,
+    token: Option<&str>,
+    query: ListQuery,
+) -> Result<Vec<Summary>, SessionError> {
+    let token = token.ok_or(SessionError::Unauthorized)?;
+    let everything = admin.is(token);
+    let owns = |s: &Session| s.authorize(Some(token), Role::Owner).is_ok();
+    if !everything && store.list(|_, s| owns(s).then_some(())).is_empty() {
+        return Err(SessionError::Forbidden(Role::Owner));
+    }
+    let needle = query.item.map(|text| text.to_lowercase());
+    Ok(store.list(|id, s| {
+        if !everything && !owns(s) {
+            return None;
+        }
+        let finished = s.question().is_none();
+        if query.finished.is_some_and(|wanted| wanted != finished) {
+            return None;
+        }
+        if let Some(needle) = &needle
+            && !s
+                .items()
+                .iter()
+                .any(|item| item.to_lowercase().contains(needle))
+        {
+            return None;
+        }
+        Some(Summary {
+            id,
+            items: s.items().len(),
+            comparisons: s.comparisons(),
+            finished,
+        })
+    }))
+}
+
+async fn create_session(
+    State(store): State<Arc<SessionStore>>,
+    Json(body): Json<CreateSession>,
+) -> Result<(StatusCode, Json<Created>), ApiError> {
+    if body.items.is_empty() {
+        return Err(ApiError::BadRequest("items must not be empty"));
+    }
+    check_engine(body.engine)?;
+    #[cfg(feature = "webhooks")]
+    if body
+        .webhook
+        .as_deref()
+        .is_some_and(|url| !webhook::is_valid(url))
+    {
+        return Err(ApiError::BadRequest(WEBHOOK_URL));
+    }
+    let known = dataset::known_pairs(&body.items, &body.preferences);
+    let id = store.create(body.items, &known, body.voting)?;
+    Ok((
+        StatusCode::CREATED,
+        Json(store.with(id, |s| {
+            #[cfg(feature = "webhooks")]
+            s.set_webhook(body.webhook);
+            Ok(Created::of(id, s))
+        })?),
+    ))
+}
+
+async fn import_session(
+    State(store): State<Arc<SessionStore>>,
+    body: String,
+) -> Result<(StatusCode, Json<Created>), ApiError> {
+    let saved = rankfast_session::Session::from_json(&body)?;
+    if saved.items.is_empty() {
+        return Err(ApiError::BadRequest("items must not be empty"));
+    }
+    let id = store.insert(Session::restore(saved)?)?;
+    Ok((
+        StatusCode::CREATED,
+        Json(store.with(id, |s| Ok(Created::of(id, s)))?),
+    ))
+}
+
+async fn delete_session(
+    State(store): State<Arc<SessionStore>>,
+    Path(id): Path<u64>,
+    token: Token,
+) -> Result<StatusCode, ApiError> {
+    store.remove(id, token.get())?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn question(
+    State(store): State<Arc<SessionStore>>,
+    Path(id): Path<u64>,
+    token: Token,
+) -> Result<Json<Progress>, ApiError> {
+    let progress = store.with(id, |s| {
+        s.authorize(token.get(), Role::Participant)?;
+        Ok(Progress::of(s))
+    })?;
+    Ok(Json(progress))
+}
+
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(session = id)))]
+async fn answer(
+    State(store): State<Arc<SessionStore>>,
+    Extension(limiter): Extension<Arc<RateLimiter>>,
+    Path(id): Path<u64>,
+    token: Token,
+    headers: HeaderMap,
+    Json(body): Json<Answer>,
+) -> Result<Json<Progress>, ApiError> {
+    let key = idempotency_key(&headers);
+    let progress = store.with(id, |s| {
+        s.authorize_answer(token.get())?;
+        limiter.admit(token.get().unwrap_or_default())?;
+        s.once(key, |s| {
+            s.expect_question(body.a, body.b, body.question_id)?;
+            s.answer(body.a, body.b, body.a_is_better)
+        })?;
+        Ok(Progress::of(s))
+    })?;
+    Ok(Json(progress))
+}
+
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(session = id)))]
+async fn vote(
+    State(store): State<Arc<SessionStore>>,
+    Extension(limiter): Extension<Arc<RateLimiter>>,
+    Path(id): Path<u64>,
+    token: Token,
+    headers: HeaderMap,
+    Json(body): Json<Ballot>,
+) -> Result<Json<Progress>, ApiError> {
+    if body.participant.is_empty() {
+        return Err(ApiError::BadRequest("participant must not be empty"));
+    }
+    let key = idempotency_key(&headers);
+    let progress = store.with(id, |s| {
+        s.authorize(token.get(), Role::Participant)?;
+        limiter.admit(token.get().unwrap_or_default())?;
+        let Answer {
+            a,
+            b,
+            a_is_better,
+            question_id,
+        } = body.answer;
+        s.once(key, |s| {
+            s.expect_question(a, b, question_id)?;
+            s.vote(
+                token.get().unwrap_or_default(),
+                &body.participant,
+                a,
+                b,
+                a_is_better,
+            )
+        })?;
+        Ok(Progress::of(s))
+    })?;
+    Ok(Json(progress))
+}
+
+async fn votes(
+    State(store): State<Arc<SessionStore>>,
+    Path(id): Path<u64>,
+    token: Token,
+) -> Result<Json<Votes>, ApiError> {
+    let votes = store.with(id, |s| {
+        s.authorize(token.get(), Role::Participant)?;
+        Ok(Votes {
+            participants: s.votes()?.clone(),
+            open: s.tallies(),
+        })
+    })?;
+    Ok(Json(votes))
+}
+
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(session = id)))]
+async fn set_items(
+    State(store): State<Arc<SessionStore>>,
+    Path(id): Path<u64>,
+    token: Token,
+    Json(body): Json<Items>,
+) -> Result<Json<Progress>, ApiError> {
+    if body.items.is_empty() {
+        return Err(ApiError::BadRequest("items must not be empty"));
+    }
+    let progress = store.with(id, |s| {
+        s.authorize(token.get(), Role::Owner)?;
+        s.set_items(body.items);
+        Ok(Progress::of(s))
+    })?;
+    Ok(Json(progress))
+}
+
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(session = id)))]
+async fn finalize(
+    State(store): State<Arc<SessionStore>>,
+    Path(id): Path<u64>,
+    token: Token,
+) -> Result<Json<RankingResult>, ApiError> {
+    let result = store.with(id, |s| {
+        s.authorize(token.get(), Role::Owner)?;
+        s.finalize();
+        RankingResult::of(s)
+    })?;
+    Ok(Json(result))
+}
+
+async fn add_token(
+    State(store): State<Arc<SessionStore>>,
+    Path(id): Path<u64>,
+    token: Token,
+) -> Result<(StatusCode, Json<ParticipantToken>), ApiError> {
+    let participant_token = store.with(id, |s| {
+        s.authorize(token.get(), Role::Owner)?;
+        Ok(s.add_participant())
+    })?;
+    Ok((
+        StatusCode::CREATED,
+        Json(ParticipantToken { participant_token }),
+    ))
+}
+
+/// The request's `Idempotency-Key` header, if it has a usable one.
+fn idempotency_key(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get("idempotency-key")
+        .and_then(|value| value.to_str().ok())
+        .filter(|key| !key.is_empty())
+}
+
+async fn result(
+    State(store): State<Arc<SessionStore>>,
+    Path(id): Path<u64>,
+    token: Token,
+) -> Result<Json<RankingResult>, ApiError> {
+    let result = store.with(id, |s| {
+        s.authorize(token.get(), Role::Participant)?;
+        RankingResult::of(s)
+    })?;
+    Ok(Json(result))
+}
+
+async fn export(
+    State(store): State<Arc<SessionStore>>,
+    Path(id): Path<u64>,
+    token: Token,
+) -> Result<Json<rankfast_session::Session>, ApiError> {
+    let saved = store.with(id, |s| {
+        s.authorize(token.get(), Role::Participant)?;
+        Ok(s.saved())
+    })?;
+    Ok(Json(saved))
+}
+
+async fn export_dataset(
+    State(store): State<Arc<SessionStore>>,
+    Path(id): Path<u64>,
+    token: Token,
+    Query(query): Query<DatasetQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let log = store.with(id, |s| {
+        s.authorize(token.get(), Role::Participant)?;
+        Ok(s.preferences())
+    })?;
+    Ok(match query.format {
+        DatasetFormat::Csv => ([(header::CONTENT_TYPE, "text/csv")], dataset::to_csv(&log)),
+        DatasetFormat::Jsonl => (
+            [(header::CONTENT_TYPE, "application/jsonl")],
+            dataset::to_jsonl(&log),
+        ),
+    })
+}
+
+async fn live(
+    State(store): State<Arc<SessionStore>>,
+    Extension(limiter): Extension<Arc<RateLimiter>>,
+    Path(id): Path<u64>,
+    Token(token): Token,
+    ws: WebSocketUpgrade,
+) -> Result<Response, ApiError> {
+    let updates = store.with(id, |s| {
+        s.authorize(token.as_deref(), Role::Participant)?;
+        Ok(s.subscribe())
+    })?;
+    let token = token.unwrap_or_default();
+    Ok(ws.on_upgrade(move |socket| crate::live::run(socket, store, limiter, id, token, updates)))
+}
+
+#[cfg(feature = "llm-judge")]
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(session = id)))]
+async fn judge(
+    State(store): State<Arc<SessionStore>>,
+    Extension(judge): Extension<Arc<LlmJudge>>,
+    Path(id): Path<u64>,
+    token: Token,
+) -> Result<Json<Progress>, ApiError> {
+    store.with(id, |s| s.authorize(token.get(), Role::Owner))?;
+    llm::run(&store, &judge, id).await?;
+    Ok(Json(store.with(id, |s| Ok(Progress::of(s)))?))
+}
+
+#[cfg(feature = "webhooks")]
+const WEBHOOK_URL: &str = "webhook must be an http or https URL";
+
+#[cfg(feature = "webhooks")]
+async fn set_webhook(
+    State(store): State<Arc<SessionStore>>,
+    Path(id): Path<u64>,
+    token: Token,
+    Json(body): Json<Webhook>,
+) -> Result<StatusCode, ApiError> {
+    if body
+        .url
+        .as_deref()
+        .is_some_and(|url| !webhook::is_valid(url))
+    {
+        return Err(ApiError::BadRequest(WEBHOOK_URL));
+    }
+    store.with(id, |s| {
+        s.authorize(token.get(), Role::Owner)?;
+        s.set_webhook(body.url);
+        Ok(())
+    })?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Request failures, rendered as JSON.
+pub(crate) enum ApiError {
+    BadRequest(&'static str),
+    InvalidSession(JsonError),
+    Session(SessionError),
+    #[cfg(feature = "llm-judge")]
+    Llm(LlmError),
+}
+
+impl From<JsonError> for ApiError {
+    fn from(e: JsonError) -> Self {
+        Self::InvalidSession(e)
+    }
+}
+
+impl From<SessionError> for ApiError {
+    fn from(e: SessionError) -> Self {
+        Self::Session(e)
+    }
+}
+
+#[cfg(feature = "llm-judge")]
+impl From<JudgeError> for ApiError {
+    fn from(e: JudgeError) -> Self {
+        match e {
+            JudgeError::Session(e) => Self::Session(e),
+            JudgeError::Llm(e) => Self::Llm(e),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let retry_after = match &self {
+            Self::Session(SessionError::RateLimited { retry_after_s }) => Some(*retry_after_s),
+            _ => None,
+        };
+        let (status, message) = match self {
+            Self::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg.to_string()),
+            Self::InvalidSession(e) => (StatusCode::BAD_REQUEST, e.to_string()),
+            Self::Session(e @ (SessionError::UnsupportedEngine | SessionError::Replay(_))) => {
+                (StatusCode::BAD_REQUEST, e.to_string())
+            }
+            Self::Session(e @ SessionError::NotFound) => (StatusCode::NOT_FOUND, e.to_string()),
+            Self::Session(e @ SessionError::Unauthorized) => {
+                (StatusCode::UNAUTHORIZED, e.to_string())
+            }
+            Self::Session(e @ (SessionError::Forbidden(_) | SessionError::WrongVoter(_))) => {
+                (StatusCode::FORBIDDEN, e.to_string())
+            }
+            Self::Session(e @ SessionError::RateLimited { .. }) => {
+                (StatusCode::TOO_MANY_REQUESTS, e.to_string())
+            }
+            Self::Session(e @ SessionError::Storage(_)) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+            }
+            Self::Session(
+                e @ (SessionError::NotAsked { .. }
+                | SessionError::Unfinished
+                | SessionError::NotVoting
+                | SessionError::Finalized
+                | SessionError::StaleQuestion { .. }),
+            ) => (StatusCode::CONFLICT, e.to_string()),
+            #[cfg(feature = "llm-judge")]
+            Self::Llm(e @ LlmError::TimedOut) => (StatusCode::GATEWAY_TIMEOUT, e.to_string()),
+            #[cfg(feature = "llm-judge")]
+            Self::Llm(e) => (StatusCode::BAD_GATEWAY, e.to_string()),
+        };
+        let mut response = (status, Json(serde_json::json!({ "error": message }))).into_response();
+        if let Some(seconds) = retry_after {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(seconds));
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use axum::response::IntoResponse;
+
+    use super::{ApiError, ListQuery, Summary, summaries};
+    use crate::access::{Admin, Role};
+    use crate::session::{SessionError, SessionStore};
+
+    fn query(item: &str) -> ListQuery {
+        ListQuery {
+            finished: None,
+            item: Some(item.to_string()),
+        }
+    }
+
+    fn ids(listed: Result<Vec<Summary>, SessionError>) -> Vec<u64> {
+        listed.unwrap().iter().map(|s| s.id).collect()
+    }
+
+    #[test]
+    fn lists_sessions_only_to_owners_and_the_admin() {
+        let store = SessionStore::default();
+        let admin = Admin::new(Some("admin".to_string()));
+        let items = |names: [&str; 2]| names.map(String::from).to_vec();
+        let mine = store
+            .create(items(["secret plan", "b"]), &[], None)
+            .unwrap();
+        let theirs = store.create(items(["other", "c"]), &[], None).unwrap();
+        let (owner, participant) = store
+            .with(mine, |s| {
+                let access = s.access();
+                Ok((access.owner.clone(), access.participants[0].clone()))
+            })
+            .unwrap();
+
+        let anonymous = summaries(&store, &admin, None, query("secret"));
+        assert!(matches!(anonymous, Err(SessionError::Unauthorized)));
+        let response = ApiError::Session(SessionError::Unauthorized).into_response();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let joined = summaries(&store, &admin, Some(&participant), query("secret"));
+        assert!(matches!(joined, Err(SessionError::Forbidden(Role::Owner))));
+
+        assert_eq!(
+            ids(summaries(&store, &admin, Some(&owner), query(""))),
+            [mine]
+        );
+        assert_eq!(
+            ids(summaries(&store, &admin, Some("admin"), query(""))),
+            [mine, theirs]
+        );
+        assert!(ids(summaries(&store, &admin, Some("admin"), query("SECRET"))) == [mine]);
+    }
+}
@@ -0,0 +1,294 @@
+//! gRPC interface, with the `grpc` feature.
+//!
+//! The `Ranking` service of `proto/rankfast.proto` serves backend pipelines
+//! where polling REST is awkward. `CreateSession` starts a session like
+//! `POST /sessions`. `Rank` is a bidirectional stream that works like
+//! [`crate::live`]: the client first sends a `Join` with the session id and
+//! an owner or participant token, then `Answer`s; the server sends the
+//! progress on join and after every accepted answer, whoever sent it, the
+//! result once the ranking is complete, and an error for a rejected answer.
+//! Answers are rate limited like over REST, see [`crate::limit`].
+//!
+//! The service listens on `RANKFAST_GRPC_ADDR` (default `127.0.0.1:50051`).
+
+use std::sync::Arc;
+
+use rankfast_session::Mode;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status, Streaming};
+
+use crate::access::Role;
+use crate::limit::RateLimiter;
+use crate::session::{Session, SessionError, SessionStore, check_engine};
+
+#[allow(clippy::pedantic)] // generated by tonic
+mod proto {
+    tonic::include_proto!("rankfast");
+}
+
+use proto::ranking_server::{Ranking, RankingServer};
+use proto::{
+    CreateSessionRequest, CreateSessionResponse, Progress, Question, RankRequest, RankUpdate,
+    Ranked, rank_request, rank_update,
+};
+
+/// Updates queued per stream before the session stops sending.
+const STREAM_BUFFER: usize = 16;
+
+/// Serves the `Ranking` service until the process ends.
+pub(crate) async fn serve(
+    store: Arc<SessionStore>,
+    limiter: Arc<RateLimiter>,
+) -> std::io::Result<()> {
+    let addr = std::env::var("RANKFAST_GRPC_ADDR")
+        .unwrap_or_else(|_| "127.0.0.1:50051".to_string())
+        .parse()
+        .map_err(std::io::Error::other)?;
+    println!("Serving gRPC on {addr}");
+    tonic::transport::Server::builder()
+        .add_service(RankingServer::new(Service { store, limiter }))
+        .serve(addr)
+        .await
+        .map_err(std::io::Error::other)
+}
+
+struct Service {
+    store: Arc<SessionStore>,
+    limiter: Arc<RateLimiter>,
+}
+
+#[tonic::async_trait]
+impl Ranking for Service {
+    async fn create_session(
+        &self,
+        request: Request<CreateSessionRequest>,
+    ) -> Result<Response<CreateSessionResponse>, Status> {
+        let CreateSessionRequest { items, engine } = request.into_inner();
+        if items.is_empty() {
+            return Err(Status::invalid_argument("items must not be empty"));
+        }
+        let mode = if engine.is_empty() {
+            Some(Mode::Exact)
+        } else {
+            Mode::from_name(&engine)
+        };
+        let mode =
+            mode.ok_or_else(|| Status::invalid_argument(format!("unknown engine {engine:?}")))?;
+        check_engine(mode).map_err(|e| status(&e))?;
+        let id = self
+            .store
+            .create(items, &[], None)
+            .map_err(|e| status(&e))?;
+        let created = self
+            .store
+            .with(id, |s| {
+                Ok(CreateSessionResponse {
+                    id,
+                    owner_token: s.access().owner.clone(),
+                    participant_token: s.access().participants[0].clone(),
+                    progress: Some(progress(s)),
+                })
+            })
+            .map_err(|e| status(&e))?;
+        Ok(Response::new(created))
+    }
+
+    type RankStream = ReceiverStream<Result<RankUpdate, Status>>;
+
+    async fn rank(
+        &self,
+        request: Request<Streaming<RankRequest>>,
+    ) -> Result<Response<Self::RankStream>, Status> {
+        let mut incoming = request.into_inner();
+        let Some(rank_request::Message::Join(join)) =
+            incoming.message().await?.and_then(|r| r.message)
+        else {
+            return Err(Status::invalid_argument("the first message must be a join"));
+        };
+        let updates = self
+            .store
+            .with(join.session_id, |s| {
+                s.authorize(Some(&join.token), Role::Participant)?;
+                Ok(s.subscribe())
+            })
+            .map_err(|e| status(&e))?;
+        let follower = Follower {
+            store: Arc::clone(&self.store),
+            limiter: Arc::clone(&self.limiter),
+            id: join.session_id,
+            token: join.token,
+        };
+        let (tx, rx) = mpsc::channel(STREAM_BUFFER);
+        tokio::spawn(follower.run(incoming, updates, tx));
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+}
+
+/// One `Rank` stream that joined a session.
+struct Follower {
+    store: Arc<SessionStore>,
+    limiter: Arc<RateLimiter>,
+    id: u64,
+    token: String,
+}
+
+type Updates = mpsc::Sender<Result<RankUpdate, Status>>;
+
+impl Follower {
+    /// Serves the stream until the client leaves or the session is gone.
+    async fn run(
+        self,
+        mut incoming: Streaming<RankRequest>,
+        mut updates: broadcast::Receiver<()>,
+        tx: Updates,
+    ) {
+        if self.send_state(&tx).await.is_err() {
+            return;
+        }
+        loop {
+            tokio::select! {
+                request = incoming.message() => match request {
+                    Ok(Some(request)) => {
+                        let Err(error) = self.handle(&request) else {
+                            // The broadcast brings the new state back to us too.
+                            continue;
+                        };
+                        if send(&tx, rank_update::Message::Error(error)).await.is_err() {
+                            return;
+                        }
+                    }
+                    Ok(None) | Err(_) => return,
+                },
+                update = updates.recv() => match update {
+                    // Missed notifications are fine: the state sent is current.
+                    Ok(()) | Err(RecvError::Lagged(_)) => {
+                        if self.send_state(&tx).await.is_err() {
+                            return;
+                        }
+                    }
+                    Err(RecvError::Closed) => return,
+                },
+            }
+        }
+    }
+
+    fn handle(&self, request: &RankRequest) -> Result<(), String> {
+        let Some(rank_request::Message::Answer(answer)) = &request.message else {
+            return Err("expected an answer".to_string());
+        };
+        let (a, b) = (index(answer.a), index(answer.b));
+        self.store
+            .with(self.id, |s| {
+                s.authorize_answer(Some(&self.token))?;
+                self.limiter.admit(&self.token)?;
+                s.expect_question(a, b, answer.question_id)?;
+                s.answer(a, b, answer.a_is_better)
+            })
+            .map_err(|e| e.to_string())
+    }
+
+    /// Sends the current progress, followed by the result once finished.
+    async fn send_state(&self, tx: &Updates) -> Result<(), ()> {
+        let Ok((progress, result)) = self.store.with(self.id, |s| Ok((progress(s), ranked(s))))
+        else {
+            return Err(());
+        };
+        send(tx, rank_update::Message::Progress(progress)).await?;
+        if let Some(result) = result {
+            send(tx, rank_update::Message::Result(result)).await?;
+        }
+        Ok(())
+    }
+}
+
+async fn send(tx: &Updates, message: rank_update::Message) -> Result<(), ()> {
+    let update = RankUpdate {
+        message: Some(message),
+    };
+    tx.send(Ok(update)).await.map_err(|_| ())
+}
+
+fn progress(session: &Session) -> Progress {
+    let items = session.items();
+    let question = |(a, b): (usize, usize)| Question {
+        // Every open question has an id.
+        id: session.question_id(a, b).unwrap_or_default(),
+        a: wide(a),
+        b: wide(b),
+        item_a: items[a].clone(),
+        item_b: items[b].clone(),
+    };
+    Progress {
+        question: session.question().map(question),
+        open_questions: session
+            .open_questions()
+            .iter()
+            .copied()
+            .map(question)
+            .collect(),
+        comparisons: wide(session.comparisons()),
+        estimate: wide(session.estimate()),
+    }
+}
+
+fn ranked(session: &Session) -> Option<Ranked> {
+    Some(Ranked {
+        ranking: session.ranking().ok()?,
+        comparisons: wide(session.comparisons()),
+    })
+}
+
+fn status(e: &SessionError) -> Status {
+    let message = e.to_string();
+    match e {
+        SessionError::NotFound => Status::not_found(message),
+        SessionError::Unauthorized => Status::unauthenticated(message),
+        SessionError::Forbidden(_) | SessionError::WrongVoter(_) => {
+            Status::permission_denied(message)
+        }
+        SessionError::RateLimited { .. } => Status::resource_exhausted(message),
+        SessionError::Storage(_) => Status::internal(message),
+        SessionError::UnsupportedEngine | SessionError::Replay(_) => {
+            Status::invalid_argument(message)
+        }
+        SessionError::NotAsked { .. }
+        | SessionError::StaleQuestion { .. }
+        | SessionError::Unfinished
+        | SessionError::NotVoting
+        | SessionError::Finalized => Status::failed_precondition(message),
+    }
+}
+
+fn wide(n: usize) -> u64 {
+    u64::try_from(n).unwrap_or(u64::MAX)
+}
+
+/// An item index from the wire; out of range ones are never asked about.
+fn index(n: u64) -> usize {
+    usize::try_from(n).unwrap_or(usize::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SessionError, index, progress, status};
+    use crate::session::Session;
+
+    #[test]
+    fn questions_and_errors_map_to_the_wire() {
+        let session = Session::new(["x", "y"].map(String::from).to_vec(), &[], None);
+        let sent = progress(&session);
+        let question = sent.question.unwrap();
+        assert_eq!(
+            (question.a, question.b, question.item_a.as_str()),
+            (0, 1, "x")
+        );
+        assert_eq!(sent.open_questions, vec![question]);
+        assert_eq!(index(u64::MAX), usize::MAX);
+        assert_eq!(
+            status(&SessionError::NotFound).code(),
+            tonic::Code::NotFound
+        );
+    }
+}
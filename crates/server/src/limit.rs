@@ -0,0 +1,161 @@
+//! Per-token rate limiting of answers.
+//!
+//! Each session token gets a bucket of `RANKFAST_ANSWER_BURST` answers
+//! (default 10) that refills at `RANKFAST_ANSWER_RATE` answers per second
+//! (default 5). An answer or vote that finds its bucket empty is rejected
+//! before it reaches the session: with `429 Too Many Requests` and a
+//! `Retry-After` header over REST, and with an error message over
+//! [`crate::live`] and gRPC. Buckets that have refilled are dropped, so tokens
+//! no longer answering take no memory.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::session::SessionError;
+
+const DEFAULT_RATE: f64 = 5.0;
+const DEFAULT_BURST: f64 = 10.0;
+
+/// Token buckets by session token.
+pub(crate) struct RateLimiter {
+    /// Answers regained per second.
+    rate: f64,
+    /// Answers a full bucket holds.
+    burst: f64,
+    buckets: Mutex<Buckets>,
+}
+
+#[derive(Default)]
+struct Buckets {
+    by_token: HashMap<String, Bucket>,
+    /// When full buckets were last dropped.
+    pruned_at: Option<Instant>,
+}
+
+struct Bucket {
+    left: f64,
+    at: Instant,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(rate: f64, burst: f64) -> Self {
+        Self {
+            rate,
+            burst: burst.max(1.0),
+            buckets: Mutex::default(),
+        }
+    }
+
+    /// A limiter configured from `RANKFAST_ANSWER_RATE` and
+    /// `RANKFAST_ANSWER_BURST`; values that are not positive numbers fall
+    /// back to the defaults.
+    pub(crate) fn from_env() -> Self {
+        let var = |name: &str, default: f64| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse::<f64>().ok())
+                .filter(|&v| v > 0.0)
+                .unwrap_or(default)
+        };
+        Self::new(
+            var("RANKFAST_ANSWER_RATE", DEFAULT_RATE),
+            var("RANKFAST_ANSWER_BURST", DEFAULT_BURST),
+        )
+    }
+
+    /// Takes one answer from the bucket of `token`, which must already be
+    /// authorized, or fails with [`SessionError::RateLimited`].
+    pub(crate) fn admit(&self, token: &str) -> Result<(), SessionError> {
+        self.check_at(token, Instant::now())
+            .map_err(|wait| SessionError::RateLimited {
+                retry_after_s: wait.as_secs() + 1,
+            })
+    }
+
+    /// Takes one answer from `token`'s bucket, or says how long until one
+    /// is back.
+    fn check_at(&self, token: &str, now: Instant) -> Result<(), Duration> {
+        let mut buckets = self.lock();
+        self.prune(&mut buckets, now);
+        let bucket = buckets.by_token.entry(token.to_string()).or_insert(Bucket {
+            left: self.burst,
+            at: now,
+        });
+        let elapsed = now.saturating_duration_since(bucket.at).as_secs_f64();
+        bucket.left = (bucket.left + elapsed * self.rate).min(self.burst);
+        bucket.at = now;
+        if bucket.left < 1.0 {
+            return Err(Duration::from_secs_f64((1.0 - bucket.left) / self.rate));
+        }
+        bucket.left -= 1.0;
+        Ok(())
+    }
+
+    /// Drops the buckets that refilled since their last answer, which are
+    /// no different from new ones. Runs at most once per refill time, the
+    /// time an empty bucket takes to fill.
+    fn prune(&self, buckets: &mut Buckets, now: Instant) {
+        let refill = Duration::try_from_secs_f64(self.burst / self.rate).unwrap_or(Duration::MAX);
+        let idle = |at: Instant| now.saturating_duration_since(at) >= refill;
+        if buckets.pruned_at.is_some_and(|at| !idle(at)) {
+            return;
+        }
+        buckets.pruned_at = Some(now);
+        buckets.by_token.retain(|_, bucket| !idle(bucket.at));
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Buckets> {
+        // Buckets are updated field by field, but any values are safe to
+        // resume from.
+        self.buckets
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::RateLimiter;
+
+    #[test]
+    fn buckets_refill_per_token() {
+        let limiter = RateLimiter::new(2.0, 3.0);
+        let start = Instant::now();
+        for _ in 0..3 {
+            assert_eq!(limiter.check_at("t", start), Ok(()));
+        }
+        assert_eq!(
+            limiter.check_at("t", start),
+            Err(Duration::from_millis(500))
+        );
+        assert_eq!(limiter.check_at("other", start), Ok(()));
+        assert_eq!(
+            limiter.check_at("t", start + Duration::from_millis(500)),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn full_buckets_are_dropped() {
+        let limiter = RateLimiter::new(2.0, 3.0);
+        let start = Instant::now();
+        assert_eq!(limiter.check_at("a", start), Ok(()));
+        assert_eq!(
+            limiter.check_at("b", start + Duration::from_secs(1)),
+            Ok(())
+        );
+        assert_eq!(limiter.lock().by_token.len(), 2);
+        // Refilling takes 1.5 s: "a" is full again, "b" is not yet.
+        assert_eq!(
+            limiter.check_at("c", start + Duration::from_secs(2)),
+            Ok(())
+        );
+        let buckets = limiter.lock();
+        let mut tokens: Vec<&str> = buckets.by_token.keys().map(String::as_str).collect();
+        tokens.sort_unstable();
+        assert_eq!(tokens, ["b", "c"]);
+    }
+}
@@ -0,0 +1,221 @@
+//! WebSocket protocol for ranking together.
+//!
+//! Every client connected to `/sessions/{id}/live?token=...`, with an owner
+//! or participant token (see [`crate::access`]), receives the session's
+//! state on connect and again after each accepted answer or vote, whoever
+//! sent it (including REST clients). A projector can simply listen while
+//! voters on their phones answer any of the open questions. The session
+//! being deleted closes the connection.
+//!
+//! Messages are JSON objects tagged by `type`:
+//!
+//! - client → server: `{"type": "answer", "a": 0, "b": 1, "a_is_better": true}`,
+//!   or in a voting session `{"type": "vote", "participant": "ann", ...}` with
+//!   the same fields; `question_id` may be added as over REST, and
+//!   `idempotency_key` like the REST `Idempotency-Key` header
+//! - server → client: `{"type": "progress", ...}` with the fields of a REST
+//!   question response, `{"type": "result", "ranking": [...], ...}` once the
+//!   ranking is complete, and `{"type": "error", "error": "..."}` for a
+//!   rejected answer or vote (sent only to its author).
+//!
+//! Answers and votes are rate limited per token like over REST, see
+//! [`crate::limit`].
+
+use std::sync::Arc;
+
+use axum::extract::ws::{Message, WebSocket};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::access::Role;
+use crate::api::{Answer, Ballot, Progress, RankingResult};
+use crate::limit::RateLimiter;
+use crate::session::SessionStore;
+
+#[derive(Deserialize)]
+struct ClientMessage {
+    #[serde(flatten)]
+    request: Request,
+    /// Makes a resent message safe, see [`crate::session::Session::once`].
+    #[serde(default)]
+    idempotency_key: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Request {
+    Answer(Answer),
+    Vote(Ballot),
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerMessage {
+    Progress(Progress),
+    Result(RankingResult),
+    Error { error: String },
+}
+
+/// Serves one connection, made with `token`, until the client leaves or the
+/// session is gone.
+pub(crate) async fn run(
+    mut socket: WebSocket,
+    store: Arc<SessionStore>,
+    limiter: Arc<RateLimiter>,
+    id: u64,
+    token: String,
+    mut updates: broadcast::Receiver<()>,
+) {
+    if send_state(&mut socket, &store, id).await.is_err() {
+        return;
+    }
+    loop {
+        tokio::select! {
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Text(text))) => {
+                    let Err(error) = handle(&store, &limiter, id, &token, &text) else {
+                        // The broadcast brings the new state back to us too.
+                        continue;
+                    };
+                    if send(&mut socket, &ServerMessage::Error { error }).await.is_err() {
+                        return;
+                    }
+                }
+                Some(Ok(Message::Close(_)) | Err(_)) | None => return,
+                Some(Ok(_)) => {}
+            },
+            update = updates.recv() => match update {
+                // Missed notifications are fine: the state sent is current.
+                Ok(()) | Err(RecvError::Lagged(_)) => {
+                    if send_state(&mut socket, &store, id).await.is_err() {
+                        return;
+                    }
+                }
+                Err(RecvError::Closed) => return,
+            },
+        }
+    }
+}
+
+/// Applies one message from the client. The token is checked again, as
+/// the session may have been replaced since the connection was made.
+fn handle(
+    store: &SessionStore,
+    limiter: &RateLimiter,
+    id: u64,
+    token: &str,
+    text: &str,
+) -> Result<(), String> {
+    let message: ClientMessage = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    store
+        .with(id, |s| {
+            s.authorize(Some(token), Role::Participant)?;
+            if let Request::Answer(_) = message.request {
+                s.authorize_answer(Some(token))?;
+            }
+            limiter.admit(token)?;
+            s.once(message.idempotency_key.as_deref(), |s| {
+                match message.request {
+                    Request::Answer(answer) => {
+                        s.expect_question(answer.a, answer.b, answer.question_id)?;
+                        s.answer(answer.a, answer.b, answer.a_is_better)
+                    }
+                    Request::Vote(Ballot {
+                        participant,
+                        answer,
+                    }) => {
+                        s.expect_question(answer.a, answer.b, answer.question_id)?;
+                        s.vote(token, &participant, answer.a, answer.b, answer.a_is_better)
+                    }
+                }
+            })
+        })
+        .map_err(|e| e.to_string())
+}
+
+/// Sends the current progress, followed by the result once finished.
+async fn send_state(socket: &mut WebSocket, store: &SessionStore, id: u64) -> Result<(), ()> {
+    let Ok((progress, result)) =
+        store.with(id, |s| Ok((Progress::of(s), RankingResult::of(s).ok())))
+    else {
+        return Err(());
+    };
+    send(socket, &ServerMessage::Progress(progress)).await?;
+    if let Some(result) = result {
+        send(socket, &ServerMessage::Result(result)).await?;
+    }
+    Ok(())
+}
+
+async fn send(socket: &mut WebSocket, message: &ServerMessage) -> Result<(), ()> {
+    let text = serde_json::to_string(message).map_err(|_| ())?;
+    socket.send(Message::text(text)).await.map_err(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::handle;
+    use crate::limit::RateLimiter;
+    use crate::session::SessionStore;
+    use crate::vote::VotingRules;
+
+    #[test]
+    fn bursts_and_retries_are_held_back() {
+        let store = SessionStore::default();
+        let items = ["p", "q", "r", "s", "t", "u"].map(String::from).to_vec();
+        let id = store.create(items, &[], None).unwrap();
+        let token = store
+            .with(id, |s| Ok(s.access().participants[0].clone()))
+            .unwrap();
+        let limiter = RateLimiter::new(0.001, 2.0);
+        let send = |text: &str| handle(&store, &limiter, id, &token, text);
+
+        let first =
+            r#"{"type": "answer", "a": 0, "b": 1, "a_is_better": true, "idempotency_key": "k"}"#;
+        assert_eq!(send(first), Ok(()));
+        // The resent message is not answered twice.
+        assert_eq!(send(first), Ok(()));
+        assert_eq!(store.with(id, |s| Ok(s.comparisons())).unwrap(), 1);
+
+        let error = send(r#"{"type": "answer", "a": 2, "b": 3, "a_is_better": true}"#);
+        assert!(error.unwrap_err().starts_with("too many answers"));
+        assert_eq!(store.with(id, |s| Ok(s.comparisons())).unwrap(), 1);
+        assert_eq!(
+            handle(&store, &limiter, id, "guess", first),
+            Err("a valid session token is required".to_string())
+        );
+    }
+
+    #[test]
+    fn votes_are_the_only_way_in_for_participants() {
+        let store = SessionStore::default();
+        let rules = VotingRules {
+            quorum: 2,
+            weights: HashMap::new(),
+        };
+        let items = ["p", "q"].map(String::from).to_vec();
+        let id = store.create(items, &[], Some(rules)).unwrap();
+        let token = store
+            .with(id, |s| Ok(s.access().participants[0].clone()))
+            .unwrap();
+        let limiter = RateLimiter::new(5.0, 10.0);
+        let send = |text: &str| handle(&store, &limiter, id, &token, text);
+
+        assert_eq!(
+            send(r#"{"type": "answer", "a": 0, "b": 1, "a_is_better": true}"#),
+            Err("only the owner token allows this".to_string())
+        );
+        let vote = |name: &str| {
+            send(&format!(
+                r#"{{"type": "vote", "participant": "{name}", "a": 0, "b": 1, "a_is_better": true}}"#
+            ))
+        };
+        assert_eq!(vote("ann"), Ok(()));
+        assert_eq!(
+            vote("bob"),
+            Err("this token cannot vote as bob".to_string())
+        );
+    }
+}
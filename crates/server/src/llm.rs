@@ -0,0 +1,342 @@
+//! Answering questions with a language model.
+//!
+//! With the `llm-judge` feature, `POST /sessions/{id}/judge` asks an
+//! OpenAI-compatible chat completions endpoint every question until the
+//! ranking is complete, and returns the session's progress. The model is
+//! configured from the environment:
+//!
+//! - `RANKFAST_LLM_URL`: the endpoint, by default
+//!   `https://api.openai.com/v1/chat/completions`
+//! - `RANKFAST_LLM_MODEL`: the model name, by default `gpt-4o-mini`
+//! - `RANKFAST_LLM_KEY` or `OPENAI_API_KEY`: the bearer token, if any
+//! - `RANKFAST_LLM_PROMPT`: the prompt, with `{a}` and `{b}` standing for
+//!   the two items, see [`DEFAULT_PROMPT`]
+//! - `RANKFAST_LLM_TIMEOUT_S`: how long to wait for each verdict, in
+//!   seconds, by default as long as it takes
+//! - `RANKFAST_LLM_ON_TIMEOUT`: what to do when the wait runs out, see
+//!   [`OnTimeout`]: `retry` (the default), `tie` or `defer`
+//!
+//! Each time out is listed under `timeouts` in the session's progress.
+//!
+//! The model must reply `A` or `B`. Verdicts are cached per pair of items
+//! for the life of the server, in either order, so re-ranking the same
+//! documents or resuming a session does not ask again.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::session::{SessionError, SessionStore};
+
+/// Asks the model to pick between `{a}` and `{b}`.
+pub(crate) const DEFAULT_PROMPT: &str = "Which of these two is better?\n\n\
+     A: {a}\n\nB: {b}\n\nReply with the single letter A or B.";
+
+const DEFAULT_URL: &str = "https://api.openai.com/v1/chat/completions";
+const DEFAULT_MODEL: &str = "gpt-4o-mini";
+
+/// How many more times a question that timed out is asked before judging
+/// fails, with [`OnTimeout::Retry`].
+const TIMEOUT_RETRIES: usize = 2;
+
+/// What to do with a question the model took too long to answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum OnTimeout {
+    /// Ask again, up to [`TIMEOUT_RETRIES`] times, then stop judging.
+    Retry,
+    /// Keep the pair in input order, as the stepper breaks ties.
+    Tie,
+    /// Leave the question for people and judge the others.
+    Defer,
+}
+
+impl OnTimeout {
+    /// The policy named by `RANKFAST_LLM_ON_TIMEOUT`, retrying if unset or
+    /// unknown.
+    fn parse(value: Option<&str>) -> Self {
+        match value.map(str::to_ascii_lowercase).as_deref() {
+            Some("tie") => Self::Tie,
+            Some("defer") => Self::Defer,
+            _ => Self::Retry,
+        }
+    }
+}
+
+/// A question the model took too long to answer.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct TimedOut {
+    pub(crate) a: usize,
+    pub(crate) b: usize,
+    /// What was done instead.
+    pub(crate) then: OnTimeout,
+    /// Unix milliseconds.
+    pub(crate) at_ms: u64,
+}
+
+/// Why the model gave no answer.
+#[derive(Debug)]
+pub(crate) enum LlmError {
+    /// The endpoint could not be reached or returned an error status.
+    Request(reqwest::Error),
+    /// No verdict within `RANKFAST_LLM_TIMEOUT_S`.
+    TimedOut,
+    /// The response carried no message content.
+    NoContent,
+    /// The model replied with something other than A or B.
+    BadReply(String),
+}
+
+impl From<reqwest::Error> for LlmError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            Self::TimedOut
+        } else {
+            Self::Request(e)
+        }
+    }
+}
+
+impl fmt::Display for LlmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Request(e) => write!(f, "the model could not be asked: {e}"),
+            Self::TimedOut => f.write_str("the model took too long to answer"),
+            Self::NoContent => f.write_str("the model's response had no message"),
+            Self::BadReply(reply) => write!(f, "the model replied {reply:?}; expected A or B"),
+        }
+    }
+}
+
+/// A chat completions endpoint, its prompt, and every verdict so far.
+pub(crate) struct LlmJudge {
+    client: reqwest::Client,
+    url: String,
+    model: String,
+    key: Option<String>,
+    prompt: String,
+    deadline: Option<Duration>,
+    on_timeout: OnTimeout,
+    /// Whether the first item of each pair is better, keyed by the items in
+    /// sorted order.
+    verdicts: Mutex<HashMap<(String, String), bool>>,
+}
+
+impl LlmJudge {
+    /// A judge configured from the `RANKFAST_LLM_*` variables.
+    pub(crate) fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+        Self {
+            client: reqwest::Client::new(),
+            url: var("RANKFAST_LLM_URL").unwrap_or_else(|| DEFAULT_URL.to_string()),
+            model: var("RANKFAST_LLM_MODEL").unwrap_or_else(|| DEFAULT_MODEL.to_string()),
+            key: var("RANKFAST_LLM_KEY").or_else(|| var("OPENAI_API_KEY")),
+            prompt: var("RANKFAST_LLM_PROMPT").unwrap_or_else(|| DEFAULT_PROMPT.to_string()),
+            deadline: var("RANKFAST_LLM_TIMEOUT_S")
+                .and_then(|s| s.parse().ok())
+                .and_then(|s: f64| Duration::try_from_secs_f64(s).ok())
+                .filter(|deadline| !deadline.is_zero()),
+            on_timeout: OnTimeout::parse(var("RANKFAST_LLM_ON_TIMEOUT").as_deref()),
+            verdicts: Mutex::default(),
+        }
+    }
+
+    /// Whether `a` is better than `b`, from the cache or else the model.
+    pub(crate) async fn judge(&self, a: &str, b: &str) -> Result<bool, LlmError> {
+        if let Some(a_is_better) = self.cached(a, b) {
+            return Ok(a_is_better);
+        }
+        let a_is_better = self.ask(a, b).await?;
+        let (key, first_is_better) = pair_key(a, b, a_is_better);
+        self.lock().insert(key, first_is_better);
+        Ok(a_is_better)
+    }
+
+    fn cached(&self, a: &str, b: &str) -> Option<bool> {
+        // With `a` assumed better, the flag says whether `a` sorts first.
+        let (key, a_first) = pair_key(a, b, true);
+        let first_is_better = *self.lock().get(&key)?;
+        Some(first_is_better == a_first)
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    async fn ask(&self, a: &str, b: &str) -> Result<bool, LlmError> {
+        let body = json!({
+            "model": self.model,
+            "messages": [{ "role": "user", "content": render(&self.prompt, a, b) }],
+            "temperature": 0,
+        });
+        let mut request = self.client.post(&self.url).json(&body);
+        if let Some(key) = &self.key {
+            request = request.bearer_auth(key);
+        }
+        if let Some(deadline) = self.deadline {
+            request = request.timeout(deadline);
+        }
+        let response: Value = request
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)?
+            .json()
+            .await?;
+        let content = response["choices"][0]["message"]["content"]
+            .as_str()
+            .ok_or(LlmError::NoContent)?;
+        decide(content)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<(String, String), bool>> {
+        // Verdicts are inserted whole; a poisoned map is still consistent.
+        self.verdicts
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+/// Why judging a session stopped before the ranking was complete.
+pub(crate) enum JudgeError {
+    Session(SessionError),
+    Llm(LlmError),
+}
+
+/// Answers session `id`'s questions with `judge` until none are left.
+///
+/// The store is not locked while the model thinks, so people can keep
+/// answering alongside; a question someone else answered first is skipped.
+/// Questions the model takes too long on are handled by the judge's
+/// [`OnTimeout`] policy and logged on the session, so with
+/// [`OnTimeout::Defer`] this can return before the ranking is complete.
+pub(crate) async fn run(store: &SessionStore, judge: &LlmJudge, id: u64) -> Result<(), JudgeError> {
+    let mut deferred = Vec::new();
+    let mut retries = 0;
+    loop {
+        let question = store
+            .with(id, |s| {
+                let open = s.open_questions().iter();
+                Ok(open
+                    .copied()
+                    .find(|question| !deferred.contains(question))
+                    .map(|(a, b)| (a, b, s.items()[a].clone(), s.items()[b].clone())))
+            })
+            .map_err(JudgeError::Session)?;
+        let Some((a, b, item_a, item_b)) = question else {
+            return Ok(());
+        };
+        let a_is_better = match judge.judge(&item_a, &item_b).await {
+            Ok(a_is_better) => a_is_better,
+            Err(LlmError::TimedOut) => {
+                let then = judge.on_timeout;
+                if then == OnTimeout::Retry && retries == TIMEOUT_RETRIES {
+                    return Err(JudgeError::Llm(LlmError::TimedOut));
+                }
+                store
+                    .with(id, |s| {
+                        s.record_timeout(a, b, then);
+                        Ok(())
+                    })
+                    .map_err(JudgeError::Session)?;
+                match then {
+                    OnTimeout::Retry => {
+                        retries += 1;
+                        continue;
+                    }
+                    OnTimeout::Defer => {
+                        deferred.push((a, b));
+                        retries = 0;
+                        continue;
+                    }
+                    OnTimeout::Tie => a < b,
+                }
+            }
+            Err(e) => return Err(JudgeError::Llm(e)),
+        };
+        retries = 0;
+        match store.with(id, |s| s.answer(a, b, a_is_better)) {
+            Ok(()) | Err(SessionError::NotAsked { .. }) => {}
+            Err(e) => return Err(JudgeError::Session(e)),
+        }
+    }
+}
+
+/// The cache key for `a` against `b` and whether the first item of the key
+/// is better, given whether `a` is.
+fn pair_key(a: &str, b: &str, a_is_better: bool) -> ((String, String), bool) {
+    if a <= b {
+        ((a.to_string(), b.to_string()), a_is_better)
+    } else {
+        ((b.to_string(), a.to_string()), !a_is_better)
+    }
+}
+
+/// `prompt` with the placeholders filled in, without expanding placeholders
+/// that appear inside the items.
+fn render(prompt: &str, a: &str, b: &str) -> String {
+    prompt
+        .split("{a}")
+        .map(|part| part.replace("{b}", b))
+        .collect::<Vec<_>>()
+        .join(a)
+}
+
+/// The side chosen in the model's `reply`: a leading A or B, ignoring case,
+/// quotes and markup.
+fn decide(reply: &str) -> Result<bool, LlmError> {
+    let mut chars = reply
+        .trim_start_matches(|c: char| !c.is_ascii_alphanumeric())
+        .chars();
+    let letter = chars.next().map(|c| c.to_ascii_uppercase());
+    // "A" or "A." but not "Apples".
+    let word = chars.next().is_some_and(|c| c.is_ascii_alphanumeric());
+    match letter {
+        Some('A') if !word => Ok(true),
+        Some('B') if !word => Ok(false),
+        _ => Err(LlmError::BadReply(reply.trim().to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DEFAULT_PROMPT, LlmError, LlmJudge, OnTimeout, decide, pair_key, render};
+
+    #[test]
+    fn reads_a_leading_letter() {
+        assert!(decide("A").unwrap());
+        assert!(!decide(" b.\n").unwrap());
+        assert!(decide("**A**").unwrap());
+        assert!(matches!(decide("Apples"), Err(LlmError::BadReply(_))));
+        assert!(matches!(decide("neither"), Err(LlmError::BadReply(_))));
+        assert!(matches!(decide(""), Err(LlmError::BadReply(_))));
+    }
+
+    #[test]
+    fn fills_in_the_prompt_once() {
+        assert_eq!(render("{a} vs {b}", "x {b}", "y"), "x {b} vs y");
+        let prompt = render(DEFAULT_PROMPT, "first", "second");
+        assert!(prompt.contains("A: first") && prompt.contains("B: second"));
+    }
+
+    #[test]
+    fn caches_verdicts_in_either_order() {
+        assert_eq!(pair_key("x", "y", true), (("x".into(), "y".into()), true));
+        assert_eq!(pair_key("y", "x", true), (("x".into(), "y".into()), false));
+
+        let judge = LlmJudge::from_env();
+        assert_eq!(judge.cached("x", "y"), None);
+        let (key, first_is_better) = pair_key("y", "x", true);
+        judge.lock().insert(key, first_is_better);
+        assert_eq!(judge.cached("y", "x"), Some(true));
+        assert_eq!(judge.cached("x", "y"), Some(false));
+    }
+
+    #[test]
+    fn reads_the_timeout_policy() {
+        assert_eq!(OnTimeout::parse(Some("tie")), OnTimeout::Tie);
+        assert_eq!(OnTimeout::parse(Some("Defer")), OnTimeout::Defer);
+        assert_eq!(OnTimeout::parse(Some("skip")), OnTimeout::Retry);
+        assert_eq!(OnTimeout::parse(None), OnTimeout::Retry);
+    }
+}
@@ -0,0 +1,85 @@
+//! HTTP server for ranking sessions shared across devices.
+//!
+//! Listens on `RANKFAST_ADDR` (default `127.0.0.1:3000`). Sessions are kept
+//! in memory, and with the `sqlite` feature also in the database file named
+//! by `RANKFAST_DB`, see [`storage`]. Clients either poll the REST endpoints
+//! in [`api`] or follow a session over the WebSocket protocol in [`live`],
+//! or with the `grpc` feature over the gRPC service in [`grpc`]. With the
+//! `llm-judge`
+//! feature, a language model can answer a session's questions, see [`llm`].
+//! Sessions can also be decided by the votes of several participants, see
+//! [`vote`]. With the `webhooks` feature, a session's complete ranking is
+//! sent as a POST to a URL its creator gave, see [`webhook`]. With the `tracing`
+//! feature, requests, questions and answers are logged to stderr as
+//! `tracing` spans and events, filtered by `RUST_LOG`
+//! (`RUST_LOG=rankfast=trace,rankfast_server=debug` shows everything).
+
+mod access;
+mod api;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod limit;
+mod live;
+#[cfg(feature = "llm-judge")]
+mod llm;
+mod session;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod storage;
+mod vote;
+#[cfg(feature = "webhooks")]
+mod webhook;
+
+use std::sync::Arc;
+
+use limit::RateLimiter;
+use session::SessionStore;
+
+#[tokio::main]
+async fn main() -> std::io::Result<()> {
+    #[cfg(feature = "tracing")]
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
+        .init();
+    let addr = std::env::var("RANKFAST_ADDR").unwrap_or_else(|_| "127.0.0.1:3000".to_string());
+    let listener = tokio::net::TcpListener::bind(&addr).await?;
+    println!("Listening on http://{}", listener.local_addr()?);
+    let store = Arc::new(open_store()?);
+    let limiter = Arc::new(RateLimiter::from_env());
+    let rest = axum::serve(
+        listener,
+        api::router(Arc::clone(&store), Arc::clone(&limiter)),
+    );
+    tokio::try_join!(rest.into_future(), serve_grpc(store, limiter))?;
+    Ok(())
+}
+
+/// The gRPC service, see [`grpc`].
+#[cfg(feature = "grpc")]
+async fn serve_grpc(store: Arc<SessionStore>, limiter: Arc<RateLimiter>) -> std::io::Result<()> {
+    grpc::serve(store, limiter).await
+}
+
+#[cfg(not(feature = "grpc"))]
+#[allow(clippy::needless_pass_by_value)] // consumed with the `grpc` feature
+async fn serve_grpc(_: Arc<SessionStore>, _: Arc<RateLimiter>) -> std::io::Result<()> {
+    std::future::pending().await
+}
+
+/// The session store, on the database at `RANKFAST_DB` if set.
+#[cfg(feature = "sqlite")]
+fn open_store() -> std::io::Result<SessionStore> {
+    let Ok(path) = std::env::var("RANKFAST_DB") else {
+        return Ok(SessionStore::default());
+    };
+    sqlite::Sqlite::open(&path)
+        .and_then(|db| SessionStore::open(Box::new(db)))
+        .map_err(|e| std::io::Error::other(format!("{path}: {e}")))
+}
+
+#[cfg(not(feature = "sqlite"))]
+#[allow(clippy::unnecessary_wraps)] // fallible with the `sqlite` feature
+fn open_store() -> std::io::Result<SessionStore> {
+    Ok(SessionStore::default())
+}
@@ -0,0 +1,1076 @@
+//! Ranking sessions held by the server.
+//!
+//! A [`Session`] wraps a [`Stepper`] together with the item labels, so
+//! handlers only ever deal in indices and strings. Sessions live in a
+//! [`SessionStore`] keyed by an opaque id, and are lost on restart unless
+//! the store was opened on a [`Storage`]. Every accepted answer and vote is
+//! announced on the session's update channel, which live connections
+//! subscribe to. Each session carries its access tokens, see
+//! [`crate::access`].
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rankfast::{Ranking, Step, Stepper, estimate_turns};
+use rankfast_session::{Answer, Mode, Preference};
+use tokio::sync::broadcast;
+
+use crate::access::{Access, Role};
+use crate::storage::{Storage, StorageError};
+use crate::vote::{Tally, Vote, Voting, VotingRules};
+
+/// Pending notifications per subscriber before slow ones start skipping.
+const UPDATE_BUFFER: usize = 16;
+
+/// Idempotency keys remembered per session.
+const IDEMPOTENCY_KEYS: usize = 256;
+
+/// Why a request against a session was rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum SessionError {
+    /// No session with the given id.
+    NotFound,
+    /// The answered pair is not a question the session is waiting on.
+    NotAsked { a: usize, b: usize },
+    /// The answer was meant for an earlier question over the same pair,
+    /// before the items changed.
+    StaleQuestion { expected: u64, given: u64 },
+    /// The ranking is still missing answers.
+    Unfinished,
+    /// The session asks for another engine than the exact sort, the only
+    /// one the server runs, see [`check_engine`].
+    UnsupportedEngine,
+    /// A restored session's answers do not fit its items.
+    Replay(rankfast_session::SessionError),
+    /// The session could not be saved.
+    Storage(StorageError),
+    /// A vote was cast on a session created without voting rules.
+    NotVoting,
+    /// The session was finalized and takes no more answers.
+    Finalized,
+    /// The request carried no token, or one the session does not know.
+    Unauthorized,
+    /// The token is valid but does not allow the request; it needs the
+    /// given role.
+    Forbidden(Role),
+    /// The token votes under another name than the one given.
+    WrongVoter(String),
+    /// The token sent too many answers; it may try again after the given
+    /// number of seconds, see [`crate::limit`].
+    RateLimited { retry_after_s: u64 },
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound => f.write_str("no such session"),
+            Self::NotAsked { a, b } => write!(f, "({a}, {b}) is not an open question"),
+            Self::StaleQuestion { expected, given } => {
+                write!(f, "question {given} was replaced by question {expected}")
+            }
+            Self::Unfinished => f.write_str("ranking is not finished"),
+            Self::UnsupportedEngine => f.write_str("the server only runs the exact engine"),
+            Self::Replay(e) => write!(f, "cannot resume the session: {e}"),
+            Self::Storage(e) => e.fmt(f),
+            Self::NotVoting => f.write_str("the session does not take votes"),
+            Self::Finalized => f.write_str("the session was finalized"),
+            Self::Unauthorized => f.write_str("a valid session token is required"),
+            Self::Forbidden(role) => write!(f, "only the {role} token allows this"),
+            Self::WrongVoter(name) => write!(f, "this token cannot vote as {name}"),
+            Self::RateLimited { retry_after_s } => {
+                write!(f, "too many answers; retry in {retry_after_s} s")
+            }
+        }
+    }
+}
+
+/// One ranking in progress.
+pub(crate) struct Session {
+    items: Vec<String>,
+    /// `(better, worse)` pairs known before the first question.
+    known: Vec<(usize, usize)>,
+    stepper: Stepper,
+    step: Step,
+    open: Vec<(usize, usize)>,
+    /// The id of each open question and when it opened, in Unix
+    /// milliseconds, keyed by the pair in ascending order.
+    question_ids: HashMap<(usize, usize), (u64, u64)>,
+    next_question_id: u64,
+    order: Option<Ranking<usize>>,
+    /// When each pair answered on this server was answered, in Unix
+    /// milliseconds, keyed by the pair in ascending order. Answers restored
+    /// from a saved session have none.
+    answered_at_ms: HashMap<(usize, usize), u64>,
+    /// Participants' votes, when the session is decided by vote. Votes are
+    /// not saved: a restored session counts only its decided answers.
+    voting: Option<Voting>,
+    /// Questions the language model took too long on, oldest first. Not
+    /// saved.
+    #[cfg(feature = "llm-judge")]
+    timeouts: Vec<crate::llm::TimedOut>,
+    /// Where to send the complete ranking, see [`crate::webhook`], and
+    /// whether it was sent. Only the URL is saved.
+    #[cfg(feature = "webhooks")]
+    webhook: Option<String>,
+    #[cfg(feature = "webhooks")]
+    webhook_sent: bool,
+    access: Access,
+    /// Outcomes of answers sent with an idempotency key, and the keys from
+    /// oldest to newest.
+    outcomes: HashMap<String, Result<(), SessionError>>,
+    outcome_keys: VecDeque<String>,
+    /// Whether the owner ended the session before the ranking was complete.
+    finalized: bool,
+    /// Bumped on every change that needs saving.
+    revision: u64,
+    updates: broadcast::Sender<()>,
+}
+
+impl Session {
+    /// A session over `items` that skips the questions the `known` pairs
+    /// decide, see [`rankfast::consistent_pairs`], and takes votes if
+    /// `voting` is given, see [`crate::vote`].
+    pub(crate) fn new(
+        items: Vec<String>,
+        known: &[(usize, usize)],
+        voting: Option<VotingRules>,
+    ) -> Self {
+        let known = rankfast::consistent_pairs(items.len(), known);
+        let mut session = Self {
+            stepper: Stepper::with_known_pairs(items.len(), &known),
+            items,
+            known,
+            step: Step::Done,
+            open: Vec::new(),
+            question_ids: HashMap::new(),
+            next_question_id: 0,
+            order: None,
+            answered_at_ms: HashMap::new(),
+            voting: voting.map(Voting::new),
+            #[cfg(feature = "llm-judge")]
+            timeouts: Vec::new(),
+            #[cfg(feature = "webhooks")]
+            webhook: None,
+            #[cfg(feature = "webhooks")]
+            webhook_sent: false,
+            access: Access::new(),
+            outcomes: HashMap::new(),
+            outcome_keys: VecDeque::new(),
+            finalized: false,
+            revision: 0,
+            updates: broadcast::channel(UPDATE_BUFFER).0,
+        };
+        session.refresh();
+        session
+    }
+
+    pub(crate) fn items(&self) -> &[String] {
+        &self.items
+    }
+
+    /// The question to ask next, or `None` once the ranking is complete.
+    pub(crate) fn question(&self) -> Option<(usize, usize)> {
+        match self.step {
+            Step::Compare { a, b, .. } => Some((a, b)),
+            Step::Done => None,
+        }
+    }
+
+    /// Every question that can be answered right now, current one first.
+    pub(crate) fn open_questions(&self) -> &[(usize, usize)] {
+        &self.open
+    }
+
+    /// The id of the open question `a` vs `b`. Ids are never reused within
+    /// a session, so a client can tell its question apart from a later one
+    /// over the same pair.
+    pub(crate) fn question_id(&self, a: usize, b: usize) -> Option<u64> {
+        self.question_ids
+            .get(&(a.min(b), a.max(b)))
+            .map(|&(id, _)| id)
+    }
+
+    /// Checks that an answer to `a` vs `b` was meant for the open question
+    /// with id `expected`, when the client sent one.
+    pub(crate) fn expect_question(
+        &self,
+        a: usize,
+        b: usize,
+        expected: Option<u64>,
+    ) -> Result<(), SessionError> {
+        let (Some(given), Some(current)) = (expected, self.question_id(a, b)) else {
+            // Unknown pairs are rejected when answered.
+            return Ok(());
+        };
+        if given == current {
+            Ok(())
+        } else {
+            Err(SessionError::StaleQuestion {
+                expected: current,
+                given,
+            })
+        }
+    }
+
+    /// Applies `answer` once per idempotency `key`: a retry with a key
+    /// seen before gets the first attempt's outcome instead. The last
+    /// [`IDEMPOTENCY_KEYS`] keys are remembered.
+    pub(crate) fn once(
+        &mut self,
+        key: Option<&str>,
+        answer: impl FnOnce(&mut Self) -> Result<(), SessionError>,
+    ) -> Result<(), SessionError> {
+        let Some(key) = key else {
+            return answer(self);
+        };
+        if let Some(outcome) = self.outcomes.get(key) {
+            return outcome.clone();
+        }
+        let outcome = answer(self);
+        if self.outcome_keys.len() == IDEMPOTENCY_KEYS
+            && let Some(oldest) = self.outcome_keys.pop_front()
+        {
+            self.outcomes.remove(&oldest);
+        }
+        self.outcome_keys.push_back(key.to_string());
+        self.outcomes.insert(key.to_string(), outcome.clone());
+        outcome
+    }
+
+    /// Records that `a` is (or is not) better than `b` and notifies
+    /// subscribers.
+    ///
+    /// Any of [`Session::open_questions`] is accepted, not only
+    /// [`Session::question`], so several devices can answer in parallel. In
+    /// a voting session this overrides the votes on the question, which is
+    /// up to the owner, see [`Session::authorize_answer`].
+    pub(crate) fn answer(
+        &mut self,
+        a: usize,
+        b: usize,
+        a_is_better: bool,
+    ) -> Result<(), SessionError> {
+        if self.finalized {
+            return Err(SessionError::Finalized);
+        }
+        self.stepper
+            .answer_question(a, b, a_is_better)
+            .ok_or(SessionError::NotAsked { a, b })?;
+        let key = (a.min(b), a.max(b));
+        let answered_at = now_ms();
+        self.answered_at_ms.insert(key, answered_at);
+        #[cfg(feature = "tracing")]
+        tracing::info!(
+            a,
+            b,
+            a_is_better,
+            latency_ms = self
+                .question_ids
+                .get(&key)
+                .map(|&(_, asked_at)| answered_at.saturating_sub(asked_at)),
+            comparisons = self.stepper.comparisons_made(),
+            "question answered"
+        );
+        if let Some(voting) = &mut self.voting {
+            voting.close(a, b);
+        }
+        self.revision += 1;
+        self.refresh();
+        self.notify();
+        Ok(())
+    }
+
+    /// Logs that the language model timed out on `a` vs `b` and what was
+    /// done instead, and notifies subscribers.
+    #[cfg(feature = "llm-judge")]
+    pub(crate) fn record_timeout(&mut self, a: usize, b: usize, then: crate::llm::OnTimeout) {
+        self.timeouts.push(crate::llm::TimedOut {
+            a,
+            b,
+            then,
+            at_ms: now_ms(),
+        });
+        self.notify();
+    }
+
+    #[cfg(feature = "llm-judge")]
+    pub(crate) fn timeouts(&self) -> &[crate::llm::TimedOut] {
+        &self.timeouts
+    }
+
+    /// Records `participant`'s vote on the open question `a` vs `b`, sent
+    /// with `token`, and answers it once the votes decide, see
+    /// [`crate::vote`]. A participant token is bound to the name it first
+    /// votes as, and cannot vote as anyone else.
+    pub(crate) fn vote(
+        &mut self,
+        token: &str,
+        participant: &str,
+        a: usize,
+        b: usize,
+        a_is_better: bool,
+    ) -> Result<(), SessionError> {
+        if self.voting.is_none() {
+            return Err(SessionError::NotVoting);
+        }
+        if self.finalized {
+            return Err(SessionError::Finalized);
+        }
+        if !self.open.contains(&(a, b)) && !self.open.contains(&(b, a)) {
+            return Err(SessionError::NotAsked { a, b });
+        }
+        if !self.access.may_vote_as(token, participant) {
+            return Err(SessionError::WrongVoter(participant.to_string()));
+        }
+        if self.access.bind(token, participant) {
+            self.revision += 1;
+        }
+        let voting = self.voting.as_mut().ok_or(SessionError::NotVoting)?;
+        if let Some(a_won) = voting.vote(participant, a, b, a_is_better) {
+            return self.answer(a, b, a_won);
+        }
+        self.notify();
+        Ok(())
+    }
+
+    /// The weighted votes on every undecided question; empty unless the
+    /// session takes votes.
+    pub(crate) fn tallies(&self) -> Vec<Tally> {
+        self.voting
+            .as_ref()
+            .map(Voting::tallies)
+            .unwrap_or_default()
+    }
+
+    /// Every vote by participant, in the order they were cast.
+    pub(crate) fn votes(&self) -> Result<&BTreeMap<String, Vec<Vote>>, SessionError> {
+        self.voting
+            .as_ref()
+            .map(Voting::log)
+            .ok_or(SessionError::NotVoting)
+    }
+
+    /// Ends the session now, ranking the items as far as the answers tell
+    /// them apart, see [`Stepper::finalize_partial`]. Does nothing to a
+    /// complete ranking.
+    pub(crate) fn finalize(&mut self) {
+        if self.order.is_some() {
+            return;
+        }
+        self.order = Some(self.stepper.finalize_partial());
+        self.finalized = true;
+        self.step = Step::Done;
+        self.open.clear();
+        self.question_ids.clear();
+        self.revision += 1;
+        self.notify();
+    }
+
+    #[cfg(feature = "webhooks")]
+    pub(crate) fn is_finalized(&self) -> bool {
+        self.finalized
+    }
+
+    /// Sets the URL to send the complete ranking to, or removes it. A
+    /// ranking already complete is sent again.
+    #[cfg(feature = "webhooks")]
+    pub(crate) fn set_webhook(&mut self, url: Option<String>) {
+        self.webhook = url;
+        self.webhook_sent = false;
+        self.revision += 1;
+    }
+
+    /// Sets the URL a stored session had. A ranking already complete was
+    /// sent before it was stored, so it is not sent again.
+    #[cfg(feature = "webhooks")]
+    fn restore_webhook(&mut self, url: Option<String>) {
+        self.webhook = url;
+        self.webhook_sent = self.order.is_some();
+    }
+
+    /// The URL to send the complete ranking to, as saved.
+    #[cfg_attr(not(feature = "webhooks"), allow(clippy::unused_self))]
+    fn webhook(&self) -> Option<&str> {
+        #[cfg(feature = "webhooks")]
+        return self.webhook.as_deref();
+        #[cfg(not(feature = "webhooks"))]
+        None
+    }
+
+    /// The webhook URL, once per complete ranking: after the ranking is
+    /// complete and until it is taken.
+    #[cfg(feature = "webhooks")]
+    pub(crate) fn take_webhook(&mut self) -> Option<String> {
+        if self.webhook_sent || self.order.is_none() {
+            return None;
+        }
+        let url = self.webhook.clone()?;
+        self.webhook_sent = true;
+        Some(url)
+    }
+
+    /// Restarts the session over new `items`. What the answers and known
+    /// pairs decided between items that stay, matched by label, carries
+    /// over as known pairs; votes on undecided questions are dropped.
+    pub(crate) fn set_items(&mut self, items: Vec<String>) {
+        let index: HashMap<&str, usize> = items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| (item.as_str(), i))
+            .collect();
+        let answered = self
+            .stepper
+            .history()
+            .iter()
+            .map(|&(a, b, a_is_better)| if a_is_better { (a, b) } else { (b, a) });
+        let known: Vec<(usize, usize)> = self
+            .known
+            .iter()
+            .copied()
+            .chain(answered)
+            .filter_map(|(better, worse)| {
+                let better = *index.get(self.items[better].as_str())?;
+                let worse = *index.get(self.items[worse].as_str())?;
+                Some((better, worse))
+            })
+            .collect();
+        let voting = self.voting.as_ref().map(Voting::rules);
+        let mut fresh = Self::new(items, &known, voting);
+        std::mem::swap(&mut fresh.access, &mut self.access);
+        std::mem::swap(&mut fresh.outcomes, &mut self.outcomes);
+        std::mem::swap(&mut fresh.outcome_keys, &mut self.outcome_keys);
+        #[cfg(feature = "webhooks")]
+        std::mem::swap(&mut fresh.webhook, &mut self.webhook);
+        fresh.updates = self.updates.clone();
+        fresh.revision = self.revision + 1;
+        // Ids keep counting, so answers meant for the old items are stale.
+        fresh.next_question_id = self.next_question_id;
+        fresh.question_ids.clear();
+        fresh.number_questions();
+        *self = fresh;
+        self.notify();
+    }
+
+    /// The role `token` has, if it is at least `needed`.
+    pub(crate) fn authorize(
+        &self,
+        token: Option<&str>,
+        needed: Role,
+    ) -> Result<Role, SessionError> {
+        let role = token
+            .and_then(|t| self.access.role(t))
+            .ok_or(SessionError::Unauthorized)?;
+        if role < needed {
+            return Err(SessionError::Forbidden(needed));
+        }
+        Ok(role)
+    }
+
+    /// The role `token` has, if it may answer directly: any token in a
+    /// plain session, only the owner's when the session is decided by vote.
+    pub(crate) fn authorize_answer(&self, token: Option<&str>) -> Result<Role, SessionError> {
+        let needed = if self.voting.is_some() {
+            Role::Owner
+        } else {
+            Role::Participant
+        };
+        self.authorize(token, needed)
+    }
+
+    pub(crate) fn access(&self) -> &Access {
+        &self.access
+    }
+
+    /// Issues another participant token and returns it.
+    pub(crate) fn add_participant(&mut self) -> String {
+        self.revision += 1;
+        self.access.add_participant()
+    }
+
+    /// Starts a session from a saved one, replaying its answers. Answers
+    /// beyond the end of the ranking are rejected, see
+    /// [`validate_session`](rankfast_session::validate_session).
+    pub(crate) fn restore(saved: rankfast_session::Session) -> Result<Self, SessionError> {
+        check_engine(saved.engine)?;
+        if let Err(e) = saved.validate() {
+            return Err(SessionError::Replay(e));
+        }
+        let mut session = Self::new(saved.items, &saved.known, None);
+        for answer in saved.answers {
+            let Some((a, b)) = session.question() else {
+                break;
+            };
+            session.answer(a, b, answer.a_is_better(a, b))?;
+        }
+        session.answered_at_ms.clear();
+        Ok(session)
+    }
+
+    /// The session in the shared format, with the answers in the order the
+    /// stepper applied them.
+    pub(crate) fn saved(&self) -> rankfast_session::Session {
+        let answers = self
+            .stepper
+            .history()
+            .iter()
+            .map(|&(_, _, a_is_better)| if a_is_better { Answer::A } else { Answer::B })
+            .collect();
+        rankfast_session::Session {
+            known: self.known.clone(),
+            ..rankfast_session::Session::new(self.items.clone(), answers, Mode::Exact)
+        }
+    }
+
+    /// Every answer as pairwise preference data, see
+    /// [`rankfast_session::dataset`].
+    pub(crate) fn preferences(&self) -> Vec<Preference> {
+        // Answers given out of turn are applied later, so the times are put
+        // in the stepper's order. Untimed restored answers all come first.
+        let timestamps: Vec<u64> = self
+            .stepper
+            .history()
+            .iter()
+            .filter_map(|&(a, b, _)| self.answered_at_ms.get(&(a.min(b), a.max(b))).copied())
+            .collect();
+        // The stepper only accepts answers to the questions it asks, so the
+        // saved answers always replay.
+        rankfast_session::preferences(&self.saved(), &timestamps).unwrap_or_default()
+    }
+
+    /// A receiver that fires after every accepted answer.
+    pub(crate) fn subscribe(&self) -> broadcast::Receiver<()> {
+        self.updates.subscribe()
+    }
+
+    pub(crate) fn comparisons(&self) -> usize {
+        self.stepper.comparisons_made()
+    }
+
+    pub(crate) fn estimate(&self) -> usize {
+        estimate_turns(self.items.len())
+    }
+
+    /// Item labels, best first.
+    pub(crate) fn ranking(&self) -> Result<Vec<String>, SessionError> {
+        let order = self.order.as_ref().ok_or(SessionError::Unfinished)?;
+        Ok(order.iter().map(|&i| self.items[i].clone()).collect())
+    }
+
+    fn notify(&self) {
+        // No receivers just means nobody is watching live.
+        let _ = self.updates.send(());
+    }
+
+    fn refresh(&mut self) {
+        self.step = self.stepper.step();
+        self.open = self.stepper.available_questions();
+        if self.step == Step::Done && self.order.is_none() {
+            self.order = self.stepper.take_order();
+        }
+        self.number_questions();
+    }
+
+    /// Gives every newly open question the next id, and forgets the ids of
+    /// answered ones.
+    fn number_questions(&mut self) {
+        let mut ids = HashMap::with_capacity(self.open.len());
+        for &(a, b) in &self.open {
+            let key = (a.min(b), a.max(b));
+            let question = if let Some(&question) = self.question_ids.get(&key) {
+                question
+            } else {
+                let id = self.next_question_id;
+                self.next_question_id += 1;
+                #[cfg(feature = "tracing")]
+                tracing::debug!(question = id, a, b, "question asked");
+                (id, now_ms())
+            };
+            ids.insert(key, question);
+        }
+        self.question_ids = ids;
+    }
+}
+
+/// Checks that sessions can run in `mode`, as picked by name, see
+/// [`Mode::from_name`]. Only the exact sort can: the others cannot answer
+/// their questions in parallel.
+pub(crate) fn check_engine(mode: Mode) -> Result<(), SessionError> {
+    if mode == Mode::Exact {
+        Ok(())
+    } else {
+        Err(SessionError::UnsupportedEngine)
+    }
+}
+
+/// The current time in Unix milliseconds, 0 if the clock is before 1970.
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| {
+            u64::try_from(since.as_millis()).unwrap_or(u64::MAX)
+        })
+}
+
+/// In-memory map of live sessions, saved to a [`Storage`] if it has one.
+#[derive(Default)]
+pub(crate) struct SessionStore {
+    sessions: Mutex<HashMap<u64, Session>>,
+    next_id: AtomicU64,
+    storage: Option<Box<dyn Storage>>,
+}
+
+impl SessionStore {
+    /// A store holding every session in `storage`, and saving to it.
+    /// Sessions that no longer replay are skipped with a warning. Sessions
+    /// stored without tokens get new ones, printed so their owner can be
+    /// told.
+    #[cfg_attr(not(feature = "sqlite"), allow(dead_code))] // only a database opens
+    pub(crate) fn open(storage: Box<dyn Storage>) -> Result<Self, StorageError> {
+        let mut sessions = HashMap::new();
+        for stored in storage.load()? {
+            let id = stored.id;
+            let mut session = match Session::restore(stored.session) {
+                Ok(session) => session,
+                Err(e) => {
+                    eprintln!("Skipped stored session {id}: {e}");
+                    continue;
+                }
+            };
+            if stored.ranked {
+                session.finalize();
+            }
+            #[cfg(feature = "webhooks")]
+            session.restore_webhook(stored.webhook);
+            if let Some(access) = stored.access {
+                session.access = access;
+            } else {
+                eprintln!(
+                    "Stored session {id} had no tokens; its owner token is now {}",
+                    session.access.owner
+                );
+                let ranking = session.ranking().ok();
+                storage.save(
+                    id,
+                    &session.saved(),
+                    ranking.as_deref(),
+                    &session.access,
+                    session.webhook(),
+                )?;
+            }
+            sessions.insert(id, session);
+        }
+        let next_id = sessions.keys().max().map_or(0, |&id| id + 1);
+        Ok(Self {
+            sessions: Mutex::new(sessions),
+            next_id: AtomicU64::new(next_id),
+            storage: Some(storage),
+        })
+    }
+
+    /// Starts a session over `items` that skips the questions the `known`
+    /// pairs decide, taking votes if `voting` is given, and returns its id.
+    pub(crate) fn create(
+        &self,
+        items: Vec<String>,
+        known: &[(usize, usize)],
+        voting: Option<VotingRules>,
+    ) -> Result<u64, SessionError> {
+        self.insert(Session::new(items, known, voting))
+    }
+
+    /// Adds `session` to the store and returns its id.
+    pub(crate) fn insert(&self, session: Session) -> Result<u64, SessionError> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.save(id, &session)?;
+        self.lock().insert(id, session);
+        Ok(id)
+    }
+
+    /// Runs `f` on session `id`, saving the session if `f` changed it and
+    /// calling its webhook if `f` completed the ranking.
+    pub(crate) fn with<R>(
+        &self,
+        id: u64,
+        f: impl FnOnce(&mut Session) -> Result<R, SessionError>,
+    ) -> Result<R, SessionError> {
+        let mut sessions = self.lock();
+        let session = sessions.get_mut(&id).ok_or(SessionError::NotFound)?;
+        let revision = session.revision;
+        let result = f(session);
+        if session.revision != revision {
+            self.save(id, session)?;
+        }
+        #[cfg(feature = "webhooks")]
+        if let Some(url) = session.take_webhook()
+            && let Some(completion) = crate::webhook::Completion::of(id, session)
+        {
+            crate::webhook::send(url, completion);
+        }
+        result
+    }
+
+    /// Deletes session `id` if `token` is its owner's. Live connections to
+    /// it close.
+    pub(crate) fn remove(&self, id: u64, token: Option<&str>) -> Result<(), SessionError> {
+        let mut sessions = self.lock();
+        let session = sessions.get(&id).ok_or(SessionError::NotFound)?;
+        session.authorize(token, Role::Owner)?;
+        if let Some(storage) = &self.storage {
+            storage.delete(id).map_err(SessionError::Storage)?;
+        }
+        sessions.remove(&id);
+        Ok(())
+    }
+
+    /// `f` of every session it picks, by id.
+    pub(crate) fn list<R>(&self, mut f: impl FnMut(u64, &Session) -> Option<R>) -> Vec<R> {
+        let sessions = self.lock();
+        let mut ids: Vec<u64> = sessions.keys().copied().collect();
+        ids.sort_unstable();
+        ids.into_iter()
+            .filter_map(|id| f(id, &sessions[&id]))
+            .collect()
+    }
+
+    fn save(&self, id: u64, session: &Session) -> Result<(), SessionError> {
+        let Some(storage) = &self.storage else {
+            return Ok(());
+        };
+        storage
+            .save(
+                id,
+                &session.saved(),
+                session.ranking().ok().as_deref(),
+                &session.access,
+                session.webhook(),
+            )
+            .map_err(SessionError::Storage)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<u64, Session>> {
+        // A panic mid-update cannot leave a session half-written: every
+        // mutation goes through the stepper, which validates before applying.
+        self.sessions
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{Session, SessionError, SessionStore, check_engine};
+    use crate::access::Role;
+    use crate::vote::VotingRules;
+    use rankfast_session::{Answer, Mode};
+
+    fn items(names: &[&str]) -> Vec<String> {
+        names.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn ranks_through_the_store() {
+        let store = SessionStore::default();
+        let id = store.create(items(&["c", "a", "b"]), &[], None).unwrap();
+        loop {
+            let question = store.with(id, |s| Ok(s.question())).unwrap();
+            let Some((a, b)) = question else { break };
+            store
+                .with(id, |s| {
+                    let better = s.items()[a] < s.items()[b];
+                    s.answer(a, b, better)
+                })
+                .unwrap();
+        }
+        let ranking = store.with(id, |s| s.ranking()).unwrap();
+        assert_eq!(ranking, items(&["a", "b", "c"]));
+    }
+
+    #[test]
+    fn rejects_unknown_sessions_and_stale_answers() {
+        let store = SessionStore::default();
+        assert_eq!(store.with(7, |s| s.ranking()), Err(SessionError::NotFound));
+
+        let id = store.create(items(&["x", "y", "z"]), &[], None).unwrap();
+        assert_eq!(
+            store.with(id, |s| s.ranking()),
+            Err(SessionError::Unfinished)
+        );
+        assert_eq!(
+            store.with(id, |s| s.answer(0, 2, true)),
+            Err(SessionError::NotAsked { a: 0, b: 2 })
+        );
+    }
+
+    #[test]
+    fn known_pairs_are_skipped_and_saved() {
+        let store = SessionStore::default();
+        let id = store
+            .create(items(&["p", "q", "r"]), &[(1, 0), (0, 2)], None)
+            .unwrap();
+        assert_eq!(store.with(id, |s| Ok(s.question())).unwrap(), None);
+        assert_eq!(
+            store.with(id, |s| s.ranking()).unwrap(),
+            items(&["q", "p", "r"])
+        );
+        let saved = store.with(id, |s| Ok(s.saved())).unwrap();
+        assert_eq!(saved.known, vec![(1, 0), (0, 2)]);
+        assert!(Session::restore(saved).unwrap().question().is_none());
+    }
+
+    #[test]
+    fn votes_decide_once_the_quorum_agrees() {
+        let store = SessionStore::default();
+        let rules = VotingRules {
+            quorum: 2,
+            weights: HashMap::new(),
+        };
+        let id = store
+            .create(items(&["p", "q", "r"]), &[], Some(rules))
+            .unwrap();
+        let (ann, bob) = store
+            .with(id, |s| {
+                Ok((s.access().participants[0].clone(), s.add_participant()))
+            })
+            .unwrap();
+        let mut updates = store.with(id, |s| Ok(s.subscribe())).unwrap();
+        store
+            .with(id, |s| s.vote(&ann, "ann", 0, 1, false))
+            .unwrap();
+        assert!(updates.try_recv().is_ok());
+        assert_eq!(store.with(id, |s| Ok(s.comparisons())).unwrap(), 0);
+        assert_eq!(store.with(id, |s| Ok(s.tallies())).unwrap().len(), 1);
+
+        store.with(id, |s| s.vote(&bob, "bob", 1, 0, true)).unwrap();
+        assert_eq!(store.with(id, |s| Ok(s.comparisons())).unwrap(), 1);
+        let saved = store.with(id, |s| Ok(s.saved())).unwrap();
+        assert_eq!(saved.answers, vec![Answer::B]);
+        assert_eq!(
+            store.with(id, |s| s.vote(&ann, "ann", 0, 1, true)),
+            Err(SessionError::NotAsked { a: 0, b: 1 })
+        );
+        let votes = store.with(id, |s| Ok(s.votes()?.clone())).unwrap();
+        assert_eq!(votes.keys().collect::<Vec<_>>(), ["ann", "bob"]);
+
+        let plain = store.create(items(&["p", "q"]), &[], None).unwrap();
+        assert_eq!(
+            store.with(plain, |s| s.vote(&ann, "ann", 0, 1, true)),
+            Err(SessionError::NotVoting)
+        );
+    }
+
+    #[test]
+    fn participants_vote_once_under_their_own_name() {
+        let store = SessionStore::default();
+        let rules = VotingRules {
+            quorum: 2,
+            weights: HashMap::new(),
+        };
+        let id = store
+            .create(items(&["p", "q", "r"]), &[], Some(rules))
+            .unwrap();
+        let access = store.with(id, |s| Ok(s.access().clone())).unwrap();
+        let participant = access.participants[0].as_str();
+        let vote = |token, name| store.with(id, |s| s.vote(token, name, 0, 1, true));
+
+        // Direct answers would skip the votes.
+        assert_eq!(
+            store.with(id, |s| s.authorize_answer(Some(participant))),
+            Err(SessionError::Forbidden(Role::Owner))
+        );
+        assert_eq!(
+            store.with(id, |s| s.authorize_answer(Some(&access.owner))),
+            Ok(Role::Owner)
+        );
+
+        // One token cannot make up the quorum under several names.
+        assert_eq!(vote(participant, "ann"), Ok(()));
+        assert_eq!(
+            vote(participant, "bob"),
+            Err(SessionError::WrongVoter("bob".to_string()))
+        );
+        let other = store.with(id, |s| Ok(s.add_participant())).unwrap();
+        assert_eq!(
+            vote(&other, "ann"),
+            Err(SessionError::WrongVoter("ann".to_string()))
+        );
+        assert_eq!(store.with(id, |s| Ok(s.comparisons())).unwrap(), 0);
+        assert_eq!(vote(&other, "bob"), Ok(()));
+        assert_eq!(store.with(id, |s| Ok(s.comparisons())).unwrap(), 1);
+
+        let plain = store.create(items(&["p", "q"]), &[], None).unwrap();
+        let token = store
+            .with(plain, |s| Ok(s.access().participants[0].clone()))
+            .unwrap();
+        assert_eq!(
+            store.with(plain, |s| s.authorize_answer(Some(&token))),
+            Ok(Role::Participant)
+        );
+    }
+
+    #[test]
+    fn owners_edit_finalize_and_delete() {
+        let store = SessionStore::default();
+        let id = store.create(items(&["p", "q", "r"]), &[], None).unwrap();
+        let access = store.with(id, |s| Ok(s.access().clone())).unwrap();
+        let (owner, participant) = (
+            Some(access.owner.as_str()),
+            Some(access.participants[0].as_str()),
+        );
+        let authorize = |token, role| store.with(id, |s| s.authorize(token, role));
+        assert_eq!(
+            authorize(participant, Role::Participant),
+            Ok(Role::Participant)
+        );
+        assert_eq!(authorize(owner, Role::Participant), Ok(Role::Owner));
+        assert_eq!(
+            authorize(participant, Role::Owner),
+            Err(SessionError::Forbidden(Role::Owner))
+        );
+        assert_eq!(
+            authorize(None, Role::Participant),
+            Err(SessionError::Unauthorized)
+        );
+
+        // p beats q, which carries over to the new items.
+        store.with(id, |s| s.answer(0, 1, true)).unwrap();
+        store
+            .with(id, |s| {
+                s.set_items(items(&["s", "q", "p"]));
+                Ok(())
+            })
+            .unwrap();
+        let saved = store.with(id, |s| Ok(s.saved())).unwrap();
+        assert_eq!((saved.known, saved.answers.len()), (vec![(2, 1)], 0));
+        assert_eq!(authorize(owner, Role::Owner), Ok(Role::Owner));
+
+        store
+            .with(id, |s| {
+                s.finalize();
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(store.with(id, |s| Ok(s.question())).unwrap(), None);
+        assert_eq!(
+            store.with(id, |s| s.answer(0, 1, true)),
+            Err(SessionError::Finalized)
+        );
+        assert_eq!(store.with(id, |s| s.ranking()).unwrap().len(), 3);
+
+        assert_eq!(
+            store.remove(id, participant),
+            Err(SessionError::Forbidden(Role::Owner))
+        );
+        store.remove(id, owner).unwrap();
+        assert_eq!(store.remove(id, owner), Err(SessionError::NotFound));
+    }
+
+    #[test]
+    fn retries_and_stale_answers_change_nothing() {
+        let store = SessionStore::default();
+        let id = store.create(items(&["p", "q", "r"]), &[], None).unwrap();
+        let asked = store.with(id, |s| Ok(s.question_id(0, 1))).unwrap();
+        let answer = |key, question_id| {
+            store.with(id, |s| {
+                s.once(key, |s| {
+                    s.expect_question(0, 1, question_id)?;
+                    s.answer(0, 1, true)
+                })
+            })
+        };
+        assert_eq!(answer(Some("click"), asked), Ok(()));
+        assert_eq!(answer(Some("click"), asked), Ok(()));
+        assert_eq!(
+            answer(Some("again"), asked),
+            Err(SessionError::NotAsked { a: 0, b: 1 })
+        );
+        assert_eq!(store.with(id, |s| Ok(s.comparisons())).unwrap(), 1);
+
+        // The same pair over new items is a new question.
+        store
+            .with(id, |s| {
+                s.set_items(items(&["x", "y"]));
+                Ok(())
+            })
+            .unwrap();
+        let renewed = store.with(id, |s| Ok(s.question_id(0, 1))).unwrap();
+        assert!(renewed > asked);
+        assert_eq!(
+            answer(None, asked),
+            Err(SessionError::StaleQuestion {
+                expected: renewed.unwrap(),
+                given: asked.unwrap(),
+            })
+        );
+        assert_eq!(answer(None, renewed), Ok(()));
+    }
+
+    #[test]
+    fn answers_notify_subscribers() {
+        let store = SessionStore::default();
+        let id = store
+            .create(items(&["p", "q", "r", "s"]), &[], None)
+            .unwrap();
+        let mut updates = store.with(id, |s| Ok(s.subscribe())).unwrap();
+        assert!(updates.try_recv().is_err());
+
+        let open = store.with(id, |s| Ok(s.open_questions().to_vec())).unwrap();
+        assert_eq!(open, vec![(0, 1), (2, 3)]);
+        store.with(id, |s| s.answer(2, 3, true)).unwrap();
+        assert!(updates.try_recv().is_ok());
+        let open = store.with(id, |s| Ok(s.open_questions().to_vec())).unwrap();
+        assert_eq!(open, vec![(0, 1)]);
+    }
+
+    #[test]
+    fn saved_sessions_restore_to_the_same_question() {
+        let store = SessionStore::default();
+        let id = store
+            .create(items(&["p", "q", "r", "s"]), &[], None)
+            .unwrap();
+        store.with(id, |s| s.answer(2, 3, false)).unwrap();
+        store.with(id, |s| s.answer(0, 1, true)).unwrap();
+        let saved = store.with(id, |s| Ok(s.saved())).unwrap();
+        assert_eq!(saved.answers, vec![Answer::A, Answer::B]);
+
+        let log = store.with(id, |s| Ok(s.preferences())).unwrap();
+        assert_eq!(log.len(), 2);
+        assert_eq!((log[0].item_a.as_str(), log[0].item_b.as_str()), ("p", "q"));
+        assert!(log.iter().all(|p| p.timestamp.is_some()));
+
+        let restored = Session::restore(saved.clone()).unwrap();
+        assert!(restored.preferences().iter().all(|p| p.timestamp.is_none()));
+        let original = store.with(id, |s| Ok(s.question())).unwrap();
+        assert_eq!(restored.question(), original);
+        assert_eq!(restored.saved(), saved);
+
+        let mut overlong = saved.clone();
+        overlong.items.truncate(2);
+        assert!(matches!(
+            Session::restore(overlong).err(),
+            Some(SessionError::Replay(
+                rankfast_session::SessionError::TooManyAnswers { used: 1, given: 2 }
+            ))
+        ));
+
+        let mut approximate = saved;
+        approximate.engine = Mode::Approximate;
+        assert_eq!(
+            Session::restore(approximate).err(),
+            Some(SessionError::UnsupportedEngine)
+        );
+    }
+
+    #[test]
+    fn only_the_exact_engine_is_served() {
+        assert_eq!(check_engine(Mode::Exact), Ok(()));
+        for name in ["binary_insertion", "tournament", "approximate", "top_k:3"] {
+            let mode = Mode::from_name(name).unwrap();
+            assert_eq!(check_engine(mode), Err(SessionError::UnsupportedEngine));
+        }
+    }
+}
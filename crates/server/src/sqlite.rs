@@ -0,0 +1,230 @@
+//! SQLite [`Storage`].
+//!
+//! One table holds everything:
+//!
+//! ```sql
+//! CREATE TABLE sessions (
+//!     id INTEGER PRIMARY KEY,
+//!     document TEXT NOT NULL,  -- the saved session: items, answers, engine
+//!     ranking TEXT,            -- JSON list of items, best first, once done
+//!     updated_ms INTEGER NOT NULL,
+//!     access TEXT,             -- JSON owner and participant tokens
+//!     webhook TEXT             -- URL the complete ranking is sent to
+//! )
+//! ```
+//!
+//! Items and answers stay in the session document rather than in tables of
+//! their own, so the database reads back exactly what the other frontends
+//! save and load.
+
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rankfast_session::Session;
+use rusqlite::{Connection, params};
+
+use crate::access::Access;
+use crate::storage::{Storage, StorageError, Stored};
+
+const SCHEMA: &str = "CREATE TABLE IF NOT EXISTS sessions (
+    id INTEGER PRIMARY KEY,
+    document TEXT NOT NULL,
+    ranking TEXT,
+    updated_ms INTEGER NOT NULL,
+    access TEXT,
+    webhook TEXT
+)";
+
+/// Sessions in a SQLite database file.
+pub(crate) struct Sqlite {
+    // A connection is not `Sync`; requests are short, so they take turns.
+    connection: Mutex<Connection>,
+}
+
+impl Sqlite {
+    /// Opens the database at `path`, creating it and its table if needed.
+    pub(crate) fn open(path: impl AsRef<Path>) -> Result<Self, StorageError> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    /// A database that lives in memory, for tests.
+    #[cfg(test)]
+    pub(crate) fn in_memory() -> Result<Self, StorageError> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(connection: Connection) -> Result<Self, StorageError> {
+        connection.execute(SCHEMA, [])?;
+        // Tables created before tokens or webhooks lack their columns;
+        // adding them again fails harmlessly.
+        let _ = connection.execute("ALTER TABLE sessions ADD COLUMN access TEXT", []);
+        let _ = connection.execute("ALTER TABLE sessions ADD COLUMN webhook TEXT", []);
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Connection> {
+        // Every write is a single statement, so a poisoned lock still
+        // guards a consistent database.
+        self.connection
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+impl Storage for Sqlite {
+    fn load(&self) -> Result<Vec<Stored>, StorageError> {
+        let connection = self.lock();
+        let mut statement = connection.prepare(
+            "SELECT id, document, ranking IS NOT NULL, access, webhook FROM sessions ORDER BY id",
+        )?;
+        let rows = statement.query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, bool>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, Option<String>>(4)?,
+            ))
+        })?;
+        let mut sessions = Vec::new();
+        for row in rows {
+            let (id, document, ranked, access, webhook) = row?;
+            let id = u64::try_from(id).map_err(|_| StorageError(format!("bad id {id}")))?;
+            let session = Session::from_json(&document)
+                .map_err(|e| StorageError(format!("session {id}: {e}")))?;
+            let access = access
+                .map(|a| serde_json::from_str(&a))
+                .transpose()
+                .map_err(|e| StorageError(format!("session {id} tokens: {e}")))?;
+            sessions.push(Stored {
+                id,
+                session,
+                access,
+                ranked,
+                webhook,
+            });
+        }
+        Ok(sessions)
+    }
+
+    fn save(
+        &self,
+        id: u64,
+        session: &Session,
+        ranking: Option<&[String]>,
+        access: &Access,
+        webhook: Option<&str>,
+    ) -> Result<(), StorageError> {
+        let id = to_sql_id(id)?;
+        // Lists of strings always serialize.
+        let ranking = ranking.map(|r| serde_json::to_string(r).unwrap_or_default());
+        let access = serde_json::to_string(access).unwrap_or_default();
+        self.lock().execute(
+            "INSERT INTO sessions (id, document, ranking, updated_ms, access, webhook)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                 ON CONFLICT (id) DO UPDATE SET
+                     document = excluded.document,
+                     ranking = excluded.ranking,
+                     updated_ms = excluded.updated_ms,
+                     access = excluded.access,
+                     webhook = excluded.webhook",
+            params![id, session.to_json(), ranking, now_ms(), access, webhook],
+        )?;
+        Ok(())
+    }
+
+    fn delete(&self, id: u64) -> Result<(), StorageError> {
+        self.lock().execute(
+            "DELETE FROM sessions WHERE id = ?1",
+            params![to_sql_id(id)?],
+        )?;
+        Ok(())
+    }
+}
+
+fn to_sql_id(id: u64) -> Result<i64, StorageError> {
+    i64::try_from(id).map_err(|_| StorageError(format!("bad id {id}")))
+}
+
+impl From<rusqlite::Error> for StorageError {
+    fn from(e: rusqlite::Error) -> Self {
+        Self(e.to_string())
+    }
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| {
+            i64::try_from(since.as_millis()).unwrap_or(i64::MAX)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Sqlite;
+    use crate::session::SessionStore;
+    use crate::storage::Storage;
+
+    #[test]
+    fn sessions_survive_reopening() {
+        let db = std::sync::Arc::new(Sqlite::in_memory().unwrap());
+        let store = SessionStore::open(Box::new(db.clone())).unwrap();
+        let items = ["b", "a"].map(String::from).to_vec();
+        let id = store.create(items, &[], None).unwrap();
+        store.with(id, |s| s.answer(0, 1, false)).unwrap();
+        let access = store.with(id, |s| Ok(s.access().clone())).unwrap();
+        #[cfg(feature = "webhooks")]
+        store
+            .with(id, |s| {
+                s.set_webhook(Some("https://example.com".into()));
+                Ok(())
+            })
+            .unwrap();
+        let unfinished = store
+            .create(["x", "y", "z"].map(String::from).to_vec(), &[], None)
+            .unwrap();
+        store
+            .with(unfinished, |s| {
+                s.finalize();
+                Ok(())
+            })
+            .unwrap();
+
+        let reopened = SessionStore::open(Box::new(db.clone())).unwrap();
+        let ranking = reopened.with(id, |s| s.ranking()).unwrap();
+        assert_eq!(ranking, ["a", "b"].map(String::from).to_vec());
+        assert_eq!(
+            reopened.with(id, |s| Ok(s.access().clone())).unwrap(),
+            access
+        );
+        assert_eq!(
+            reopened.with(unfinished, |s| Ok(s.question())).unwrap(),
+            None
+        );
+        #[cfg(feature = "webhooks")]
+        {
+            let stored = db.load().unwrap();
+            assert_eq!(stored[0].webhook.as_deref(), Some("https://example.com"));
+            // The ranking was complete when stored, so it was already sent.
+            assert_eq!(reopened.with(id, |s| Ok(s.take_webhook())).unwrap(), None);
+        }
+
+        reopened
+            .remove(unfinished, Some(&access.owner))
+            .unwrap_err();
+        let owner = reopened
+            .with(unfinished, |s| Ok(s.access().owner.clone()))
+            .unwrap();
+        reopened.remove(unfinished, Some(&owner)).unwrap();
+        assert_eq!(db.load().unwrap().len(), 1);
+        // New sessions do not reuse stored ids.
+        assert_eq!(
+            reopened.create(Vec::new(), &[], None).unwrap(),
+            unfinished + 1
+        );
+    }
+}
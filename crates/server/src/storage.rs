@@ -0,0 +1,84 @@
+//! Where sessions outlive the process.
+//!
+//! A [`Storage`] keeps each session's saved document (the shared JSON
+//! format of [`rankfast_session::json`]), its final ranking once there is
+//! one, its access tokens, which stay out of the document so exports do
+//! not leak them, and its webhook URL, see [`crate::webhook`]. [`SessionStore::open`](crate::session::SessionStore::open)
+//! restores every stored session on startup and the store saves a session
+//! again whenever it changes, so the HTTP API survives restarts.
+//! Answers given out of turn are saved once the stepper applies them.
+//!
+//! With the `sqlite` feature, setting `RANKFAST_DB` to a file path stores
+//! sessions there, see [`crate::sqlite`]; otherwise they live in memory.
+
+use std::fmt;
+
+use rankfast_session::Session;
+
+use crate::access::Access;
+
+/// Why a session could not be stored or loaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct StorageError(pub(crate) String);
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "storage failed: {}", self.0)
+    }
+}
+
+/// A session as stored.
+#[cfg_attr(not(feature = "sqlite"), allow(dead_code))] // only a database loads
+pub(crate) struct Stored {
+    pub(crate) id: u64,
+    pub(crate) session: Session,
+    /// `None` for sessions stored before they had tokens.
+    pub(crate) access: Option<Access>,
+    /// Whether a ranking was stored, which for a session whose answers do
+    /// not finish it means it was finalized early.
+    pub(crate) ranked: bool,
+    /// Where to send the complete ranking.
+    #[cfg_attr(not(feature = "webhooks"), allow(dead_code))] // only sent with webhooks
+    pub(crate) webhook: Option<String>,
+}
+
+/// A persistence backend for [`SessionStore`](crate::session::SessionStore).
+pub(crate) trait Storage: Send + Sync {
+    /// Every stored session.
+    #[cfg_attr(not(feature = "sqlite"), allow(dead_code))] // only a database loads
+    fn load(&self) -> Result<Vec<Stored>, StorageError>;
+
+    /// Inserts or replaces session `id`, with its `ranking` once finished.
+    fn save(
+        &self,
+        id: u64,
+        session: &Session,
+        ranking: Option<&[String]>,
+        access: &Access,
+        webhook: Option<&str>,
+    ) -> Result<(), StorageError>;
+
+    /// Forgets session `id`.
+    fn delete(&self, id: u64) -> Result<(), StorageError>;
+}
+
+impl<S: Storage + ?Sized> Storage for std::sync::Arc<S> {
+    fn load(&self) -> Result<Vec<Stored>, StorageError> {
+        (**self).load()
+    }
+
+    fn save(
+        &self,
+        id: u64,
+        session: &Session,
+        ranking: Option<&[String]>,
+        access: &Access,
+        webhook: Option<&str>,
+    ) -> Result<(), StorageError> {
+        (**self).save(id, session, ranking, access, webhook)
+    }
+
+    fn delete(&self, id: u64) -> Result<(), StorageError> {
+        (**self).delete(id)
+    }
+}
@@ -0,0 +1,203 @@
+//! Shared rankings decided by vote.
+//!
+//! A session created with a [`VotingRules`] takes votes from named
+//! participants instead of single answers. Each participant has one vote per
+//! question, which a later vote replaces. Once `quorum` participants voted
+//! on a question, the side with more weight wins and the answer goes to the
+//! stepper; a weighted tie waits for another vote. Participants weigh 1
+//! unless `weights` says otherwise. Every vote is kept in a per-participant
+//! log, including those on questions already decided. A participant token
+//! votes under one name only, see [`crate::access`].
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+/// How a voting session decides.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub(crate) struct VotingRules {
+    /// Votes needed on a question before it is decided.
+    pub(crate) quorum: usize,
+    /// Weight by participant; anyone else weighs 1.
+    #[serde(default)]
+    pub(crate) weights: HashMap<String, f64>,
+}
+
+/// One participant's vote.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct Vote {
+    pub(crate) a: usize,
+    pub(crate) b: usize,
+    pub(crate) a_is_better: bool,
+    /// Whether the vote went into a decision, rather than being replaced or
+    /// coming after the question was decided.
+    pub(crate) counted: bool,
+}
+
+/// Weighted votes on one open question.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct Tally {
+    pub(crate) a: usize,
+    pub(crate) b: usize,
+    pub(crate) for_a: f64,
+    pub(crate) for_b: f64,
+    pub(crate) voters: usize,
+}
+
+/// The votes of a session so far.
+#[derive(Debug, Clone)]
+pub(crate) struct Voting {
+    rules: VotingRules,
+    /// Open votes by question, keyed by the pair in ascending order: who
+    /// voted and where their vote sits in their log.
+    open: HashMap<(usize, usize), Vec<(String, usize)>>,
+    log: BTreeMap<String, Vec<Vote>>,
+}
+
+impl Voting {
+    pub(crate) fn new(rules: VotingRules) -> Self {
+        Self {
+            rules: VotingRules {
+                quorum: rules.quorum.max(1),
+                ..rules
+            },
+            open: HashMap::new(),
+            log: BTreeMap::new(),
+        }
+    }
+
+    /// Records `participant`'s vote on the open question `a` vs `b` and
+    /// returns whether `a` won, once the question is decided.
+    pub(crate) fn vote(
+        &mut self,
+        participant: &str,
+        a: usize,
+        b: usize,
+        a_is_better: bool,
+    ) -> Option<bool> {
+        let key = (a.min(b), a.max(b));
+        let log = self.log.entry(participant.to_string()).or_default();
+        log.push(Vote {
+            a,
+            b,
+            a_is_better,
+            counted: false,
+        });
+        let at = log.len() - 1;
+        let voters = self.open.entry(key).or_default();
+        voters.retain(|(name, _)| name != participant);
+        voters.push((participant.to_string(), at));
+
+        let tally = self.tally(key)?;
+        if tally.voters < self.rules.quorum {
+            return None;
+        }
+        let first_won = match tally.for_a.partial_cmp(&tally.for_b)? {
+            Ordering::Greater => true,
+            Ordering::Less => false,
+            Ordering::Equal => return None,
+        };
+        for (name, at) in self.open.remove(&key).unwrap_or_default() {
+            if let Some(vote) = self.log.get_mut(&name).and_then(|log| log.get_mut(at)) {
+                vote.counted = true;
+            }
+        }
+        // The tally is for the pair in ascending order.
+        Some(first_won == (a < b))
+    }
+
+    /// The rules the session was created with.
+    pub(crate) fn rules(&self) -> VotingRules {
+        self.rules.clone()
+    }
+
+    /// Drops the open votes on `a` vs `b`, once it was answered some other
+    /// way.
+    pub(crate) fn close(&mut self, a: usize, b: usize) {
+        self.open.remove(&(a.min(b), a.max(b)));
+    }
+
+    /// The weighted votes on every question with open votes.
+    pub(crate) fn tallies(&self) -> Vec<Tally> {
+        let mut tallies: Vec<Tally> = self.open.keys().filter_map(|&k| self.tally(k)).collect();
+        tallies.sort_by_key(|t| (t.a, t.b));
+        tallies
+    }
+
+    /// Every vote by participant.
+    pub(crate) fn log(&self) -> &BTreeMap<String, Vec<Vote>> {
+        &self.log
+    }
+
+    /// The votes on the pair `(a, b)`, with `a < b`.
+    fn tally(&self, (a, b): (usize, usize)) -> Option<Tally> {
+        let voters = self.open.get(&(a, b))?;
+        let mut tally = Tally {
+            a,
+            b,
+            for_a: 0.0,
+            for_b: 0.0,
+            voters: voters.len(),
+        };
+        for (name, at) in voters {
+            let vote = &self.log[name][*at];
+            let weight = self.rules.weights.get(name).copied().unwrap_or(1.0);
+            if vote.a_is_better == (vote.a == a) {
+                tally.for_a += weight;
+            } else {
+                tally.for_b += weight;
+            }
+        }
+        Some(tally)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Tally, Voting, VotingRules};
+
+    fn voting(quorum: usize, weights: &[(&str, f64)]) -> Voting {
+        Voting::new(VotingRules {
+            quorum,
+            weights: weights.iter().map(|&(n, w)| (n.to_string(), w)).collect(),
+        })
+    }
+
+    #[test]
+    fn majority_decides_once_the_quorum_voted() {
+        let mut votes = voting(3, &[]);
+        assert_eq!(votes.vote("ann", 0, 1, true), None);
+        // A changed vote replaces the earlier one.
+        assert_eq!(votes.vote("ann", 0, 1, false), None);
+        assert_eq!(votes.vote("bob", 1, 0, true), None);
+        let tally = Tally {
+            a: 0,
+            b: 1,
+            for_a: 0.0,
+            for_b: 2.0,
+            voters: 2,
+        };
+        assert_eq!(votes.tallies(), vec![tally]);
+        assert_eq!(votes.vote("cid", 0, 1, true), Some(false));
+        assert!(votes.tallies().is_empty());
+
+        let ann = &votes.log()["ann"];
+        assert_eq!(ann.len(), 2);
+        assert!(!ann[0].counted && ann[1].counted);
+    }
+
+    #[test]
+    fn weights_break_head_counts_and_ties_wait() {
+        let mut votes = voting(2, &[("lead", 3.0), ("x", 2.0)]);
+        assert_eq!(votes.vote("ann", 2, 3, true), None);
+        assert_eq!(votes.vote("bob", 2, 3, true), Some(true));
+        assert_eq!(votes.vote("lead", 0, 1, true), None);
+        assert_eq!(votes.vote("ann", 0, 1, false), Some(true));
+
+        // One against one is a tie at the quorum, so it waits.
+        assert_eq!(votes.vote("ann", 4, 5, true), None);
+        assert_eq!(votes.vote("bob", 5, 4, true), None);
+        assert_eq!(votes.vote("x", 4, 5, true), Some(true));
+    }
+}
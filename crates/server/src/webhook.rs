@@ -0,0 +1,255 @@
+//! Telling other services that a ranking is complete.
+//!
+//! With the `webhooks` feature, a session can be given a URL, as `webhook`
+//! when it is created or later with the owner's `PUT /sessions/{id}/webhook`
+//! and `{"url": "https://..."}` (`null` removes it). Once the ranking is
+//! complete, by its last answer or by `/finalize`, the server sends a
+//! [`Completion`] there as a JSON POST: the session's id, the ranking, the
+//! comparisons it took and the [`Stats`] of its answers. Setting the URL on
+//! a complete ranking sends it right away, and new items (`PUT /items`)
+//! make a new ranking that is sent again when complete.
+//!
+//! Each ranking is sent once, without retries or redirects; failures are
+//! logged as warnings. The URL is saved with the session.
+//!
+//! Webhooks only reach public hosts, so session creators cannot make the
+//! server call its own network: `localhost`, loopback, private, link-local
+//! and other special addresses are refused, both in the URL and in what
+//! its host name resolves to. `RANKFAST_WEBHOOK_ALLOW` lists hosts, comma
+//! separated, that are let through anyway, e.g. `localhost,10.0.0.7` for a
+//! service next to the server.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+
+use rankfast_session::{Stats, analyze};
+use reqwest::Url;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::redirect::Policy;
+use serde::Serialize;
+
+use crate::api::RankingResult;
+use crate::session::Session;
+
+/// How long a webhook gets to respond.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Hosts webhooks may reach even though they are not public.
+static ALLOWED: LazyLock<Vec<String>> = LazyLock::new(|| {
+    std::env::var("RANKFAST_WEBHOOK_ALLOW")
+        .unwrap_or_default()
+        .split(',')
+        .map(|host| host.trim().to_ascii_lowercase())
+        .filter(|host| !host.is_empty())
+        .collect()
+});
+
+static CLIENT: LazyLock<reqwest::Client> = LazyLock::new(|| {
+    reqwest::Client::builder()
+        .dns_resolver(Arc::new(PublicOnly))
+        // A redirect could point anywhere, past the checks on the URL.
+        .redirect(Policy::none())
+        .build()
+        .expect("the webhook client needs only the defaults")
+});
+
+/// What a webhook is sent.
+#[derive(Serialize)]
+pub(crate) struct Completion {
+    id: u64,
+    #[serde(flatten)]
+    result: RankingResult,
+    /// Whether the owner ended the session before the ranking was complete.
+    finalized: bool,
+    /// `None` if the answers could not be replayed, which the stepper
+    /// rules out.
+    stats: Option<Stats>,
+}
+
+impl Completion {
+    /// The completed ranking of session `id`, or `None` while it is still
+    /// missing answers.
+    pub(crate) fn of(id: u64, session: &Session) -> Option<Self> {
+        Some(Self {
+            id,
+            result: RankingResult::of(session).ok()?,
+            finalized: session.is_finalized(),
+            stats: analyze(&session.saved(), None).ok(),
+        })
+    }
+}
+
+/// Whether `url` can take webhooks: an absolute `http` or `https` URL on a
+/// public or allowed host.
+pub(crate) fn is_valid(url: &str) -> bool {
+    accepts(url, &ALLOWED)
+}
+
+fn accepts(url: &str, allowed: &[String]) -> bool {
+    let Ok(url) = Url::parse(url) else {
+        return false;
+    };
+    if !matches!(url.scheme(), "http" | "https") {
+        return false;
+    }
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    if allowed.iter().any(|a| a == host) {
+        return true;
+    }
+    if let Ok(ip) = host.parse::<IpAddr>() {
+        return is_public(ip);
+    }
+    let name = host.trim_end_matches('.');
+    name != "localhost" && !name.ends_with(".localhost")
+}
+
+/// Whether `ip` is an ordinary internet address, not one of the host, its
+/// network or a special purpose.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(v4) => is_public_v4(v4),
+            None => is_public_v6(ip),
+        },
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        // Carrier-grade NAT, 100.64.0.0/10.
+        || (a == 100 && b & 0xc0 == 64)
+        // Reserved, 240.0.0.0/4, and "this network", 0.0.0.0/8.
+        || a >= 240
+        || a == 0)
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        || ip.is_unique_local()
+        || ip.is_unicast_link_local())
+}
+
+/// Resolves host names to their public addresses only, so a name pointing
+/// into the server's network is refused like the address itself.
+struct PublicOnly;
+
+impl Resolve for PublicOnly {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_ascii_lowercase();
+        Box::pin(async move {
+            let allowed = ALLOWED.contains(&host);
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|addr| allowed || is_public(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{host} has no public address").into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Sends `completion` to `url` in the background. Does nothing outside a
+/// Tokio runtime.
+pub(crate) fn send(url: String, completion: Completion) {
+    let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+        return;
+    };
+    runtime.spawn(async move {
+        let sent = CLIENT
+            .post(&url)
+            .json(&completion)
+            .timeout(TIMEOUT)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status);
+        if let Err(e) = sent {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(session = completion.id, error = %e, "webhook failed");
+            #[cfg(not(feature = "tracing"))]
+            eprintln!("Webhook for session {} failed: {e}", completion.id);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Completion, accepts};
+    use crate::session::Session;
+
+    #[test]
+    fn accepts_only_web_urls() {
+        assert!(accepts("https://example.com/hooks/rankfast", &[]));
+        assert!(accepts("http://93.184.215.14:8080", &[]));
+        assert!(!accepts("ftp://example.com", &[]));
+        assert!(!accepts("/relative", &[]));
+        assert!(!accepts("", &[]));
+    }
+
+    #[test]
+    fn refuses_hosts_that_are_not_public() {
+        for url in [
+            "http://localhost:8080",
+            "http://LOCALHOST./",
+            "http://api.localhost",
+            "http://127.0.0.1",
+            "http://0.0.0.0",
+            "http://10.1.2.3",
+            "http://172.16.0.1",
+            "http://192.168.1.1",
+            "http://169.254.169.254/latest/meta-data",
+            "http://100.64.0.1",
+            "http://[::1]",
+            "http://[fd00::1]",
+            "http://[fe80::1]",
+            "http://[::ffff:127.0.0.1]",
+        ] {
+            assert!(!accepts(url, &[]), "{url}");
+        }
+        let allowed = ["localhost".to_string(), "10.0.0.7".to_string()];
+        assert!(accepts("http://localhost:8080/hook", &allowed));
+        assert!(accepts("http://10.0.0.7", &allowed));
+        assert!(!accepts("http://10.0.0.8", &allowed));
+    }
+
+    #[test]
+    fn calls_once_per_ranking() {
+        let mut session = Session::new(vec!["x".into(), "y".into()], &[], None);
+        session.set_webhook(Some("https://example.com".into()));
+        assert_eq!(session.take_webhook(), None);
+        assert!(Completion::of(0, &session).is_none());
+
+        session.answer(0, 1, false).unwrap();
+        assert_eq!(
+            session.take_webhook().as_deref(),
+            Some("https://example.com")
+        );
+        assert_eq!(session.take_webhook(), None);
+        let completion = serde_json::to_value(Completion::of(3, &session).unwrap()).unwrap();
+        assert_eq!(completion["id"], 3);
+        assert_eq!(completion["ranking"], serde_json::json!(["y", "x"]));
+        assert_eq!(completion["stats"]["questions"], 1);
+
+        // New items make a new ranking to report.
+        session.set_items(vec!["x".into(), "y".into(), "z".into()]);
+        assert_eq!(session.take_webhook(), None);
+        session.set_webhook(None);
+        session.finalize();
+        assert_eq!(session.take_webhook(), None);
+    }
+}
@@ -0,0 +1,228 @@
+//! Statistics about how a session went.
+//!
+//! [`analyze`] replays a session's answers like
+//! [`validate_session`](crate::validate_session) and keeps what the replay
+//! shows along the way: how many questions each item was in, how wide the
+//! binary searches were, runs of the same answer, which can mean a user
+//! stopped reading and kept pressing one key, and the answers'
+//! [`consistency`], which tells how far the ranking can be trusted.
+//! Frontends that time the answers pass the times in too. [`Stats::to_json`] is the export format.
+
+use rankfast::ratings::{Consistency, consistency};
+use rankfast::{Phase, Step};
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+
+use crate::{Answer, Engine, Session, SessionError};
+
+/// What the answers of one session show.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Stats {
+    pub items: usize,
+    /// Questions answered.
+    pub questions: usize,
+    /// What the mode expected for this many items, see
+    /// [`Mode::estimate`](crate::Mode::estimate).
+    pub estimate: usize,
+    /// Whether the answers complete the ranking.
+    pub complete: bool,
+    /// Questions each item was in, by item.
+    pub questions_per_item: Vec<usize>,
+    /// Mean places left for the item being placed, over the binary-search
+    /// questions; `None` if the engine asked none.
+    pub mean_search_range: Option<f64>,
+    /// The longest run of one answer, the first if several tie.
+    pub longest_streak: Option<Streak>,
+    /// How well the answers agree with each other. Ties and skips are left
+    /// out.
+    #[serde(serialize_with = "consistency_fields")]
+    pub consistency: Consistency,
+    /// Time per answer, when the frontend measured it.
+    pub timing: Option<Timing>,
+}
+
+/// The same answer given to consecutive questions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Streak {
+    #[serde(serialize_with = "token")]
+    pub answer: Answer,
+    /// Index of the first question of the run.
+    pub start: usize,
+    pub length: usize,
+}
+
+/// How long the answers took.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Timing {
+    /// Milliseconds spent on each of the last questions, in order. Answers
+    /// replayed from a saved session were not timed, so this can be shorter
+    /// than the answers.
+    pub per_question_ms: Vec<f64>,
+    pub median_ms: f64,
+    pub total_ms: f64,
+}
+
+impl Stats {
+    /// The statistics as a pretty-printed JSON document.
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        // Numbers, an answer token and lists of numbers always serialize.
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+/// Replays `session` and gathers its [`Stats`]; `durations_ms` are the times
+/// of its last answers, if measured. A session still in progress gives the
+/// statistics so far.
+///
+/// # Errors
+///
+/// Fails with [`SessionError::TooManyAnswers`] if the ranking is complete
+/// before the last answer.
+pub fn analyze(session: &Session, durations_ms: Option<&[f64]>) -> Result<Stats, SessionError> {
+    let n = session.items.len();
+    let mut engine = Engine::seeded(session.engine, n, &session.known);
+    let mut step = engine.step();
+    let mut questions_per_item = vec![0; n];
+    let mut ranges = Vec::new();
+    let mut outcomes = Vec::new();
+    for (used, &answer) in session.answers.iter().enumerate() {
+        let Step::Compare { a, b, context } = step else {
+            return Err(SessionError::TooManyAnswers {
+                used,
+                given: session.answers.len(),
+            });
+        };
+        questions_per_item[a] += 1;
+        questions_per_item[b] += 1;
+        if let Phase::Insertion { candidates } = context.phase {
+            ranges.push(candidates);
+        }
+        match answer {
+            Answer::A => outcomes.push((a, b)),
+            Answer::B => outcomes.push((b, a)),
+            Answer::Tie | Answer::Skip => {}
+        }
+        step = engine.answer(answer, a, b);
+    }
+    Ok(Stats {
+        items: n,
+        questions: session.answers.len(),
+        estimate: session.engine.estimate(n),
+        complete: step == Step::Done,
+        questions_per_item,
+        mean_search_range: (!ranges.is_empty())
+            .then(|| as_f64(ranges.iter().sum()) / as_f64(ranges.len())),
+        longest_streak: longest_streak(&session.answers),
+        consistency: consistency(n, &outcomes),
+        timing: durations_ms.and_then(timing),
+    })
+}
+
+fn longest_streak(answers: &[Answer]) -> Option<Streak> {
+    let mut best: Option<Streak> = None;
+    let mut start = 0;
+    for end in 1..=answers.len() {
+        if end < answers.len() && answers[end] == answers[start] {
+            continue;
+        }
+        if best.is_none_or(|best| end - start > best.length) {
+            best = Some(Streak {
+                answer: answers[start],
+                start,
+                length: end - start,
+            });
+        }
+        start = end;
+    }
+    best
+}
+
+fn timing(durations_ms: &[f64]) -> Option<Timing> {
+    if durations_ms.is_empty() {
+        return None;
+    }
+    let mut sorted = durations_ms.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let mid = sorted.len() / 2;
+    let median_ms = if sorted.len().is_multiple_of(2) {
+        f64::midpoint(sorted[mid - 1], sorted[mid])
+    } else {
+        sorted[mid]
+    };
+    Some(Timing {
+        per_question_ms: durations_ms.to_vec(),
+        median_ms,
+        total_ms: durations_ms.iter().sum(),
+    })
+}
+
+#[allow(clippy::cast_precision_loss)] // question counts are far below 2^52
+fn as_f64(count: usize) -> f64 {
+    count as f64
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)] // serde passes fields by reference
+fn token<S: Serializer>(answer: &Answer, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_char(answer.token())
+}
+
+/// [`Consistency`] with its two rates, as core has no serde support.
+fn consistency_fields<S: Serializer>(c: &Consistency, s: S) -> Result<S::Ok, S::Error> {
+    let mut fields = s.serialize_struct("Consistency", 6)?;
+    fields.serialize_field("outcomes", &c.outcomes)?;
+    fields.serialize_field("implied", &c.implied)?;
+    fields.serialize_field("contradicted", &c.contradicted)?;
+    fields.serialize_field("against_fit", &c.against_fit)?;
+    fields.serialize_field("agreement", &c.agreement())?;
+    fields.serialize_field("contradiction_rate", &c.contradiction_rate())?;
+    fields.end()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Streak, analyze};
+    use crate::{Answer, Mode, Session, SessionError};
+
+    fn session(n: usize, answers: &[Answer]) -> Session {
+        let items = (0..n).map(|i| format!("item {i}")).collect();
+        Session::new(items, answers.to_vec(), Mode::Exact)
+    }
+
+    #[test]
+    fn counts_what_the_replay_shows() {
+        // Item 2 ranks between 0 and 1.
+        let answers = [Answer::A, Answer::A, Answer::Tie];
+        let stats = analyze(&session(3, &answers), Some(&[900.0, 1000.0, 4000.0])).unwrap();
+        assert!(stats.complete);
+        assert_eq!((stats.questions, stats.estimate), (3, 3));
+        assert_eq!(stats.questions_per_item, vec![2, 2, 2]);
+        assert!(stats.mean_search_range.is_some_and(|range| range >= 2.0));
+        assert_eq!(
+            stats.longest_streak,
+            Some(Streak {
+                answer: Answer::A,
+                start: 0,
+                length: 2
+            })
+        );
+        let timing = stats.timing.as_ref().unwrap();
+        assert_eq!((timing.median_ms, timing.total_ms), (1000.0, 5900.0));
+        // The tie is left out, and a sort asks nothing it can infer.
+        assert_eq!(stats.consistency.outcomes, 2);
+        assert_eq!(stats.consistency.contradiction_rate(), None);
+        let json = stats.to_json();
+        assert!(json.contains("\"answer\": \"a\""));
+        assert!(json.contains("\"agreement\": 1.0"));
+
+        let partial = analyze(&session(3, &answers[..1]), None).unwrap();
+        assert!(!partial.complete);
+        assert_eq!(partial.timing, None);
+        assert_eq!(
+            analyze(&session(2, &answers), None),
+            Err(SessionError::TooManyAnswers { used: 1, given: 3 })
+        );
+        let empty = analyze(&session(0, &[]), Some(&[])).unwrap();
+        assert_eq!((empty.longest_streak, empty.timing), (None, None));
+    }
+}
@@ -0,0 +1,295 @@
+//! Answered comparisons as a pairwise preference dataset.
+//!
+//! Every answer is one [`Preference`]: the two items asked about, which won,
+//! when, and which session it came from. [`to_csv`] and [`to_jsonl`] write
+//! them in the usual formats for training reward models, with the columns
+//! `item_a`, `item_b`, `winner` (`a`, `b`, `tie` or `skip`), `timestamp`
+//! (Unix milliseconds, empty or `null` when not recorded) and `session_id`.
+//!
+//! `item_a` and `item_b` are the engine's question, whichever side each was
+//! shown on. The session id is derived from the items, mode and sides, see
+//! [`Session::id`], so the same run exported from different frontends lines
+//! up.
+//!
+//! Going the other way, [`read_jsonl`] loads such a log and [`known_pairs`]
+//! turns the judgments about a new session's items into
+//! [`Session::known`], so the new ranking only asks what the log leaves
+//! open.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use rankfast::consistent_pairs;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{Answer, Session, SessionError};
+
+/// One answered comparison.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Preference {
+    pub item_a: String,
+    pub item_b: String,
+    #[serde(serialize_with = "winner", deserialize_with = "read_winner")]
+    pub winner: Answer,
+    /// When the answer was given, in Unix milliseconds.
+    #[serde(default)]
+    pub timestamp: Option<u64>,
+    #[serde(default)]
+    pub session_id: String,
+}
+
+/// Why a JSON Lines log could not be read.
+#[derive(Debug)]
+pub struct DatasetError {
+    /// The line that failed, from 1.
+    pub line: usize,
+    pub error: serde_json::Error,
+}
+
+impl fmt::Display for DatasetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: not a comparison: {}", self.line, self.error)
+    }
+}
+
+impl std::error::Error for DatasetError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<DatasetError> for rankfast::Error {
+    fn from(e: DatasetError) -> Self {
+        Self::decode(e)
+    }
+}
+
+impl Session {
+    /// A stable id for the session: 16 hex digits of FNV-1a over the items,
+    /// the mode and the sides. Answers are left out, so it stays the same as
+    /// the session goes on.
+    #[must_use]
+    pub fn id(&self) -> String {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let tokens = [self.engine.token(), self.sides.token()];
+        let bytes = self
+            .items
+            .iter()
+            .chain(&tokens)
+            .flat_map(|part| part.bytes().chain([b'\n']));
+        for byte in bytes {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+        format!("{hash:016x}")
+    }
+}
+
+/// Every answer of `session` as a [`Preference`]. `timestamps` are the Unix
+/// milliseconds of its last answers, when the frontend recorded them, like
+/// the durations passed to [`analyze`](crate::analyze).
+///
+/// # Errors
+///
+/// Fails with [`SessionError::TooManyAnswers`] if the ranking is complete
+/// before the last answer.
+pub fn preferences(session: &Session, timestamps: &[u64]) -> Result<Vec<Preference>, SessionError> {
+    let replayed = session.validate()?;
+    let n = replayed.history.len();
+    let session_id = session.id();
+    Ok(replayed
+        .history
+        .into_iter()
+        .enumerate()
+        .map(|(i, (a, b, answer))| Preference {
+            item_a: session.items[a].clone(),
+            item_b: session.items[b].clone(),
+            winner: answer,
+            timestamp: (timestamps.len() + i).checked_sub(n).map(|t| timestamps[t]),
+            session_id: session_id.clone(),
+        })
+        .collect())
+}
+
+/// The preferences as CSV with a header row. Fields holding commas, quotes
+/// or line breaks are quoted.
+#[must_use]
+pub fn to_csv(preferences: &[Preference]) -> String {
+    let mut csv = String::from("item_a,item_b,winner,timestamp,session_id\n");
+    for p in preferences {
+        let timestamp = p.timestamp.map(|t| t.to_string()).unwrap_or_default();
+        let fields = [
+            csv_field(&p.item_a),
+            csv_field(&p.item_b),
+            winner_name(p.winner).to_string(),
+            timestamp,
+            csv_field(&p.session_id),
+        ];
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// The preferences as JSON Lines, one object per line.
+#[must_use]
+pub fn to_jsonl(preferences: &[Preference]) -> String {
+    preferences
+        .iter()
+        // Strings and numbers always serialize.
+        .map(|p| serde_json::to_string(p).unwrap_or_default() + "\n")
+        .collect()
+}
+
+/// Reads a log written by [`to_jsonl`], or any JSON Lines file with the
+/// same fields; `timestamp` and `session_id` may be missing. Blank lines are
+/// skipped.
+///
+/// # Errors
+///
+/// Fails on the first line that is not a comparison.
+pub fn read_jsonl(text: &str) -> Result<Vec<Preference>, DatasetError> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line).map_err(|error| DatasetError { line: i + 1, error })
+        })
+        .collect()
+}
+
+/// The `(better, worse)` pairs of `items` indices that `preferences`
+/// decide, ready for [`Session::known`]. Ties, skips and comparisons
+/// involving other items are left out, as is any judgment that contradicts
+/// an earlier one, see [`consistent_pairs`].
+#[must_use]
+pub fn known_pairs(items: &[String], preferences: &[Preference]) -> Vec<(usize, usize)> {
+    let index: HashMap<&str, usize> = items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| (item.as_str(), i))
+        .collect();
+    let pairs: Vec<(usize, usize)> = preferences
+        .iter()
+        .filter_map(|p| {
+            let a = *index.get(p.item_a.as_str())?;
+            let b = *index.get(p.item_b.as_str())?;
+            match p.winner {
+                Answer::A => Some((a, b)),
+                Answer::B => Some((b, a)),
+                Answer::Tie | Answer::Skip => None,
+            }
+        })
+        .collect();
+    consistent_pairs(items.len(), &pairs)
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+fn winner_name(answer: Answer) -> &'static str {
+    match answer {
+        Answer::A => "a",
+        Answer::B => "b",
+        Answer::Tie => "tie",
+        Answer::Skip => "skip",
+    }
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)] // serde passes fields by reference
+fn winner<S: Serializer>(answer: &Answer, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_str(winner_name(*answer))
+}
+
+fn read_winner<'de, D: Deserializer<'de>>(d: D) -> Result<Answer, D::Error> {
+    let name = String::deserialize(d)?;
+    [Answer::A, Answer::B, Answer::Tie, Answer::Skip]
+        .into_iter()
+        .find(|&answer| winner_name(answer) == name)
+        .ok_or_else(|| D::Error::custom(format!("unknown winner {name:?}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{known_pairs, preferences, read_jsonl, to_csv, to_jsonl};
+    use crate::{Answer, Mode, Session, SessionError, Sides};
+
+    fn session(items: &[&str], answers: &[Answer]) -> Session {
+        let items = items.iter().map(ToString::to_string).collect();
+        Session::new(items, answers.to_vec(), Mode::Exact)
+    }
+
+    #[test]
+    fn lists_every_answer_with_the_last_ones_timed() {
+        let saved = session(&["x", "y, the \"best\"", "z"], &[Answer::A, Answer::Tie]);
+        let log = preferences(&saved, &[1_700_000_000_000]).unwrap();
+        assert_eq!(log.len(), 2);
+        assert_eq!((log[0].item_a.as_str(), log[0].winner), ("x", Answer::A));
+        assert_eq!(
+            (log[0].timestamp, log[1].timestamp),
+            (None, Some(1_700_000_000_000))
+        );
+        assert!(log.iter().all(|p| p.session_id == saved.id()));
+
+        let csv = to_csv(&log);
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some("item_a,item_b,winner,timestamp,session_id")
+        );
+        let first = format!("x,\"y, the \"\"best\"\"\",a,,{}", saved.id());
+        assert_eq!(lines.next(), Some(first.as_str()));
+
+        let jsonl = to_jsonl(&log);
+        assert_eq!(jsonl.lines().count(), 2);
+        assert!(jsonl.contains(r#""winner":"tie","timestamp":1700000000000"#));
+
+        let overlong = session(&["x"], &[Answer::A]);
+        assert_eq!(
+            preferences(&overlong, &[]),
+            Err(SessionError::TooManyAnswers { used: 0, given: 1 })
+        );
+    }
+
+    #[test]
+    fn ids_follow_the_run_not_the_answers() {
+        let start = session(&["x", "y"], &[]);
+        let answered = session(&["x", "y"], &[Answer::B]);
+        assert_eq!(start.id(), answered.id());
+        assert_eq!(start.id().len(), 16);
+        assert_ne!(start.id(), session(&["x", "z"], &[]).id());
+        let shuffled = Session {
+            sides: Sides::Shuffled(3),
+            ..start.clone()
+        };
+        assert_ne!(start.id(), shuffled.id());
+    }
+
+    #[test]
+    fn imported_logs_seed_a_new_session() {
+        let saved = session(&["x", "y", "z"], &[Answer::A, Answer::A, Answer::B]);
+        let log = preferences(&saved, &[]).unwrap();
+        assert_eq!(read_jsonl(&format!("\n{}", to_jsonl(&log))).unwrap(), log);
+        let error =
+            read_jsonl("{\"item_a\": \"x\", \"item_b\": \"y\", \"winner\": \"c\"}").unwrap_err();
+        assert_eq!(error.line, 1);
+
+        // x, z, y: only the pairs among the new items count, and `w` is new.
+        let items = ["z", "y", "w", "x"].map(String::from).to_vec();
+        let known = known_pairs(&items, &log);
+        assert_eq!(known.len(), 3);
+        let seeded = Session {
+            known,
+            ..Session::new(items, Vec::new(), Mode::Exact)
+        };
+        let state = seeded.validate().unwrap();
+        let (a, b) = state.question().unwrap();
+        assert!(a == 2 || b == 2, "{a} vs {b}");
+    }
+}
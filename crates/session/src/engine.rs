@@ -0,0 +1,268 @@
+//! The engine behind each [`Mode`], with one `step`/`answer` surface.
+//!
+//! Replaying a session's answers through [`Engine::new`] for its mode
+//! reproduces the run question by question, so every frontend resumes a
+//! session the same way; [`validate_session`](crate::validate_session)
+//! does so and checks the answers fit. [`Engine::seeded`] starts from
+//! judgments known before the session, see [`dataset`](crate::dataset).
+//! [`Engine`] is a [`RankingEngine`] too, so code written against the trait
+//! runs any mode, picked by [`Mode::from_name`].
+
+use rankfast::active::ActiveStepper;
+use rankfast::active::default_budget;
+use rankfast::quickrank::QuickRank;
+use rankfast::tiers::Tiers;
+use rankfast::topk::TopK;
+use rankfast::tournament::RoundRobin;
+use rankfast::{Ranking, RankingEngine, Step, Stepper, consistent_pairs};
+
+use crate::{Answer, Mode};
+
+/// A running engine for one [`Mode`].
+pub enum Engine {
+    Exact(Stepper),
+    Approximate(ActiveStepper),
+    /// [`Mode::TopK`], and [`Mode::BinaryInsertion`] keeping every item.
+    TopK(TopK),
+    Tiers(Tiers),
+    QuickRank(QuickRank),
+    Tournament(RoundRobin),
+}
+
+impl Engine {
+    /// A fresh engine for `mode` over the indices `0..n`.
+    #[must_use]
+    pub fn new(mode: Mode, n: usize) -> Self {
+        match mode {
+            Mode::Exact => Self::Exact(Stepper::new(n)),
+            Mode::Approximate => Self::Approximate(ActiveStepper::with_default_budget(n)),
+            Mode::TopK(k) => Self::TopK(TopK::new(n, k)),
+            Mode::Tiers => Self::Tiers(Tiers::new(n)),
+            Mode::QuickRank => Self::QuickRank(QuickRank::new(n)),
+            Mode::BinaryInsertion => Self::TopK(TopK::new(n, n)),
+            Mode::Tournament => Self::Tournament(RoundRobin::new(n)),
+        }
+    }
+
+    /// A fresh engine for `mode` that already knows, for every
+    /// `(better, worse)` in `known`, that `better` ranks above `worse`. The
+    /// exact sort skips the questions they decide and the approximate
+    /// ranking counts them towards its budget; the other modes ask as
+    /// [`new`](Self::new) would. Pairs outside `0..n` or contradicting
+    /// earlier ones are ignored.
+    #[must_use]
+    pub fn seeded(mode: Mode, n: usize, known: &[(usize, usize)]) -> Self {
+        if known.is_empty() {
+            return Self::new(mode, n);
+        }
+        let known = consistent_pairs(n, known);
+        match mode {
+            Mode::Exact => Self::Exact(Stepper::with_known_pairs(n, &known)),
+            Mode::Approximate => Self::Approximate(ActiveStepper::with_prior_outcomes(
+                n,
+                default_budget(n),
+                &known,
+            )),
+            Mode::TopK(_)
+            | Mode::Tiers
+            | Mode::QuickRank
+            | Mode::BinaryInsertion
+            | Mode::Tournament => Self::new(mode, n),
+        }
+    }
+
+    pub fn step(&mut self) -> Step {
+        match self {
+            Self::Exact(s) => s.step(),
+            Self::Approximate(s) => s.step(),
+            Self::TopK(s) => s.step(),
+            Self::Tiers(s) => s.step(),
+            Self::QuickRank(s) => s.step(),
+            Self::Tournament(s) => s.step(),
+        }
+    }
+
+    /// Applies `answer` to the pending question `a` vs `b`. The exact sort
+    /// records ties, so tied items share a place in the final ranking.
+    pub fn answer(&mut self, answer: Answer, a: usize, b: usize) -> Step {
+        match self {
+            Self::Exact(s) if answer == Answer::Tie => s.answer_tie(),
+            _ => self.decide(answer.a_is_better(a, b)),
+        }
+    }
+
+    fn decide(&mut self, better_is_a: bool) -> Step {
+        match self {
+            Self::Exact(s) => s.answer(better_is_a),
+            Self::Approximate(s) => s.answer(better_is_a),
+            Self::TopK(s) => s.answer(better_is_a),
+            Self::Tiers(s) => s.answer(better_is_a),
+            Self::QuickRank(s) => s.answer(better_is_a),
+            Self::Tournament(s) => s.answer(better_is_a),
+        }
+    }
+
+    /// The question after answering the pending one with `better_is_a`, if
+    /// the engine can tell without answering. Only the exact sort looks
+    /// ahead; the other engines pick their next question from the answer.
+    #[must_use]
+    pub fn peek_next_after(&self, better_is_a: bool) -> Option<(usize, usize)> {
+        match self {
+            Self::Exact(s) => s.peek_next_after(better_is_a),
+            Self::Approximate(_)
+            | Self::TopK(_)
+            | Self::Tiers(_)
+            | Self::QuickRank(_)
+            | Self::Tournament(_) => None,
+        }
+    }
+
+    #[must_use]
+    pub fn comparisons_made(&self) -> usize {
+        match self {
+            Self::Exact(s) => s.comparisons_made(),
+            Self::Approximate(s) => s.comparisons_made(),
+            Self::TopK(s) => s.comparisons_made(),
+            Self::Tiers(s) => s.comparisons_made(),
+            Self::QuickRank(s) => s.comparisons_made(),
+            Self::Tournament(s) => s.comparisons_made(),
+        }
+    }
+
+    /// Upper bound on the questions still to come; see
+    /// [`Stepper::remaining_estimate`].
+    #[must_use]
+    pub fn remaining_estimate(&self) -> usize {
+        match self {
+            Self::Exact(s) => s.remaining_estimate(),
+            Self::Approximate(s) => s.remaining_estimate(),
+            Self::TopK(s) => s.remaining_estimate(),
+            Self::Tiers(s) => s.remaining_estimate(),
+            Self::QuickRank(s) => s.remaining_estimate(),
+            Self::Tournament(s) => RankingEngine::remaining_estimate(s),
+        }
+    }
+
+    /// The final order, best first, once [`step`](Self::step) returned
+    /// [`Step::Done`].
+    pub fn take_order(&mut self) -> Option<Ranking<usize>> {
+        match self {
+            Self::Exact(s) => s.take_order(),
+            Self::Approximate(s) => Some(s.order()),
+            Self::TopK(s) => Some(s.order()),
+            Self::Tiers(s) => Some(s.order()),
+            Self::QuickRank(s) => Some(s.order()),
+            Self::Tournament(s) => Some(s.result()),
+        }
+    }
+
+    /// The best ranking the answers so far support, for "Finish now".
+    #[must_use]
+    pub fn finalize_partial(&self) -> Ranking<usize> {
+        match self {
+            Self::Exact(s) => s.finalize_partial(),
+            Self::Approximate(s) => s.order(),
+            Self::TopK(s) => s.order(),
+            Self::Tiers(s) => s.order(),
+            Self::QuickRank(s) => s.order(),
+            Self::Tournament(s) => s.result(),
+        }
+    }
+}
+
+/// [`Mode::Exact`] from [`RankingEngine::new`]; [`Engine::new`] makes the
+/// others. Answers are never ties.
+impl RankingEngine for Engine {
+    fn new(n: usize) -> Self {
+        Self::new(Mode::Exact, n)
+    }
+
+    fn next_question(&mut self) -> Step {
+        self.step()
+    }
+
+    fn answer(&mut self, better_is_a: bool) -> Step {
+        self.decide(better_is_a)
+    }
+
+    fn result(&self) -> Ranking<usize> {
+        self.finalize_partial()
+    }
+
+    fn remaining_estimate(&self) -> usize {
+        Self::remaining_estimate(self)
+    }
+
+    fn comparisons_made(&self) -> usize {
+        Self::comparisons_made(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Write as _;
+
+    use rankfast::Step;
+
+    use super::Engine;
+    use crate::{Answer, Mode};
+
+    /// The questions every engine asks for fixed answers, one run per line.
+    /// Links store only the answers, so a run that asks other questions, or
+    /// the same ones in another order, changes what every saved link means.
+    const GOLDEN: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/golden/questions.txt");
+
+    /// The questions `mode` asks of `n` items, as `a-b`, when the `q`th
+    /// question `a` vs `b` gets `answer(q, a, b)`.
+    fn questions(mode: Mode, n: usize, answer: impl Fn(usize, usize, usize) -> Answer) -> String {
+        let mut engine = Engine::new(mode, n);
+        let mut asked = Vec::new();
+        let mut step = engine.step();
+        while let Step::Compare { a, b, .. } = step {
+            let given = answer(asked.len(), a, b);
+            asked.push(format!("{a}-{b}"));
+            step = engine.answer(given, a, b);
+        }
+        asked.join(" ")
+    }
+
+    #[test]
+    fn questions_match_the_golden_file() {
+        let modes = [
+            Mode::Exact,
+            Mode::Approximate,
+            Mode::TopK(3),
+            Mode::Tiers,
+            Mode::QuickRank,
+        ];
+        let mut runs = String::new();
+        for mode in modes {
+            for n in [2, 5, 9, 16] {
+                let keys: Vec<usize> = (0..n).map(|i| (i * 7 + 3) % n).collect();
+                let by_key = questions(mode, n, |_, a, b| {
+                    if keys[a] > keys[b] {
+                        Answer::A
+                    } else {
+                        Answer::B
+                    }
+                });
+                // Every kind of answer in turn, ties and skips included.
+                let cycle = [Answer::A, Answer::B, Answer::Tie, Answer::Skip];
+                let cycled = questions(mode, n, |q, _, _| cycle[q % cycle.len()]);
+                writeln!(runs, "{mode:?} {n} keys: {by_key}").unwrap();
+                writeln!(runs, "{mode:?} {n} cycle: {cycled}").unwrap();
+            }
+        }
+        if std::env::var_os("BLESS").is_some() {
+            std::fs::write(GOLDEN, &runs).unwrap();
+        }
+        let golden = std::fs::read_to_string(GOLDEN).unwrap();
+        for (expected, actual) in golden.lines().zip(runs.lines()) {
+            assert_eq!(
+                actual, expected,
+                "the questions changed; if that is intended, rerun with BLESS=1"
+            );
+        }
+        assert_eq!(golden.lines().count(), runs.lines().count());
+    }
+}
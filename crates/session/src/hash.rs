@@ -0,0 +1,367 @@
+//! URL hash encoding of the app state (items + answers).
+//!
+//! Current format: `#v2:<payload>` where the payload is base64url (no
+//! padding) of the raw-deflated text
+//!
+//! ```text
+//! abtsa;1f2e3d4c
+//! item1
+//! item2
+//! ```
+//!
+//! The header line holds one [`Answer`] token per question, then `;` and an
+//! FNV-1a checksum of the item list as 8 hex digits, then optionally `;` and
+//! the [`Mode`] token (none means an exact sort), then optionally `;` and
+//! the [`Sides`] token (none means fixed sides), then optionally `;` and the
+//! question to ask, which may itself contain `;` (none means the frontend's
+//! own); the remaining lines are the items, with any [`Item`](crate::Item)
+//! fields. Items come from a line-based text area, so they never contain
+//! newlines. Decoding stops at 1 MiB of text so a crafted link cannot
+//! inflate into something huge.
+//!
+//! The checksum catches links whose item list was edited after the answers
+//! were given: the answers then refer to questions that would no longer be
+//! asked, so they are dropped rather than replayed into a wrong ranking.
+//! Payloads without a checksum are accepted unverified.
+//!
+//! The legacy format `#item1,item2,item3!aabba` (items URI-component
+//! encoded, comma separated) is still accepted by [`parse`].
+//!
+//! [`try_parse`] says why a hash could not be read, for frontends that
+//! tell a corrupted link apart from an empty one.
+
+use std::fmt;
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use miniz_oxide::deflate::compress_to_vec;
+use miniz_oxide::inflate::{TINFLStatus, decompress_to_vec_with_limit};
+
+use crate::{Answer, Mode, Sides};
+
+/// Prefix that marks the versioned format.
+const V2_PREFIX: &str = "v2:";
+
+/// Largest decompressed payload accepted, in bytes.
+const MAX_PAYLOAD: usize = 1 << 20;
+
+/// Deflate level: the payload is tiny, so favour size over speed.
+const COMPRESSION_LEVEL: u8 = 9;
+
+/// State decoded from a hash.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HashState {
+    pub items: Vec<String>,
+    pub answers: Vec<Answer>,
+    pub mode: Mode,
+    pub sides: Sides,
+    /// The question asked instead of the frontend's default.
+    pub prompt: Option<String>,
+    /// The checksum did not match the items, so the answers were dropped.
+    pub mismatch: bool,
+}
+
+/// Why a hash could not be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HashError {
+    /// The payload is not base64url of deflated UTF-8 text.
+    Encoding,
+    /// The payload inflates past the size limit.
+    TooLarge,
+    /// The header's checksum, mode or sides are not valid.
+    Header,
+    /// An answer token that no [`Answer`] has.
+    Answer(char),
+}
+
+impl fmt::Display for HashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Encoding => f.write_str("the link is not validly encoded"),
+            Self::TooLarge => write!(f, "the link holds more than {MAX_PAYLOAD} bytes"),
+            Self::Header => f.write_str("the link's header is malformed"),
+            Self::Answer(token) => write!(f, "{token:?} is not an answer"),
+        }
+    }
+}
+
+impl std::error::Error for HashError {}
+
+impl From<HashError> for rankfast::Error {
+    fn from(e: HashError) -> Self {
+        Self::decode(e)
+    }
+}
+
+/// Parses a URL hash (with or without the leading `#`). Malformed hashes
+/// yield no items.
+#[must_use]
+pub fn parse(hash: &str) -> HashState {
+    try_parse(hash).unwrap_or_default()
+}
+
+/// Parses a URL hash like [`parse`], failing on malformed hashes.
+///
+/// # Errors
+///
+/// Fails with the first problem found, see [`HashError`]. An empty hash is
+/// not an error.
+pub fn try_parse(hash: &str) -> Result<HashState, HashError> {
+    let hash = hash.strip_prefix('#').unwrap_or(hash);
+    if hash.is_empty() {
+        return Ok(HashState::default());
+    }
+    match hash.strip_prefix(V2_PREFIX) {
+        Some(payload) => parse_v2(payload),
+        None => parse_legacy(hash),
+    }
+}
+
+/// Builds the hash (without `#`) for `items` and the `answers` given in
+/// `mode`, shown with `sides` under `prompt`.
+#[must_use]
+pub fn build(
+    items: &[String],
+    answers: &[Answer],
+    mode: Mode,
+    sides: Sides,
+    prompt: Option<&str>,
+) -> String {
+    let items: Vec<String> = items.iter().map(|item| item.replace('\n', " ")).collect();
+    let tokens: String = answers.iter().map(|a| a.token()).collect();
+    let mut text = format!("{tokens};{:08x}", checksum(&items));
+    if mode != Mode::Exact || sides != Sides::Fixed || prompt.is_some() {
+        text.push(';');
+        text.push_str(&mode.token());
+    }
+    if sides != Sides::Fixed || prompt.is_some() {
+        text.push(';');
+        text.push_str(&sides.token());
+    }
+    if let Some(prompt) = prompt {
+        text.push(';');
+        text.push_str(&prompt.replace('\n', " "));
+    }
+    for item in &items {
+        text.push('\n');
+        text.push_str(item);
+    }
+    let compressed = compress_to_vec(text.as_bytes(), COMPRESSION_LEVEL);
+    format!("{V2_PREFIX}{}", URL_SAFE_NO_PAD.encode(compressed))
+}
+
+/// 32-bit FNV-1a over the items, each terminated by a newline.
+fn checksum(items: &[String]) -> u32 {
+    let mut hash: u32 = 0x811c_9dc5;
+    for byte in items.iter().flat_map(|item| item.bytes().chain([b'\n'])) {
+        hash ^= u32::from(byte);
+        hash = hash.wrapping_mul(0x0100_0193);
+    }
+    hash
+}
+
+fn parse_v2(payload: &str) -> Result<HashState, HashError> {
+    let compressed = URL_SAFE_NO_PAD
+        .decode(payload)
+        .map_err(|_| HashError::Encoding)?;
+    let bytes = decompress_to_vec_with_limit(&compressed, MAX_PAYLOAD).map_err(|e| {
+        if e.status == TINFLStatus::HasMoreOutput {
+            HashError::TooLarge
+        } else {
+            HashError::Encoding
+        }
+    })?;
+    let text = String::from_utf8(bytes).map_err(|_| HashError::Encoding)?;
+
+    let mut lines = text.split('\n');
+    let header = lines.next().unwrap_or_default();
+    let items: Vec<String> = lines
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect();
+
+    let mut fields = header.splitn(5, ';');
+    let answers = fields.next().unwrap_or_default();
+    let expected = match fields.next() {
+        Some(sum) => Some(u32::from_str_radix(sum, 16).map_err(|_| HashError::Header)?),
+        None => None,
+    };
+    let mode = Mode::from_token(fields.next().unwrap_or_default()).ok_or(HashError::Header)?;
+    let sides = Sides::from_token(fields.next().unwrap_or_default()).ok_or(HashError::Header)?;
+    let prompt = fields.next().filter(|p| !p.is_empty()).map(str::to_string);
+    if expected.is_some_and(|sum| sum != checksum(&items)) {
+        return Ok(HashState {
+            items,
+            answers: Vec::new(),
+            mode,
+            sides,
+            prompt,
+            mismatch: true,
+        });
+    }
+    Ok(HashState {
+        items,
+        answers: parse_answers(answers)?,
+        mode,
+        sides,
+        prompt,
+        mismatch: false,
+    })
+}
+
+fn parse_legacy(hash: &str) -> Result<HashState, HashError> {
+    let (items_part, answers_part) = hash.split_once('!').unwrap_or((hash, ""));
+    let items = items_part
+        .split(',')
+        .map(decode_uri_component)
+        .filter(|s| !s.is_empty())
+        .collect();
+    Ok(HashState {
+        items,
+        answers: parse_answers(answers_part)?,
+        mode: Mode::Exact,
+        sides: Sides::Fixed,
+        prompt: None,
+        mismatch: false,
+    })
+}
+
+fn parse_answers(s: &str) -> Result<Vec<Answer>, HashError> {
+    s.chars()
+        .map(|token| Answer::from_token(token).ok_or(HashError::Answer(token)))
+        .collect()
+}
+
+/// Percent-decodes `s`, falling back to the raw text when the escapes are
+/// malformed or not UTF-8 (like `decodeURIComponent` throwing).
+fn decode_uri_component(s: &str) -> String {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut rest = s.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let Some(value) = tail
+                .get(..2)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            else {
+                return s.to_string();
+            };
+            bytes.push(value);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).unwrap_or_else(|_| s.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HashError, HashState, MAX_PAYLOAD, build, checksum, parse, try_parse};
+    use crate::{Answer, Mode, Sides};
+    use base64::Engine;
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use miniz_oxide::deflate::compress_to_vec;
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(ToString::to_string).collect()
+    }
+
+    fn v2(text: &str) -> String {
+        format!(
+            "#v2:{}",
+            URL_SAFE_NO_PAD.encode(compress_to_vec(text.as_bytes(), 9))
+        )
+    }
+
+    #[test]
+    fn round_trips_awkward_items() {
+        let items = strings(&["Salt, pepper", "100% juice", "a!b", "naïve", "#tag"]);
+        let answers = vec![Answer::A, Answer::B, Answer::Tie, Answer::Skip];
+        let hash = build(&items, &answers, Mode::Exact, Sides::Fixed, None);
+        assert!(hash.starts_with("v2:"));
+        assert_eq!(
+            parse(&format!("#{hash}")),
+            HashState {
+                items,
+                answers,
+                mode: Mode::Exact,
+                sides: Sides::Fixed,
+                prompt: None,
+                mismatch: false
+            }
+        );
+    }
+
+    #[test]
+    fn keeps_the_mode_sides_and_prompt() {
+        let items = strings(&["x", "y", "z"]);
+        for mode in [
+            Mode::Exact,
+            Mode::Approximate,
+            Mode::TopK(2),
+            Mode::Tiers,
+            Mode::QuickRank,
+            Mode::Tournament,
+        ] {
+            for sides in [Sides::Fixed, Sides::Shuffled(42)] {
+                let parsed = parse(&build(&items, &[Answer::A], mode, sides, None));
+                assert_eq!((parsed.mode, parsed.sides), (mode, sides));
+            }
+        }
+        let prompt = "Which bug is worse; the crash?";
+        let parsed = parse(&build(&items, &[], Mode::Exact, Sides::Fixed, Some(prompt)));
+        assert_eq!(parsed.prompt.as_deref(), Some(prompt));
+        assert_eq!(parsed.items, items);
+        let sum = checksum(&items);
+        assert_eq!(
+            parse(&v2(&format!("a;{sum:08x};x\nx\ny\nz"))),
+            HashState::default()
+        );
+    }
+
+    #[test]
+    fn still_reads_legacy_hashes() {
+        let state = parse("#Pizza,Sushi%20rolls,Tacos!ab");
+        assert_eq!(state.items, strings(&["Pizza", "Sushi rolls", "Tacos"]));
+        assert_eq!(state.answers, vec![Answer::A, Answer::B]);
+        assert_eq!(parse("#a,%E0%A4,b").items, strings(&["a", "%E0%A4", "b"]));
+        assert_eq!(parse(""), HashState::default());
+    }
+
+    #[test]
+    fn edited_items_drop_the_answers() {
+        let original = strings(&["x", "y", "z"]);
+        let sum = checksum(&original);
+        assert_eq!(
+            parse(&v2(&format!("ab;{sum:08x}\nx\ny\nz"))).answers.len(),
+            2
+        );
+
+        let edited = parse(&v2(&format!("ab;{sum:08x}\nx\nz\ny")));
+        assert!(edited.mismatch);
+        assert!(edited.answers.is_empty());
+        assert_eq!(edited.items, strings(&["x", "z", "y"]));
+
+        // Written before checksums existed: accepted as is.
+        assert_eq!(parse(&v2("ab\nx\nz\ny")).answers.len(), 2);
+    }
+
+    #[test]
+    fn rejects_corrupt_and_oversized_payloads() {
+        assert_eq!(parse("#v2:not*base64"), HashState::default());
+        assert_eq!(parse("#v2:AAAA"), HashState::default());
+        assert_eq!(parse(&v2("ab;nothex\nx")), HashState::default());
+
+        let bomb = vec![b'a'; MAX_PAYLOAD + 1];
+        let hash = URL_SAFE_NO_PAD.encode(compress_to_vec(&bomb, 9));
+        assert_eq!(parse(&format!("#v2:{hash}")), HashState::default());
+
+        assert_eq!(try_parse("#v2:AAAA"), Err(HashError::Encoding));
+        assert_eq!(try_parse(&format!("#v2:{hash}")), Err(HashError::TooLarge));
+        assert_eq!(try_parse(&v2("ab;nothex\nx")), Err(HashError::Header));
+        assert_eq!(try_parse("#x,y!az"), Err(HashError::Answer('z')));
+        assert_eq!(try_parse(""), Ok(HashState::default()));
+    }
+}
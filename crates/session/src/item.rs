@@ -0,0 +1,327 @@
+//! Items with more than a label.
+//!
+//! Items stay plain strings everywhere (URL hash, stepper, results), so the
+//! extra parts of an [`Item`] travel inside them as `|`-separated fields
+//! after the label:
+//!
+//! ```text
+//! Abbey Road | https://img.example/abbey.jpg | description: The last one recorded | link: https://example.org/abbey
+//! ```
+//!
+//! A bare URL field is the image; `image:`, `description:` and `link:`
+//! fields may come in any order. Fields are read from the end and the first
+//! one that is none of these ends them, so labels may contain `|` but field
+//! values may not. A bare image URL on its own line is an image labelled
+//! with its file name. Only `http(s)` and `data:image/` URLs are kept, so
+//! anything else stays part of the label.
+//!
+//! Words at the end of the label that start with `#` and a letter are
+//! tags, as in `Sushi #food #fancy`; [`by_tag`] splits a ranking by them.
+//!
+//! JSON documents may spell items as objects instead, see [`lines`].
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// File extensions that make a bare URL an image.
+const IMAGE_EXTENSIONS: [&str; 8] = [
+    ".png", ".jpg", ".jpeg", ".gif", ".webp", ".svg", ".avif", ".bmp",
+];
+
+/// The parts of an item.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Item {
+    pub label: String,
+    /// Longer text, shown on demand.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Picture URL shown with the label.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    /// Page about the item.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+    /// Categories, without the `#`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl Item {
+    /// Reads an item string.
+    #[must_use]
+    pub fn parse(text: &str) -> Self {
+        let mut item = Self::default();
+        let mut rest = text;
+        while let Some((head, field)) = rest.rsplit_once('|') {
+            if !item.read_field(field.trim()) {
+                break;
+            }
+            rest = head;
+        }
+        let label = rest.trim();
+        if rest.len() == text.len() && is_safe_url(label) && looks_like_image(label) {
+            item.image = Some(label.to_string());
+        } else {
+            let (label, tags) = split_tags(label);
+            item.label = label.to_string();
+            item.tags = tags;
+        }
+        if item.label.is_empty()
+            && let Some(image) = &item.image
+        {
+            item.label = file_name(image).to_string();
+        }
+        item
+    }
+
+    /// The item as a string that [`parse`](Self::parse) reads back; URLs
+    /// that would not be kept are left out.
+    #[must_use]
+    pub fn to_line(&self) -> String {
+        let mut line = clean(&self.label);
+        for tag in &self.tags {
+            line.push_str(" #");
+            line.push_str(&clean(tag).replace(char::is_whitespace, "-"));
+        }
+        if let Some(image) = self.image.as_deref().filter(|url| is_safe_url(url)) {
+            line.push_str(" | ");
+            line.push_str(&clean(image));
+        }
+        if let Some(description) = &self.description {
+            line.push_str(" | description: ");
+            line.push_str(&clean(description));
+        }
+        if let Some(link) = self.link.as_deref().filter(|url| is_safe_url(url)) {
+            line.push_str(" | link: ");
+            line.push_str(&clean(link));
+        }
+        line
+    }
+
+    /// Takes in one field, or says it is not one.
+    fn read_field(&mut self, field: &str) -> bool {
+        if let Some(text) = field.strip_prefix("description:") {
+            let text = text.trim();
+            self.description = (!text.is_empty()).then(|| text.to_string());
+            return true;
+        }
+        let (slot, url) = match (field.strip_prefix("image:"), field.strip_prefix("link:")) {
+            (Some(url), _) => (&mut self.image, url.trim()),
+            (_, Some(url)) => (&mut self.link, url.trim()),
+            (None, None) => (&mut self.image, field),
+        };
+        if !is_safe_url(url) {
+            return false;
+        }
+        *slot = Some(url.to_string());
+        true
+    }
+}
+
+/// The items of `order` (best first) that carry each tag, in the same
+/// order, by tag name.
+#[must_use]
+pub fn by_tag(items: &[String], order: &[usize]) -> BTreeMap<String, Vec<usize>> {
+    let mut groups: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for &item in order {
+        for tag in Item::parse(&items[item]).tags {
+            groups.entry(tag).or_default().push(item);
+        }
+    }
+    groups
+}
+
+/// Items of a JSON document, each a string or an [`Item`] object; objects
+/// are read as their [`Item::to_line`], and items are written as strings.
+pub mod lines {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::Item;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Entry {
+        Line(String),
+        Parts(Item),
+    }
+
+    /// Writes the items as they are.
+    ///
+    /// # Errors
+    ///
+    /// Fails only if the serializer does.
+    pub fn serialize<S: Serializer>(items: &[String], s: S) -> Result<S::Ok, S::Error> {
+        items.serialize(s)
+    }
+
+    /// Reads strings and objects alike.
+    ///
+    /// # Errors
+    ///
+    /// Fails on anything but a list of strings and objects with a `label`.
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<String>, D::Error> {
+        Ok(Vec::<Entry>::deserialize(d)?
+            .into_iter()
+            .map(|entry| match entry {
+                Entry::Line(line) => line,
+                Entry::Parts(item) => item.to_line(),
+            })
+            .collect())
+    }
+}
+
+fn is_safe_url(url: &str) -> bool {
+    let lower = url.to_ascii_lowercase();
+    lower.starts_with("https://")
+        || lower.starts_with("http://")
+        || lower.starts_with("data:image/")
+}
+
+fn looks_like_image(url: &str) -> bool {
+    if url.to_ascii_lowercase().starts_with("data:image/") {
+        return true;
+    }
+    let path = url
+        .split(['?', '#'])
+        .next()
+        .unwrap_or(url)
+        .to_ascii_lowercase();
+    IMAGE_EXTENSIONS.iter().any(|ext| path.ends_with(ext))
+}
+
+/// Last path segment of a URL, without query or fragment.
+fn file_name(url: &str) -> &str {
+    if url.to_ascii_lowercase().starts_with("data:") {
+        return "image";
+    }
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    path.trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .filter(|s| !s.is_empty())
+        .unwrap_or(url)
+}
+
+/// Takes the tags off the end of `label`; a label that is only tags keeps
+/// them as text.
+fn split_tags(label: &str) -> (&str, Vec<String>) {
+    let mut rest = label;
+    let mut tags = Vec::new();
+    while let Some((head, word)) = rest.rsplit_once(char::is_whitespace) {
+        let Some(tag) = word
+            .strip_prefix('#')
+            .filter(|tag| tag.starts_with(char::is_alphabetic) && !tag.contains('#'))
+        else {
+            break;
+        };
+        if !tags.iter().any(|seen| seen == tag) {
+            tags.push(tag.to_string());
+        }
+        rest = head.trim_end();
+    }
+    tags.reverse();
+    (rest, tags)
+}
+
+/// `text` fit for one field: no newlines, no separators.
+fn clean(text: &str) -> String {
+    text.trim().replace(['\n', '\r'], " ").replace('|', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Item, by_tag};
+
+    fn item(label: &str, image: Option<&str>) -> Item {
+        Item {
+            label: label.to_string(),
+            image: image.map(ToString::to_string),
+            ..Item::default()
+        }
+    }
+
+    #[test]
+    fn splits_label_and_image() {
+        assert_eq!(
+            Item::parse("Abbey Road | https://img.example/abbey.jpg"),
+            item("Abbey Road", Some("https://img.example/abbey.jpg"))
+        );
+        assert_eq!(
+            Item::parse("https://img.example/covers/revolver.png?w=200"),
+            item(
+                "revolver.png",
+                Some("https://img.example/covers/revolver.png?w=200")
+            )
+        );
+    }
+
+    #[test]
+    fn leaves_text_and_unsafe_urls_alone() {
+        assert_eq!(Item::parse("Pizza"), item("Pizza", None));
+        assert_eq!(Item::parse("a|b"), item("a|b", None));
+        assert_eq!(
+            Item::parse("x|javascript:alert(1)"),
+            item("x|javascript:alert(1)", None)
+        );
+        assert_eq!(
+            Item::parse("https://example.com/page"),
+            item("https://example.com/page", None)
+        );
+        assert_eq!(
+            Item::parse("x | link: javascript:alert(1)"),
+            item("x | link: javascript:alert(1)", None)
+        );
+    }
+
+    #[test]
+    fn reads_back_every_part() {
+        let full = Item {
+            label: "Salt | pepper".to_string(),
+            description: Some("Two\nlines".to_string()),
+            image: Some("https://img.example/s.png".to_string()),
+            link: Some("https://example.org/s".to_string()),
+            tags: vec!["spice".to_string()],
+        };
+        let line = full.to_line();
+        assert_eq!(
+            line,
+            "Salt / pepper #spice | https://img.example/s.png | description: Two lines \
+             | link: https://example.org/s"
+        );
+        assert_eq!(
+            Item::parse(&line),
+            Item {
+                label: "Salt / pepper".to_string(),
+                description: Some("Two lines".to_string()),
+                ..full
+            }
+        );
+        assert_eq!(
+            Item::parse("Tea | description: Green"),
+            Item {
+                label: "Tea".to_string(),
+                description: Some("Green".to_string()),
+                ..Item::default()
+            }
+        );
+    }
+
+    #[test]
+    fn tags_split_the_ranking() {
+        let sushi = Item::parse("Sushi rolls #food #fancy #food");
+        assert_eq!(sushi.label, "Sushi rolls");
+        assert_eq!(sushi.tags, ["fancy", "food"]);
+        assert_eq!(Item::parse(&sushi.to_line()), sushi);
+        assert_eq!(Item::parse("#tag"), item("#tag", None));
+        assert_eq!(Item::parse("Song #1"), item("Song #1", None));
+
+        let items = ["Pizza #food", "Sushi #food #fancy", "Opera #fancy", "Walk"]
+            .map(String::from)
+            .to_vec();
+        let groups = by_tag(&items, &[2, 1, 3, 0]);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups["fancy"], [2, 1]);
+        assert_eq!(groups["food"], [1, 0]);
+    }
+}
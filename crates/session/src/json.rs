@@ -0,0 +1,306 @@
+//! Sessions as JSON documents.
+//!
+//! The document is what every frontend saves and loads:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "items": ["Pizza", "Sushi", "Tacos"],
+//!   "answers": "abt",
+//!   "engine": "exact",
+//!   "sides": {"shuffled": 1234},
+//!   "prompt": "Which would you rather eat?",
+//!   "known": [[2, 0]],
+//!   "final_order": [1, 0, 2]
+//! }
+//! ```
+//!
+//! `items` may also hold [`Item`](crate::Item) objects such as
+//! `{"label": "Tacos", "description": "Al pastor"}`, which are read as
+//! their item strings. `answers` uses the same tokens as the URL [`hash`]. `engine` is
+//! `"exact"`, `"approximate"`, `"tiers"`, `"quick_rank"`,
+//! `"binary_insertion"`, `"tournament"` or `{"top_k": 10}`, see
+//! [`Mode::name`](crate::Mode::name), and defaults to `"exact"`. `sides` is `"fixed"` or `{"shuffled": seed}`, see [`Sides`],
+//! and defaults to `"fixed"`. `prompt` is the question asked instead of the
+//! frontend's own, left out when there is none. `known` lists
+//! `[better, worse]` item indices judged before the session, which the
+//! answers were given on top of (see [`Engine::seeded`](crate::Engine::seeded));
+//! it is left out when empty and has no place in the URL hash. `final_order`
+//! is the ranking as the user finalized it, item indices best first, when
+//! they reordered the result by hand; the answers still give the ranking as
+//! compared. It is left out when the result was kept as is, and has no
+//! place in the URL hash either. Documents from a newer [`VERSION`] are
+//! rejected rather than misread.
+
+use std::fmt;
+
+use rankfast::Ranking;
+use serde::{Deserialize, Serialize};
+
+use crate::{Answer, Engine, HashState, Mode, SessionError, SessionState, Sides, hash, replay};
+
+/// Schema version written by [`Session::to_json`].
+pub const VERSION: u32 = 1;
+
+/// A saved session: the items, the answers given so far and the engine
+/// they were given to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    /// Schema version the document was written with.
+    pub version: u32,
+    #[serde(with = "crate::item::lines")]
+    pub items: Vec<String>,
+    /// One answer per question asked, in order.
+    #[serde(with = "tokens")]
+    pub answers: Vec<Answer>,
+    #[serde(default)]
+    pub engine: Mode,
+    #[serde(default)]
+    pub sides: Sides,
+    /// The question asked, when not the frontend's default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    /// `(better, worse)` pairs known before the first question.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub known: Vec<(usize, usize)>,
+    /// The ranking after changes by hand, best first, see
+    /// [`finalized_ranking`](Self::finalized_ranking).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub final_order: Option<Vec<usize>>,
+}
+
+/// Why a JSON document could not be read as a [`Session`].
+#[derive(Debug)]
+pub enum JsonError {
+    /// Not JSON, or not shaped like a session.
+    Invalid(serde_json::Error),
+    /// Written by a newer version of the schema.
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Invalid(e) => write!(f, "not a rankfast session: {e}"),
+            Self::UnsupportedVersion(v) => {
+                write!(f, "session version {v} is newer than {VERSION}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for JsonError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Invalid(e) => Some(e),
+            Self::UnsupportedVersion(_) => None,
+        }
+    }
+}
+
+impl From<JsonError> for rankfast::Error {
+    fn from(e: JsonError) -> Self {
+        Self::decode(e)
+    }
+}
+
+impl Session {
+    /// A session at the current [`VERSION`], with [`Sides::Fixed`], the
+    /// default prompt, nothing known in advance and no changes by hand.
+    #[must_use]
+    pub fn new(items: Vec<String>, answers: Vec<Answer>, engine: Mode) -> Self {
+        Self {
+            version: VERSION,
+            items,
+            answers,
+            engine,
+            sides: Sides::Fixed,
+            prompt: None,
+            known: Vec::new(),
+            final_order: None,
+        }
+    }
+
+    /// Reads a session document.
+    ///
+    /// # Errors
+    ///
+    /// Fails on malformed documents and on versions newer than [`VERSION`].
+    pub fn from_json(text: &str) -> Result<Self, JsonError> {
+        let session: Self = serde_json::from_str(text).map_err(JsonError::Invalid)?;
+        if session.version > VERSION {
+            return Err(JsonError::UnsupportedVersion(session.version));
+        }
+        Ok(session)
+    }
+
+    /// The session as a pretty-printed document.
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        // Strings, a token string and a plain enum always serialize.
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    /// Replays the answers on top of the known pairs, see
+    /// [`validate_session`](crate::validate_session).
+    ///
+    /// # Errors
+    ///
+    /// Fails if the ranking is complete before the last answer.
+    pub fn validate(&self) -> Result<SessionState, SessionError> {
+        let engine = Engine::seeded(self.engine, self.items.len(), &self.known);
+        replay::replay(engine, &self.answers)
+    }
+
+    /// The ranking as finalized by hand, when [`final_order`](Self::final_order)
+    /// ranks every item exactly once. The ranking as compared comes from
+    /// [`validate`](Self::validate).
+    #[must_use]
+    pub fn finalized_ranking(&self) -> Option<Ranking<usize>> {
+        let order = self.final_order.as_ref()?;
+        let mut seen = vec![false; self.items.len()];
+        for &item in order {
+            if std::mem::replace(seen.get_mut(item)?, true) {
+                return None;
+            }
+        }
+        (order.len() == seen.len()).then(|| Ranking::new(order.clone()))
+    }
+
+    /// The URL hash (without `#`) holding the same session, except for the
+    /// known pairs and the final order.
+    #[must_use]
+    pub fn to_hash(&self) -> String {
+        hash::build(
+            &self.items,
+            &self.answers,
+            self.engine,
+            self.sides,
+            self.prompt.as_deref(),
+        )
+    }
+}
+
+impl From<HashState> for Session {
+    fn from(state: HashState) -> Self {
+        Self {
+            sides: state.sides,
+            prompt: state.prompt,
+            ..Self::new(state.items, state.answers, state.mode)
+        }
+    }
+}
+
+/// Answers as one token string, like in the hash.
+mod tokens {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use crate::Answer;
+
+    pub(super) fn serialize<S: Serializer>(answers: &[Answer], s: S) -> Result<S::Ok, S::Error> {
+        s.collect_str(&answers.iter().map(|a| a.token()).collect::<String>())
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<Answer>, D::Error> {
+        String::deserialize(d)?
+            .chars()
+            .map(|c| {
+                Answer::from_token(c)
+                    .ok_or_else(|| D::Error::custom(format!("unknown answer token {c:?}")))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{JsonError, Session, VERSION};
+    use crate::{Answer, Mode, Sides, hash};
+
+    fn session(engine: Mode) -> Session {
+        let items = ["Pizza", "Sushi", "Tacos"].map(String::from).to_vec();
+        Session::new(items, vec![Answer::A, Answer::B, Answer::Tie], engine)
+    }
+
+    #[test]
+    fn round_trips_through_json_and_the_hash() {
+        for engine in [
+            Mode::Exact,
+            Mode::Approximate,
+            Mode::TopK(2),
+            Mode::Tiers,
+            Mode::QuickRank,
+            Mode::BinaryInsertion,
+            Mode::Tournament,
+        ] {
+            let saved = session(engine);
+            assert_eq!(Session::from_json(&saved.to_json()).unwrap(), saved);
+            assert_eq!(Session::from(hash::parse(&saved.to_hash())), saved);
+        }
+        let shuffled = Session {
+            sides: Sides::Shuffled(9),
+            prompt: Some("Which is tastier?".to_string()),
+            ..session(Mode::Exact)
+        };
+        assert_eq!(Session::from_json(&shuffled.to_json()).unwrap(), shuffled);
+        assert_eq!(Session::from(hash::parse(&shuffled.to_hash())), shuffled);
+        assert!(session(Mode::TopK(2)).to_json().contains(r#""top_k": 2"#));
+    }
+
+    #[test]
+    fn keeps_the_order_finalized_by_hand_apart() {
+        // Item 2 ranks between 0 and 1, which takes every question.
+        let adjusted = Session {
+            answers: vec![Answer::A, Answer::A, Answer::Tie],
+            final_order: Some(vec![2, 0, 1]),
+            ..session(Mode::Exact)
+        };
+        assert_eq!(Session::from_json(&adjusted.to_json()).unwrap(), adjusted);
+        assert_eq!(adjusted.finalized_ranking().unwrap(), vec![2, 0, 1]);
+        // The answers still rank as compared.
+        assert_eq!(
+            adjusted.validate().unwrap().finished().unwrap(),
+            vec![0, 2, 1]
+        );
+        assert!(!session(Mode::Exact).to_json().contains("final_order"));
+
+        for order in [vec![2, 0], vec![2, 0, 0], vec![2, 0, 3]] {
+            let broken = Session {
+                final_order: Some(order),
+                ..session(Mode::Exact)
+            };
+            assert_eq!(broken.finalized_ranking(), None);
+        }
+    }
+
+    #[test]
+    fn reads_the_documented_format() {
+        let text = r#"{"version": 1, "items": ["x", "y"], "answers": "bs"}"#;
+        let loaded = Session::from_json(text).unwrap();
+        assert_eq!(loaded.answers, vec![Answer::B, Answer::Skip]);
+        assert_eq!(loaded.engine, Mode::Exact);
+        assert_eq!(loaded.sides, Sides::Fixed);
+        assert_eq!(loaded.prompt, None);
+
+        let text = r#"{"version": 1, "items": ["x", {"label": "y", "link": "https://y.example"}], "answers": ""}"#;
+        assert_eq!(
+            Session::from_json(text).unwrap().items,
+            ["x", "y | link: https://y.example"]
+        );
+
+        let text = r#"{"version": 1, "items": [], "answers": "x"}"#;
+        assert!(matches!(
+            Session::from_json(text),
+            Err(JsonError::Invalid(_))
+        ));
+        let text = format!(
+            r#"{{"version": {}, "items": [], "answers": ""}}"#,
+            VERSION + 1
+        );
+        assert!(matches!(
+            Session::from_json(&text),
+            Err(JsonError::UnsupportedVersion(_))
+        ));
+    }
+}
@@ -0,0 +1,341 @@
+//! Session state shared by the rankfast frontends.
+//!
+//! A session is a list of items, the [`Answer`]s given so far and the
+//! [`Mode`] they were given in; replaying the answers through the mode's
+//! [`Engine`] reproduces the ranking, and [`validate_session`] checks they
+//! fit. [`Sides`] records how each question was shown, which the answers
+//! never depend on. [`hash`] encodes sessions in the web app's URL hash and
+//! [`json`] as documents to save or send, so a run started in one frontend
+//! can be continued in another. [`analyze`] sums up how a session went,
+//! [`dataset`] exports its answers as pairwise preference data and
+//! [`head_to_head`] lays every pair out as a matrix. Items are strings that
+//! may carry a description, image, link and tags, see [`Item`].
+
+pub mod analytics;
+pub mod dataset;
+pub mod engine;
+pub mod hash;
+pub mod item;
+pub mod json;
+pub mod matrix;
+pub mod replay;
+
+pub use analytics::{Stats, analyze};
+pub use dataset::{Preference, preferences};
+pub use engine::Engine;
+pub use hash::{HashError, HashState};
+pub use item::Item;
+pub use json::Session;
+pub use matrix::{Cell, head_to_head};
+pub use replay::{SessionError, SessionState, validate_session, without_item};
+
+use rankfast::active::default_budget;
+use rankfast::quickrank::QuickRank;
+use rankfast::tiers::Tiers;
+use rankfast::topk::TopK;
+use rankfast::{EngineKind, estimate_turns};
+use serde::{Deserialize, Serialize};
+
+/// One answer to "which do you prefer?".
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Answer {
+    /// The first item shown (`a`).
+    A,
+    /// The second item shown (`b`).
+    B,
+    /// Both are equally good (`t`).
+    Tie,
+    /// No opinion on this pair (`s`).
+    Skip,
+}
+
+impl Answer {
+    /// The answer's one-letter token.
+    #[must_use]
+    pub fn token(self) -> char {
+        match self {
+            Self::A => 'a',
+            Self::B => 'b',
+            Self::Tie => 't',
+            Self::Skip => 's',
+        }
+    }
+
+    /// Reads a token written by [`token`](Self::token).
+    #[must_use]
+    pub fn from_token(c: char) -> Option<Self> {
+        match c {
+            'a' => Some(Self::A),
+            'b' => Some(Self::B),
+            't' => Some(Self::Tie),
+            's' => Some(Self::Skip),
+            _ => None,
+        }
+    }
+
+    /// The strict answer fed to the sorter for the question `a` vs `b`.
+    ///
+    /// The sorter needs a strict order, so ties and skips keep the items in
+    /// input order: whichever was entered first ranks higher.
+    #[must_use]
+    pub fn a_is_better(self, a: usize, b: usize) -> bool {
+        match self {
+            Self::A => true,
+            Self::B => false,
+            Self::Tie | Self::Skip => a < b,
+        }
+    }
+
+    /// The same answer seen from the other item's side.
+    #[must_use]
+    pub fn flipped(self) -> Self {
+        match self {
+            Self::A => Self::B,
+            Self::B => Self::A,
+            other => other,
+        }
+    }
+}
+
+/// How the items are ranked. Saved sessions and configuration name the
+/// modes as [`EngineKind`] names its engines, see [`name`](Self::name).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Mode {
+    /// Full Ford-Johnson sort.
+    #[default]
+    Exact,
+    /// Fixed question budget, ranked by fitted scores.
+    Approximate,
+    /// Only the best `k` items, in order.
+    TopK(usize),
+    /// Tiers first, then each tier sorted.
+    Tiers,
+    /// Quicksort: every item of a group against one anchor in a row.
+    QuickRank,
+    /// Binary insertion of each item into the ranking of those before it.
+    BinaryInsertion,
+    /// Every pair once, ranked by wins.
+    Tournament,
+}
+
+impl Mode {
+    /// Hash token for the mode; empty for [`Mode::Exact`] so older links
+    /// keep meaning the same thing.
+    #[must_use]
+    pub fn token(self) -> String {
+        match self {
+            Self::Exact => String::new(),
+            Self::Approximate => "a".to_string(),
+            Self::TopK(k) => format!("k{k}"),
+            Self::Tiers => "t".to_string(),
+            Self::QuickRank => "q".to_string(),
+            Self::BinaryInsertion => "b".to_string(),
+            Self::Tournament => "r".to_string(),
+        }
+    }
+
+    /// Reads a token written by [`token`](Self::token).
+    #[must_use]
+    pub fn from_token(token: &str) -> Option<Self> {
+        match token {
+            "" => Some(Self::Exact),
+            "a" => Some(Self::Approximate),
+            "t" => Some(Self::Tiers),
+            "q" => Some(Self::QuickRank),
+            "b" => Some(Self::BinaryInsertion),
+            "r" => Some(Self::Tournament),
+            _ => token.strip_prefix('k')?.parse().ok().map(Self::TopK),
+        }
+    }
+
+    /// Upper bound on the questions this mode asks for `n` items, except
+    /// for [`Mode::QuickRank`]: quicksort has no useful bound, so it gives
+    /// the count for even splits.
+    #[must_use]
+    pub fn estimate(self, n: usize) -> usize {
+        match self {
+            Self::Exact => estimate_turns(n),
+            Self::Approximate => default_budget(n),
+            Self::TopK(k) => TopK::estimate(n, k),
+            Self::Tiers => Tiers::estimate(n),
+            Self::QuickRank => QuickRank::estimate(n),
+            Self::BinaryInsertion => TopK::estimate(n, n),
+            Self::Tournament => n * n.saturating_sub(1) / 2,
+        }
+    }
+
+    /// The mode's name in configuration, the [`EngineKind`] name of its
+    /// engine, or `top_k:K` for [`Mode::TopK`].
+    #[must_use]
+    pub fn name(self) -> String {
+        let kind = match self {
+            Self::TopK(k) => return format!("top_k:{k}"),
+            Self::Exact => EngineKind::FordJohnson,
+            Self::Approximate => EngineKind::Ratings,
+            Self::Tiers => EngineKind::Tiers,
+            Self::QuickRank => EngineKind::QuickRank,
+            Self::BinaryInsertion => EngineKind::BinaryInsertion,
+            Self::Tournament => EngineKind::Tournament,
+        };
+        kind.name().to_string()
+    }
+
+    /// Reads a name written by [`name`](Self::name).
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.strip_prefix("top_k:") {
+            Some(k) => k.parse().ok().map(Self::TopK),
+            None => EngineKind::from_name(name).map(Self::from),
+        }
+    }
+}
+
+impl From<EngineKind> for Mode {
+    fn from(kind: EngineKind) -> Self {
+        match kind {
+            EngineKind::FordJohnson => Self::Exact,
+            EngineKind::BinaryInsertion => Self::BinaryInsertion,
+            EngineKind::Tournament => Self::Tournament,
+            EngineKind::QuickRank => Self::QuickRank,
+            EngineKind::Ratings => Self::Approximate,
+            EngineKind::Tiers => Self::Tiers,
+        }
+    }
+}
+
+/// Which item of each question is shown first.
+///
+/// People lean towards one side (usually the left), so
+/// [`Sides::Shuffled`] swaps the pair on about half the questions, picked by
+/// a seed kept with the session so a reloaded run shows the same layout.
+/// Answers are always recorded for the engine's `a` vs `b`:
+/// [`present`](Self::present) gives the pair as shown and
+/// [`record`](Self::record) maps an answer to the shown pair back.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Sides {
+    /// `a` first, always.
+    #[default]
+    Fixed,
+    /// Swapped per question, from the seed.
+    Shuffled(u32),
+}
+
+impl Sides {
+    /// Hash token; empty for [`Sides::Fixed`].
+    #[must_use]
+    pub fn token(self) -> String {
+        match self {
+            Self::Fixed => String::new(),
+            Self::Shuffled(seed) => format!("s{seed:x}"),
+        }
+    }
+
+    /// Reads a token written by [`token`](Self::token).
+    #[must_use]
+    pub fn from_token(token: &str) -> Option<Self> {
+        if token.is_empty() {
+            return Some(Self::Fixed);
+        }
+        let seed = token.strip_prefix('s')?;
+        u32::from_str_radix(seed, 16).ok().map(Self::Shuffled)
+    }
+
+    /// Whether question number `question` (from 0) shows `b` first.
+    #[must_use]
+    pub fn swapped(self, question: usize) -> bool {
+        let Self::Shuffled(seed) = self else {
+            return false;
+        };
+        // SplitMix64 finalizer over seed and question.
+        let mut z = (u64::from(seed) << 32 ^ question as u64).wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        (z ^ (z >> 31)) & 1 == 1
+    }
+
+    /// The pair `(first, second)` to show for question number `question`
+    /// asking `a` vs `b`.
+    #[must_use]
+    pub fn present(self, question: usize, a: usize, b: usize) -> (usize, usize) {
+        if self.swapped(question) {
+            (b, a)
+        } else {
+            (a, b)
+        }
+    }
+
+    /// The answer to record for `shown`, given to the pair as
+    /// [`present`](Self::present) showed it.
+    #[must_use]
+    pub fn record(self, question: usize, shown: Answer) -> Answer {
+        if self.swapped(question) {
+            shown.flipped()
+        } else {
+            shown
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rankfast::EngineKind;
+
+    use super::{Answer, Mode, Sides};
+
+    #[test]
+    fn mode_tokens_round_trip() {
+        for mode in [
+            Mode::Exact,
+            Mode::Approximate,
+            Mode::TopK(10),
+            Mode::Tiers,
+            Mode::QuickRank,
+            Mode::BinaryInsertion,
+            Mode::Tournament,
+        ] {
+            assert_eq!(Mode::from_token(&mode.token()), Some(mode));
+            assert_eq!(Mode::from_name(&mode.name()), Some(mode));
+        }
+        assert_eq!(Mode::from_token("kx"), None);
+        assert_eq!(Mode::from_token("z"), None);
+        assert_eq!(Mode::from_name("top_k:x"), None);
+    }
+
+    #[test]
+    fn modes_are_saved_under_their_engine_names() {
+        for kind in EngineKind::ALL {
+            let saved = serde_json::to_string(&Mode::from(kind)).unwrap();
+            assert_eq!(saved, format!("{:?}", kind.name()));
+        }
+    }
+
+    #[test]
+    fn ties_and_skips_keep_input_order() {
+        assert!(Answer::Tie.a_is_better(1, 4));
+        assert!(!Answer::Skip.a_is_better(4, 1));
+        assert_eq!(Answer::A.flipped(), Answer::B);
+        assert_eq!(Answer::Tie.flipped(), Answer::Tie);
+    }
+
+    #[test]
+    fn shuffled_sides_decode_to_the_engine_pair() {
+        for sides in [Sides::Fixed, Sides::Shuffled(0), Sides::Shuffled(0xbeef)] {
+            assert_eq!(Sides::from_token(&sides.token()), Some(sides));
+        }
+        assert_eq!(Sides::from_token("sz"), None);
+
+        let sides = Sides::Shuffled(7);
+        let swaps = (0..1000).filter(|&q| sides.swapped(q)).count();
+        assert!((400..600).contains(&swaps), "{swaps}");
+        for question in 0..20 {
+            // Picking whichever item is shown first always means that item.
+            let (first, _) = sides.present(question, 3, 5);
+            let recorded = sides.record(question, Answer::A);
+            assert_eq!(recorded.a_is_better(3, 5), first == 3);
+            assert_eq!(sides.record(question, Answer::Tie), Answer::Tie);
+        }
+        assert!((0..100).all(|q| !Sides::Fixed.swapped(q)));
+    }
+}
@@ -0,0 +1,99 @@
+//! Every pair of items at a glance.
+//!
+//! [`head_to_head`] tells for each pair whether it was answered directly,
+//! follows from other answers by transitivity, or is still open, so the
+//! reason one item ranks above another can be audited. Only won and lost
+//! answers carry over transitively: a tie or skip says nothing about a
+//! third item.
+
+use crate::Answer;
+
+/// How the row item fares against the column item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cell {
+    /// The same item.
+    Itself,
+    /// Answered better when the two met.
+    Won,
+    /// Answered worse when the two met.
+    Lost,
+    /// Answered equal when the two met.
+    Tied,
+    /// Never met, but better by transitivity.
+    InferredWon,
+    /// Never met, but worse by transitivity.
+    InferredLost,
+    /// Skipped, or never met and not decided by other answers.
+    Open,
+}
+
+/// The `n × n` matrix of `history`'s `(a, b, answer)` questions: row `i`,
+/// column `j` is how `i` fares against `j`. The last answer wins if a pair
+/// was asked more than once.
+#[must_use]
+pub fn head_to_head(n: usize, history: &[(usize, usize, Answer)]) -> Vec<Vec<Cell>> {
+    let mut cells = vec![vec![Cell::Open; n]; n];
+    for (i, row) in cells.iter_mut().enumerate() {
+        row[i] = Cell::Itself;
+    }
+    for &(a, b, answer) in history {
+        if a >= n || b >= n || a == b {
+            continue;
+        }
+        let (ab, ba) = match answer {
+            Answer::A => (Cell::Won, Cell::Lost),
+            Answer::B => (Cell::Lost, Cell::Won),
+            Answer::Tie => (Cell::Tied, Cell::Tied),
+            Answer::Skip => (Cell::Open, Cell::Open),
+        };
+        cells[a][b] = ab;
+        cells[b][a] = ba;
+    }
+
+    // `beaten[i]` lists the items `i` beat directly.
+    let beaten: Vec<Vec<usize>> = cells
+        .iter()
+        .map(|row| (0..n).filter(|&j| row[j] == Cell::Won).collect())
+        .collect();
+    for from in 0..n {
+        let mut seen = vec![false; n];
+        let mut stack = beaten[from].clone();
+        while let Some(item) = stack.pop() {
+            if std::mem::replace(&mut seen[item], true) {
+                continue;
+            }
+            stack.extend(&beaten[item]);
+            if cells[from][item] == Cell::Open {
+                cells[from][item] = Cell::InferredWon;
+                cells[item][from] = Cell::InferredLost;
+            }
+        }
+    }
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cell, head_to_head};
+    use crate::Answer;
+
+    #[test]
+    fn wins_carry_over_and_ties_do_not() {
+        let history = [
+            (0, 1, Answer::A),
+            (2, 1, Answer::B),
+            (2, 3, Answer::Tie),
+            (0, 3, Answer::Skip),
+        ];
+        let cells = head_to_head(4, &history);
+        assert_eq!(cells[0][0], Cell::Itself);
+        assert_eq!((cells[0][1], cells[1][0]), (Cell::Won, Cell::Lost));
+        assert_eq!(cells[1][2], Cell::Won);
+        assert_eq!(
+            (cells[0][2], cells[2][0]),
+            (Cell::InferredWon, Cell::InferredLost)
+        );
+        assert_eq!(cells[2][3], Cell::Tied);
+        assert_eq!((cells[0][3], cells[1][3]), (Cell::Open, Cell::Open));
+    }
+}
@@ -0,0 +1,308 @@
+//! Checked replay of a session's answers.
+//!
+//! A session stores only its answers, so resuming one means replaying them
+//! through the [`Engine`] for its [`Mode`]. [`validate_session`] does that
+//! and says where the answers stopped fitting instead of dropping the rest:
+//! answers left over once the ranking is complete usually mean the item list
+//! changed after they were given. Answers that run out early are a session
+//! in progress, which [`SessionState::finished`] reports as an error for
+//! callers that need a complete ranking.
+
+use std::fmt;
+
+use rankfast::{Ranking, Step};
+
+use crate::{Answer, Engine, Mode};
+
+/// A session replayed up to its last answer.
+pub struct SessionState {
+    /// The engine, ready for the next answer.
+    pub engine: Engine,
+    /// What the engine asks next.
+    pub step: Step,
+    /// Every question asked, with the answer given to it.
+    pub history: Vec<(usize, usize, Answer)>,
+}
+
+impl SessionState {
+    /// The question waiting for an answer, or `None` once the ranking is
+    /// complete.
+    #[must_use]
+    pub fn question(&self) -> Option<(usize, usize)> {
+        match self.step {
+            Step::Compare { a, b, .. } => Some((a, b)),
+            Step::Done => None,
+        }
+    }
+
+    /// The final ranking.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`SessionError::Truncated`] while a question is open.
+    pub fn finished(mut self) -> Result<Ranking<usize>, SessionError> {
+        match self.step {
+            Step::Compare { a, b, .. } => Err(SessionError::Truncated {
+                used: self.history.len(),
+                next: (a, b),
+            }),
+            Step::Done => Ok(self.engine.take_order().unwrap_or_default()),
+        }
+    }
+}
+
+/// Where a replay stopped fitting its answers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionError {
+    /// The ranking was complete after the first `used` of the `given`
+    /// answers.
+    TooManyAnswers { used: usize, given: usize },
+    /// All `used` answers fit, but question `next` is still open.
+    Truncated { used: usize, next: (usize, usize) },
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooManyAnswers { used, given } => write!(
+                f,
+                "the ranking was complete after answer {used} of {given}; \
+                 were items changed after answering?"
+            ),
+            Self::Truncated { used, next: (a, b) } => write!(
+                f,
+                "the answers stop after {used}, with {a} vs {b} still to answer"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+impl From<SessionError> for rankfast::Error {
+    fn from(e: SessionError) -> Self {
+        Self::decode(e)
+    }
+}
+
+/// Replays `answers` to the questions the engine for `mode` asks about
+/// `items`.
+///
+/// # Errors
+///
+/// Fails with [`SessionError::TooManyAnswers`] if the ranking is complete
+/// before the last answer.
+pub fn validate_session(
+    items: &[String],
+    answers: &[Answer],
+    mode: Mode,
+) -> Result<SessionState, SessionError> {
+    replay(Engine::new(mode, items.len()), answers)
+}
+
+/// Replays `answers` through `engine`, see [`validate_session`].
+pub(crate) fn replay(mut engine: Engine, answers: &[Answer]) -> Result<SessionState, SessionError> {
+    let mut step = engine.step();
+    let mut history = Vec::with_capacity(answers.len());
+    for &answer in answers {
+        let Step::Compare { a, b, .. } = step else {
+            return Err(SessionError::TooManyAnswers {
+                used: history.len(),
+                given: answers.len(),
+            });
+        };
+        history.push((a, b, answer));
+        step = engine.answer(answer, a, b);
+    }
+    Ok(SessionState {
+        engine,
+        step,
+        history,
+    })
+}
+
+/// The answers that rebuild the session over `items` without item `idx`,
+/// up to the first question they leave open.
+///
+/// The session is replayed, the answers about `idx` are dropped, and what
+/// they implied is kept: if `a` beat `idx` and `idx` beat `b`, `a` still
+/// ranks above `b`. A fresh run over the remaining items, renumbered past
+/// `idx`, is then answered with every question those answers decide,
+/// directly or by transitivity. Skipped questions are forgotten and ties
+/// stay ties when the same pair is asked again, see
+/// [`Stepper::remove_item`](rankfast::Stepper::remove_item) for the same
+/// on a stepper. Answers left over once the ranking is complete are
+/// ignored.
+#[must_use]
+pub fn without_item(items: &[String], answers: &[Answer], mode: Mode, idx: usize) -> Vec<Answer> {
+    let n = items.len();
+    if idx >= n {
+        return answers.to_vec();
+    }
+    let mut engine = Engine::new(mode, n);
+    let mut step = engine.step();
+    let mut better = Vec::new();
+    let mut ties = Vec::new();
+    for &answer in answers {
+        let Step::Compare { a, b, .. } = step else {
+            break;
+        };
+        match answer {
+            Answer::Skip => {}
+            Answer::Tie => ties.push((a.min(b), a.max(b))),
+            _ if answer.a_is_better(a, b) => better.push((a, b)),
+            _ => better.push((b, a)),
+        }
+        step = engine.answer(answer, a, b);
+    }
+    // Ties still order the sorter's items, in input order.
+    better.extend(ties.iter().copied());
+    let above: Vec<usize> = better.iter().filter(|p| p.1 == idx).map(|p| p.0).collect();
+    let below: Vec<usize> = better.iter().filter(|p| p.0 == idx).map(|p| p.1).collect();
+    for &a in &above {
+        better.extend(below.iter().map(|&b| (a, b)));
+    }
+    let shift = |item: usize| if item > idx { item - 1 } else { item };
+    let kept = |pairs: Vec<(usize, usize)>| -> Vec<(usize, usize)> {
+        pairs
+            .into_iter()
+            .filter(|&(a, b)| a != idx && b != idx)
+            .map(|(a, b)| (shift(a), shift(b)))
+            .collect()
+    };
+    let (better, ties) = (kept(better), kept(ties));
+
+    let mut engine = Engine::new(mode, n - 1);
+    let mut step = engine.step();
+    let mut rebuilt = Vec::new();
+    while let Step::Compare { a, b, .. } = step {
+        let answer = if ties.contains(&(a.min(b), a.max(b))) {
+            Answer::Tie
+        } else if reaches(&better, a, b) {
+            Answer::A
+        } else if reaches(&better, b, a) {
+            Answer::B
+        } else {
+            break;
+        };
+        rebuilt.push(answer);
+        step = engine.answer(answer, a, b);
+    }
+    rebuilt
+}
+
+/// Whether a chain of `(better, worse)` pairs leads from `from` to `to`.
+fn reaches(pairs: &[(usize, usize)], from: usize, to: usize) -> bool {
+    let mut seen = vec![from];
+    let mut stack = vec![from];
+    while let Some(node) = stack.pop() {
+        for &(better, worse) in pairs {
+            if better == node && !seen.contains(&worse) {
+                if worse == to {
+                    return true;
+                }
+                seen.push(worse);
+                stack.push(worse);
+            }
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SessionError, validate_session, without_item};
+    use crate::{Answer, Mode};
+
+    fn items(n: usize) -> Vec<String> {
+        (0..n).map(|i| format!("item {i}")).collect()
+    }
+
+    #[test]
+    fn reports_where_the_answers_stop_fitting() {
+        // Item 2 ranks between 0 and 1, which takes every question.
+        let answers = [Answer::A, Answer::A, Answer::Tie];
+        let complete = validate_session(&items(3), &answers, Mode::Exact).unwrap();
+        assert_eq!(complete.question(), None);
+        assert_eq!(complete.history.len(), 3);
+        assert_eq!(complete.finished().unwrap().len(), 3);
+
+        let partial = validate_session(&items(3), &answers[..1], Mode::Exact).unwrap();
+        let next = partial.question().unwrap();
+        assert_eq!(partial.history, vec![(0, 1, Answer::A)]);
+        assert_eq!(
+            partial.finished().err(),
+            Some(SessionError::Truncated { used: 1, next })
+        );
+
+        // The same answers after an item was removed.
+        assert_eq!(
+            validate_session(&items(2), &answers, Mode::Exact).err(),
+            Some(SessionError::TooManyAnswers { used: 1, given: 3 })
+        );
+        let error = validate_session(&items(2), &answers, Mode::Exact)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            rankfast::Error::from(error),
+            rankfast::Error::Decode(_)
+        ));
+        assert!(validate_session(&[], &[], Mode::Approximate).is_ok());
+    }
+
+    #[test]
+    fn replays_every_mode() {
+        for mode in [
+            Mode::Exact,
+            Mode::Approximate,
+            Mode::TopK(2),
+            Mode::Tiers,
+            Mode::QuickRank,
+            Mode::BinaryInsertion,
+            Mode::Tournament,
+        ] {
+            let mut answers = Vec::new();
+            loop {
+                let state = validate_session(&items(5), &answers, mode).unwrap();
+                if state.question().is_none() {
+                    break;
+                }
+                answers.push(Answer::B);
+            }
+            // Always answering "b" keeps every item below QuickRank's anchor.
+            let bound = if mode == Mode::QuickRank {
+                5 * 4 / 2
+            } else {
+                mode.estimate(5)
+            };
+            assert!(answers.len() <= bound, "{mode:?}");
+            answers.push(Answer::A);
+            assert!(matches!(
+                validate_session(&items(5), &answers, mode),
+                Err(SessionError::TooManyAnswers { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn removing_an_item_keeps_what_the_answers_implied() {
+        let values = [3, 0, 4, 1, 2];
+        let mut answers = Vec::new();
+        while let Some((a, b)) = validate_session(&items(5), &answers, Mode::Exact)
+            .unwrap()
+            .question()
+        {
+            answers.push(if values[a] > values[b] {
+                Answer::A
+            } else {
+                Answer::B
+            });
+        }
+        // The best item is gone, but everything ranked below it stays put.
+        let rebuilt = without_item(&items(5), &answers, Mode::Exact, 2);
+        assert!(rebuilt.len() < answers.len());
+        let state = validate_session(&items(4), &rebuilt, Mode::Exact).unwrap();
+        assert_eq!(*state.finished().unwrap(), [0, 3, 2, 1]);
+        assert!(without_item(&items(5), &[], Mode::Exact, 0).is_empty());
+    }
+}
@@ -0,0 +1,177 @@
+//! JavaScript bindings for the rankfast engine.
+//!
+//! Build with `wasm-pack build crates/wasm` (or `cargo build --target
+//! wasm32-unknown-unknown` plus `wasm-bindgen`). Names follow JavaScript
+//! conventions:
+//!
+//! ```js
+//! const stepper = new Stepper(items.length);
+//! for (let q = stepper.nextQuestion(); q; q = stepper.answer(prefer(q.a, q.b))) {}
+//! const order = stepper.order(); // indices, best first
+//! ```
+
+use js_sys::{Array, Function};
+use wasm_bindgen::prelude::*;
+
+/// Sorts `items` with `better(a, b)`, which should return `true` when `a`
+/// ranks above `b`.
+///
+/// # Errors
+///
+/// Returns the first exception thrown by `better`, which is not called
+/// again after that.
+#[wasm_bindgen(js_name = rankItems)]
+pub fn rank_items(items: Vec<JsValue>, better: &Function) -> Result<Array, JsValue> {
+    // A `JsValue` cannot cross into `rankfast::Error`, so it waits here.
+    let mut thrown = JsValue::UNDEFINED;
+    let ranked = rankfast::try_rank_items(items, |a, b| {
+        better
+            .call2(&JsValue::NULL, a, b)
+            .map(|result| result.is_truthy())
+            .map_err(|e| {
+                thrown = e;
+                "better threw"
+            })
+    });
+    match ranked {
+        Ok(ranked) => Ok(ranked.into_iter().collect()),
+        Err(_) => Err(thrown),
+    }
+}
+
+/// Upper bound on the number of questions needed to rank `n` items.
+#[wasm_bindgen(js_name = estimateTurns)]
+#[must_use]
+pub fn estimate_turns(n: usize) -> usize {
+    rankfast::estimate_turns(n)
+}
+
+/// Kendall's tau between two orderings of item indices, best first: 1 for
+/// the same order, -1 for the reverse. Only indices in both count.
+#[wasm_bindgen(js_name = kendallTau)]
+#[must_use]
+#[allow(clippy::needless_pass_by_value)] // wasm-bindgen hands over owned vectors
+pub fn kendall_tau(a: Vec<usize>, b: Vec<usize>) -> f64 {
+    rankfast::metrics::kendall_tau(&a, &b)
+}
+
+/// Spearman's rho between two orderings, like [`kendall_tau`].
+#[wasm_bindgen(js_name = spearmanRho)]
+#[must_use]
+#[allow(clippy::needless_pass_by_value)] // wasm-bindgen hands over owned vectors
+pub fn spearman_rho(a: Vec<usize>, b: Vec<usize>) -> f64 {
+    rankfast::metrics::spearman_rho(&a, &b)
+}
+
+/// [`kendall_tau`] with disagreements near the top of `a` weighing more.
+#[wasm_bindgen(js_name = weightedKendallTau)]
+#[must_use]
+#[allow(clippy::needless_pass_by_value)] // wasm-bindgen hands over owned vectors
+pub fn weighted_kendall_tau(a: Vec<usize>, b: Vec<usize>) -> f64 {
+    rankfast::metrics::weighted_kendall_tau(&a, &b)
+}
+
+/// [`spearman_rho`] with items near the top of `a` weighing more.
+#[wasm_bindgen(js_name = weightedSpearmanRho)]
+#[must_use]
+#[allow(clippy::needless_pass_by_value)] // wasm-bindgen hands over owned vectors
+pub fn weighted_spearman_rho(a: Vec<usize>, b: Vec<usize>) -> f64 {
+    rankfast::metrics::weighted_spearman_rho(&a, &b)
+}
+
+/// A pending question: is item `a` better than item `b`?
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy)]
+pub struct Question {
+    pub a: usize,
+    pub b: usize,
+}
+
+/// Step-by-step ranking of the indices `0..n`.
+#[wasm_bindgen]
+pub struct Stepper {
+    inner: rankfast::Stepper,
+    current: Option<Question>,
+    order: Option<rankfast::Ranking<usize>>,
+}
+
+#[wasm_bindgen]
+impl Stepper {
+    /// Creates a stepper that ranks the indices `0..n`.
+    #[wasm_bindgen(constructor)]
+    #[must_use]
+    pub fn new(n: usize) -> Self {
+        let mut stepper = Self {
+            inner: rankfast::Stepper::new(n),
+            current: None,
+            order: None,
+        };
+        stepper.advance();
+        stepper
+    }
+
+    /// The question to ask now, or `undefined` once the ranking is done.
+    #[wasm_bindgen(js_name = nextQuestion)]
+    #[must_use]
+    pub fn next_question(&self) -> Option<Question> {
+        self.current
+    }
+
+    /// Answers the current question and returns the next one.
+    pub fn answer(&mut self, a_is_better: bool) -> Option<Question> {
+        if self.current.is_some() {
+            self.inner.answer(a_is_better);
+            self.advance();
+        }
+        self.current
+    }
+
+    /// Answers the current question as a tie and returns the next one. Tied
+    /// items share a place in [`positions`](Self::positions).
+    #[wasm_bindgen(js_name = answerTie)]
+    pub fn answer_tie(&mut self) -> Option<Question> {
+        if self.current.is_some() {
+            self.inner.answer_tie();
+            self.advance();
+        }
+        self.current
+    }
+
+    /// Whether every question has been answered.
+    #[wasm_bindgen(getter = isDone)]
+    #[must_use]
+    pub fn is_done(&self) -> bool {
+        self.order.is_some()
+    }
+
+    /// Number of answers given so far.
+    #[wasm_bindgen(getter = comparisonsMade)]
+    #[must_use]
+    pub fn comparisons_made(&self) -> usize {
+        self.inner.comparisons_made()
+    }
+
+    /// Final order of indices, best first, or `undefined` until done.
+    #[must_use]
+    pub fn order(&self) -> Option<Vec<usize>> {
+        self.order.as_ref().map(|order| order.to_vec())
+    }
+
+    /// The 1-based place of each index in [`order`](Self::order), shared by
+    /// tied items, or `undefined` until done.
+    #[must_use]
+    pub fn positions(&self) -> Option<Vec<usize>> {
+        let order = self.order.as_ref()?;
+        Some(order.positions().map(|(place, _)| place).collect())
+    }
+
+    fn advance(&mut self) {
+        match self.inner.step() {
+            rankfast::Step::Compare { a, b, .. } => self.current = Some(Question { a, b }),
+            rankfast::Step::Done => {
+                self.current = None;
+                self.order = self.inner.take_order();
+            }
+        }
+    }
+}
@@ -0,0 +1,76 @@
+// Offline support for Rankfast.
+//
+// The app is a static shell plus hashed assets (Trunk adds a content hash to
+// every file name), so:
+// - on install, the shell is fetched and every asset it references is cached;
+// - page loads go to the network first and fall back to the cached shell;
+// - assets are served from the cache first and cached on first use.
+// Bump CACHE to drop everything cached by an older worker.
+
+const CACHE = "rankfast-v1";
+
+// Files that are not referenced from index.html's attributes.
+const EXTRA = ["manifest.webmanifest", "icon.svg"];
+
+async function precache() {
+  const cache = await caches.open(CACHE);
+  const response = await fetch("./", { cache: "no-cache" });
+  const html = await response.clone().text();
+  await cache.put("./", response);
+
+  const assets = new Set(EXTRA);
+  for (const match of html.matchAll(/(?:href|src)="([^"#]+)"|from\s+'([^']+)'/g)) {
+    assets.add(match[1] ?? match[2]);
+  }
+  const sameOrigin = [...assets]
+    .map((path) => new URL(path, self.registration.scope))
+    .filter((url) => url.origin === self.location.origin);
+  await cache.addAll(sameOrigin.map((url) => url.href));
+}
+
+self.addEventListener("install", (event) => {
+  event.waitUntil(precache().then(() => self.skipWaiting()));
+});
+
+self.addEventListener("activate", (event) => {
+  event.waitUntil(
+    caches
+      .keys()
+      .then((keys) => Promise.all(keys.filter((key) => key !== CACHE).map((key) => caches.delete(key))))
+      .then(() => self.clients.claim()),
+  );
+});
+
+async function fromNetworkFirst(request) {
+  const cache = await caches.open(CACHE);
+  try {
+    const response = await fetch(request);
+    if (response.ok) {
+      await cache.put("./", response.clone());
+    }
+    return response;
+  } catch {
+    return (await cache.match("./")) ?? Response.error();
+  }
+}
+
+async function fromCacheFirst(request) {
+  const cached = await caches.match(request);
+  if (cached) {
+    return cached;
+  }
+  const response = await fetch(request);
+  if (response.ok) {
+    const cache = await caches.open(CACHE);
+    await cache.put(request, response.clone());
+  }
+  return response;
+}
+
+self.addEventListener("fetch", (event) => {
+  const { request } = event;
+  if (request.method !== "GET" || new URL(request.url).origin !== self.location.origin) {
+    return;
+  }
+  event.respondWith(request.mode === "navigate" ? fromNetworkFirst(request) : fromCacheFirst(request));
+});
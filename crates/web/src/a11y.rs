@@ -0,0 +1,67 @@
+//! Accessibility helpers shared by the views.
+//!
+//! Each question and the final ranking replace the previous view wholesale,
+//! which would drop keyboard focus on the body and leave screen readers
+//! silent. [`focus_on_mount`] moves focus to the new region instead. Motion
+//! follows the system's `prefers-reduced-motion` setting in CSS, and
+//! [`MotionToggle`] lets users turn it off on top of that.
+
+use leptos::html::ElementType;
+use leptos::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::i18n::use_texts;
+
+/// Local storage key for the reduced-motion choice.
+const STORAGE_KEY: &str = "rankfast-reduce-motion";
+
+/// Focuses the element behind `node` once it is mounted.
+pub(crate) fn focus_on_mount<E>(node: NodeRef<E>)
+where
+    E: ElementType,
+    E::Output: JsCast + Clone + 'static,
+{
+    Effect::new(move |_| {
+        if let Some(el) = node.get()
+            && let Some(el) = el.dyn_ref::<web_sys::HtmlElement>()
+        {
+            let _ = el.focus();
+        }
+    });
+}
+
+/// Whether the user asked for reduced motion last time.
+pub(crate) fn saved_reduce_motion() -> bool {
+    window()
+        .local_storage()
+        .ok()
+        .flatten()
+        .and_then(|storage| storage.get_item(STORAGE_KEY).ok().flatten())
+        .is_some_and(|value| value == "1")
+}
+
+/// Checkbox that switches off transitions, remembered across visits.
+#[component]
+pub(crate) fn MotionToggle(
+    reduce_motion: ReadSignal<bool>,
+    set_reduce_motion: WriteSignal<bool>,
+) -> impl IntoView {
+    let t = use_texts();
+    view! {
+        <label class="motion-toggle">
+            <input
+                type="checkbox"
+                prop:checked=move || reduce_motion.get()
+                on:change=move |ev| {
+                    let reduce = event_target_checked(&ev);
+                    if let Ok(Some(storage)) = window().local_storage() {
+                        let _ = storage.set_item(STORAGE_KEY, if reduce { "1" } else { "0" });
+                    }
+                    set_reduce_motion.set(reduce);
+                }
+            />
+            " "
+            {move || t.get().reduce_motion}
+        </label>
+    }
+}
@@ -0,0 +1,156 @@
+use leptos::ev::KeyboardEvent;
+use leptos::html;
+use leptos::prelude::*;
+use rankfast_session::{Answer, Item};
+
+use crate::a11y::focus_on_mount;
+use crate::i18n::{fill, use_texts};
+use crate::item::ItemLabel;
+use crate::timing::describe;
+
+/// One question. The region takes focus when it appears, so keyboard and
+/// screen-reader users land on the new pair, and the arrow keys pick a side.
+/// "Finish now" stops asking and shows the ranking so far. While an item's
+/// place is being searched, `narrows` names it with the places left.
+/// `prompt` replaces the default question. Item descriptions show as
+/// tooltips.
+#[component]
+#[allow(clippy::needless_pass_by_value)] // component props are owned
+pub(crate) fn Compare(
+    a: String,
+    b: String,
+    on_answer: Callback<Answer>,
+    on_finish: Callback<()>,
+    narrows: Option<(String, usize)>,
+    prompt: Option<String>,
+) -> impl IntoView {
+    let t = use_texts();
+    let region = NodeRef::<html::Section>::new();
+    focus_on_mount(region);
+
+    let (a_item, b_item) = (Item::parse(&a), Item::parse(&b));
+    let (a_name, b_name) = (a_item.label, b_item.label);
+    let on_key = move |ev: KeyboardEvent| {
+        let answer = match ev.key().as_str() {
+            "ArrowLeft" => Answer::A,
+            "ArrowRight" => Answer::B,
+            _ => return,
+        };
+        ev.prevent_default();
+        on_answer.run(answer);
+    };
+
+    view! {
+        <section
+            class="compare"
+            node_ref=region
+            tabindex="-1"
+            aria-labelledby="compare-prompt"
+            aria-describedby="compare-keys"
+            on:keydown=on_key
+        >
+            <h2 id="compare-prompt" class="compare-prompt">
+                {move || prompt.clone().unwrap_or_else(|| t.get().prompt.to_string())}
+            </h2>
+            {narrows
+                .map(|(name, candidates)| {
+                    view! {
+                        <p class="compare-context">
+                            {move || fill(t.get().narrows, &[&name, &candidates])}
+                        </p>
+                    }
+                })}
+            <p id="compare-keys" class="sr-only">{move || t.get().keys_hint}</p>
+            <div class="compare-buttons">
+                <button
+                    class="choice-btn"
+                    aria-label=move || fill(t.get().choose, &[&a_name])
+                    aria-keyshortcuts="ArrowLeft"
+                    title=a_item.description
+                    on:click=move |_| on_answer.run(Answer::A)
+                >
+                    <ItemLabel text=a />
+                </button>
+                <span class="vs" aria-hidden="true">{move || t.get().versus}</span>
+                <button
+                    class="choice-btn"
+                    aria-label=move || fill(t.get().choose, &[&b_name])
+                    aria-keyshortcuts="ArrowRight"
+                    title=b_item.description
+                    on:click=move |_| on_answer.run(Answer::B)
+                >
+                    <ItemLabel text=b />
+                </button>
+            </div>
+            <div class="compare-extra">
+                <button class="extra-btn" on:click=move |_| on_answer.run(Answer::Tie)>
+                    {move || t.get().tie}
+                </button>
+                <button class="extra-btn" on:click=move |_| on_answer.run(Answer::Skip)>
+                    {move || t.get().skip}
+                </button>
+                <button class="extra-btn finish-btn" on:click=move |_| on_finish.run(())>
+                    {move || t.get().finish_now}
+                </button>
+            </div>
+        </section>
+    }
+}
+
+/// Questions answered out of the projected total, as a bar and as a polite
+/// live announcement for screen readers, with the time left at the current
+/// pace once it can be told.
+#[component]
+pub(crate) fn Progress(
+    #[prop(into)] comparisons: Signal<usize>,
+    #[prop(into)] total: Signal<usize>,
+    #[prop(into)] done: Signal<bool>,
+    /// Milliseconds left, see [`Pace::time_left`](crate::timing::Pace::time_left).
+    #[prop(into)]
+    time_left: Signal<Option<f64>>,
+) -> impl IntoView {
+    let t = use_texts();
+    let percent = move || {
+        (100 * comparisons.get())
+            .checked_div(total.get())
+            .unwrap_or(100)
+    };
+    let announcement = move || {
+        if done.get() {
+            t.get().ranking_done.to_string()
+        } else {
+            fill(
+                t.get().progress_announce,
+                &[&(comparisons.get() + 1), &total.get()],
+            )
+        }
+    };
+
+    view! {
+        <div class="progress-area">
+            <div class="progress-text" aria-hidden="true">
+                <span>{move || t.get().comparison}</span>
+                <span class="progress-numbers">
+                    {move || comparisons.get()} " / " {move || total.get()}
+                </span>
+            </div>
+            <div
+                class="progress-bar"
+                role="progressbar"
+                aria-label=move || t.get().progress_label
+                aria-valuemin="0"
+                aria-valuemax=move || total.get().to_string()
+                aria-valuenow=move || comparisons.get().to_string()
+            >
+                <div class="progress-fill" style:width=move || format!("{}%", percent()) />
+            </div>
+            {move || {
+                time_left
+                    .get()
+                    .filter(|_| !done.get())
+                    .map(|ms| view! { <p class="time-left">{describe(t.get(), ms)}</p> })
+            }}
+            <p class="sr-only" aria-live="polite">{announcement}</p>
+        </div>
+    }
+}
@@ -0,0 +1,99 @@
+//! A panel with the exact sort's internals, for contributors.
+//!
+//! Opening the app with `?debug` in the URL shows, under each question,
+//! every recursion level of the [`Stepper`](rankfast::Stepper): what it
+//! ranks, how far its pairing got, and while it inserts, the chain so far,
+//! the partners still to insert and the bounds of the running search. It is
+//! rebuilt from the answers like the rest of the state, so it follows every
+//! answer and every step back. Meant for working on the algorithm, its
+//! labels are not translated.
+
+use leptos::prelude::*;
+use rankfast::stepper::{LevelStage, LevelState};
+use rankfast_session::{Engine, Item};
+
+/// Whether the page was opened with `?debug`.
+pub(crate) fn enabled() -> bool {
+    let search = window().location().search().unwrap_or_default();
+    search
+        .trim_start_matches('?')
+        .split('&')
+        .any(|param| param == "debug" || param.starts_with("debug="))
+}
+
+/// The levels of `engine`, when it is the exact sort and the panel is on.
+pub(crate) fn levels(engine: &Engine) -> Vec<LevelState> {
+    match engine {
+        Engine::Exact(stepper) if enabled() => stepper.levels(),
+        _ => Vec::new(),
+    }
+}
+
+/// The recursion levels, the whole list first.
+#[component]
+#[allow(clippy::needless_pass_by_value)] // component props are owned
+pub(crate) fn DebugPanel(
+    #[prop(into)] items: Signal<Vec<String>>,
+    levels: Vec<LevelState>,
+) -> impl IntoView {
+    let name = |item: usize| items.with(|items| Item::parse(&items[item]).label);
+    let names = |list: &[usize]| list.iter().map(|&i| name(i)).collect::<Vec<_>>().join(", ");
+    let levels = levels
+        .into_iter()
+        .enumerate()
+        .map(|(depth, level)| {
+            let stage = match level.stage {
+                LevelStage::Pairing { compared, pairs } => {
+                    view! { <p>{format!("Pairing: {compared} of {pairs} pairs compared")}</p> }
+                        .into_any()
+                }
+                LevelStage::RankingWinners => {
+                    view! { <p>"Waiting for the level below to rank the pair winners"</p> }
+                        .into_any()
+                }
+                LevelStage::Inserting {
+                    chain,
+                    pending,
+                    search,
+                } => {
+                    let chain = chain
+                        .iter()
+                        .enumerate()
+                        .map(|(pos, &item)| {
+                            let in_range = search.is_some_and(|s| (s.lo..s.hi).contains(&pos));
+                            view! { <li class:debug-in-range=in_range>{name(item)}</li> }
+                        })
+                        .collect_view();
+                    let search = search.map(|s| {
+                        format!(
+                            "Searching for {} in places {}..={}",
+                            name(s.item),
+                            s.lo,
+                            s.hi
+                        )
+                    });
+                    view! {
+                        <p>{search}</p>
+                        <ol class="debug-chain" start="0">{chain}</ol>
+                        <p>{format!("Pending: {}", names(&pending))}</p>
+                    }
+                    .into_any()
+                }
+                LevelStage::Done => view! { <p>"Ranked"</p> }.into_any(),
+            };
+            view! {
+                <li>
+                    <h4>{format!("Level {depth}: {} items", level.items.len())}</h4>
+                    <p class="debug-items">{names(&level.items)}</p>
+                    {stage}
+                </li>
+            }
+        })
+        .collect_view();
+    view! {
+        <details class="debug-panel" open>
+            <summary>"Debug: sort internals"</summary>
+            <ol class="debug-levels">{levels}</ol>
+        </details>
+    }
+}
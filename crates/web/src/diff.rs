@@ -0,0 +1,112 @@
+//! Two rankings side by side.
+//!
+//! Pasting two session links (or just their hashes) shows both rankings,
+//! with how far each item moved from the first to the second and how many
+//! pairs changed order, see [`rankfast::diff()`]. Unfinished sessions are
+//! compared on the best ranking their answers support.
+
+use leptos::prelude::*;
+use rankfast::diff::{Change, Movement};
+use rankfast::{RankDiff, diff};
+use rankfast_session::{Item, hash};
+
+use crate::derive_state;
+use crate::i18n::{Texts, fill, use_texts};
+
+/// The links to compare and what changed between them.
+#[component]
+pub(crate) fn RankingDiff() -> impl IntoView {
+    let t = use_texts();
+    let (old, set_old) = signal(String::new());
+    let (new, set_new) = signal(String::new());
+    let result = Memo::new(move |_| {
+        let old = ranking_of(&old.get())?;
+        let new = ranking_of(&new.get())?;
+        Some(diff(&old, &new))
+    });
+
+    let link = move |label: fn(&Texts) -> &'static str,
+                     value: ReadSignal<String>,
+                     set: WriteSignal<String>| {
+        view! {
+            <label class="diff-link">
+                {move || label(t.get())}
+                <input
+                    type="url"
+                    prop:value=move || value.get()
+                    on:input=move |ev| set.set(event_target_value(&ev))
+                />
+            </label>
+        }
+    };
+
+    view! {
+        <details class="diff-panel">
+            <summary>{move || t.get().diff_title}</summary>
+            {link(|t| t.diff_old, old, set_old)}
+            {link(|t| t.diff_new, new, set_new)}
+            {move || result.get().map(|result| view! { <DiffColumns result /> })}
+        </details>
+    }
+}
+
+/// Both rankings, each item marked with its move.
+#[component]
+#[allow(clippy::needless_pass_by_value)] // component props are owned
+fn DiffColumns(result: RankDiff<String>) -> impl IntoView {
+    let t = use_texts();
+    let RankDiff {
+        changes,
+        kendall_tau,
+        pairs,
+    } = result;
+    let mut before: Vec<&Change<String>> = changes.iter().filter(|c| c.old.is_some()).collect();
+    before.sort_by_key(|c| c.old);
+    let row = |change: &Change<String>| {
+        let movement = change.movement;
+        let badge = move || match movement {
+            Movement::Up(places) => format!("\u{25b2}{places}"),
+            Movement::Down(places) => format!("\u{25bc}{places}"),
+            Movement::Same => String::new(),
+            Movement::New => t.get().diff_added.to_string(),
+            Movement::Removed => t.get().diff_removed.to_string(),
+        };
+        view! {
+            <li
+                class="diff-item"
+                class:up=matches!(movement, Movement::Up(_) | Movement::New)
+                class:down=matches!(movement, Movement::Down(_) | Movement::Removed)
+            >
+                <span class="diff-label">{change.item.clone()}</span>
+                " "
+                <span class="diff-badge">{badge}</span>
+            </li>
+        }
+    };
+    view! {
+        <p class="diff-distance">{move || fill(t.get().diff_distance, &[&kendall_tau, &pairs])}</p>
+        <div class="diff-columns">
+            <ol>{before.into_iter().map(row).collect_view()}</ol>
+            <ol>{changes.iter().filter(|c| c.new.is_some()).map(row).collect_view()}</ol>
+        </div>
+    }
+}
+
+/// Labels of the ranking a session link leads to, best first; `None`
+/// without items or if the link cannot be read.
+fn ranking_of(link: &str) -> Option<Vec<String>> {
+    let link = link.trim();
+    let state = hash::parse(link.split_once('#').map_or(link, |(_, hash)| hash));
+    if state.items.is_empty() {
+        return None;
+    }
+    let order = derive_state(&state.items, state.mode, &state.answers, true)
+        .ok()?
+        .ranking?;
+    Some(
+        order
+            .iter()
+            .map(|&item| Item::parse(&state.items[item]).label)
+            .collect(),
+    )
+}
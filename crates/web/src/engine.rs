@@ -0,0 +1,48 @@
+//! Engine choice in the input form.
+//!
+//! An exact sort of a long list asks too many questions to finish in one
+//! sitting, so above [`BUDGET_WARNING`] estimated questions the form offers
+//! an approximate ranking or a top-k selection instead. The chosen
+//! [`Mode`] is stored in the URL hash next to the
+//! answers, since replaying answers through a different engine would
+//! produce a different ranking.
+//!
+//! Below the warning, the form tells how many questions an exact ranking
+//! usually takes, from [`rankfast::simulate_expected_turns`].
+//!
+//! Opening the app with `?engine=<name>` starts every ranking with that
+//! engine, whatever the length of the list. The names are those of
+//! [`Mode::from_name`], the ones sessions
+//! are saved with and the CLI's `--engine` takes.
+
+use leptos::prelude::window;
+use rankfast_session::Mode;
+
+/// Estimated questions above which the input form suggests a cheaper mode.
+pub(crate) const BUDGET_WARNING: usize = 150;
+
+/// Places kept by [`Mode::TopK`] when offered from the form.
+pub(crate) const DEFAULT_TOP_K: usize = 10;
+
+/// The engine named by `?engine=` in the page's URL, if any.
+pub(crate) fn configured() -> Option<Mode> {
+    let search = window().location().search().unwrap_or_default();
+    search
+        .trim_start_matches('?')
+        .split('&')
+        .find_map(|param| param.strip_prefix("engine="))
+        .and_then(Mode::from_name)
+}
+
+/// Simulated runs behind [`usual_turns`].
+const SIMULATION_TRIALS: usize = 40;
+
+/// Questions an exact ranking of `n` items usually asks.
+pub(crate) fn usual_turns(n: usize) -> f64 {
+    rankfast::simulate_expected_turns(n, SIMULATION_TRIALS, random_u64).mean
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)] // in 0..2^53
+fn random_u64() -> u64 {
+    (js_sys::Math::random() * 9_007_199_254_740_992.0) as u64
+}
@@ -0,0 +1,44 @@
+//! Guards against losing a ranking halfway through.
+//!
+//! A run lives only in the URL, so closing the tab or loading another list
+//! over it leaves it to be dug out of the browser history. While a run has
+//! answers but no ranking yet, [`before_unload`] has the browser ask before
+//! the page goes, and [`confirm_leave`] asks before the app replaces the
+//! run, copying its link first so it can be resumed later. The embedded
+//! widget belongs to its host page and never asks.
+
+use leptos::prelude::*;
+
+use crate::widget;
+
+/// Asks the browser to confirm leaving the page while `in_progress`.
+pub(crate) fn before_unload(ev: &web_sys::BeforeUnloadEvent, in_progress: bool) {
+    if in_progress && !widget::ENABLED {
+        ev.prevent_default();
+        // Older browsers only ask when a return value is set.
+        ev.set_return_value("");
+    }
+}
+
+/// Whether the user agrees to leave the unfinished run at `resume_hash`,
+/// asked with `message`. On yes, the run's full link is copied to the
+/// clipboard.
+pub(crate) fn confirm_leave(message: &str, resume_hash: &str) -> bool {
+    if widget::ENABLED {
+        return true;
+    }
+    let win = window();
+    if !win.confirm_with_message(message).unwrap_or(true) {
+        return false;
+    }
+    let location = win.location();
+    let url = format!(
+        "{}{}{}#{resume_hash}",
+        location.origin().unwrap_or_default(),
+        location.pathname().unwrap_or_default(),
+        location.search().unwrap_or_default()
+    );
+    // Best effort: without clipboard access the link is still in history.
+    let _ = win.navigator().clipboard().write_text(&url);
+    true
+}
@@ -0,0 +1,79 @@
+//! A JavaScript API for pages that drive the app.
+//!
+//! Every build sets `window.rankfast`, so a host page can start rankings
+//! and collect results instead of building URL hashes. Same-origin parents
+//! of an iframe reach it as `iframe.contentWindow.rankfast`; cross-origin
+//! ones use the widget's messages, see [`crate::widget`].
+//!
+//! - `rankfast.load(items)` starts a new ranking of an array of strings,
+//!   cleaned up like typed items, and returns `true`; anything but an array
+//!   of strings is ignored and returns `false`
+//! - `rankfast.onResult(callback)` calls `callback({ranking, comparisons})`
+//!   each time a ranking is complete, items best first
+
+use std::cell::RefCell;
+
+use js_sys::{Array, Function, Object, Reflect};
+use leptos::prelude::*;
+use rankfast::normalize_items;
+use wasm_bindgen::prelude::*;
+
+thread_local! {
+    /// Functions passed to `onResult`.
+    static ON_RESULT: RefCell<Vec<Function>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Sets `window.rankfast`, starting rankings with `load`.
+pub(crate) fn install(load: impl Fn(Vec<String>) + 'static) {
+    let load = Closure::<dyn Fn(JsValue) -> bool>::new(move |value: JsValue| {
+        let Some(items) = items(&value) else {
+            return false;
+        };
+        load(items);
+        true
+    });
+    let on_result = Closure::<dyn Fn(JsValue)>::new(|callback: JsValue| {
+        if let Ok(callback) = callback.dyn_into::<Function>() {
+            ON_RESULT.with_borrow_mut(|callbacks| callbacks.push(callback));
+        }
+    });
+    let api = Object::new();
+    let _ = Reflect::set(&api, &"load".into(), load.as_ref());
+    let _ = Reflect::set(&api, &"onResult".into(), on_result.as_ref());
+    let _ = Reflect::set(&window(), &"rankfast".into(), &api);
+    // The API lives as long as the page.
+    load.forget();
+    on_result.forget();
+}
+
+/// Calls every `onResult` callback with `result`.
+pub(crate) fn notify(result: &Object) {
+    // Callbacks may register more callbacks.
+    let callbacks = ON_RESULT.with_borrow(Clone::clone);
+    for callback in callbacks {
+        // A failing callback is the host's problem, not the next one's.
+        let _ = callback.call1(&JsValue::NULL, result);
+    }
+}
+
+/// `{ranking, comparisons}`, plus the given `type` for messages.
+pub(crate) fn result(kind: Option<&str>, ranking: &[String], comparisons: usize) -> Object {
+    let result = Object::new();
+    if let Some(kind) = kind {
+        let _ = Reflect::set(&result, &"type".into(), &kind.into());
+    }
+    let ranking: Array = ranking.iter().map(|item| JsValue::from_str(item)).collect();
+    let _ = Reflect::set(&result, &"ranking".into(), &ranking);
+    let _ = Reflect::set(&result, &"comparisons".into(), &comparisons.into());
+    result
+}
+
+/// Items sent by a host page, normalized; `None` unless `value` is an
+/// array of strings.
+pub(crate) fn items(value: &JsValue) -> Option<Vec<String>> {
+    if !Array::is_array(value) {
+        return None;
+    }
+    let strings: Option<Vec<String>> = Array::from(value).iter().map(|v| v.as_string()).collect();
+    Some(normalize_items(strings?.iter().map(String::as_str)).items)
+}
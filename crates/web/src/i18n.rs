@@ -0,0 +1,788 @@
+//! UI strings in every supported language.
+//!
+//! Each [`Locale`] has one static [`Texts`] table. Strings that take values
+//! use numbered placeholders (`{0}`, `{1}`, ...) filled in by [`fill`], so
+//! translations can reorder them. The active locale lives in a context
+//! signal set up by [`provide_locale`]: it starts from a choice saved in
+//! local storage or the browser's preferred languages, and the header's
+//! switcher changes it.
+
+use std::fmt::Display;
+
+use leptos::prelude::*;
+
+/// Local storage key for a manually chosen locale.
+const STORAGE_KEY: &str = "rankfast-locale";
+
+/// A supported UI language.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum Locale {
+    #[default]
+    En,
+    Fr,
+    De,
+    Es,
+}
+
+impl Locale {
+    pub(crate) const ALL: [Self; 4] = [Self::En, Self::Fr, Self::De, Self::Es];
+
+    /// BCP 47 language code.
+    pub(crate) fn code(self) -> &'static str {
+        match self {
+            Self::En => "en",
+            Self::Fr => "fr",
+            Self::De => "de",
+            Self::Es => "es",
+        }
+    }
+
+    /// The language's own name for itself, for the switcher.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::En => "English",
+            Self::Fr => "Français",
+            Self::De => "Deutsch",
+            Self::Es => "Español",
+        }
+    }
+
+    /// Matches a language tag such as `fr` or `de-AT` by its primary subtag.
+    pub(crate) fn from_code(tag: &str) -> Option<Self> {
+        let primary = tag.split(['-', '_']).next()?;
+        Self::ALL
+            .into_iter()
+            .find(|locale| locale.code().eq_ignore_ascii_case(primary))
+    }
+
+    pub(crate) fn texts(self) -> &'static Texts {
+        match self {
+            Self::En => &EN,
+            Self::Fr => &FR,
+            Self::De => &DE,
+            Self::Es => &ES,
+        }
+    }
+}
+
+/// Replaces `{0}`, `{1}`, ... in `template` with `args`. Arguments are not
+/// scanned again, so item names containing braces come through unchanged.
+pub(crate) fn fill(template: &str, args: &[&dyn Display]) -> String {
+    let mut text = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        text.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let arg = after
+            .find('}')
+            .and_then(|close| Some((after[..close].parse::<usize>().ok()?, close)))
+            .and_then(|(i, close)| Some((args.get(i)?, close)));
+        if let Some((arg, close)) = arg {
+            text.push_str(&arg.to_string());
+            rest = &after[close + 1..];
+        } else {
+            text.push('{');
+            rest = after;
+        }
+    }
+    text.push_str(rest);
+    text
+}
+
+/// Picks the initial locale, shares it with every component and keeps the
+/// document language in sync. Returns the setter for the switcher.
+pub(crate) fn provide_locale() -> WriteSignal<Locale> {
+    let (locale, set_locale) = signal(detect());
+    provide_context(locale);
+    Effect::new(move |_| {
+        if let Some(root) = document().document_element() {
+            let _ = root.set_attribute("lang", locale.get().code());
+        }
+    });
+    set_locale
+}
+
+/// The active locale's strings, tracked reactively.
+pub(crate) fn use_texts() -> Signal<&'static Texts> {
+    let locale = expect_context::<ReadSignal<Locale>>();
+    Signal::derive(move || locale.get().texts())
+}
+
+/// Language picker for the header.
+#[component]
+pub(crate) fn LocaleSwitcher(set_locale: WriteSignal<Locale>) -> impl IntoView {
+    let locale = expect_context::<ReadSignal<Locale>>();
+    let t = use_texts();
+    view! {
+        <select
+            class="locale-select"
+            aria-label=move || t.get().language
+            on:change=move |ev| {
+                if let Some(chosen) = Locale::from_code(&event_target_value(&ev)) {
+                    save(chosen);
+                    set_locale.set(chosen);
+                }
+            }
+        >
+            {Locale::ALL
+                .into_iter()
+                .map(|option| {
+                    view! {
+                        <option value=option.code() selected=move || locale.get() == option>
+                            {option.name()}
+                        </option>
+                    }
+                })
+                .collect_view()}
+        </select>
+    }
+}
+
+/// Remembers a manual choice so it wins over the browser languages next
+/// time.
+fn save(locale: Locale) {
+    if let Ok(Some(storage)) = window().local_storage() {
+        let _ = storage.set_item(STORAGE_KEY, locale.code());
+    }
+}
+
+fn detect() -> Locale {
+    let win = window();
+    let saved = win
+        .local_storage()
+        .ok()
+        .flatten()
+        .and_then(|storage| storage.get_item(STORAGE_KEY).ok().flatten());
+    let languages = win.navigator().languages();
+    let preferred = languages.iter().filter_map(|tag| tag.as_string());
+    saved
+        .into_iter()
+        .chain(preferred)
+        .find_map(|tag| Locale::from_code(&tag))
+        .unwrap_or_default()
+}
+
+/// Every user-visible string.
+pub(crate) struct Texts {
+    pub(crate) subtitle: &'static str,
+    pub(crate) language: &'static str,
+    pub(crate) hash_mismatch: &'static str,
+    /// `{0}` = answers given after the ranking was complete.
+    pub(crate) hash_extra_answers: &'static str,
+    pub(crate) corrupt_link_title: &'static str,
+    pub(crate) corrupt_link_body: &'static str,
+    pub(crate) corrupt_link_reset: &'static str,
+
+    pub(crate) input_title: &'static str,
+    pub(crate) input_hint: &'static str,
+    pub(crate) import_file: &'static str,
+    /// `{0}` items merged into earlier ones: `{1}`.
+    pub(crate) duplicates_one: &'static str,
+    pub(crate) duplicates_other: &'static str,
+    /// `{0}` items, `{1}` questions.
+    pub(crate) budget_warning: &'static str,
+    pub(crate) mode_exact: &'static str,
+    /// `{0}` questions.
+    pub(crate) mode_approximate: &'static str,
+    /// Top `{0}`, `{1}` questions.
+    pub(crate) mode_top_k: &'static str,
+    /// `{0}` questions to the tiers, `{1}` in all.
+    pub(crate) mode_tiers: &'static str,
+    /// `{0}` questions.
+    pub(crate) mode_quick: &'static str,
+    pub(crate) need_two: &'static str,
+    /// `{0}` items.
+    pub(crate) start: &'static str,
+    /// `{0}` questions.
+    pub(crate) usual_questions: &'static str,
+    pub(crate) diff_title: &'static str,
+    pub(crate) diff_old: &'static str,
+    pub(crate) diff_new: &'static str,
+    /// `{0}` of `{1}` pairs.
+    pub(crate) diff_distance: &'static str,
+    pub(crate) diff_added: &'static str,
+    pub(crate) diff_removed: &'static str,
+
+    /// `{0}` rows.
+    pub(crate) import_title: &'static str,
+    pub(crate) import_column: &'static str,
+    pub(crate) import_header: &'static str,
+    /// `{0}` values not shown.
+    pub(crate) import_more: &'static str,
+    /// `{0}` items.
+    pub(crate) import_use: &'static str,
+    pub(crate) import_keep: &'static str,
+
+    pub(crate) reduce_motion: &'static str,
+    pub(crate) install: &'static str,
+    pub(crate) resume_session: &'static str,
+    pub(crate) save_session: &'static str,
+    pub(crate) shuffle_sides: &'static str,
+    pub(crate) prompt_label: &'static str,
+
+    pub(crate) comparison: &'static str,
+    pub(crate) progress_label: &'static str,
+    /// Question `{0}` of at most `{1}`, read out by screen readers.
+    pub(crate) progress_announce: &'static str,
+    pub(crate) ranking_done: &'static str,
+    pub(crate) time_left_under_minute: &'static str,
+    pub(crate) time_left_minute: &'static str,
+    /// About `{0}` minutes.
+    pub(crate) time_left_minutes: &'static str,
+    pub(crate) prompt: &'static str,
+    /// Places item `{0}` among `{1}` candidate places.
+    pub(crate) narrows: &'static str,
+    pub(crate) keys_hint: &'static str,
+    /// Accessible name of a choice button for item `{0}`.
+    pub(crate) choose: &'static str,
+    pub(crate) versus: &'static str,
+    pub(crate) tie: &'static str,
+    pub(crate) skip: &'static str,
+    pub(crate) finish_now: &'static str,
+    pub(crate) single_item: &'static str,
+    pub(crate) items_heading: &'static str,
+    pub(crate) remove_item: &'static str,
+    /// Asked before another list replaces an unfinished ranking.
+    pub(crate) leave_unfinished: &'static str,
+    pub(crate) restart: &'static str,
+    pub(crate) new_list: &'static str,
+    pub(crate) widget_waiting: &'static str,
+
+    pub(crate) results_title: &'static str,
+    pub(crate) results_hint: &'static str,
+    pub(crate) tab_ranking: &'static str,
+    pub(crate) tab_matrix: &'static str,
+    pub(crate) matrix_legend: &'static str,
+    /// `{0}` percent.
+    pub(crate) consistency: &'static str,
+    /// `{0}` of `{1}` answers.
+    pub(crate) consistency_contradicted: &'static str,
+    /// `{0}` won, `{1}` lost.
+    pub(crate) record: &'static str,
+    /// `{0}` won, `{1}` lost, `{2}` tied.
+    pub(crate) record_tied: &'static str,
+    /// `{0}` won, `{1}` lost.
+    pub(crate) record_title: &'static str,
+    pub(crate) filter: &'static str,
+    pub(crate) column_item: &'static str,
+    pub(crate) column_won: &'static str,
+    pub(crate) column_lost: &'static str,
+    pub(crate) beat: &'static str,
+    pub(crate) lost_to: &'static str,
+    pub(crate) tied_with: &'static str,
+    pub(crate) skipped_vs: &'static str,
+    pub(crate) tags_heading: &'static str,
+    pub(crate) stats_heading: &'static str,
+    /// `{0}` questions asked, `{1}` expected.
+    pub(crate) stats_questions: &'static str,
+    /// `{0}` places on average.
+    pub(crate) stats_search: &'static str,
+    /// `{0}` item, `{1}` questions.
+    pub(crate) stats_busiest: &'static str,
+    /// `{0}` answers in a row, from question `{1}`.
+    pub(crate) stats_streak: &'static str,
+    /// `{0}` seconds.
+    pub(crate) stats_pace: &'static str,
+    pub(crate) stats_download: &'static str,
+    pub(crate) dataset_csv: &'static str,
+    pub(crate) dataset_jsonl: &'static str,
+    pub(crate) reorder_title: &'static str,
+    pub(crate) reorder_hint: &'static str,
+    /// `{0}` = the item's label.
+    pub(crate) move_up: &'static str,
+    /// `{0}` = the item's label.
+    pub(crate) move_down: &'static str,
+    pub(crate) reorder_reset: &'static str,
+    pub(crate) question_history: &'static str,
+    pub(crate) question_history_hint: &'static str,
+    /// `{0}` = the question's number, from 1.
+    pub(crate) rewind_to: &'static str,
+}
+
+static EN: Texts = Texts {
+    subtitle: "Pairwise ranking tool",
+    language: "Language",
+    hash_mismatch: "This link's items were changed after it was shared, so its answers no longer apply. Starting over.",
+    hash_extra_answers: "This link holds {0} more answers than its ranking needs, so they were ignored. Were items removed after it was shared?",
+    corrupt_link_title: "This link is corrupted",
+    corrupt_link_body: "It could not be read, so there is no ranking to resume. Was it cut short or edited by hand?",
+    corrupt_link_reset: "Start a new ranking",
+
+    input_title: "Enter items to rank",
+    input_hint: "One item per line (minimum 2). Use label | image URL to show a picture, and add | description: \u{2026} or | link: \u{2026} for details, and end the label with #tags to group items. You can also paste or drop a CSV or JSON file.",
+    import_file: "Import file\u{2026}",
+    duplicates_one: "{0} duplicate will be merged: {1}",
+    duplicates_other: "{0} duplicates will be merged: {1}",
+    budget_warning: "Ranking {0} items exactly can take up to {1} questions.",
+    mode_exact: "Rank everything exactly",
+    mode_approximate: "Approximate ranking ({0} questions)",
+    mode_top_k: "Only find the top {0} (up to {1} questions)",
+    mode_tiers: "Tiers S, A, B and C after {0} questions, then each tier in order (about {1} in all)",
+    mode_quick: "Compare each item with one anchor at a time (about {0} questions)",
+    need_two: "Enter at least 2 items",
+    start: "Start ranking ({0} items)",
+    usual_questions: "Usually about {0} questions.",
+    diff_title: "Compare two rankings",
+    diff_old: "Earlier session link",
+    diff_new: "Later session link",
+    diff_distance: "{0} of {1} pairs changed order",
+    diff_added: "new",
+    diff_removed: "removed",
+
+    import_title: "This looks like a table with {0} rows. Rank one column?",
+    import_column: "Column",
+    import_header: "First row is a header",
+    import_more: "and {0} more",
+    import_use: "Use {0} items",
+    import_keep: "Keep text as is",
+
+    reduce_motion: "Reduce motion",
+    install: "Install app",
+    resume_session: "Resume saved session ({0} answers)",
+    save_session: "Save session",
+    shuffle_sides: "Shuffle which side each item is shown on",
+    prompt_label: "Question to ask (optional)",
+
+    comparison: "Comparison",
+    progress_label: "Progress",
+    progress_announce: "Question {0} of up to {1}",
+    ranking_done: "Ranking complete",
+    time_left_under_minute: "Less than a minute left",
+    time_left_minute: "About a minute left",
+    time_left_minutes: "About {0} minutes left",
+    prompt: "Which do you prefer?",
+    narrows: "This question narrows {0}'s place among {1} candidates.",
+    keys_hint: "Use the left and right arrow keys to choose.",
+    choose: "Choose {0}",
+    versus: "vs",
+    tie: "They're equal",
+    skip: "Skip",
+    finish_now: "Finish now",
+    single_item: "Only one item \u{2014} no comparisons needed!",
+    items_heading: "Items being ranked",
+    remove_item: "Remove {0}",
+    leave_unfinished: "This ranking is not finished. Leave it anyway? Its link will be copied to the clipboard so you can pick it up later.",
+    restart: "Restart with the same items",
+    new_list: "New list",
+    widget_waiting: "Waiting for items to rank\u{2026}",
+
+    results_title: "Your Ranking",
+    results_hint: "Click an item to see the comparisons behind its place",
+    tab_ranking: "Ranking",
+    tab_matrix: "Head to head",
+    matrix_legend: "\u{2713} won, \u{2717} lost, = tied; (\u{2713}) and (\u{2717}) follow from other answers. Read each row against the columns, both in ranking order.",
+    consistency: "Consistency: {0}% of your answers agree with each other",
+    consistency_contradicted: "{0} of {1} answers went against what earlier answers implied",
+    record: "{0}W · {1}L",
+    record_tied: "{0}W · {1}L · {2}T",
+    record_title: "Won {0} and lost {1} direct comparisons",
+    filter: "Filter items",
+    column_item: "Item",
+    column_won: "Won",
+    column_lost: "Lost",
+    beat: "Beat",
+    lost_to: "Lost to",
+    tied_with: "Tied with",
+    skipped_vs: "Skipped vs",
+    tags_heading: "By tag",
+    stats_heading: "Statistics",
+    stats_questions: "{0} questions asked, {1} expected",
+    stats_search: "{0} places left on average when placing an item",
+    stats_busiest: "Most asked about: {0}, in {1} questions",
+    stats_streak: "Longest run of the same answer: {0} in a row, from question {1}",
+    stats_pace: "Median time per answer: {0} s",
+    stats_download: "Download statistics",
+    dataset_csv: "Download comparisons (CSV)",
+    dataset_jsonl: "Download comparisons (JSONL)",
+    reorder_title: "Adjust the order by hand",
+    reorder_hint: "Drag items, or use the arrows, to move them. Saved sessions keep both the order set here and the one your answers gave.",
+    move_up: "Move {0} up",
+    move_down: "Move {0} down",
+    reorder_reset: "Back to the compared order",
+    question_history: "Questions so far",
+    question_history_hint: "Pick a question to answer it again. The answers from there on are dropped.",
+    rewind_to: "Answer again from question {0}",
+};
+
+static FR: Texts = Texts {
+    subtitle: "Outil de classement par paires",
+    language: "Langue",
+    hash_mismatch: "Les éléments de ce lien ont été modifiés après son partage : ses réponses ne s'appliquent plus. On recommence.",
+    hash_extra_answers: "Ce lien contient {0} réponses de plus que son classement n'en demande : elles ont été ignorées. Des éléments ont-ils été retirés après son partage ?",
+    corrupt_link_title: "Ce lien est corrompu",
+    corrupt_link_body: "Il n'a pas pu être lu : il n'y a pas de classement à reprendre. A-t-il été tronqué ou modifié à la main ?",
+    corrupt_link_reset: "Commencer un nouveau classement",
+
+    input_title: "Saisissez les éléments à classer",
+    input_hint: "Un élément par ligne (2 minimum). Écrivez libellé | URL d'image pour afficher une image, et ajoutez | description: \u{2026} ou | link: \u{2026} pour les détails, et terminez le libellé par des #catégories pour regrouper les éléments. Vous pouvez aussi coller ou déposer un fichier CSV ou JSON.",
+    import_file: "Importer un fichier\u{2026}",
+    duplicates_one: "{0} doublon sera fusionné : {1}",
+    duplicates_other: "{0} doublons seront fusionnés : {1}",
+    budget_warning: "Classer exactement {0} éléments peut demander jusqu'à {1} questions.",
+    mode_exact: "Tout classer exactement",
+    mode_approximate: "Classement approximatif ({0} questions)",
+    mode_top_k: "Trouver seulement les {0} meilleurs (jusqu'à {1} questions)",
+    mode_tiers: "Des rangs S, A, B et C en {0} questions, puis chaque rang dans l'ordre (environ {1} en tout)",
+    mode_quick: "Comparer chaque élément à un seul repère à la fois (environ {0} questions)",
+    need_two: "Saisissez au moins 2 éléments",
+    start: "Commencer le classement ({0} éléments)",
+    usual_questions: "En général, environ {0} questions.",
+    diff_title: "Comparer deux classements",
+    diff_old: "Lien de la session précédente",
+    diff_new: "Lien de la session suivante",
+    diff_distance: "{0} paires sur {1} ont changé d'ordre",
+    diff_added: "nouveau",
+    diff_removed: "retiré",
+
+    import_title: "Cela ressemble à un tableau de {0} lignes. Classer une colonne ?",
+    import_column: "Colonne",
+    import_header: "La première ligne est un en-tête",
+    import_more: "et {0} de plus",
+    import_use: "Utiliser {0} éléments",
+    import_keep: "Garder le texte tel quel",
+
+    reduce_motion: "Réduire les animations",
+    install: "Installer l'application",
+    resume_session: "Reprendre la session enregistrée ({0} réponses)",
+    save_session: "Enregistrer la session",
+    shuffle_sides: "Alterner au hasard le côté de chaque élément",
+    prompt_label: "Question à poser (facultatif)",
+
+    comparison: "Comparaison",
+    progress_label: "Progression",
+    progress_announce: "Question {0} sur {1} au plus",
+    ranking_done: "Classement terminé",
+    time_left_under_minute: "Moins d'une minute restante",
+    time_left_minute: "Environ une minute restante",
+    time_left_minutes: "Environ {0} minutes restantes",
+    prompt: "Lequel préférez-vous ?",
+    narrows: "Cette question précise la place de {0} parmi {1} possibles.",
+    keys_hint: "Utilisez les flèches gauche et droite pour choisir.",
+    choose: "Choisir {0}",
+    versus: "ou",
+    tie: "Ils se valent",
+    skip: "Passer",
+    finish_now: "Terminer maintenant",
+    single_item: "Un seul élément \u{2014} aucune comparaison nécessaire !",
+    items_heading: "Éléments à classer",
+    remove_item: "Retirer {0}",
+    leave_unfinished: "Ce classement n'est pas terminé. Le quitter quand même ? Son lien sera copié dans le presse-papiers pour le reprendre plus tard.",
+    restart: "Recommencer avec les mêmes éléments",
+    new_list: "Nouvelle liste",
+    widget_waiting: "En attente des éléments à classer\u{2026}",
+
+    results_title: "Votre classement",
+    results_hint: "Cliquez sur un élément pour voir les comparaisons qui justifient sa place",
+    tab_ranking: "Classement",
+    tab_matrix: "Face à face",
+    matrix_legend: "\u{2713} gagné, \u{2717} perdu, = égalité ; (\u{2713}) et (\u{2717}) découlent d'autres réponses. Lisez chaque ligne contre les colonnes, dans l'ordre du classement.",
+    consistency: "Cohérence : {0} % de vos réponses s'accordent entre elles",
+    consistency_contradicted: "{0} réponses sur {1} contredisaient ce que les précédentes impliquaient",
+    record: "{0}V · {1}D",
+    record_tied: "{0}V · {1}D · {2}N",
+    record_title: "{0} comparaisons directes gagnées et {1} perdues",
+    filter: "Filtrer les éléments",
+    column_item: "Élément",
+    column_won: "Gagnés",
+    column_lost: "Perdus",
+    beat: "A battu",
+    lost_to: "A perdu contre",
+    tied_with: "À égalité avec",
+    skipped_vs: "Passé contre",
+    tags_heading: "Par catégorie",
+    stats_heading: "Statistiques",
+    stats_questions: "{0} questions posées, {1} prévues",
+    stats_search: "{0} places possibles en moyenne pour placer un élément",
+    stats_busiest: "Le plus souvent comparé : {0}, dans {1} questions",
+    stats_streak: "Plus longue série de la même réponse : {0} d'affilée, dès la question {1}",
+    stats_pace: "Temps médian par réponse : {0} s",
+    stats_download: "Télécharger les statistiques",
+    dataset_csv: "Télécharger les comparaisons (CSV)",
+    dataset_jsonl: "Télécharger les comparaisons (JSONL)",
+    reorder_title: "Ajuster l'ordre à la main",
+    reorder_hint: "Faites glisser les éléments, ou utilisez les flèches, pour les déplacer. Les sessions enregistrées gardent à la fois l'ordre fixé ici et celui donné par vos réponses.",
+    move_up: "Monter {0}",
+    move_down: "Descendre {0}",
+    reorder_reset: "Revenir à l'ordre des comparaisons",
+    question_history: "Questions posées",
+    question_history_hint: "Choisissez une question pour y répondre à nouveau. Les réponses à partir de celle-ci sont effacées.",
+    rewind_to: "Répondre à nouveau à partir de la question {0}",
+};
+
+static DE: Texts = Texts {
+    subtitle: "Paarweises Ranking-Tool",
+    language: "Sprache",
+    hash_mismatch: "Die Einträge dieses Links wurden nach dem Teilen geändert, daher gelten seine Antworten nicht mehr. Es geht von vorne los.",
+    hash_extra_answers: "Dieser Link enthält {0} Antworten mehr, als seine Rangliste braucht; sie wurden ignoriert. Wurden nach dem Teilen Einträge entfernt?",
+    corrupt_link_title: "Dieser Link ist beschädigt",
+    corrupt_link_body: "Er konnte nicht gelesen werden, daher gibt es keine Rangliste zum Fortsetzen. Wurde er gekürzt oder von Hand bearbeitet?",
+    corrupt_link_reset: "Neue Rangliste beginnen",
+
+    input_title: "Einträge zum Ordnen eingeben",
+    input_hint: "Ein Eintrag pro Zeile (mindestens 2). Mit Name | Bild-URL wird ein Bild angezeigt, mit | description: \u{2026} oder | link: \u{2026} weitere Angaben, und mit #Kategorien am Ende des Namens werden Einträge gruppiert. Du kannst auch eine CSV- oder JSON-Datei einfügen oder hineinziehen.",
+    import_file: "Datei importieren\u{2026}",
+    duplicates_one: "{0} Duplikat wird zusammengeführt: {1}",
+    duplicates_other: "{0} Duplikate werden zusammengeführt: {1}",
+    budget_warning: "Ein exaktes Ranking von {0} Einträgen kann bis zu {1} Fragen dauern.",
+    mode_exact: "Alles exakt ordnen",
+    mode_approximate: "Ungefähres Ranking ({0} Fragen)",
+    mode_top_k: "Nur die besten {0} finden (bis zu {1} Fragen)",
+    mode_tiers: "Stufen S, A, B und C nach {0} Fragen, dann jede Stufe sortiert (etwa {1} insgesamt)",
+    mode_quick: "Jedes Element mit jeweils einem Bezugspunkt vergleichen (etwa {0} Fragen)",
+    need_two: "Mindestens 2 Einträge eingeben",
+    start: "Ranking starten ({0} Einträge)",
+    usual_questions: "Meist etwa {0} Fragen.",
+    diff_title: "Zwei Rankings vergleichen",
+    diff_old: "Link der früheren Sitzung",
+    diff_new: "Link der späteren Sitzung",
+    diff_distance: "{0} von {1} Paaren haben die Reihenfolge gewechselt",
+    diff_added: "neu",
+    diff_removed: "entfernt",
+
+    import_title: "Das sieht nach einer Tabelle mit {0} Zeilen aus. Eine Spalte ordnen?",
+    import_column: "Spalte",
+    import_header: "Erste Zeile ist eine Kopfzeile",
+    import_more: "und {0} weitere",
+    import_use: "{0} Einträge übernehmen",
+    import_keep: "Text so lassen",
+
+    reduce_motion: "Bewegung reduzieren",
+    install: "App installieren",
+    resume_session: "Gespeicherte Sitzung fortsetzen ({0} Antworten)",
+    save_session: "Sitzung speichern",
+    shuffle_sides: "Seite der Einträge zufällig wechseln",
+    prompt_label: "Gestellte Frage (optional)",
+
+    comparison: "Vergleich",
+    progress_label: "Fortschritt",
+    progress_announce: "Frage {0} von höchstens {1}",
+    ranking_done: "Ranking fertig",
+    time_left_under_minute: "Weniger als eine Minute übrig",
+    time_left_minute: "Etwa eine Minute übrig",
+    time_left_minutes: "Etwa {0} Minuten übrig",
+    prompt: "Was gefällt dir besser?",
+    narrows: "Diese Frage grenzt den Platz von {0} unter {1} Möglichkeiten ein.",
+    keys_hint: "Wähle mit den Pfeiltasten links und rechts.",
+    choose: "{0} wählen",
+    versus: "oder",
+    tie: "Gleich gut",
+    skip: "Überspringen",
+    finish_now: "Jetzt beenden",
+    single_item: "Nur ein Eintrag \u{2014} keine Vergleiche nötig!",
+    items_heading: "Einträge im Ranking",
+    remove_item: "{0} entfernen",
+    leave_unfinished: "Dieses Ranking ist noch nicht fertig. Trotzdem verlassen? Sein Link wird in die Zwischenablage kopiert, damit du später weitermachen kannst.",
+    restart: "Mit denselben Einträgen neu beginnen",
+    new_list: "Neue Liste",
+    widget_waiting: "Warte auf die zu ordnenden Einträge\u{2026}",
+
+    results_title: "Dein Ranking",
+    results_hint: "Klicke auf einen Eintrag, um die Vergleiche hinter seinem Platz zu sehen",
+    tab_ranking: "Ranking",
+    tab_matrix: "Direktvergleich",
+    matrix_legend: "\u{2713} gewonnen, \u{2717} verloren, = gleichauf; (\u{2713}) und (\u{2717}) folgen aus anderen Antworten. Jede Zeile wird gegen die Spalten gelesen, beide in Ranking-Reihenfolge.",
+    consistency: "Stimmigkeit: {0} % deiner Antworten passen zueinander",
+    consistency_contradicted: "{0} von {1} Antworten widersprachen dem, was frühere nahelegten",
+    record: "{0}S · {1}N",
+    record_tied: "{0}S · {1}N · {2}U",
+    record_title: "{0} direkte Vergleiche gewonnen und {1} verloren",
+    filter: "Einträge filtern",
+    column_item: "Eintrag",
+    column_won: "Siege",
+    column_lost: "Niederlagen",
+    beat: "Schlug",
+    lost_to: "Verlor gegen",
+    tied_with: "Gleichauf mit",
+    skipped_vs: "Übersprungen gegen",
+    tags_heading: "Nach Kategorie",
+    stats_heading: "Statistik",
+    stats_questions: "{0} Fragen gestellt, {1} erwartet",
+    stats_search: "Im Schnitt {0} mögliche Plätze beim Einordnen eines Eintrags",
+    stats_busiest: "Am häufigsten gefragt: {0}, in {1} Fragen",
+    stats_streak: "Längste Folge derselben Antwort: {0} hintereinander, ab Frage {1}",
+    stats_pace: "Mittlere Zeit pro Antwort: {0} s",
+    stats_download: "Statistik herunterladen",
+    dataset_csv: "Vergleiche herunterladen (CSV)",
+    dataset_jsonl: "Vergleiche herunterladen (JSONL)",
+    reorder_title: "Reihenfolge von Hand anpassen",
+    reorder_hint: "Ziehe Einträge oder nutze die Pfeile, um sie zu verschieben. Gespeicherte Sitzungen behalten sowohl die hier gesetzte Reihenfolge als auch die aus deinen Antworten.",
+    move_up: "{0} nach oben",
+    move_down: "{0} nach unten",
+    reorder_reset: "Zurück zur verglichenen Reihenfolge",
+    question_history: "Bisherige Fragen",
+    question_history_hint: "Wähle eine Frage, um sie erneut zu beantworten. Die Antworten ab dieser Frage werden verworfen.",
+    rewind_to: "Ab Frage {0} neu antworten",
+};
+
+static ES: Texts = Texts {
+    subtitle: "Herramienta de clasificación por pares",
+    language: "Idioma",
+    hash_mismatch: "Los elementos de este enlace cambiaron después de compartirlo, así que sus respuestas ya no sirven. Empezamos de nuevo.",
+    hash_extra_answers: "Este enlace tiene {0} respuestas más de las que su clasificación necesita, así que se ignoraron. ¿Se quitaron elementos después de compartirlo?",
+    corrupt_link_title: "Este enlace está dañado",
+    corrupt_link_body: "No se pudo leer, así que no hay ninguna clasificación que retomar. ¿Se cortó o se editó a mano?",
+    corrupt_link_reset: "Empezar una nueva clasificación",
+
+    input_title: "Introduce los elementos a clasificar",
+    input_hint: "Un elemento por línea (mínimo 2). Usa etiqueta | URL de imagen para mostrar una imagen, y añade | description: \u{2026} o | link: \u{2026} para los detalles, y termina la etiqueta con #categorías para agrupar elementos. También puedes pegar o soltar un archivo CSV o JSON.",
+    import_file: "Importar archivo\u{2026}",
+    duplicates_one: "Se fusionará {0} duplicado: {1}",
+    duplicates_other: "Se fusionarán {0} duplicados: {1}",
+    budget_warning: "Clasificar exactamente {0} elementos puede requerir hasta {1} preguntas.",
+    mode_exact: "Clasificarlo todo con exactitud",
+    mode_approximate: "Clasificación aproximada ({0} preguntas)",
+    mode_top_k: "Encontrar solo los {0} mejores (hasta {1} preguntas)",
+    mode_tiers: "Niveles S, A, B y C tras {0} preguntas, luego cada nivel en orden (unas {1} en total)",
+    mode_quick: "Comparar cada elemento con un solo referente a la vez (unas {0} preguntas)",
+    need_two: "Introduce al menos 2 elementos",
+    start: "Empezar a clasificar ({0} elementos)",
+    usual_questions: "Normalmente unas {0} preguntas.",
+    diff_title: "Comparar dos clasificaciones",
+    diff_old: "Enlace de la sesión anterior",
+    diff_new: "Enlace de la sesión posterior",
+    diff_distance: "{0} de {1} pares cambiaron de orden",
+    diff_added: "nuevo",
+    diff_removed: "eliminado",
+
+    import_title: "Parece una tabla de {0} filas. ¿Clasificar una columna?",
+    import_column: "Columna",
+    import_header: "La primera fila es un encabezado",
+    import_more: "y {0} más",
+    import_use: "Usar {0} elementos",
+    import_keep: "Dejar el texto como está",
+
+    reduce_motion: "Reducir movimiento",
+    install: "Instalar la aplicación",
+    resume_session: "Reanudar la sesión guardada ({0} respuestas)",
+    save_session: "Guardar sesión",
+    shuffle_sides: "Alternar al azar el lado de cada elemento",
+    prompt_label: "Pregunta a mostrar (opcional)",
+
+    comparison: "Comparación",
+    progress_label: "Progreso",
+    progress_announce: "Pregunta {0} de {1} como máximo",
+    ranking_done: "Clasificación terminada",
+    time_left_under_minute: "Queda menos de un minuto",
+    time_left_minute: "Queda un minuto aproximadamente",
+    time_left_minutes: "Quedan unos {0} minutos",
+    prompt: "¿Cuál prefieres?",
+    narrows: "Esta pregunta acota el puesto de {0} entre {1} posibles.",
+    keys_hint: "Usa las flechas izquierda y derecha para elegir.",
+    choose: "Elegir {0}",
+    versus: "o",
+    tie: "Son iguales",
+    skip: "Omitir",
+    finish_now: "Terminar ahora",
+    single_item: "Solo un elemento: ¡no hace falta comparar!",
+    items_heading: "Elementos a clasificar",
+    remove_item: "Quitar {0}",
+    leave_unfinished: "Esta clasificación no está terminada. ¿Salir de todos modos? Su enlace se copiará al portapapeles para retomarla más tarde.",
+    restart: "Empezar de nuevo con los mismos elementos",
+    new_list: "Nueva lista",
+    widget_waiting: "Esperando los elementos a ordenar\u{2026}",
+
+    results_title: "Tu clasificación",
+    results_hint: "Haz clic en un elemento para ver las comparaciones detrás de su puesto",
+    tab_ranking: "Clasificación",
+    tab_matrix: "Cara a cara",
+    matrix_legend: "\u{2713} ganó, \u{2717} perdió, = empate; (\u{2713}) y (\u{2717}) se deducen de otras respuestas. Lee cada fila frente a las columnas, ambas en el orden de la clasificación.",
+    consistency: "Coherencia: el {0} % de tus respuestas concuerdan entre sí",
+    consistency_contradicted: "{0} de {1} respuestas contradijeron lo que implicaban las anteriores",
+    record: "{0}G · {1}P",
+    record_tied: "{0}G · {1}P · {2}E",
+    record_title: "Ganó {0} y perdió {1} comparaciones directas",
+    filter: "Filtrar elementos",
+    column_item: "Elemento",
+    column_won: "Ganadas",
+    column_lost: "Perdidas",
+    beat: "Ganó a",
+    lost_to: "Perdió contra",
+    tied_with: "Empató con",
+    skipped_vs: "Omitido frente a",
+    tags_heading: "Por categoría",
+    stats_heading: "Estadísticas",
+    stats_questions: "{0} preguntas hechas, {1} previstas",
+    stats_search: "{0} puestos posibles de media al colocar un elemento",
+    stats_busiest: "Más preguntado: {0}, en {1} preguntas",
+    stats_streak: "Racha más larga de la misma respuesta: {0} seguidas, desde la pregunta {1}",
+    stats_pace: "Tiempo mediano por respuesta: {0} s",
+    stats_download: "Descargar estadísticas",
+    dataset_csv: "Descargar comparaciones (CSV)",
+    dataset_jsonl: "Descargar comparaciones (JSONL)",
+    reorder_title: "Ajustar el orden a mano",
+    reorder_hint: "Arrastra los elementos, o usa las flechas, para moverlos. Las sesiones guardadas conservan tanto el orden fijado aquí como el que dieron tus respuestas.",
+    move_up: "Subir {0}",
+    move_down: "Bajar {0}",
+    reorder_reset: "Volver al orden de las comparaciones",
+    question_history: "Preguntas hasta ahora",
+    question_history_hint: "Elige una pregunta para responderla de nuevo. Las respuestas desde ella se descartan.",
+    rewind_to: "Responder de nuevo desde la pregunta {0}",
+};
+
+#[cfg(test)]
+mod tests {
+    use super::{Locale, fill};
+
+    #[test]
+    fn matches_browser_language_tags() {
+        assert_eq!(Locale::from_code("fr-CA"), Some(Locale::Fr));
+        assert_eq!(Locale::from_code("DE"), Some(Locale::De));
+        assert_eq!(Locale::from_code("es_MX"), Some(Locale::Es));
+        assert_eq!(Locale::from_code("pt-BR"), None);
+    }
+
+    #[test]
+    fn every_locale_keeps_the_placeholders() {
+        for locale in Locale::ALL {
+            let t = locale.texts();
+            let templates = [
+                (t.duplicates_one, 2),
+                (t.duplicates_other, 2),
+                (t.budget_warning, 2),
+                (t.mode_approximate, 1),
+                (t.mode_top_k, 2),
+                (t.mode_tiers, 2),
+                (t.mode_quick, 1),
+                (t.start, 1),
+                (t.usual_questions, 1),
+                (t.progress_announce, 2),
+                (t.choose, 1),
+                (t.narrows, 2),
+                (t.time_left_minutes, 1),
+                (t.hash_extra_answers, 1),
+                (t.resume_session, 1),
+                (t.import_title, 1),
+                (t.import_more, 1),
+                (t.import_use, 1),
+                (t.record, 2),
+                (t.record_tied, 3),
+                (t.record_title, 2),
+                (t.consistency, 1),
+                (t.consistency_contradicted, 2),
+                (t.stats_questions, 2),
+                (t.stats_search, 1),
+                (t.stats_busiest, 2),
+                (t.stats_streak, 2),
+                (t.stats_pace, 1),
+                (t.move_up, 1),
+                (t.move_down, 1),
+                (t.rewind_to, 1),
+            ];
+            for (template, count) in templates {
+                for i in 0..count {
+                    assert!(
+                        template.contains(&format!("{{{i}}}")),
+                        "{locale:?}: {template}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn fills_placeholders_once() {
+        assert_eq!(fill("{1} before {0}", &[&"a", &2]), "2 before a");
+        assert_eq!(fill("merged: {0}", &[&"{0} {x}"]), "merged: {0} {x}");
+        assert_eq!(fill("{2} {", &[&1]), "{2} {");
+    }
+}
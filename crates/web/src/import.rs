@@ -0,0 +1,328 @@
+//! Turning pasted or dropped spreadsheets into an item list.
+//!
+//! [`parse`] recognises a JSON array (of strings, numbers or objects) or
+//! delimited text (comma, semicolon or tab, with RFC 4180 quoting) and
+//! returns it as a [`Table`]; [`ImportPanel`] then lets the user pick the
+//! column to rank. Plain one-item-per-line text is not a table, so it is left
+//! alone. Dropped or chosen files are read into the text area by
+//! [`read_file`] and go through the same path.
+
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use serde_json::Value;
+use wasm_bindgen_futures::JsFuture;
+
+use crate::i18n::{fill, use_texts};
+
+/// Values shown in the preview before importing a column.
+const PREVIEW_ROWS: usize = 5;
+
+/// Rows of cells with a name for each column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Table {
+    pub(crate) columns: Vec<String>,
+    pub(crate) rows: Vec<Vec<String>>,
+    /// Whether the first line could be a header (delimited text only).
+    pub(crate) delimited: bool,
+}
+
+impl Table {
+    /// Non-empty, trimmed values of column `index`, one per row.
+    pub(crate) fn column(&self, index: usize) -> Vec<String> {
+        self.rows
+            .iter()
+            .filter_map(|row| row.get(index))
+            .map(|cell| cell.trim().to_string())
+            .filter(|cell| !cell.is_empty())
+            .collect()
+    }
+}
+
+/// Parses `text` as JSON or delimited text. For delimited text,
+/// `has_header` takes column names from the first row; otherwise columns
+/// are numbered. Returns `None` for anything that is not a table.
+pub(crate) fn parse(text: &str, has_header: bool) -> Option<Table> {
+    let trimmed = text.trim();
+    if trimmed.starts_with('[') {
+        return parse_json(trimmed);
+    }
+    let delimiter = detect_delimiter(trimmed)?;
+    let mut rows = parse_delimited(trimmed, delimiter);
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let columns = if has_header && !rows.is_empty() {
+        let mut header = rows.remove(0);
+        header.resize(width, String::new());
+        header
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let name = name.trim().to_string();
+                if name.is_empty() { numbered(i) } else { name }
+            })
+            .collect()
+    } else {
+        (0..width).map(numbered).collect()
+    };
+    Some(Table {
+        columns,
+        rows,
+        delimited: true,
+    })
+}
+
+/// Name of an unnamed column, kept free of words so it reads in any locale.
+fn numbered(index: usize) -> String {
+    format!("#{}", index + 1)
+}
+
+/// The delimiter that splits the first line, preferring tabs, then
+/// semicolons, then commas.
+fn detect_delimiter(text: &str) -> Option<char> {
+    let first = text.lines().next()?;
+    ['\t', ';', ','].into_iter().find(|&d| first.contains(d))
+}
+
+/// Splits delimited text into rows, honouring double-quoted cells (which may
+/// contain delimiters, newlines and `""` escapes).
+fn parse_delimited(text: &str, delimiter: char) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    cell.push('"');
+                    chars.next();
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if cell.trim().is_empty() => {
+                cell.clear();
+                quoted = true;
+            }
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut cell));
+                rows.push(std::mem::take(&mut row));
+            }
+            c if c == delimiter && !quoted => row.push(std::mem::take(&mut cell)),
+            c => cell.push(c),
+        }
+    }
+    row.push(cell);
+    rows.push(row);
+    rows.retain(|row| row.iter().any(|cell| !cell.trim().is_empty()));
+    rows
+}
+
+fn parse_json(text: &str) -> Option<Table> {
+    let Value::Array(values) = serde_json::from_str(text).ok()? else {
+        return None;
+    };
+
+    if !values.iter().all(Value::is_object) {
+        let rows = values.iter().map(|v| vec![cell_text(v)]).collect();
+        return Some(Table {
+            columns: vec![numbered(0)],
+            rows,
+            delimited: false,
+        });
+    }
+
+    // Union of keys, in order of first appearance.
+    let mut columns: Vec<String> = Vec::new();
+    for value in &values {
+        if let Value::Object(map) = value {
+            for key in map.keys() {
+                if !columns.contains(key) {
+                    columns.push(key.clone());
+                }
+            }
+        }
+    }
+    let rows = values
+        .iter()
+        .map(|value| {
+            columns
+                .iter()
+                .map(|key| value.get(key).map(cell_text).unwrap_or_default())
+                .collect()
+        })
+        .collect();
+    Some(Table {
+        columns,
+        rows,
+        delimited: false,
+    })
+}
+
+fn cell_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+/// Offers to replace pasted table text with one of its columns.
+///
+/// Shown while the text parses as a [`Table`]; importing or dismissing hides
+/// it until the text changes again, since an imported column may itself
+/// contain commas.
+#[component]
+pub(crate) fn ImportPanel(
+    text: ReadSignal<String>,
+    set_text: WriteSignal<String>,
+) -> impl IntoView {
+    let t = use_texts();
+    let (has_header, set_has_header) = signal(true);
+    let (column, set_column) = signal(0usize);
+    let (dismissed, set_dismissed) = signal(None::<String>);
+
+    let table = Memo::new(move |_| {
+        let text = text.get();
+        if dismissed.with(|d| d.as_deref() == Some(text.as_str())) {
+            return None;
+        }
+        parse(&text, has_header.get())
+    });
+    let values = Memo::new(move |_| {
+        table
+            .get()
+            .map(|t| t.column(column.get().min(t.columns.len().saturating_sub(1))))
+            .unwrap_or_default()
+    });
+
+    let import = move |_| {
+        let lines = values.get().join("\n");
+        set_dismissed.set(Some(lines.clone()));
+        set_text.set(lines);
+    };
+    let dismiss = move |_| set_dismissed.set(Some(text.get()));
+
+    move || {
+        table.get().map(|parsed| {
+            let rows = parsed.rows.len();
+            view! {
+                <div class="import-panel">
+                    <p class="import-title">
+                        {move || fill(t.get().import_title, &[&rows])}
+                    </p>
+                    <div class="import-controls">
+                        <label>
+                            {move || t.get().import_column}
+                            " "
+                            <select on:change=move |ev| {
+                                set_column.set(event_target_value(&ev).parse().unwrap_or(0));
+                            }>
+                                {parsed
+                                    .columns
+                                    .iter()
+                                    .enumerate()
+                                    .map(|(i, name)| {
+                                        view! {
+                                            <option value=i.to_string() selected=move || column.get() == i>
+                                                {name.clone()}
+                                            </option>
+                                        }
+                                    })
+                                    .collect_view()}
+                            </select>
+                        </label>
+                        {parsed.delimited.then(|| view! {
+                            <label>
+                                <input
+                                    type="checkbox"
+                                    prop:checked=move || has_header.get()
+                                    on:change=move |ev| set_has_header.set(event_target_checked(&ev))
+                                />
+                                " " {move || t.get().import_header}
+                            </label>
+                        })}
+                    </div>
+                    <ul class="import-preview">
+                        {move || {
+                            let values = values.get();
+                            let more = values.len().saturating_sub(PREVIEW_ROWS);
+                            values
+                                .into_iter()
+                                .take(PREVIEW_ROWS)
+                                .map(|v| view! { <li>{v}</li> }.into_any())
+                                .chain((more > 0).then(|| {
+                                    view! { <li class="import-more">{fill(t.get().import_more, &[&more])}</li> }
+                                        .into_any()
+                                }))
+                                .collect_view()
+                        }}
+                    </ul>
+                    <div class="import-actions">
+                        <button class="import-btn" on:click=import>
+                            {move || fill(t.get().import_use, &[&values.get().len()])}
+                        </button>
+                        <button class="extra-btn" on:click=dismiss>{move || t.get().import_keep}</button>
+                    </div>
+                </div>
+            }
+        })
+    }
+}
+
+/// Reads `file` as text into `set_text`.
+pub(crate) fn read_file(file: &web_sys::File, set_text: WriteSignal<String>) {
+    let promise = file.text();
+    spawn_local(async move {
+        if let Ok(text) = JsFuture::from(promise).await {
+            set_text.set(text.as_string().unwrap_or_default());
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+
+    #[test]
+    fn reads_csv_with_quotes_and_header() {
+        let csv =
+            "name,year\n\"Abbey Road\",1969\n\"Help, I need\",1965\r\n\"Say \"\"hi\"\"\",1970\n";
+        let table = parse(csv, true).unwrap();
+        assert_eq!(table.columns, vec!["name", "year"]);
+        assert_eq!(
+            table.column(0),
+            vec!["Abbey Road", "Help, I need", "Say \"hi\""]
+        );
+        assert_eq!(table.column(1), vec!["1969", "1965", "1970"]);
+    }
+
+    #[test]
+    fn prefers_tabs_and_numbers_headerless_columns() {
+        let table = parse("a, b\tx\nc\ty\n", false).unwrap();
+        assert_eq!(table.columns, vec!["#1", "#2"]);
+        assert_eq!(table.column(0), vec!["a, b", "c"]);
+    }
+
+    #[test]
+    fn reads_json_arrays() {
+        let table = parse(r#"["Pizza", 3, null]"#, true).unwrap();
+        assert_eq!(table.column(0), vec!["Pizza", "3"]);
+
+        let table = parse(
+            r#"[{"title": "A", "n": 1}, {"n": 2, "title": "B", "extra": true}]"#,
+            true,
+        )
+        .unwrap();
+        assert_eq!(table.columns, vec!["n", "title", "extra"]);
+        assert_eq!(table.column(1), vec!["A", "B"]);
+        assert_eq!(table.column(2), vec!["true"]);
+    }
+
+    #[test]
+    fn plain_lines_are_not_a_table() {
+        assert_eq!(parse("Pizza\nSushi\nTacos", true), None);
+        assert_eq!(parse("[not json", true), None);
+    }
+}
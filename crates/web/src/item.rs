@@ -0,0 +1,41 @@
+//! How an item's text is displayed.
+//!
+//! Items stay plain strings everywhere (URL hash, stepper, results); only
+//! rendering looks inside them, through [`Item::parse`]: an image is shown
+//! with the label and a description as its tooltip.
+
+use leptos::prelude::*;
+use rankfast_session::Item;
+
+/// An item as thumbnail plus label, or just the label, with its
+/// description as a tooltip.
+#[component]
+#[allow(clippy::needless_pass_by_value)] // component props are owned
+pub(crate) fn ItemLabel(#[prop(into)] text: String) -> impl IntoView {
+    let Item {
+        label,
+        description,
+        image,
+        ..
+    } = Item::parse(&text);
+    view! {
+        {image.map(|src| {
+            view! { <img class="item-thumb" src=src alt="" loading="lazy" /> }
+        })}
+        <span class="item-label" title=description>
+            {label}
+        </span>
+    }
+}
+
+/// Preloads the images of `items` without showing them, so an upcoming
+/// question renders at once.
+#[component]
+#[allow(clippy::needless_pass_by_value)] // component props are owned
+pub(crate) fn PreloadImages(items: Vec<String>) -> impl IntoView {
+    items
+        .iter()
+        .filter_map(|text| Item::parse(text).image)
+        .map(|href| view! { <link rel="preload" r#as="image" href=href /> })
+        .collect_view()
+}
@@ -1,68 +1,78 @@
-mod stepper;
-
-use std::sync::Arc;
-
+mod a11y;
+mod compare;
+mod debug;
+mod diff;
+mod engine;
+mod guard;
+mod host;
+mod i18n;
+mod import;
+mod item;
+mod pwa;
+mod reorder;
+mod results;
+mod rewind;
+mod timing;
+mod widget;
+
+use a11y::MotionToggle;
+use compare::{Compare, Progress};
+use debug::DebugPanel;
+use diff::RankingDiff;
+use engine::{BUDGET_WARNING, DEFAULT_TOP_K, configured, usual_turns};
+use i18n::{LocaleSwitcher, fill, use_texts};
+use import::{ImportPanel, read_file};
+use item::{ItemLabel, PreloadImages};
 use leptos::ev;
 use leptos::prelude::*;
-use rankfast::estimate_turns;
-use stepper::{Step, Stepper};
-
-/// Parses the URL hash into items and answers.
-///
-/// Format: `#item1,item2,item3!aabba`
-/// - Items are comma-separated, each URI-component-encoded
-/// - `!` separates items from answers
-/// - Answers are `a` (true) / `b` (false) chars
-fn parse_hash() -> (Vec<String>, Vec<bool>) {
-    let hash = window().location().hash().unwrap_or_default();
-    let hash = hash.strip_prefix('#').unwrap_or(&hash);
-
-    if hash.is_empty() {
-        return (Vec::new(), Vec::new());
-    }
-
-    let (items_part, answers_part) = match hash.split_once('!') {
-        Some((i, a)) => (i, a),
-        None => (hash, ""),
-    };
-
-    let items: Vec<String> = items_part
-        .split(',')
-        .map(decode_uri_component)
-        .filter(|s| !s.is_empty())
-        .collect();
-
-    let answers: Vec<bool> = answers_part
-        .chars()
-        .filter_map(|c| match c {
-            'a' => Some(true),
-            'b' => Some(false),
-            _ => None,
-        })
-        .collect();
-
-    (items, answers)
+use pwa::InstallButton;
+use rankfast::stepper::LevelState;
+use rankfast::tiers::Tiers;
+use rankfast::{Phase, Ranking, Step, normalize_items};
+use rankfast_session::{
+    Answer, HashError, HashState, Item, Mode, Session, SessionError, SessionState, Sides, analyze,
+    hash, preferences, validate_session, without_item,
+};
+use results::Results;
+use rewind::QuestionHistory;
+use timing::Pace;
+
+/// Reads items and answers from the current URL hash, see [`hash`].
+fn parse_hash() -> Result<HashState, HashError> {
+    hash::try_parse(&window().location().hash().unwrap_or_default())
 }
 
-/// Builds a URL hash string from items and answers.
-fn build_hash(items: &[String], answers: &[bool]) -> String {
-    let items_part: String = items
-        .iter()
-        .map(|s| encode_uri_component(s))
-        .collect::<Vec<_>>()
-        .join(",");
-
-    if answers.is_empty() {
-        return items_part;
+/// Removes the URL hash, as a new history entry.
+fn clear_hash() {
+    if widget::ENABLED {
+        return;
+    }
+    let win = window();
+    let location = win.location();
+    let url = format!(
+        "{}{}",
+        location.pathname().unwrap_or_default(),
+        location.search().unwrap_or_default()
+    );
+    if let Ok(h) = win.history() {
+        let _ = h.push_state_with_url(&wasm_bindgen::JsValue::NULL, "", Some(&url));
     }
-
-    let answers_part: String = answers.iter().map(|&b| if b { 'a' } else { 'b' }).collect();
-    format!("{items_part}!{answers_part}")
 }
 
-/// Pushes the full state (items + answers) to the URL hash as a new history entry.
-fn push_hash_full(items: &[String], answers: &[bool]) {
-    let hash = build_hash(items, answers);
+/// Pushes the full state (items + answers + mode + sides + prompt) to the
+/// URL hash as a new history entry.
+fn push_hash_full(
+    items: &[String],
+    answers: &[Answer],
+    mode: Mode,
+    sides: Sides,
+    prompt: Option<&str>,
+) {
+    // An embedded widget stays out of the host page's history.
+    if widget::ENABLED {
+        return;
+    }
+    let hash = hash::build(items, answers, mode, sides, prompt);
     let win = window();
     if let Ok(h) = win.history() {
         let url = format!("#{hash}");
@@ -70,186 +80,453 @@ fn push_hash_full(items: &[String], answers: &[bool]) {
     }
 }
 
-fn encode_uri_component(s: &str) -> String {
-    js_sys::encode_uri_component(s).into()
-}
-
-fn decode_uri_component(s: &str) -> String {
-    js_sys::decode_uri_component(s).map_or_else(|_| s.to_string(), String::from)
-}
-
-/// Replays the answer sequence through a fresh stepper and returns
-/// the resulting UI state.
-fn derive_state(n: usize, answers: &[bool]) -> RankState {
-    let mut stepper = Stepper::new(n);
-    let mut last_step = stepper.step();
-
-    for &answer in answers {
-        if last_step == Step::Done {
-            break;
-        }
-        last_step = stepper.answer(answer);
-    }
-
-    match last_step {
-        Step::Compare { a, b } => RankState {
+/// The state `answers` lead to; `finished` ends the run early with the best
+/// ranking those answers support. Answers past the end of the ranking are
+/// left out and counted in [`RankState::ignored`].
+///
+/// Fails if the answers cannot be replayed at all, which a link edited by
+/// hand can cause; the app then offers to start over.
+fn derive_state(
+    items: &[String],
+    mode: Mode,
+    answers: &[Answer],
+    finished: bool,
+) -> Result<RankState, SessionError> {
+    let (replayed, ignored) = match validate_session(items, answers, mode) {
+        Ok(replayed) => (replayed, 0),
+        Err(SessionError::TooManyAnswers { used, given }) => (
+            validate_session(items, &answers[..used], mode)?,
+            given - used,
+        ),
+        Err(e) => return Err(e),
+    };
+    let SessionState {
+        engine: mut stepper,
+        step,
+        history,
+    } = replayed;
+
+    Ok(match step {
+        Step::Compare { .. } if finished => RankState {
+            current: None,
+            searching: None,
+            upcoming: Vec::new(),
+            ranking: Some(stepper.finalize_partial()),
+            comparisons: stepper.comparisons_made(),
+            remaining: 0,
+            history,
+            ignored,
+            levels: Vec::new(),
+        },
+        Step::Compare { a, b, context } => RankState {
             current: Some((a, b)),
+            searching: match context.phase {
+                Phase::Insertion { candidates } => Some(candidates),
+                Phase::Pairing | Phase::Adaptive | Phase::Partition { .. } => None,
+            },
+            upcoming: [true, false]
+                .into_iter()
+                .filter_map(|better_is_a| stepper.peek_next_after(better_is_a))
+                .flat_map(|(a, b)| [a, b])
+                .collect(),
             ranking: None,
             comparisons: stepper.comparisons_made(),
+            remaining: stepper.remaining_estimate(),
+            history,
+            ignored,
+            levels: debug::levels(&stepper),
         },
         Step::Done => RankState {
             current: None,
+            searching: None,
+            upcoming: Vec::new(),
             ranking: stepper.take_order(),
             comparisons: stepper.comparisons_made(),
+            remaining: 0,
+            history,
+            ignored,
+            levels: Vec::new(),
         },
-    }
+    })
 }
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone, Default, PartialEq)]
 struct RankState {
     current: Option<(usize, usize)>,
-    ranking: Option<Vec<usize>>,
+    /// Places `a` of the current question can still take, while the engine
+    /// is searching for its place.
+    searching: Option<usize>,
+    /// Items of the questions that may come next, to preload.
+    upcoming: Vec<usize>,
+    ranking: Option<Ranking<usize>>,
     comparisons: usize,
+    /// Upper bound on the questions still to come.
+    remaining: usize,
+    /// Every question asked so far with the answer given.
+    history: Vec<(usize, usize, Answer)>,
+    /// Answers given after the ranking was already complete.
+    ignored: usize,
+    /// The exact sort's recursion levels while the [`DebugPanel`] is on.
+    levels: Vec<LevelState>,
 }
 
 fn main() {
     console_error_panic_hook::set_once();
+    if !widget::ENABLED {
+        pwa::register_service_worker();
+    }
     mount_to_body(App);
 }
 
 #[component]
 fn App() -> impl IntoView {
-    let (initial_items, initial_answers) = parse_hash();
-
-    let (items, set_items) = signal(initial_items);
-    let (answers, set_answers) = signal(initial_answers);
+    let set_locale = i18n::provide_locale();
+    let t = use_texts();
+    let initial = parse_hash();
+    // The link could not be read at all; the app offers to start over.
+    let (unreadable, set_unreadable) = signal(initial.is_err());
+    let initial = initial.unwrap_or_default();
+
+    let (items, set_items) = signal(initial.items);
+    let (answers, set_answers) = signal(initial.answers);
+    let (mode, set_mode) = signal(initial.mode);
+    let (sides, set_sides) = signal(initial.sides);
+    let (prompt, set_prompt) = signal(initial.prompt);
+    let (mismatch, set_mismatch) = signal(initial.mismatch);
+    // "Finish now" was pressed; loading another session clears it.
+    let (finished, set_finished) = signal(false);
+    let (pace, set_pace) = signal(Pace::default());
+    // The ranking as reordered by hand; any change to the run drops it.
+    let (final_order, set_final_order) = signal(None::<Vec<usize>>);
+    let (reduce_motion, set_reduce_motion) = signal(a11y::saved_reduce_motion());
 
     // All UI state is derived from the items + answer history.
-    let state = Memo::new(move |_| {
-        let cur_items = items.get();
-        derive_state(cur_items.len(), &answers.get())
+    let replay = Memo::new(move |_| {
+        items.with(|items| derive_state(items, mode.get(), &answers.get(), finished.get()))
     });
+    let state = Memo::new(move |_| replay.get().unwrap_or_default());
+    let corrupt = move || unreadable.get() || replay.with(Result::is_err);
+
+    // Projected total, which shrinks as answers rule out worst cases.
+    let estimate = Memo::new(move |_| {
+        let s = state.get();
+        s.comparisons + s.remaining
+    });
+
+    // A run with answers and no ranking yet is only saved in the URL.
+    let in_progress = Memo::new(move |_| {
+        !corrupt() && answers.with(|a| !a.is_empty()) && state.with(|s| s.ranking.is_none())
+    });
+    let _beforeunload = window_event_listener(ev::beforeunload, move |ev| {
+        guard::before_unload(&ev, in_progress.get());
+    });
+
+    // Whether the run may be dropped: at once unless it is unfinished, in
+    // which case the user is asked and its link copied.
+    let may_leave = move || {
+        if !in_progress.get() {
+            return true;
+        }
+        let resume = hash::build(
+            &items.get(),
+            &answers.get(),
+            mode.get(),
+            sides.get(),
+            prompt.get().as_deref(),
+        );
+        guard::confirm_leave(t.get().leave_unfinished, &resume)
+    };
 
-    let estimate = Memo::new(move |_| estimate_turns(items.get().len()));
+    // Sets every signal from a link.
+    let load = move |parsed: Result<HashState, HashError>| {
+        set_unreadable.set(parsed.is_err());
+        let parsed = parsed.unwrap_or_default();
+        set_items.set(parsed.items);
+        set_answers.set(parsed.answers);
+        set_mode.set(parsed.mode);
+        set_sides.set(parsed.sides);
+        set_prompt.set(parsed.prompt);
+        set_mismatch.set(parsed.mismatch);
+        set_finished.set(false);
+        set_pace.set(Pace::default());
+        set_final_order.set(None);
+    };
 
     // Sync URL -> signals on back/forward and manual hash edits.
-    let _popstate = window_event_listener(ev::popstate, move |_| {
-        let (new_items, new_answers) = parse_hash();
-        set_items.set(new_items);
-        set_answers.set(new_answers);
+    let load_hash = move || {
+        let parsed = parse_hash();
+        // Another list replaces the run; moving through its own answers
+        // does not.
+        let same_list = parsed
+            .as_ref()
+            .is_ok_and(|p| items.with(|items| p.items == *items));
+        if !same_list && !may_leave() {
+            // Keep the run, and its link in the address bar.
+            push_hash_full(
+                &items.get(),
+                &answers.get(),
+                mode.get(),
+                sides.get(),
+                prompt.get().as_deref(),
+            );
+            return;
+        }
+        load(parsed);
+    };
+    let _popstate = window_event_listener(ev::popstate, move |_| load_hash());
+    let _hashchange = window_event_listener(ev::hashchange, move |_| load_hash());
+
+    // Starting over keeps the items and drops the answers; a new list
+    // drops both and goes back to the input form.
+    let restart = move |_| {
+        if !may_leave() {
+            return;
+        }
+        let same_items = HashState {
+            items: items.get(),
+            mode: mode.get(),
+            sides: sides.get(),
+            prompt: prompt.get(),
+            ..HashState::default()
+        };
+        push_hash_full(
+            &same_items.items,
+            &[],
+            same_items.mode,
+            same_items.sides,
+            same_items.prompt.as_deref(),
+        );
+        load(Ok(same_items));
+    };
+    let new_list = move |_| {
+        if may_leave() {
+            clear_hash();
+            load(Ok(HashState::default()));
+        }
+    };
+
+    // Going back to a past question keeps the answers before it only.
+    let rewind = Callback::new(move |kept: usize| {
+        let dropped = answers.with(Vec::len).saturating_sub(kept);
+        let mut rewound = answers.get();
+        rewound.truncate(kept);
+        push_hash_full(
+            &items.get(),
+            &rewound,
+            mode.get(),
+            sides.get(),
+            prompt.get().as_deref(),
+        );
+        set_mismatch.set(false);
+        set_finished.set(false);
+        set_final_order.set(None);
+        set_pace.update(|pace| pace.forget(dropped));
+        set_answers.set(rewound);
     });
-    let _hashchange = window_event_listener(ev::hashchange, move |_| {
-        let (new_items, new_answers) = parse_hash();
+
+    // Host pages start rankings through `window.rankfast` or, around the
+    // widget, messages.
+    let load_items = move |new_items: Vec<String>| {
+        let sides = Sides::Shuffled(random_seed());
+        push_hash_full(&new_items, &[], Mode::Exact, sides, None);
+        set_answers.set(Vec::new());
+        set_mode.set(Mode::Exact);
+        set_sides.set(sides);
+        set_prompt.set(None);
+        set_mismatch.set(false);
+        set_unreadable.set(false);
+        set_finished.set(false);
+        set_pace.set(Pace::default());
+        set_final_order.set(None);
         set_items.set(new_items);
-        set_answers.set(new_answers);
+    };
+    host::install(load_items);
+    if widget::ENABLED {
+        widget::listen(load_items);
+    }
+    let result = Memo::new(move |_| {
+        items.with(|items| {
+            state.with(|s| {
+                let order = s.ranking.as_ref()?;
+                let ranking: Vec<String> = order.iter().map(|&i| items[i].clone()).collect();
+                Some((ranking, s.comparisons))
+            })
+        })
+    });
+    Effect::new(move |_| {
+        if let Some((ranking, comparisons)) = result.get() {
+            host::notify(&host::result(None, &ranking, comparisons));
+            if widget::ENABLED {
+                widget::post_result(&ranking, comparisons);
+            }
+        }
     });
 
     view! {
-        <main class="app">
-            <header class="header">
-                <h1>"Rankfast"</h1>
-                <p class="subtitle">"Pairwise ranking tool"</p>
-            </header>
+        <main class="app" class:reduce-motion=move || reduce_motion.get()>
+            {(!widget::ENABLED)
+                .then(|| {
+                    view! {
+                        <header class="header">
+                            <h1>"Rankfast"</h1>
+                            <p class="subtitle">{move || t.get().subtitle}</p>
+                            <div class="header-controls">
+                                <LocaleSwitcher set_locale />
+                                <MotionToggle reduce_motion set_reduce_motion />
+                                <InstallButton />
+                            </div>
+                        </header>
+                    }
+                })}
+
+            <Show when=move || mismatch.get()>
+                <p class="hash-warning" role="alert">
+                    {move || t.get().hash_mismatch}
+                </p>
+            </Show>
+            <Show when=move || state.with(|s| s.ignored > 0)>
+                <p class="hash-warning" role="alert">
+                    {move || fill(t.get().hash_extra_answers, &[&state.with(|s| s.ignored)])}
+                </p>
+            </Show>
 
             {move || {
                 let cur_items = items.get();
-                if cur_items.is_empty() {
-                    view! { <InputForm set_items set_answers /> }.into_any()
+                if corrupt() {
+                    view! {
+                        <section class="corrupt-link" role="alert">
+                            <h2>{move || t.get().corrupt_link_title}</h2>
+                            <p>{move || t.get().corrupt_link_body}</p>
+                            <button
+                                class="start-btn"
+                                on:click=move |_| {
+                                    clear_hash();
+                                    load_hash();
+                                }
+                            >
+                                {move || t.get().corrupt_link_reset}
+                            </button>
+                        </section>
+                    }
+                    .into_any()
+                } else if cur_items.is_empty() && widget::ENABLED {
+                    view! { <p class="no-compare">{move || t.get().widget_waiting}</p> }.into_any()
+                } else if cur_items.is_empty() {
+                    view! { <InputForm set_items set_answers set_mode set_sides set_prompt set_final_order /> }.into_any()
                 } else {
-                    let items_arc = Arc::new(cur_items);
-                    let items_for_ranking = items_arc.clone();
-                    let items_for_tags = items_arc.clone();
+                    // Dropping an item keeps the answers about the others.
+                    let remove = move |idx: usize| {
+                        let mut new_items = items.get();
+                        let kept = without_item(&new_items, &answers.get(), mode.get(), idx);
+                        new_items.remove(idx);
+                        push_hash_full(
+                            &new_items,
+                            &kept,
+                            mode.get(),
+                            sides.get(),
+                            prompt.get().as_deref(),
+                        );
+                        set_mismatch.set(false);
+                        set_final_order.set(None);
+                        set_answers.set(kept);
+                        set_items.set(new_items);
+                    };
                     view! {
-                        <div class="progress-area">
-                            <div class="progress-text">
-                                <span>"Comparison"</span>
-                                <span class="progress-numbers">
-                                    {move || state.get().comparisons} " / " {move || estimate.get()}
-                                </span>
-                            </div>
-                            <div class="progress-bar">
-                                <div
-                                    class="progress-fill"
-                                    style:width=move || {
-                                        let est = estimate.get();
-                                        let pct = if est > 0 {
-                                            100 * state.get().comparisons / est
-                                        } else {
-                                            100
-                                        };
-                                        format!("{pct}%")
-                                    }
-                                />
-                            </div>
-                        </div>
+                        <Progress
+                            comparisons=Signal::derive(move || state.get().comparisons)
+                            total=estimate
+                            done=Signal::derive(move || state.get().ranking.is_some())
+                            time_left=Signal::derive(move || {
+                                pace.with(|pace| pace.time_left(state.get().remaining))
+                            })
+                        />
 
                         {
-                            let items_inner = items_for_ranking.clone();
                             move || {
-                                let items_inner = items_inner.clone();
                                 let s = state.get();
                                 match (s.ranking, s.current) {
-                                    (Some(order), _) => view! {
-                                        <section class="results">
-                                            <h2 class="results-title">"Your Ranking"</h2>
-                                            <ol class="ranking-list">
-                                                {order
-                                                    .iter()
-                                                    .enumerate()
-                                                    .map(|(rank, &idx)| {
-                                                        view! {
-                                                            <li
-                                                                class="ranking-item"
-                                                                class:gold={rank == 0}
-                                                                class:silver={rank == 1}
-                                                                class:bronze={rank == 2}
-                                                            >
-                                                                <span class="rank-number">{rank + 1}</span>
-                                                                <span class="rank-name">
-                                                                    {items_inner[idx].clone()}
-                                                                </span>
-                                                            </li>
-                                                        }
-                                                    })
-                                                    .collect_view()}
-                                            </ol>
-                                        </section>
+                                    (Some(order), _) => {
+                                        let answers = s.history.iter().map(|h| h.2).collect();
+                                        let session = Session {
+                                            sides: sides.get(),
+                                            ..Session::new(items.get(), answers, mode.get())
+                                        };
+                                        let stats =
+                                            analyze(&session, Some(&pace.with(Pace::gaps))).ok();
+                                        let preferences =
+                                            preferences(&session, &pace.with(Pace::stamps_ms))
+                                                .unwrap_or_default();
+                                        view! {
+                                            <Results
+                                                items
+                                                order
+                                                history=s.history
+                                                stats
+                                                preferences
+                                                // Read once: the list keeps its own
+                                                // order while it is reordered.
+                                                final_order=final_order.get_untracked()
+                                                on_reorder=Callback::new(move |order| {
+                                                    set_final_order.set(order);
+                                                })
+                                            />
+                                        }
+                                        .into_any()
                                     }
-                                    .into_any(),
                                     (None, Some((a, b))) => {
-                                        let on_a = move |_| {
-                                            set_answers.update(|ans| {
-                                                ans.push(true);
-                                                push_hash_full(&items.get(), ans);
-                                            });
-                                        };
-                                        let on_b = move |_| {
+                                        // Answers are recorded for `a` vs `b`,
+                                        // whichever side each is shown on.
+                                        let question = s.history.len();
+                                        let narrows = s.searching.map(|candidates| {
+                                            (items.with(|items| Item::parse(&items[a]).label), candidates)
+                                        });
+                                        let shown = sides.get();
+                                        let (first, second) = shown.present(question, a, b);
+                                        let give = Callback::new(move |answer: Answer| {
+                                            set_mismatch.set(false);
+                                            set_pace.update(|pace| pace.record(js_sys::Date::now()));
                                             set_answers.update(|ans| {
-                                                ans.push(false);
-                                                push_hash_full(&items.get(), ans);
+                                                ans.push(shown.record(question, answer));
+                                                push_hash_full(
+                                                    &items.get(),
+                                                    ans,
+                                                    mode.get(),
+                                                    shown,
+                                                    prompt.get().as_deref(),
+                                                );
                                             });
-                                        };
-
+                                        });
+                                        let finish = Callback::new(move |()| {
+                                            set_finished.set(true);
+                                        });
+                                        let (a, b, upcoming) = items.with(|items| {
+                                            let upcoming =
+                                                s.upcoming.iter().map(|&item| items[item].clone()).collect();
+                                            (items[first].clone(), items[second].clone(), upcoming)
+                                        });
                                         view! {
-                                            <section class="compare">
-                                                <h2 class="compare-prompt">"Which do you prefer?"</h2>
-                                                <div class="compare-buttons">
-                                                    <button class="choice-btn" on:click=on_a>
-                                                        {items_inner[a].clone()}
-                                                    </button>
-                                                    <span class="vs">"vs"</span>
-                                                    <button class="choice-btn" on:click=on_b>
-                                                        {items_inner[b].clone()}
-                                                    </button>
-                                                </div>
-                                            </section>
+                                            <Compare
+                                                a
+                                                b
+                                                on_answer=give
+                                                on_finish=finish
+                                                narrows
+                                                prompt=prompt.get()
+                                            />
+                                            <PreloadImages items=upcoming />
+                                            {(!s.levels.is_empty())
+                                                .then(|| {
+                                                    view! {
+                                                        <DebugPanel items levels=s.levels />
+                                                    }
+                                                })}
                                         }
                                         .into_any()
                                     }
                                     _ => view! {
                                         <section class="results">
                                             <p class="no-compare">
-                                                "Only one item \u{2014} no comparisons needed!"
+                                                {move || t.get().single_item}
                                             </p>
                                         </section>
                                     }
@@ -257,18 +534,77 @@ fn App() -> impl IntoView {
                                 }
                             }
                         }
-
-                        <section class="items">
-                            <h3 class="items-heading">"Items being ranked"</h3>
-                            <div class="items-tags">
-                                {items_for_tags
-                                    .iter()
-                                    .map(|name| {
-                                        view! { <span class="item-tag">{name.clone()}</span> }
-                                    })
-                                    .collect_view()}
-                            </div>
-                        </section>
+                        {move || {
+                            let history = state.get().history;
+                            (!history.is_empty())
+                                .then(|| {
+                                    view! {
+                                        <QuestionHistory
+                                            items
+                                            history
+                                            on_rewind=rewind
+                                        />
+                                    }
+                                })
+                        }}
+
+                        {(!widget::ENABLED)
+                            .then(|| {
+                                view! {
+                                    <section class="items">
+                                        <a
+                                            class="save-session"
+                                            download="rankfast-session.json"
+                                            href=move || {
+                                                let saved = Session {
+                                                    sides: sides.get(),
+                                                    prompt: prompt.get(),
+                                                    final_order: final_order.get(),
+                                                    ..Session::new(items.get(), answers.get(), mode.get())
+                                                };
+                                                let json = String::from(
+                                                    js_sys::encode_uri_component(&saved.to_json()),
+                                                );
+                                                format!("data:application/json,{json}")
+                                            }
+                                        >
+                                            {move || t.get().save_session}
+                                        </a>
+                                        <div class="session-controls">
+                                            <button class="extra-btn" on:click=restart>
+                                                {move || t.get().restart}
+                                            </button>
+                                            <button class="extra-btn" on:click=new_list>
+                                                {move || t.get().new_list}
+                                            </button>
+                                        </div>
+                                        <h3 class="items-heading">{move || t.get().items_heading}</h3>
+                                        <div class="items-tags">
+                                            {cur_items
+                                                .iter()
+                                                .enumerate()
+                                                .map(|(idx, name)| {
+                                                    let label = Item::parse(name).label;
+                                                    view! {
+                                                        <span class="item-tag">
+                                                            <ItemLabel text=name.clone() />
+                                                            <button
+                                                                class="item-remove"
+                                                                aria-label=move || {
+                                                                    fill(t.get().remove_item, &[&label])
+                                                                }
+                                                                on:click=move |_| remove(idx)
+                                                            >
+                                                                "×"
+                                                            </button>
+                                                        </span>
+                                                    }
+                                                })
+                                                .collect_view()}
+                                        </div>
+                                    </section>
+                                }
+                            })}
                     }
                     .into_any()
                 }
@@ -280,32 +616,126 @@ fn App() -> impl IntoView {
 #[component]
 fn InputForm(
     set_items: WriteSignal<Vec<String>>,
-    set_answers: WriteSignal<Vec<bool>>,
+    set_answers: WriteSignal<Vec<Answer>>,
+    set_mode: WriteSignal<Mode>,
+    set_sides: WriteSignal<Sides>,
+    set_prompt: WriteSignal<Option<String>>,
+    set_final_order: WriteSignal<Option<Vec<usize>>>,
 ) -> impl IntoView {
+    let t = use_texts();
     let (text, set_text) = signal(String::new());
+    let (question, set_question) = signal(String::new());
+    let configured = configured();
+    let (choice, set_choice) = signal(configured.unwrap_or(Mode::Exact));
+    let (shuffle, set_shuffle) = signal(true);
+
+    let normalized = Memo::new(move |_| normalize_items(text.get().lines()));
+    let item_count = Memo::new(move |_| normalized.with(|n| n.items.len()));
+    let over_budget = Memo::new(move |_| Mode::Exact.estimate(item_count.get()) > BUDGET_WARNING);
+    let usual = Memo::new(move |_| {
+        let n = item_count.get();
+        (n >= 2 && !over_budget.get()).then(|| format!("{:.0}", usual_turns(n)))
+    });
+    // A dropped or pasted session document resumes instead of importing.
+    // Links cannot hold known pairs, so sessions with some are not resumed.
+    let saved = Memo::new(move |_| {
+        Session::from_json(text.get().trim())
+            .ok()
+            .filter(|session| session.known.is_empty())
+    });
 
     let on_start = move |_| {
-        let raw = text.get();
-        let new_items: Vec<String> = raw
-            .lines()
-            .map(|l| l.trim().to_string())
-            .filter(|l| !l.is_empty())
-            .collect();
-
+        let new_items = normalized.get().items;
         if new_items.len() >= 2 {
-            push_hash_full(&new_items, &[]);
+            let mode = if over_budget.get() || configured.is_some() {
+                choice.get()
+            } else {
+                Mode::Exact
+            };
+            let sides = if shuffle.get() {
+                Sides::Shuffled(random_seed())
+            } else {
+                Sides::Fixed
+            };
+            let question = question.get().trim().to_string();
+            let question = (!question.is_empty()).then_some(question);
+            push_hash_full(&new_items, &[], mode, sides, question.as_deref());
             set_answers.set(Vec::new());
+            set_mode.set(mode);
+            set_sides.set(sides);
+            set_prompt.set(question);
             set_items.set(new_items);
         }
     };
 
-    let item_count =
-        Memo::new(move |_| text.get().lines().filter(|l| !l.trim().is_empty()).count());
+    let budget_warning = move || {
+        over_budget.get().then(|| {
+            let n = item_count.get();
+            let option = move |mode: Mode, label: String| {
+                view! {
+                    <label class="mode-option">
+                        <input
+                            type="radio"
+                            name="mode"
+                            prop:checked=move || choice.get() == mode
+                            on:change=move |_| set_choice.set(mode)
+                        />
+                        {label}
+                    </label>
+                }
+            };
+            view! {
+                <div class="budget-warning" role="alert">
+                    <p>{fill(t.get().budget_warning, &[&n, &Mode::Exact.estimate(n)])}</p>
+                    {option(Mode::Exact, t.get().mode_exact.to_string())}
+                    {option(
+                        Mode::Approximate,
+                        fill(t.get().mode_approximate, &[&Mode::Approximate.estimate(n)]),
+                    )}
+                    {option(
+                        Mode::TopK(DEFAULT_TOP_K),
+                        fill(
+                            t.get().mode_top_k,
+                            &[&DEFAULT_TOP_K, &Mode::TopK(DEFAULT_TOP_K).estimate(n)],
+                        ),
+                    )}
+                    {option(
+                        Mode::Tiers,
+                        fill(
+                            t.get().mode_tiers,
+                            &[&Tiers::tiering_estimate(n), &Mode::Tiers.estimate(n)],
+                        ),
+                    )}
+                    {option(
+                        Mode::QuickRank,
+                        fill(t.get().mode_quick, &[&Mode::QuickRank.estimate(n)]),
+                    )}
+                </div>
+            }
+        })
+    };
+    let duplicate_note = move || {
+        normalized.with(|n| {
+            let names: Vec<&str> = n.duplicates.iter().map(|d| d.text.as_str()).collect();
+            (!names.is_empty()).then(|| {
+                let template = if names.len() == 1 {
+                    t.get().duplicates_one
+                } else {
+                    t.get().duplicates_other
+                };
+                view! {
+                    <p class="duplicate-note">
+                        {fill(template, &[&names.len(), &names.join(", ")])}
+                    </p>
+                }
+            })
+        })
+    };
 
     view! {
         <section class="input-form">
-            <h2 class="input-title">"Enter items to rank"</h2>
-            <p class="input-hint">"One item per line (minimum 2)"</p>
+            <h2 class="input-title">{move || t.get().input_title}</h2>
+            <p class="input-hint">{move || t.get().input_hint}</p>
             <textarea
                 class="item-textarea"
                 rows="8"
@@ -314,7 +744,79 @@ fn InputForm(
                 on:input=move |ev| {
                     set_text.set(event_target_value(&ev));
                 }
+                on:dragover=|ev| ev.prevent_default()
+                on:drop=move |ev| {
+                    let file = ev.data_transfer().and_then(|dt| dt.files()).and_then(|f| f.get(0));
+                    if let Some(file) = file {
+                        ev.prevent_default();
+                        read_file(&file, set_text);
+                    }
+                }
             />
+            <label class="file-pick">
+                {move || t.get().import_file}
+                <input
+                    type="file"
+                    accept=".csv,.tsv,.json,.txt,text/csv,application/json,text/plain"
+                    on:change=move |ev| {
+                        let input: web_sys::HtmlInputElement = event_target(&ev);
+                        if let Some(file) = input.files().and_then(|f| f.get(0)) {
+                            read_file(&file, set_text);
+                        }
+                    }
+                />
+            </label>
+            <ImportPanel text set_text />
+            {move || {
+                saved
+                    .get()
+                    .map(|session| {
+                        let count = session.answers.len();
+                        let resume = move |_| {
+                            push_hash_full(
+                                &session.items,
+                                &session.answers,
+                                session.engine,
+                                session.sides,
+                                session.prompt.as_deref(),
+                            );
+                            set_answers.set(session.answers.clone());
+                            set_mode.set(session.engine);
+                            set_sides.set(session.sides);
+                            set_prompt.set(session.prompt.clone());
+                            // Only an order of every item is kept.
+                            set_final_order
+                                .set(session.finalized_ranking().map(|order| order.to_vec()));
+                            set_items.set(session.items.clone());
+                        };
+                        view! {
+                            <button class="resume-btn" on:click=resume>
+                                {move || fill(t.get().resume_session, &[&count])}
+                            </button>
+                        }
+                    })
+            }}
+            {duplicate_note}
+            {budget_warning}
+            <label class="prompt-option">
+                {move || t.get().prompt_label}
+                <input
+                    type="text"
+                    class="prompt-input"
+                    placeholder=move || t.get().prompt
+                    prop:value=move || question.get()
+                    on:input=move |ev| set_question.set(event_target_value(&ev))
+                />
+            </label>
+            <label class="shuffle-option">
+                <input
+                    type="checkbox"
+                    prop:checked=move || shuffle.get()
+                    on:change=move |ev| set_shuffle.set(event_target_checked(&ev))
+                />
+                " "
+                {move || t.get().shuffle_sides}
+            </label>
             <button
                 class="start-btn"
                 on:click=on_start
@@ -323,12 +825,30 @@ fn InputForm(
                 {move || {
                     let count = item_count.get();
                     if count < 2 {
-                        "Enter at least 2 items".to_string()
+                        t.get().need_two.to_string()
                     } else {
-                        format!("Start ranking ({count} items)")
+                        fill(t.get().start, &[&count])
                     }
                 }}
             </button>
+            {move || {
+                usual
+                    .get()
+                    .map(|turns| {
+                        view! {
+                            <p class="usual-questions">
+                                {fill(t.get().usual_questions, &[&turns])}
+                            </p>
+                        }
+                    })
+            }}
+            <RankingDiff />
         </section>
     }
 }
+
+/// A fresh seed for [`Sides::Shuffled`].
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)] // in 0..2^32
+fn random_seed() -> u32 {
+    (js_sys::Math::random() * 4_294_967_296.0) as u32
+}
@@ -0,0 +1,65 @@
+//! Installable, offline-capable app.
+//!
+//! The engine runs entirely in the page, so once the assets are cached the
+//! app needs no network at all. `public/sw.js` caches the shell and every
+//! asset it references; [`register_service_worker`] installs it where the
+//! browser supports service workers (secure contexts only). Browsers that
+//! offer installation fire `beforeinstallprompt`, which [`InstallButton`]
+//! keeps so the user can trigger the prompt from the header.
+
+use js_sys::{Function, Reflect};
+use leptos::prelude::*;
+use wasm_bindgen::{JsCast, JsValue};
+
+use crate::i18n::use_texts;
+
+/// Service worker script, copied next to `index.html` by Trunk.
+const SERVICE_WORKER: &str = "sw.js";
+
+/// Registers the service worker. Unsupported browsers and insecure origins
+/// simply run online.
+pub(crate) fn register_service_worker() {
+    let navigator = window().navigator();
+    if !Reflect::has(&navigator, &JsValue::from_str("serviceWorker")).unwrap_or(false) {
+        return;
+    }
+    // The promise rejects on insecure origins; nothing to do about it.
+    let _ = navigator.service_worker().register(SERVICE_WORKER);
+}
+
+/// "Install app" button, shown once the browser offers installation.
+#[component]
+pub(crate) fn InstallButton() -> impl IntoView {
+    let t = use_texts();
+    let (prompt, set_prompt) = signal_local(None::<web_sys::Event>);
+
+    let _offer = window_event_listener_untyped("beforeinstallprompt", move |ev| {
+        // Keep the browser's mini-infobar away and offer our own button.
+        ev.prevent_default();
+        set_prompt.set(Some(ev));
+    });
+    let _installed = window_event_listener_untyped("appinstalled", move |_| set_prompt.set(None));
+
+    let install = move |_| {
+        let Some(event) = prompt.get_untracked() else {
+            return;
+        };
+        // The saved event can prompt only once.
+        set_prompt.set(None);
+        if let Ok(show) = Reflect::get(&event, &JsValue::from_str("prompt"))
+            && let Some(show) = show.dyn_ref::<Function>()
+        {
+            let _ = show.call0(&event);
+        }
+    };
+
+    move || {
+        prompt.with(Option::is_some).then(|| {
+            view! {
+                <button class="install-btn" on:click=install>
+                    {move || t.get().install}
+                </button>
+            }
+        })
+    }
+}
@@ -0,0 +1,125 @@
+//! Tweaking a finished ranking by hand.
+//!
+//! The answers give the ranking as compared, but the user may still want a
+//! place or two changed once they see it whole. [`Reorder`] lists the
+//! ranking with every item draggable to another place, and buttons moving
+//! it one place up or down for keyboards and screen readers. The order set
+//! here is reported apart from the answers, as the session's
+//! [`final_order`](rankfast_session::Session::final_order), so a saved
+//! session tells both orders apart.
+
+use leptos::prelude::*;
+use rankfast_session::Item;
+
+use crate::i18n::{fill, use_texts};
+
+/// The ranking in a fold-out list that can be reordered. `on_change` gets
+/// the new order after every move, or `None` once it is back to `compared`.
+#[component]
+#[allow(clippy::needless_pass_by_value)] // component props are owned
+pub(crate) fn Reorder(
+    items: Signal<Vec<String>>,
+    /// The ranking as the answers gave it, best first.
+    compared: Vec<usize>,
+    /// The order set by hand so far, if any.
+    initial: Option<Vec<usize>>,
+    on_change: Callback<Option<Vec<usize>>>,
+) -> impl IntoView {
+    let t = use_texts();
+    let adjusted = initial.is_some();
+    let (order, set_order) = signal(initial.unwrap_or_else(|| compared.clone()));
+    let compared = StoredValue::new(compared);
+    // Position of the item being dragged.
+    let (dragged, set_dragged) = signal(None::<usize>);
+
+    let report = move || {
+        let order = order.get();
+        let changed = compared.with_value(|compared| order != *compared);
+        on_change.run(changed.then_some(order));
+    };
+    let move_item = move |from: usize, to: usize| {
+        if from != to {
+            set_order.update(|order| {
+                let item = order.remove(from);
+                order.insert(to, item);
+            });
+            report();
+        }
+    };
+    let reset = move |_| {
+        set_order.set(compared.get_value());
+        report();
+    };
+    let is_adjusted = move || compared.with_value(|compared| order.with(|o| o != compared));
+
+    let rows = move || {
+        let order = order.get();
+        let last = order.len().saturating_sub(1);
+        order
+            .into_iter()
+            .enumerate()
+            .map(|(pos, item)| {
+                let label = items.with(|items| Item::parse(&items[item]).label);
+                let drag_label = label.clone();
+                view! {
+                    <li
+                        class="reorder-item"
+                        class:dragging=move || dragged.get() == Some(pos)
+                        draggable="true"
+                        on:dragstart=move |ev| {
+                            set_dragged.set(Some(pos));
+                            // Firefox only starts a drag that carries data.
+                            if let Some(data) = ev.data_transfer() {
+                                let _ = data.set_data("text/plain", &drag_label);
+                            }
+                        }
+                        on:dragover=move |ev| ev.prevent_default()
+                        on:drop=move |ev| {
+                            ev.prevent_default();
+                            if let Some(from) = dragged.get() {
+                                move_item(from, pos);
+                            }
+                            set_dragged.set(None);
+                        }
+                        on:dragend=move |_| set_dragged.set(None)
+                    >
+                        <span class="reorder-place">{pos + 1}</span>
+                        <span class="reorder-label">{label.clone()}</span>
+                        <button
+                            class="reorder-move"
+                            aria-label={
+                                let label = label.clone();
+                                move || fill(t.get().move_up, &[&label])
+                            }
+                            disabled=pos == 0
+                            on:click=move |_| move_item(pos, pos.saturating_sub(1))
+                        >
+                            "\u{2191}"
+                        </button>
+                        <button
+                            class="reorder-move"
+                            aria-label=move || fill(t.get().move_down, &[&label])
+                            disabled=pos == last
+                            on:click=move |_| move_item(pos, (pos + 1).min(last))
+                        >
+                            "\u{2193}"
+                        </button>
+                    </li>
+                }
+            })
+            .collect_view()
+    };
+
+    view! {
+        <details class="reorder" open=adjusted>
+            <summary>{move || t.get().reorder_title}</summary>
+            <p class="reorder-hint">{move || t.get().reorder_hint}</p>
+            <ol class="reorder-list">{rows}</ol>
+            <Show when=is_adjusted>
+                <button class="extra-btn" on:click=reset>
+                    {move || t.get().reorder_reset}
+                </button>
+            </Show>
+        </details>
+    }
+}
@@ -0,0 +1,681 @@
+use std::sync::Arc;
+
+use leptos::html;
+use leptos::prelude::*;
+use rankfast::Ranking;
+use rankfast::ratings::Consistency;
+use rankfast_session::item::by_tag;
+use rankfast_session::{Answer, Cell, Item, Preference, Stats, dataset, head_to_head};
+
+use crate::a11y::focus_on_mount;
+use crate::i18n::{Texts, fill, use_texts};
+use crate::item::ItemLabel;
+use crate::reorder::Reorder;
+
+/// Agreement below which the consistency note is shown as a warning.
+const LOW_CONSISTENCY: f64 = 0.9;
+
+/// Final ranking: a podium for the top three and a sortable, filterable
+/// table for everyone else. Clicking an item reveals the comparisons it took
+/// part in, reconstructed from the answer history; a second tab shows every
+/// pair at once in a [`HeadToHead`] matrix. How consistent the
+/// answers were shows under the title. The ranking can be [`Reorder`]ed by
+/// hand, tagged items are ranked again per tag, and the session's [`Stats`]
+/// fold out below, followed by the answers as preference data to download.
+#[component]
+#[allow(clippy::needless_pass_by_value)] // component props are owned
+pub(crate) fn Results(
+    #[prop(into)] items: Signal<Vec<String>>,
+    order: Ranking<usize>,
+    history: Vec<(usize, usize, Answer)>,
+    stats: Option<Stats>,
+    preferences: Vec<Preference>,
+    /// The order set by hand, see [`Reorder`].
+    final_order: Option<Vec<usize>>,
+    on_reorder: Callback<Option<Vec<usize>>>,
+) -> impl IntoView {
+    let t = use_texts();
+    let heading = NodeRef::<html::H2>::new();
+    focus_on_mount(heading);
+    let history = Arc::new(history);
+    let tag_order = order.to_vec();
+    let entries: Vec<Entry> = items.with(|items| {
+        order
+            .map(|item| (item, &items[item]))
+            .positions()
+            .map(|(rank, &(item, text))| Entry {
+                rank,
+                item,
+                name: Item::parse(text).label,
+                text: text.clone(),
+                record: Record::of(&history, item),
+            })
+            .collect()
+    });
+    let (podium, rest) = entries.split_at(entries.len().min(3));
+    let podium = podium.to_vec();
+    let rest = rest.to_vec();
+
+    let (matrix, set_matrix) = signal(false);
+    let (expanded, set_expanded) = signal(None::<usize>);
+    let toggle = Callback::new(move |item: usize| {
+        set_expanded.update(|e| *e = if *e == Some(item) { None } else { Some(item) });
+    });
+
+    let podium_items: Vec<usize> = podium.iter().map(|e| e.item).collect();
+    let podium_details = {
+        let history = history.clone();
+        move || {
+            expanded
+                .get()
+                .filter(|item| podium_items.contains(item))
+                .map(|item| view! { <MatchupList items history=history.clone() item /> })
+        }
+    };
+
+    view! {
+        <section class="results">
+            <h2 class="results-title" node_ref=heading tabindex="-1">
+                {move || t.get().results_title}
+            </h2>
+            <p class="results-hint">{move || t.get().results_hint}</p>
+            {stats.as_ref().map(|stats| view! { <ConsistencyNote consistency=stats.consistency /> })}
+            <ResultTabs matrix set_matrix />
+            <div role="tabpanel" hidden=move || matrix.get()>
+                <Podium entries=podium expanded toggle />
+                {podium_details}
+                {(!rest.is_empty())
+                    .then(|| {
+                        view! {
+                            <RankTable
+                                entries=rest
+                                items
+                                history=history.clone()
+                                expanded
+                                toggle
+                            />
+                        }
+                    })}
+            </div>
+            {
+                let order = tag_order.clone();
+                move || {
+                    matrix
+                        .get()
+                        .then(|| {
+                            view! {
+                                <div role="tabpanel">
+                                    <HeadToHead
+                                        items
+                                        order=order.clone()
+                                        history=history.clone()
+                                    />
+                                </div>
+                            }
+                        })
+                }
+            }
+            <Reorder items compared=tag_order.clone() initial=final_order on_change=on_reorder />
+            <TagRankings items order=tag_order />
+            {stats.map(|stats| view! { <StatsPanel stats items /> })}
+            <DatasetLinks preferences />
+        </section>
+    }
+}
+
+/// The tabs switching between the ranking and the [`HeadToHead`] matrix.
+#[component]
+fn ResultTabs(matrix: ReadSignal<bool>, set_matrix: WriteSignal<bool>) -> impl IntoView {
+    let t = use_texts();
+    view! {
+        <div class="results-tabs" role="tablist">
+            <button
+                class="results-tab"
+                role="tab"
+                aria-selected=move || (!matrix.get()).to_string()
+                on:click=move |_| set_matrix.set(false)
+            >
+                {move || t.get().tab_ranking}
+            </button>
+            <button
+                class="results-tab"
+                role="tab"
+                aria-selected=move || matrix.get().to_string()
+                on:click=move |_| set_matrix.set(true)
+            >
+                {move || t.get().tab_matrix}
+            </button>
+        </div>
+    }
+}
+
+/// Share of the answers the fitted scores agree with, flagged below
+/// [`LOW_CONSISTENCY`], and the answers that contradicted earlier ones.
+#[component]
+fn ConsistencyNote(consistency: Consistency) -> impl IntoView {
+    let t = use_texts();
+    let agreement = consistency.agreement();
+    let percent = format!("{:.0}", agreement * 100.0);
+    let Consistency {
+        outcomes,
+        implied,
+        contradicted,
+        ..
+    } = consistency;
+    (outcomes > 0).then(|| {
+        view! {
+            <p class="results-consistency" class:low=agreement < LOW_CONSISTENCY>
+                {move || fill(t.get().consistency, &[&percent])}
+                {(contradicted > 0)
+                    .then(|| {
+                        view! {
+                            <br />
+                            {move || {
+                                fill(t.get().consistency_contradicted, &[&contradicted, &implied])
+                            }}
+                        }
+                    })}
+            </p>
+        }
+    })
+}
+
+/// Every pair of items in ranking order, row items against column items:
+/// answered directly, inferred from other answers, or open, see
+/// [`head_to_head`]. Columns are numbered by place to keep the table narrow.
+#[component]
+#[allow(clippy::needless_pass_by_value)] // component props are owned
+fn HeadToHead(
+    items: Signal<Vec<String>>,
+    order: Vec<usize>,
+    history: Arc<Vec<(usize, usize, Answer)>>,
+) -> impl IntoView {
+    let t = use_texts();
+    let cells = head_to_head(items.with(Vec::len), &history);
+    let names: Vec<String> = items.with(|items| {
+        order
+            .iter()
+            .map(|&i| Item::parse(&items[i]).label)
+            .collect()
+    });
+    view! {
+        <div class="matrix-scroll">
+            <table class="matrix">
+                <thead>
+                    <tr>
+                        <th></th>
+                        {names
+                            .iter()
+                            .enumerate()
+                            .map(|(col, name)| {
+                                view! {
+                                    <th scope="col" title=name.clone()>
+                                        {col + 1}
+                                    </th>
+                                }
+                            })
+                            .collect_view()}
+                    </tr>
+                </thead>
+                <tbody>
+                    {order
+                        .iter()
+                        .zip(&names)
+                        .enumerate()
+                        .map(|(row, (&i, name))| {
+                            view! {
+                                <tr>
+                                    <th scope="row">{format!("{}. {name}", row + 1)}</th>
+                                    {order
+                                        .iter()
+                                        .map(|&j| {
+                                            let cell = cells[i][j];
+                                            view! {
+                                                <td class=cell_class(cell)>{cell_symbol(cell)}</td>
+                                            }
+                                        })
+                                        .collect_view()}
+                                </tr>
+                            }
+                        })
+                        .collect_view()}
+                </tbody>
+            </table>
+        </div>
+        <p class="matrix-legend">{move || t.get().matrix_legend}</p>
+    }
+}
+
+fn cell_symbol(cell: Cell) -> &'static str {
+    match cell {
+        Cell::Itself => "\u{2014}",
+        Cell::Won => "\u{2713}",
+        Cell::Lost => "\u{2717}",
+        Cell::Tied => "=",
+        Cell::InferredWon => "(\u{2713})",
+        Cell::InferredLost => "(\u{2717})",
+        Cell::Open => "",
+    }
+}
+
+fn cell_class(cell: Cell) -> &'static str {
+    match cell {
+        Cell::Itself => "cell-self",
+        Cell::Won => "cell-won",
+        Cell::Lost => "cell-lost",
+        Cell::Tied => "cell-tied",
+        Cell::InferredWon => "cell-won inferred",
+        Cell::InferredLost => "cell-lost inferred",
+        Cell::Open => "cell-open",
+    }
+}
+
+/// The ranking split by tag: each tag's items in their overall order.
+#[component]
+#[allow(clippy::needless_pass_by_value)] // component props are owned
+fn TagRankings(items: Signal<Vec<String>>, order: Vec<usize>) -> impl IntoView {
+    let t = use_texts();
+    let groups = items.with(|items| by_tag(items, &order));
+    (!groups.is_empty()).then(|| {
+        view! {
+            <section class="tag-rankings">
+                <h3 class="tag-rankings-heading">{move || t.get().tags_heading}</h3>
+                {groups
+                    .into_iter()
+                    .map(|(tag, members)| {
+                        view! {
+                            <div class="tag-ranking">
+                                <h4 class="tag-name">{format!("#{tag}")}</h4>
+                                <ol>
+                                    {members
+                                        .into_iter()
+                                        .map(|item| {
+                                            view! {
+                                                <li>
+                                                    <ItemLabel text=items
+                                                        .with(|items| items[item].clone()) />
+                                                </li>
+                                            }
+                                        })
+                                        .collect_view()}
+                                </ol>
+                            </div>
+                        }
+                    })
+                    .collect_view()}
+            </section>
+        }
+    })
+}
+
+/// How the session went, with the numbers as a JSON download.
+#[component]
+#[allow(clippy::needless_pass_by_value)] // component props are owned
+fn StatsPanel(stats: Stats, items: Signal<Vec<String>>) -> impl IntoView {
+    let t = use_texts();
+    let href = format!(
+        "data:application/json,{}",
+        String::from(js_sys::encode_uri_component(&stats.to_json()))
+    );
+    let Stats {
+        questions,
+        estimate,
+        questions_per_item,
+        mean_search_range,
+        longest_streak,
+        timing,
+        ..
+    } = stats;
+    let busiest = questions_per_item
+        .iter()
+        .enumerate()
+        .max_by_key(|&(_, &count)| count)
+        .filter(|&(_, &count)| count > 0)
+        .map(|(item, &count)| (items.with(|items| Item::parse(&items[item]).label), count));
+    view! {
+        <details class="stats">
+            <summary>{move || t.get().stats_heading}</summary>
+            <ul>
+                <li>{move || fill(t.get().stats_questions, &[&questions, &estimate])}</li>
+                {mean_search_range
+                    .map(|range| {
+                        let range = format!("{range:.1}");
+                        view! { <li>{move || fill(t.get().stats_search, &[&range])}</li> }
+                    })}
+                {busiest
+                    .map(|(name, count)| {
+                        view! { <li>{move || fill(t.get().stats_busiest, &[&name, &count])}</li> }
+                    })}
+                {longest_streak
+                    .filter(|streak| streak.length > 1)
+                    .map(|streak| {
+                        let from = streak.start + 1;
+                        view! {
+                            <li>{move || fill(t.get().stats_streak, &[&streak.length, &from])}</li>
+                        }
+                    })}
+                {timing
+                    .map(|timing| {
+                        let seconds = format!("{:.1}", timing.median_ms / 1000.0);
+                        view! { <li>{move || fill(t.get().stats_pace, &[&seconds])}</li> }
+                    })}
+            </ul>
+            <a class="stats-download" download="rankfast-stats.json" href=href>
+                {move || t.get().stats_download}
+            </a>
+        </details>
+    }
+}
+
+/// Every answered comparison as CSV and JSON Lines downloads, see
+/// [`dataset`].
+#[component]
+#[allow(clippy::needless_pass_by_value)] // component props are owned
+fn DatasetLinks(preferences: Vec<Preference>) -> impl IntoView {
+    let t = use_texts();
+    let href = |mime: &str, text: String| {
+        format!(
+            "data:{mime},{}",
+            String::from(js_sys::encode_uri_component(&text))
+        )
+    };
+    let csv = href("text/csv", dataset::to_csv(&preferences));
+    let jsonl = href("application/jsonl", dataset::to_jsonl(&preferences));
+    (!preferences.is_empty()).then(|| {
+        view! {
+            <p class="dataset-download">
+                <a class="stats-download" download="rankfast-comparisons.csv" href=csv>
+                    {move || t.get().dataset_csv}
+                </a>
+                " "
+                <a class="stats-download" download="rankfast-comparisons.jsonl" href=jsonl>
+                    {move || t.get().dataset_jsonl}
+                </a>
+            </p>
+        }
+    })
+}
+
+/// One ranked item with its direct record.
+#[derive(Clone)]
+struct Entry {
+    rank: usize,
+    item: usize,
+    /// Display label, used for sorting and filtering.
+    name: String,
+    /// Raw item text, rendered through [`ItemLabel`].
+    text: String,
+    record: Record,
+}
+
+/// Direct comparisons won, lost and tied by one item. Skips count as none.
+#[derive(Clone, Copy, Default)]
+struct Record {
+    won: usize,
+    lost: usize,
+    tied: usize,
+}
+
+impl Record {
+    fn of(history: &[(usize, usize, Answer)], item: usize) -> Self {
+        let mut record = Self::default();
+        for (_, answer) in matchups(history, item) {
+            match answer {
+                Answer::A => record.won += 1,
+                Answer::B => record.lost += 1,
+                Answer::Tie => record.tied += 1,
+                Answer::Skip => {}
+            }
+        }
+        record
+    }
+}
+
+#[component]
+fn RecordBadge(record: Record) -> impl IntoView {
+    let t = use_texts();
+    let label = move || {
+        let Record { won, lost, tied } = record;
+        if tied > 0 {
+            fill(t.get().record_tied, &[&won, &lost, &tied])
+        } else {
+            fill(t.get().record, &[&won, &lost])
+        }
+    };
+    view! {
+        <span
+            class="record-badge"
+            title=move || fill(t.get().record_title, &[&record.won, &record.lost])
+        >
+            {label}
+        </span>
+    }
+}
+
+/// Top three arranged second, first, third.
+#[component]
+#[allow(clippy::needless_pass_by_value)] // component props are owned
+fn Podium(
+    entries: Vec<Entry>,
+    expanded: ReadSignal<Option<usize>>,
+    toggle: Callback<usize>,
+) -> impl IntoView {
+    let mut places = entries;
+    if places.len() >= 2 {
+        places.swap(0, 1);
+    }
+    view! {
+        <ol class="podium">
+            {places
+                .into_iter()
+                .map(|entry| {
+                    let item = entry.item;
+                    view! {
+                        <li class="podium-place">
+                            <button
+                                class="podium-step"
+                                class:gold={entry.rank == 1}
+                                class:silver={entry.rank == 2}
+                                class:bronze={entry.rank == 3}
+                                aria-expanded=move || (expanded.get() == Some(item)).to_string()
+                                on:click=move |_| toggle.run(item)
+                            >
+                                <span class="rank-number">{entry.rank}</span>
+                                <span class="podium-name">
+                                    <ItemLabel text=entry.text />
+                                </span>
+                                <RecordBadge record=entry.record />
+                            </button>
+                        </li>
+                    }
+                })
+                .collect_view()}
+        </ol>
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SortKey {
+    Rank,
+    Name,
+    Won,
+    Lost,
+}
+
+/// Table of the remaining places. Headers sort (click again to reverse) and
+/// the search box filters by name.
+#[component]
+#[allow(clippy::needless_pass_by_value)] // component props are owned
+fn RankTable(
+    entries: Vec<Entry>,
+    items: Signal<Vec<String>>,
+    history: Arc<Vec<(usize, usize, Answer)>>,
+    expanded: ReadSignal<Option<usize>>,
+    toggle: Callback<usize>,
+) -> impl IntoView {
+    let t = use_texts();
+    let (sort, set_sort) = signal((SortKey::Rank, true));
+    let (filter, set_filter) = signal(String::new());
+
+    let rows = move || {
+        let needle = filter.get().to_lowercase();
+        let mut rows: Vec<Entry> = entries
+            .iter()
+            .filter(|e| e.name.to_lowercase().contains(&needle))
+            .cloned()
+            .collect();
+        let (key, ascending) = sort.get();
+        rows.sort_by(|x, y| {
+            let ord = match key {
+                SortKey::Rank => x.rank.cmp(&y.rank),
+                SortKey::Name => x.name.to_lowercase().cmp(&y.name.to_lowercase()),
+                SortKey::Won => x.record.won.cmp(&y.record.won),
+                SortKey::Lost => x.record.lost.cmp(&y.record.lost),
+            };
+            let ord = ord.then(x.rank.cmp(&y.rank));
+            if ascending { ord } else { ord.reverse() }
+        });
+        rows.into_iter()
+            .map(|entry| {
+                let item = entry.item;
+                let is_open = move || expanded.get() == Some(item);
+                view! {
+                    <tr class="table-row" on:click=move |_| toggle.run(item)>
+                        <td class="col-rank">{entry.rank}</td>
+                        <td class="col-name">
+                            <button
+                                class="table-item"
+                                aria-expanded=move || is_open().to_string()
+                            >
+                                <ItemLabel text=entry.text />
+                            </button>
+                        </td>
+                        <td class="col-num">{entry.record.won}</td>
+                        <td class="col-num">{entry.record.lost}</td>
+                    </tr>
+                    <tr class="table-details" hidden=move || !is_open()>
+                        <td colspan="4">
+                            <MatchupList items history=history.clone() item />
+                        </td>
+                    </tr>
+                }
+            })
+            .collect_view()
+    };
+
+    let header = move |label: fn(&Texts) -> &'static str, key: SortKey| {
+        let aria_sort = move || match sort.get() {
+            (k, true) if k == key => "ascending",
+            (k, false) if k == key => "descending",
+            _ => "none",
+        };
+        let arrow = move || match sort.get() {
+            (k, true) if k == key => " \u{25b2}",
+            (k, false) if k == key => " \u{25bc}",
+            _ => "",
+        };
+        view! {
+            <th aria-sort=aria_sort>
+                <button
+                    class="sort-btn"
+                    on:click=move |_| {
+                        set_sort.update(|(k, ascending)| {
+                            if *k == key {
+                                *ascending = !*ascending;
+                            } else {
+                                *k = key;
+                                *ascending = true;
+                            }
+                        });
+                    }
+                >
+                    {move || label(t.get())}
+                    {arrow}
+                </button>
+            </th>
+        }
+    };
+
+    view! {
+        <div class="rank-table">
+            <input
+                class="table-filter"
+                type="search"
+                placeholder=move || t.get().filter
+                aria-label=move || t.get().filter
+                prop:value=move || filter.get()
+                on:input=move |ev| set_filter.set(event_target_value(&ev))
+            />
+            <table>
+                <thead>
+                    <tr>
+                        {header(|_| "#", SortKey::Rank)}
+                        {header(|t| t.column_item, SortKey::Name)}
+                        {header(|t| t.column_won, SortKey::Won)}
+                        {header(|t| t.column_lost, SortKey::Lost)}
+                    </tr>
+                </thead>
+                <tbody>{rows}</tbody>
+            </table>
+        </div>
+    }
+}
+
+/// The comparisons `item` took part in, in the order asked.
+#[component]
+#[allow(clippy::needless_pass_by_value)] // component props are owned
+fn MatchupList(
+    items: Signal<Vec<String>>,
+    history: Arc<Vec<(usize, usize, Answer)>>,
+    item: usize,
+) -> impl IntoView {
+    let t = use_texts();
+    view! {
+        <ul class="matchups">
+            {matchups(&history, item)
+                .into_iter()
+                .map(|(opponent, answer)| {
+                    let label = move || {
+                        let t = t.get();
+                        match answer {
+                            Answer::A => t.beat,
+                            Answer::B => t.lost_to,
+                            Answer::Tie => t.tied_with,
+                            Answer::Skip => t.skipped_vs,
+                        }
+                    };
+                    view! {
+                        <li
+                            class="matchup"
+                            class:won={answer == Answer::A}
+                            class:lost={answer == Answer::B}
+                            class:even={matches!(answer, Answer::Tie | Answer::Skip)}
+                        >
+                            <span class="matchup-result">{label}</span>
+                            <span class="matchup-opponent">
+                                <ItemLabel text=items.with(|items| items[opponent].clone()) />
+                            </span>
+                        </li>
+                    }
+                })
+                .collect_view()}
+        </ul>
+    }
+}
+
+/// Opponents `item` faced, in the order asked, with the answer from
+/// `item`'s side ([`Answer::A`] means `item` won).
+fn matchups(history: &[(usize, usize, Answer)], item: usize) -> Vec<(usize, Answer)> {
+    history
+        .iter()
+        .filter_map(|&(a, b, answer)| {
+            if a == item {
+                Some((b, answer))
+            } else if b == item {
+                Some((a, answer.flipped()))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
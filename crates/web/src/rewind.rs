@@ -0,0 +1,61 @@
+//! Going back several questions at once.
+//!
+//! The browser's back button takes back one answer at a time. The
+//! [`QuestionHistory`] panel lists every question asked so far with the
+//! answer given, and picking one drops that answer and every later one.
+//! The state is replayed from the answers that are left like after any
+//! other change, so the question comes up again as it was first asked.
+
+use leptos::prelude::*;
+use rankfast_session::{Answer, Item};
+
+use crate::i18n::{fill, use_texts};
+
+/// Every question asked, first to last, each a button that rewinds to it.
+/// `on_rewind` gets the number of answers to keep.
+#[component]
+#[allow(clippy::needless_pass_by_value)] // component props are owned
+pub(crate) fn QuestionHistory(
+    #[prop(into)] items: Signal<Vec<String>>,
+    history: Vec<(usize, usize, Answer)>,
+    on_rewind: Callback<usize>,
+) -> impl IntoView {
+    let t = use_texts();
+    let questions = history
+        .into_iter()
+        .enumerate()
+        .map(|(question, (a, b, answer))| {
+            let number = question + 1;
+            let result = move || {
+                let t = t.get();
+                match answer {
+                    Answer::A => t.beat,
+                    Answer::B => t.lost_to,
+                    Answer::Tie => t.tied_with,
+                    Answer::Skip => t.skipped_vs,
+                }
+            };
+            view! {
+                <li>
+                    <button
+                        class="history-question"
+                        title=move || fill(t.get().rewind_to, &[&number])
+                        on:click=move |_| on_rewind.run(question)
+                    >
+                        <span class="history-number">{number}</span>
+                        <span>{items.with(|items| Item::parse(&items[a]).label)}</span>
+                        <span class="history-answer">{result}</span>
+                        <span>{items.with(|items| Item::parse(&items[b]).label)}</span>
+                    </button>
+                </li>
+            }
+        })
+        .collect_view();
+    view! {
+        <details class="question-history">
+            <summary>{move || t.get().question_history}</summary>
+            <p class="question-history-hint">{move || t.get().question_history_hint}</p>
+            <ol class="question-history-list">{questions}</ol>
+        </details>
+    }
+}
@@ -0,0 +1,120 @@
+//! Time left, from the pace of the answers so far.
+//!
+//! [`Pace`] keeps the time of every answer in memory. The median gap between
+//! answers times the engine's estimate of the questions still to come gives
+//! the time left; the median shrugs off the odd long pause that would throw
+//! a mean off. Times are not saved with the session, so a reloaded or
+//! resumed run starts timing afresh.
+
+use crate::i18n::{Texts, fill};
+
+/// Gaps between answers needed before an estimate is shown.
+const MIN_GAPS: usize = 3;
+
+const MINUTE_MS: f64 = 60_000.0;
+
+/// When each answer was given, in milliseconds.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Pace {
+    stamps: Vec<f64>,
+}
+
+impl Pace {
+    /// Records an answer given at `now_ms`.
+    pub(crate) fn record(&mut self, now_ms: f64) {
+        self.stamps.push(now_ms);
+    }
+
+    /// Forgets the last `count` answers, taken back by rewinding.
+    pub(crate) fn forget(&mut self, count: usize) {
+        self.stamps
+            .truncate(self.stamps.len().saturating_sub(count));
+    }
+
+    /// When each answer was given, in whole Unix milliseconds.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)] // Date.now() is a whole, positive count
+    pub(crate) fn stamps_ms(&self) -> Vec<u64> {
+        self.stamps.iter().map(|&stamp| stamp as u64).collect()
+    }
+
+    /// Milliseconds between each answer and the one before, so one fewer
+    /// than the answers recorded.
+    pub(crate) fn gaps(&self) -> Vec<f64> {
+        self.stamps.windows(2).map(|w| w[1] - w[0]).collect()
+    }
+
+    /// Median time between answers, once there are enough of them.
+    pub(crate) fn median_gap(&self) -> Option<f64> {
+        let mut gaps = self.gaps();
+        if gaps.len() < MIN_GAPS {
+            return None;
+        }
+        gaps.sort_by(f64::total_cmp);
+        let mid = gaps.len() / 2;
+        Some(if gaps.len().is_multiple_of(2) {
+            f64::midpoint(gaps[mid - 1], gaps[mid])
+        } else {
+            gaps[mid]
+        })
+    }
+
+    /// Milliseconds needed for `remaining` more questions at the median pace.
+    pub(crate) fn time_left(&self, remaining: usize) -> Option<f64> {
+        let remaining = f64::from(u32::try_from(remaining).unwrap_or(u32::MAX));
+        self.median_gap().map(|gap| gap * remaining)
+    }
+}
+
+/// "About 2 minutes left", rounded to whole minutes.
+pub(crate) fn describe(t: &Texts, ms: f64) -> String {
+    let minutes = (ms / MINUTE_MS).round();
+    if ms < MINUTE_MS {
+        t.time_left_under_minute.to_string()
+    } else if minutes <= 1.0 {
+        t.time_left_minute.to_string()
+    } else {
+        fill(t.time_left_minutes, &[&format!("{minutes:.0}")])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MINUTE_MS, Pace, describe};
+    use crate::i18n::Locale;
+
+    fn pace(stamps: &[f64]) -> Pace {
+        let mut pace = Pace::default();
+        for &stamp in stamps {
+            pace.record(stamp);
+        }
+        pace
+    }
+
+    #[test]
+    fn median_ignores_long_pauses() {
+        assert_eq!(pace(&[0.0, 1000.0, 2000.0]).median_gap(), None);
+        let steady = pace(&[0.0, 1000.0, 2000.0, 3000.0]);
+        assert_eq!(steady.median_gap(), Some(1000.0));
+        let paused = pace(&[0.0, 1000.0, 900_000.0, 901_500.0, 903_000.0]);
+        assert_eq!(paused.median_gap(), Some(1500.0));
+        assert_eq!(steady.time_left(30), Some(30_000.0));
+    }
+
+    #[test]
+    fn forgets_the_answers_taken_back() {
+        let mut rewound = pace(&[0.0, 1000.0, 2000.0, 3000.0, 60_000.0]);
+        rewound.forget(1);
+        assert_eq!(rewound, pace(&[0.0, 1000.0, 2000.0, 3000.0]));
+        // Answers from before the page loaded have no times to forget.
+        rewound.forget(10);
+        assert_eq!(rewound, Pace::default());
+    }
+
+    #[test]
+    fn rounds_to_minutes() {
+        let t = Locale::En.texts();
+        assert_eq!(describe(t, 30_000.0), t.time_left_under_minute);
+        assert_eq!(describe(t, 80_000.0), t.time_left_minute);
+        assert_eq!(describe(t, 2.4 * MINUTE_MS), "About 2 minutes left");
+    }
+}
@@ -0,0 +1,76 @@
+//! The embeddable widget build, with the `widget` feature.
+//!
+//! `trunk build widget.html` builds the app for an iframe: no header,
+//! install prompt or URL history, and no item form. The host page drives it
+//! with `postMessage`:
+//!
+//! - widget → host: `{"type": "rankfast:ready"}` once it listens, and
+//!   `{"type": "rankfast:result", "ranking": [...], "comparisons": n}` each
+//!   time a ranking is complete, items best first
+//! - host → widget: `{"type": "rankfast:load", "items": [...]}` starts a new
+//!   ranking of the items, cleaned up like typed ones
+//!
+//! Only messages from the embedding page are read. Results go to it
+//! whatever its origin, since it is the page the user is on.
+
+use js_sys::{Object, Reflect};
+use leptos::prelude::*;
+use wasm_bindgen::JsValue;
+
+/// Whether this is the widget build.
+pub(crate) const ENABLED: bool = cfg!(feature = "widget");
+
+/// Starts a ranking with `load` whenever the host sends items, then tells
+/// the host the widget is ready. Does nothing outside an iframe.
+pub(crate) fn listen(load: impl Fn(Vec<String>) + 'static) {
+    let Some(host) = host() else {
+        return;
+    };
+    let host = JsValue::from(host);
+    // The listener lives as long as the page.
+    let _message = window_event_listener(leptos::ev::message, move |ev| {
+        if ev.source().map(JsValue::from).as_ref() != Some(&host) {
+            return;
+        }
+        let data = ev.data();
+        if field(&data, "type").as_string().as_deref() != Some("rankfast:load") {
+            return;
+        }
+        if let Some(items) = crate::host::items(&field(&data, "items")) {
+            load(items);
+        }
+    });
+    post(&message("rankfast:ready"));
+}
+
+/// Tells the host page the ranking is complete.
+pub(crate) fn post_result(ranking: &[String], comparisons: usize) {
+    post(&crate::host::result(
+        Some("rankfast:result"),
+        ranking,
+        comparisons,
+    ));
+}
+
+/// The embedding page, if the app runs in a frame.
+fn host() -> Option<web_sys::Window> {
+    let parent = window().parent().ok().flatten()?;
+    (JsValue::from(parent.clone()) != JsValue::from(window())).then_some(parent)
+}
+
+fn post(message: &Object) {
+    if let Some(host) = host() {
+        // The host may have gone away; there is no one else to tell.
+        let _ = host.post_message(message, "*");
+    }
+}
+
+fn message(kind: &str) -> Object {
+    let message = Object::new();
+    let _ = Reflect::set(&message, &"type".into(), &kind.into());
+    message
+}
+
+fn field(object: &JsValue, name: &str) -> JsValue {
+    Reflect::get(object, &name.into()).unwrap_or(JsValue::UNDEFINED)
+}
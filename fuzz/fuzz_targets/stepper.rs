@@ -0,0 +1,55 @@
+//! Drives the stepper with arbitrary keys and arbitrary read-only calls
+//! between questions, and checks it asks exactly what `rank_permutation`
+//! asks and ends on the same order.
+//!
+//! The first byte is the item count, the next bytes are the items' keys
+//! (equal bytes tie), and the rest picks the calls made before each
+//! question:
+//!
+//! ```text
+//! cargo +nightly fuzz run stepper
+//! ```
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rankfast::{Step, Stepper, rank_permutation};
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&n, rest)) = data.split_first() else {
+        return;
+    };
+    let (keys, noise) = rest.split_at(usize::from(n % 65).min(rest.len()));
+
+    let mut expected = Vec::new();
+    let order = rank_permutation(keys.len(), |a, b| {
+        expected.push((a.min(b), a.max(b)));
+        keys[a] < keys[b]
+    });
+
+    let mut stepper = Stepper::new(keys.len());
+    let mut noise = noise.iter().copied().cycle();
+    let mut asked = Vec::new();
+    loop {
+        match noise.next().unwrap_or(0) % 5 {
+            0 => {}
+            1 => drop(stepper.step()),
+            2 => drop(stepper.available_questions()),
+            3 => drop(stepper.peek_next_after(asked.len() % 2 == 0)),
+            _ => drop((stepper.finalize_partial(), stepper.remaining_estimate())),
+        }
+        let Step::Compare { a, b, .. } = stepper.step() else {
+            break;
+        };
+        asked.push((a.min(b), a.max(b)));
+        assert_eq!(asked.last(), expected.get(asked.len() - 1), "diverged");
+        let a_better = if a < b {
+            keys[b] >= keys[a]
+        } else {
+            keys[a] < keys[b]
+        };
+        stepper.answer(a_better);
+    }
+    assert_eq!(asked, expected);
+    assert_eq!(stepper.take_order(), Some(order));
+});